@@ -0,0 +1,110 @@
+# Deutsche Meldungen von cfg2hcl. Fehlende Schlüssel werden aus en.ftl genommen.
+# Terraform-Begriffe, Attribut- und Kommandonamen bleiben englisch.
+
+## Ausgabepräfixe und Zusammenfassung der Warnungen
+warning = Warnung: { $message }
+error = Fehler: { $message }
+at-path = { $message } (bei '{ $path }')
+warning-group = { $group } — { $count } Vorkommen, z. B. { $examples } (--show-all listet alle auf)
+warning-summary = { $count } Warnung(en) in { $resources } Ressource(n)
+warnings-as-errors = { $count } Warnung(en) als Fehler gewertet (--warnings-as-errors)
+warnings-no-files-written = { $count } Warnung(en) mit --warnings-as-errors; keine Dateien geschrieben
+config-validation-failed = Prüfung der Konfiguration mit { $count } Fehler(n) fehlgeschlagen
+naming-validation-failed = Prüfung der Namenskonventionen mit { $count } Fehler(n) fehlgeschlagen
+iam-validation-failed = Prüfung der IAM-Mitglieder mit { $count } Fehler(n) fehlgeschlagen
+
+## Schema-Prüfung der Ressourcen
+missing-parameter = Pflichtparameter '{ $attribute }' fehlt bei Ressource '{ $resource }' ({ $type })
+missing-parameter-group = Pflichtparameter '{ $attribute }' fehlt ({ $type })
+missing-block = Pflichtblock '{ $block }' fehlt bei Ressource '{ $resource }' ({ $type })
+missing-block-group = Pflichtblock '{ $block }' fehlt ({ $type })
+unknown-field = Unbekanntes Feld '{ $field }' bei Ressource '{ $resource }' ({ $type })
+unknown-field-group = Unbekanntes Feld '{ $field }' ({ $type })
+missing-labels = Vorgeschriebene Label(s) [{ $labels }] fehlen bei Ressource '{ $resource }' ({ $type })
+missing-labels-group = Vorgeschriebene Label(s) [{ $labels }] fehlen ({ $type })
+no-project-context = Ressource '{ $resource }' ({ $type }) benötigt den Parameter 'project', ist aber außerhalb eines Projekts definiert und gibt kein Projekt explizit an.
+no-project-context-group = { $type } außerhalb eines Projekts ohne explizites 'project'
+no-folder-context = Ressource '{ $resource }' ({ $type }) benötigt den Parameter 'folder', ist aber außerhalb eines Ordners definiert und gibt keinen Ordner explizit an.
+no-folder-context-group = { $type } außerhalb eines Ordners ohne explizites 'folder'
+service-not-enabled = { $resource } benötigt { $service }, der in { $project } nicht aktiviert ist (unter project_service ergänzen)
+service-not-enabled-group = { $type } benötigt { $service }, der in { $project } nicht aktiviert ist
+timeouts-unsupported = { $type } hat keine Timeouts; 'timeouts' von Ressource '{ $resource }' wird weggelassen
+timeouts-unsupported-group = { $type } hat keine Timeouts
+timeout-unknown = Unbekannter Timeout '{ $operation }' bei Ressource '{ $resource }' ({ $type }), erwartet: { $expected }
+timeout-unknown-group = Unbekannter Timeout '{ $operation }' ({ $type })
+timeout-invalid = Timeout '{ $operation }' von Ressource '{ $resource }' ({ $type }) ist keine Dauer wie 30m oder 1h30m: '{ $value }'
+timeout-invalid-group = Timeout '{ $operation }' ist keine Dauer ({ $type })
+implicit-reference = '{ $value }' bei '{ $path }' wird als Referenz gelesen; schreibe '!ref { $value }', da einfache Strings mit implicit_references = "off" Strings bleiben
+implicit-reference-group = Einfacher String als Referenz gelesen (implicit_references)
+authoritative-iam-binding = { $type } bei '{ $path }' ist autoritativ: Mitglieder, denen seine Rollen außerhalb des YAML gegeben wurden, verlieren sie beim Anwenden
+authoritative-iam-policy = { $type } bei '{ $path }' ist autoritativ: jede Berechtigung des Scopes, die nicht im YAML steht, wird beim Anwenden entfernt
+authoritative-iam-group = Autoritatives IAM entfernt Berechtigungen von außerhalb des YAML
+provider-labels-routed = default_labels mit mode provider von '{ $path }' werden nicht angewendet: seine Ressourcen nutzen den Provider { $provider } des provider-alias, der sie nicht bekommt
+provider-labels-routed-group = Provider-Default-Labels ohne eigenen Provider
+default-labels-spelling = 'default-labels' ist kein Schlüssel; Labels für die Provider werden als 'default_labels' mit 'mode: provider' und 'labels:' geschrieben
+
+## Fehler im YAML-Modell
+transpile-failed = Transpilierung mit { $count } Fehler(n) fehlgeschlagen
+iam-mode-invalid = iam-mode muss member, binding oder policy sein
+secret-in-variables = !secret kann nicht in variables verwendet werden; verwende es dort, wo der Wert gebraucht wird
+folder-parent-invalid = parent '{ $parent }' des Ordners ist weder folders/<id> noch organizations/<id>
+folder-parent-type = parent eines Ordners muss folders/<id>, organizations/<id> oder ein !ref sein
+folder-parent-missing = Ordner hat keinen übergeordneten Ordner und keine Organisation
+ou-parent-type = parent einer Organizational Unit muss eine ID (r-..., ou-...) oder ein !ref sein
+ou-parent-missing = Organizational Unit hat keine übergeordnete Unit und keinen Organisations-Root
+policy-id-invalid = Eine Policy muss eine Policy-ID (p-...) oder ein !ref sein
+provider-alias-unknown = provider-alias '{ $provider }' nennt keinen unter providers: deklarierten Provider (erwartet: <provider>.<alias>)
+org-policy-name-missing = Pflichtattribut 'name' fehlt bei google_org_policy_policy
+org-policy-parent-missing = Kein 'parent' angegeben und keine Organisation, kein Ordner und kein Projekt im Kontext
+instance-reference-outside-loop = '{ $reference }' kann nur in einer Ressource mit 'for_each' oder 'count' verwendet werden
+project-several-parents = Projekt hat mehr als einen parent ({ $parents }); behalte einen
+ref-needs-string = !ref braucht einen String
+project-folder-invalid = 'folder' eines Projekts muss folders/<id> oder !ref <Ordnerschlüssel> sein
+project-org-without-id = 'org: true' braucht customer-organization-id
+project-org-invalid = 'org' eines Projekts muss true oder eine Organisations-ID sein
+enabled-invalid = 'enabled' muss true oder false sein; für Bedingungen 'when: !expr ...' verwenden
+when-invalid = 'when' muss true, false oder ein Ausdruck wie !expr var.env == "prod" sein
+when-with-count = 'when' kann nicht mit 'count' oder 'for_each' kombiniert werden
+module-not-mapping = Ein Modul muss ein Mapping mit mindestens 'source' sein
+module-source-missing = Pflichtattribut 'source' fehlt beim Modul
+local-name-invalid = '{ $name }' ist kein gültiger Name für ein local
+output-name-invalid = '{ $name }' ist kein gültiger Output-Name; verwende Buchstaben, Ziffern, '_' und '-', beginnend mit einem Buchstaben oder '_'
+output-value-missing = Pflichtattribut 'value' fehlt beim Output
+output-description-invalid = 'description' eines Outputs muss ein String sein
+output-sensitive-invalid = 'sensitive' eines Outputs muss true oder false sein
+output-attribute-unknown = Unbekanntes Output-Attribut '{ $attribute }' (erwartet: value, description, sensitive oder depends_on)
+resource-label-invalid = '{ $label }' ist kein gültiges Ressourcenlabel; verwende Buchstaben, Ziffern, '_' und '-', beginnend mit einem Buchstaben oder '_'
+address-duplicate = { $address } wird schon aus '{ $previous }' erzeugt; benenne einen der beiden Einträge um
+depends-on-invalid = 'depends_on' muss eine Liste von Ressourcenschlüsseln oder Adressen sein
+depends-on-no-match = 'depends_on'-Eintrag '{ $entry }' passt zu keinem Ressourcenschlüssel und keiner Adresse
+depends-on-ambiguous = 'depends_on'-Eintrag '{ $entry }' passt zu { $matches }; stelle den Schlüssel seines Ordners oder Projekts voran
+ref-no-match = '!ref { $reference }' passt zu keinem Ressourcenschlüssel und keiner Adresse
+ref-ambiguous = '!ref { $reference }' passt zu { $matches }; stelle den Schlüssel seines Ordners oder Projekts voran
+ref-needs-instance = '!ref { $reference }' verweist auf { $address }, das count oder for_each hat (z. B. durch `when:`); gib die Instanz nach dem Schlüssel an, etwa [0] oder [each.key]
+labels-not-mergeable = Default-Labels können nur in ein `labels:`-Mapping oder einen Ausdruck übernommen werden
+cex-unsupported = { $type } kann nicht aufgeteilt werden; CEX_ unterstützt *_iam_member und { $supported }
+
+## Prüfung der Konfiguration
+org-id-missing = 'customer-organization-id' fehlt
+org-id-not-numeric = 'customer-organization-id' muss die numerische Organisations-ID sein, gefunden: '{ $value }'
+deployment-mode-invalid = 'deployment-mode' muss 'local' oder 'cloud' sein, gefunden: '{ $value }'
+cloud-without-backend = 'deployment-mode' ist 'cloud', aber es ist kein 'gcs'-Backend definiert
+backend-without-bucket = das 'gcs'-Backend hat keinen 'bucket'
+infra-project-undeclared = Infra-Projekt '{ $project }' ist im YAML nicht als Projekt deklariert
+group-member-undeclared = Gruppenmitglied '{ $member }' verweist auf keine deklarierte cloud_identity_group
+iam-lockout-binding = das autoritative Binding von { $role } auf { $scope } enthält das IaC-Servicekonto '{ $member }' nicht; das Anwenden entzieht Terraform selbst die Rolle
+iam-lockout-policy = die autoritative IAM-Policy von { $scope } gibt dem IaC-Servicekonto '{ $member }' keine IAM-Admin-Rolle; das Anwenden sperrt Terraform aus
+
+## IAM-Mitglieder
+member-outside-domains = IAM-Mitglied '{ $member }' liegt außerhalb der erlaubten Domains [{ $domains }]
+member-invalid-principal = Ungültiger Workload-/Workforce-Identity-Principal '{ $member }'
+member-no-type = IAM-Mitglied '{ $member }' hat keinen Principal-Typ (erwartet: user:, group:, serviceAccount:, domain:, principal:// oder principalSet://)
+member-unknown-type = IAM-Mitglied '{ $member }' hat den unbekannten Principal-Typ '{ $kind }:' (erwartet: user:, group:, serviceAccount:, domain:, principal:// oder principalSet://)
+member-invalid-email = IAM-Mitglied '{ $member }' ist keine gültige E-Mail-Adresse
+member-invalid-service-account = IAM-Mitglied '{ $member }' ist keine gültige Service-Account-E-Mail
+member-invalid-domain = IAM-Mitglied '{ $member }' ist keine gültige Domain
+member-missing-project = Bei IAM-Mitglied '{ $member }' fehlt die Projekt-ID
+
+## Namenskonventionen
+naming-mismatch = { $attribute } '{ $value }' von { $type } entspricht nicht dem Namensmuster '{ $pattern }'
+naming-missing-placeholder = { $attribute } für { $type } kann nicht erzeugt werden: Platzhalter '{"{"}{ $placeholder }{"}"}' hat keinen Wert
@@ -0,0 +1,112 @@
+# User-facing messages of cfg2hcl, English (the fallback for missing translations).
+# Fluent syntax (https://projectfluent.org), loaded with the fluent-bundle crate: `key = text`,
+# variables are written as { $name }; selectors such as { $count -> [one] ... *[other] ... } work
+# with numeric arguments.
+
+## Output prefixes and the warning summary
+warning = Warning: { $message }
+error = Error: { $message }
+at-path = { $message } (at '{ $path }')
+warning-group = { $group } — { $count } occurrences, e.g. { $examples } (--show-all lists every one)
+warning-summary = { $count } warning(s) in { $resources } resource(s)
+warnings-as-errors = { $count } warning(s) treated as errors (--warnings-as-errors)
+warnings-no-files-written = { $count } warning(s) with --warnings-as-errors; no files written
+config-validation-failed = Configuration validation failed with { $count } error(s)
+naming-validation-failed = Naming validation failed with { $count } error(s)
+iam-validation-failed = IAM member validation failed with { $count } error(s)
+
+## Schema validation of resources
+missing-parameter = Missing mandatory parameter '{ $attribute }' for resource '{ $resource }' ({ $type })
+missing-parameter-group = Missing mandatory parameter '{ $attribute }' ({ $type })
+missing-block = Missing mandatory block '{ $block }' for resource '{ $resource }' ({ $type })
+missing-block-group = Missing mandatory block '{ $block }' ({ $type })
+unknown-field = Unknown field '{ $field }' for resource '{ $resource }' ({ $type })
+unknown-field-group = Unknown field '{ $field }' ({ $type })
+missing-labels = Missing required label(s) [{ $labels }] for resource '{ $resource }' ({ $type })
+missing-labels-group = Missing required label(s) [{ $labels }] ({ $type })
+no-project-context = Resource '{ $resource }' ({ $type }) requires a 'project' parameter but is defined outside a project context and no explicit project is provided.
+no-project-context-group = { $type } outside a project context without an explicit 'project'
+no-folder-context = Resource '{ $resource }' ({ $type }) requires a 'folder' parameter but is defined outside a folder context and no explicit folder is provided.
+no-folder-context-group = { $type } outside a folder context without an explicit 'folder'
+service-not-enabled = { $resource } needs { $service }, which is not enabled in { $project } (add it to project_service)
+service-not-enabled-group = { $type } needs { $service }, which is not enabled in { $project }
+timeouts-unsupported = { $type } has no timeouts; 'timeouts' of resource '{ $resource }' is left out
+timeouts-unsupported-group = { $type } has no timeouts
+timeout-unknown = Unknown timeout '{ $operation }' for resource '{ $resource }' ({ $type }), expected { $expected }
+timeout-unknown-group = Unknown timeout '{ $operation }' ({ $type })
+timeout-invalid = Timeout '{ $operation }' of resource '{ $resource }' ({ $type }) is not a duration like 30m or 1h30m: '{ $value }'
+timeout-invalid-group = Timeout '{ $operation }' is not a duration ({ $type })
+implicit-reference = '{ $value }' at '{ $path }' is read as a reference; write '!ref { $value }', as plain strings stay strings with implicit_references = "off"
+implicit-reference-group = Plain string read as a reference (implicit_references)
+authoritative-iam-binding = { $type } at '{ $path }' is authoritative: members granted its roles outside the YAML lose them on apply
+authoritative-iam-policy = { $type } at '{ $path }' is authoritative: every grant of the scope that is not in the YAML is removed on apply
+authoritative-iam-group = Authoritative IAM removes grants made outside the YAML
+provider-labels-routed = default_labels with mode provider of '{ $path }' are not applied: its resources use the provider { $provider } of the provider-alias, which does not get them
+provider-labels-routed-group = Provider default labels without a provider of their own
+default-labels-spelling = 'default-labels' is not a key; labels for the providers are written as 'default_labels' with 'mode: provider' and 'labels:'
+
+## Errors in the YAML model
+transpile-failed = Transpilation failed with { $count } error(s)
+iam-mode-invalid = iam-mode must be member, binding or policy
+secret-in-variables = !secret cannot be used in variables; use it where the value is needed
+folder-parent-invalid = Folder parent '{ $parent }' is neither folders/<id> nor organizations/<id>
+folder-parent-type = Folder parent must be folders/<id>, organizations/<id> or a !ref
+folder-parent-missing = Folder has no parent folder or organization
+ou-parent-type = Organizational unit parent must be an ID (r-..., ou-...) or a !ref
+ou-parent-missing = Organizational unit has no parent unit or organization root
+policy-id-invalid = A policy must be a policy ID (p-...) or a !ref
+provider-alias-unknown = provider-alias '{ $provider }' names no provider declared under providers: (expected <provider>.<alias>)
+org-policy-name-missing = Mandatory 'name' attribute missing for google_org_policy_policy
+org-policy-parent-missing = No 'parent' given and no organization, folder or project in scope
+instance-reference-outside-loop = '{ $reference }' can only be used in a resource with 'for_each' or 'count'
+project-several-parents = Project has more than one parent ({ $parents }); keep one
+ref-needs-string = !ref needs a string
+project-folder-invalid = Project 'folder' must be folders/<id> or !ref <folder key>
+project-org-without-id = 'org: true' needs customer-organization-id
+project-org-invalid = Project 'org' must be true or an organization ID
+enabled-invalid = 'enabled' must be true or false; use 'when: !expr ...' for conditions
+when-invalid = 'when' must be true, false or an expression like !expr var.env == "prod"
+when-with-count = 'when' cannot be combined with 'count' or 'for_each'
+module-not-mapping = Module must be a mapping with at least 'source'
+module-source-missing = Mandatory 'source' attribute missing for module
+local-name-invalid = '{ $name }' is not a valid local name
+output-name-invalid = '{ $name }' is not a valid output name; use letters, digits, '_' and '-', starting with a letter or '_'
+output-value-missing = Mandatory 'value' attribute missing for output
+output-description-invalid = Output 'description' must be a string
+output-sensitive-invalid = Output 'sensitive' must be true or false
+output-attribute-unknown = Unknown output attribute '{ $attribute }' (expected value, description, sensitive or depends_on)
+resource-label-invalid = '{ $label }' is no valid resource label; use letters, digits, '_' and '-', starting with a letter or '_'
+address-duplicate = { $address } is already generated from '{ $previous }'; rename one of the two entries
+depends-on-invalid = 'depends_on' must be a list of resource keys or addresses
+depends-on-no-match = 'depends_on' entry '{ $entry }' matches no resource key or address
+depends-on-ambiguous = 'depends_on' entry '{ $entry }' matches { $matches }; prefix it with the key of its folder or project
+ref-no-match = '!ref { $reference }' matches no resource key or address
+ref-ambiguous = '!ref { $reference }' matches { $matches }; prefix it with the key of its folder or project
+ref-needs-instance = '!ref { $reference }' refers to { $address }, which has count or for_each (e.g. from `when:`); add the instance after its key, like [0] or [each.key]
+labels-not-mergeable = default labels can only be merged into a `labels:` mapping or expression
+cex-unsupported = { $type } cannot be exploded; CEX_ supports *_iam_member and { $supported }
+
+## Configuration checks
+org-id-missing = 'customer-organization-id' is missing
+org-id-not-numeric = 'customer-organization-id' must be the numeric organization ID, got '{ $value }'
+deployment-mode-invalid = 'deployment-mode' must be 'local' or 'cloud', got '{ $value }'
+cloud-without-backend = 'deployment-mode' is 'cloud' but no 'gcs' backend is defined
+backend-without-bucket = the 'gcs' backend has no 'bucket'
+infra-project-undeclared = infra project '{ $project }' is not declared as a project in the YAML
+group-member-undeclared = group member '{ $member }' does not reference a declared cloud_identity_group
+iam-lockout-binding = the authoritative binding of { $role } on { $scope } leaves out the IaC service account '{ $member }'; applying it takes the role away from Terraform itself
+iam-lockout-policy = the authoritative IAM policy of { $scope } grants the IaC service account '{ $member }' no IAM admin role; applying it locks Terraform out
+
+## IAM members
+member-outside-domains = IAM member '{ $member }' is outside the allowed domains [{ $domains }]
+member-invalid-principal = Invalid workload/workforce identity principal '{ $member }'
+member-no-type = IAM member '{ $member }' has no principal type (expected user:, group:, serviceAccount:, domain:, principal:// or principalSet://)
+member-unknown-type = IAM member '{ $member }' has unknown principal type '{ $kind }:' (expected user:, group:, serviceAccount:, domain:, principal:// or principalSet://)
+member-invalid-email = IAM member '{ $member }' is not a valid email address
+member-invalid-service-account = IAM member '{ $member }' is not a valid service account email
+member-invalid-domain = IAM member '{ $member }' is not a valid domain
+member-missing-project = IAM member '{ $member }' is missing the project ID
+
+## Naming conventions
+naming-mismatch = { $attribute } '{ $value }' of { $type } does not match naming pattern '{ $pattern }'
+naming-missing-placeholder = Cannot generate { $attribute } for { $type }: placeholder '{"{"}{ $placeholder }{"}"}' has no value
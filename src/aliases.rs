@@ -0,0 +1,34 @@
+use std::collections::BTreeMap;
+
+/// Built-in short YAML keys for resource types, e.g. `bucket:` instead of `google_storage_bucket:`.
+pub const DEFAULT_RESOURCE_ALIASES: &[(&str, &str)] = &[
+    ("bucket", "google_storage_bucket"),
+    ("sa", "google_service_account"),
+    ("repo", "google_artifact_registry_repository"),
+    ("topic", "google_pubsub_topic"),
+    ("subscription", "google_pubsub_subscription"),
+    ("dataset", "google_bigquery_dataset"),
+    ("network", "google_compute_network"),
+    ("subnet", "google_compute_subnetwork"),
+];
+
+/// The built-in aliases with `custom` (the `[resource_aliases]` table of config.toml) on top;
+/// an empty target removes a built-in alias.
+pub fn resource_aliases(custom: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    let mut aliases: BTreeMap<String, String> = DEFAULT_RESOURCE_ALIASES.iter()
+        .map(|(alias, tf_type)| (alias.to_string(), tf_type.to_string()))
+        .collect();
+    for (alias, tf_type) in custom {
+        if tf_type.is_empty() {
+            aliases.remove(alias);
+        } else {
+            aliases.insert(alias.clone(), tf_type.clone());
+        }
+    }
+    aliases
+}
+
+/// The resource type a YAML key stands for: the target of its alias, or the key itself.
+pub fn resolve<'a>(aliases: &'a BTreeMap<String, String>, key: &'a str) -> &'a str {
+    aliases.get(key).map(String::as_str).unwrap_or(key)
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use google_cloud_auth::credentials::Builder;
+use crate::manifest::STATE_DIR;
+
+/// `[audit]` table of config.toml. The local log is always written.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AuditConfig {
+    /// GCS bucket receiving a copy of every record (one object per record)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gcs_bucket: Option<String>,
+    /// Object name prefix inside the bucket, e.g. `cfg2hcl-audit/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gcs_prefix: Option<String>,
+}
+
+impl AuditConfig {
+    pub fn is_empty(&self) -> bool {
+        self.gcs_bucket.is_none() && self.gcs_prefix.is_none()
+    }
+}
+
+/// A state-mutating command run by cfg2hcl. Stored as one JSON line in `.cfg2hcl/audit.log`.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord {
+    /// RFC 3339, UTC
+    pub timestamp: String,
+    pub user: String,
+    pub host: String,
+    pub command: String,
+    pub working_dir: PathBuf,
+    /// State addresses the command touched
+    pub addresses: Vec<String>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub cfg2hcl_version: String,
+}
+
+impl AuditRecord {
+    pub fn new(command: &[&str], working_dir: &Path, addresses: Vec<String>, success: bool) -> Self {
+        let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "unknown".to_string());
+        let host = std::env::var("HOSTNAME").ok()
+            .or_else(|| fs::read_to_string("/etc/hostname").ok().map(|h| h.trim().to_string()))
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            timestamp: rfc3339_utc(now),
+            user,
+            host,
+            command: command.join(" "),
+            working_dir: working_dir.to_path_buf(),
+            addresses,
+            success,
+            note: None,
+            cfg2hcl_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+}
+
+pub fn log_path(config_dir: &Path) -> PathBuf {
+    config_dir.join(STATE_DIR).join("audit.log")
+}
+
+/// Appends the record to `.cfg2hcl/audit.log` and, if configured, uploads a copy to GCS.
+/// Only a failing local write is an error; the state change has already happened by then.
+pub async fn record(config_dir: &Path, config: &AuditConfig, record: &AuditRecord) -> Result<(), Box<dyn std::error::Error>> {
+    let line = serde_json::to_string(record)?;
+    let path = log_path(config_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&path)
+        .map_err(|e| format!("Failed to open audit log '{}': {}", path.display(), e))?;
+    writeln!(file, "{}", line)
+        .map_err(|e| format!("Failed to write audit log '{}': {}", path.display(), e))?;
+
+    if let Some(bucket) = &config.gcs_bucket {
+        if let Err(e) = upload(bucket, config.gcs_prefix.as_deref().unwrap_or(""), record, line).await {
+            eprintln!("Warning: Audit record not copied to gs://{}: {}", bucket, e);
+        }
+    }
+    Ok(())
+}
+
+async fn upload(bucket: &str, prefix: &str, record: &AuditRecord, line: String) -> Result<(), Box<dyn std::error::Error>> {
+    let credentials = Builder::default()
+        .with_scopes(["https://www.googleapis.com/auth/devstorage.read_write"])
+        .build_access_token_credentials()?;
+    let token = credentials.access_token().await?;
+
+    // Objects are immutable, so every record gets its own, sortable by time
+    let object = format!("{}{}-{}-{}.json", prefix, record.timestamp, record.user, std::process::id());
+    let url = format!("{}/upload/storage/v1/b/{}/o", crate::endpoints::base("storage"), bucket);
+    let res = reqwest::Client::new()
+        .post(&url)
+        .query(&[("uploadType", "media"), ("name", object.as_str())])
+        .bearer_auth(&token.token)
+        .header("Content-Type", "application/json")
+        .body(line)
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        return Err(format!("{} {}", res.status(), res.text().await.unwrap_or_default()).into());
+    }
+    Ok(())
+}
+
+/// Formats Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`.
+fn rfc3339_utc(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use git2::{Blame, Repository};
+use serde::Serialize;
+use crate::provenance::{Provenance, Source};
+
+/// A resource block of a generated file with the YAML entry and commit it comes from.
+#[derive(Debug, Serialize)]
+pub struct Annotation {
+    /// 1-based line of the block in the HCL file
+    pub line: usize,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// Newest commit among the lines of the YAML entry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<LastCommit>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LastCommit {
+    /// Short ID, `uncommitted` for lines changed in the working tree
+    pub commit: String,
+    pub date: String,
+    pub author: String,
+    pub summary: String,
+}
+
+/// A YAML file's working tree content with its `git blame` at HEAD, if it is in a repository.
+type Blamed<'r> = Option<(String, &'r Repository, Blame<'r>)>;
+
+/// Annotates the resource blocks of a generated HCL file (optionally only the one containing
+/// `line`) with their YAML entry from the provenance and the last commit that touched that entry.
+pub fn blame(hcl_file: &Path, provenance: &Provenance, line: Option<usize>) -> Result<Vec<Annotation>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(hcl_file)
+        .map_err(|e| format!("Failed to read '{}': {}", hcl_file.display(), e))?;
+    let blocks = crate::provenance::top_level_blocks(&content);
+    let blocks: Vec<(usize, String)> = match line {
+        Some(line) => blocks.into_iter().take_while(|(start, _)| *start <= line).last()
+            .and_then(|(start, address)| Some((start, address?)))
+            .into_iter().collect(),
+        None => blocks.into_iter().filter_map(|(start, address)| Some((start, address?))).collect(),
+    };
+
+    // Repositories and blames are kept per YAML file, most entries share a few files
+    let mut repos: HashMap<PathBuf, Option<Repository>> = HashMap::new();
+    let sources: Vec<Option<Source>> = blocks.iter().map(|(_, address)| provenance.resources.get(address).cloned()).collect();
+    for source in sources.iter().flatten() {
+        if let Some(file) = &source.file {
+            repos.entry(file.clone()).or_insert_with(|| {
+                Repository::discover(file.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."))).ok()
+            });
+        }
+    }
+    let mut blamed: HashMap<PathBuf, Blamed> = HashMap::new();
+    for (file, repo) in &repos {
+        blamed.insert(file.clone(), repo.as_ref().and_then(|repo| blame_file(repo, file)));
+    }
+
+    Ok(blocks.into_iter().zip(sources).map(|((line, address), source)| {
+        let commit = source.as_ref().and_then(|s| {
+            let (content, repo, blame) = blamed.get(s.file.as_ref()?)?.as_ref()?;
+            last_commit(repo, blame, content, s.line?)
+        });
+        Annotation { line, address, source, commit }
+    }).collect())
+}
+
+fn blame_file<'r>(repo: &'r Repository, file: &Path) -> Blamed<'r> {
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    let relative = file.canonicalize().ok()?.strip_prefix(&workdir).ok()?.to_path_buf();
+    let content = fs::read_to_string(file).ok()?;
+    Some((content, repo, repo.blame_file(&relative, None).ok()?))
+}
+
+/// Newest commit among the lines of the YAML entry starting at (1-based) `line`: the entry line
+/// and everything indented below it.
+fn last_commit(repo: &Repository, head_blame: &Blame, content: &str, line: usize) -> Option<LastCommit> {
+    // Blame the working tree version, so that line numbers match the provenance
+    let blame = head_blame.blame_buffer(content.as_bytes()).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let indent = |text: &str| text.len() - text.trim_start().len();
+    let entry_indent = indent(lines.get(line - 1)?);
+    let end = lines.iter().enumerate().skip(line)
+        .find(|(_, text)| !text.trim().is_empty() && !text.trim_start().starts_with('#') && indent(text) <= entry_indent)
+        .map_or(lines.len(), |(idx, _)| idx);
+
+    let mut newest: Option<(i64, LastCommit)> = None;
+    for n in line..=end {
+        let Some(hunk) = blame.get_line(n) else { continue };
+        let id = hunk.final_commit_id();
+        if id.is_zero() {
+            return Some(LastCommit {
+                commit: "uncommitted".to_string(),
+                date: String::new(),
+                author: "Not Committed Yet".to_string(),
+                summary: String::new(),
+            });
+        }
+        let Ok(commit) = repo.find_commit(id) else { continue };
+        let time = commit.time().seconds();
+        if newest.as_ref().is_some_and(|(t, _)| *t >= time) {
+            continue;
+        }
+        newest = Some((time, LastCommit {
+            commit: commit.as_object().short_id().ok()?.as_str().unwrap_or_default().to_string(),
+            date: crate::history::date(time),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+        }));
+    }
+    newest.map(|(_, commit)| commit)
+}
+
+/// Prints each block with its YAML entry and last commit.
+pub fn print_annotations(annotations: &[Annotation]) {
+    for annotation in annotations {
+        println!("{} {}", annotation.line, annotation.address);
+        match &annotation.source {
+            Some(source) => {
+                println!("    from {}", source.describe());
+                if !source.owners.is_empty() {
+                    println!("    owned by {}", source.owners.join(" "));
+                }
+            }
+            None => println!("    not in the provenance of the last transpile"),
+        }
+        if let Some(commit) = &annotation.commit {
+            if commit.date.is_empty() {
+                println!("    changed in the working tree");
+            } else {
+                println!("    last changed in {} ({}, {}): {}", commit.commit, commit.date, commit.author, commit.summary);
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@ pub async fn bootstrap(
     config_file: PathBuf,
     dry_run: bool,
     runtime_config: crate::ToolConfig,
+    config_dir: PathBuf,
     cli_config: Option<PathBuf>,
     cli_validation: Option<String>,
     cli_verbose: bool,
@@ -18,7 +19,7 @@ pub async fn bootstrap(
     let mut final_proj_id = None;
     let mut final_bucket = None;
 
-    println!("Loading configuration from {}...", config_file.display());
+    status!("Loading configuration from {}...", config_file.display());
     let content = fs::read_to_string(&config_file)?;
     let yaml: Value = serde_yaml::from_str(&content)?;
 
@@ -83,10 +84,10 @@ pub async fn bootstrap(
         return Ok(());
     }
 
-    println!("Starting bootstrap process...");
+    status!("Starting bootstrap process...");
 
     // 1. Get Authentication Token
-    println!("Authenticating using Application Default Credentials...");
+    status!("Authenticating using Application Default Credentials...");
     let scopes = ["https://www.googleapis.com/auth/cloud-platform"];
     let credentials = Builder::default()
         .with_scopes(scopes)
@@ -116,10 +117,10 @@ pub async fn bootstrap(
         }
 
         if let Some(admin_user) = first_admin_user {
-            println!("Ensuring {} has roles/resourcemanager.folderAdmin on {}...", admin_user, parent);
+            status!("Ensuring {} has roles/resourcemanager.folderAdmin on {}...", admin_user, parent);
 
             // Get current IAM policy
-            let policy_url = format!("https://cloudresourcemanager.googleapis.com/v3/{}:getIamPolicy", parent);
+            let policy_url = format!("{}/v3/{}:getIamPolicy", crate::endpoints::base("cloudresourcemanager"), parent);
             let res = client.post(&policy_url)
                 .bearer_auth(&token.token)
                 .json(&serde_json::json!({}))
@@ -147,7 +148,7 @@ pub async fn bootstrap(
                              } else {
                                  // Already exists
                                  found = true;
-                                 println!("User already has the role.");
+                                 status!("User already has the role.");
                              }
                         }
                     }
@@ -177,7 +178,7 @@ pub async fn bootstrap(
 
                 if !found || true { // Force update to be sure, or improve logic.
                     // For this snippet, let's just write it back.
-                    let set_policy_url = format!("https://cloudresourcemanager.googleapis.com/v3/{}:setIamPolicy", parent);
+                    let set_policy_url = format!("{}/v3/{}:setIamPolicy", crate::endpoints::base("cloudresourcemanager"), parent);
                     let res = client.post(&set_policy_url)
                         .bearer_auth(&token.token)
                         .json(&serde_json::json!({ "policy": policy }))
@@ -185,7 +186,7 @@ pub async fn bootstrap(
                         .await?;
 
                     if res.status().is_success() {
-                         println!("Successfully updated IAM policy.");
+                         status!("Successfully updated IAM policy.");
                     } else {
                          let err = res.text().await?;
                          println!("Warning: Failed to set IAM policy: {}", err);
@@ -209,10 +210,10 @@ pub async fn bootstrap(
     let mut current_parent = parent.clone();
 
     if let Some(folder_display_name) = infra_folder_name {
-        println!("Checking for existing Infrastructure Folder: {}...", folder_display_name);
+        status!("Checking for existing Infrastructure Folder: {}...", folder_display_name);
 
         // 2a. Search for folder by display name in the parent
-        let search_url = "https://cloudresourcemanager.googleapis.com/v3/folders";
+        let search_url = crate::endpoints::url("cloudresourcemanager", "v3/folders");
         let res = client.get(search_url)
             .query(&[("parent", &parent)])
             .bearer_auth(&token.token)
@@ -236,11 +237,11 @@ pub async fn bootstrap(
 
         if let Some(folder_id) = resolved_folder_id {
             current_parent = folder_id;
-            println!("Found existing folder: {}.", current_parent);
+            status!("Found existing folder: {}.", current_parent);
         } else {
             // 2b. Not found, proceed with creation
-            println!("Creating Infrastructure Folder: {}...", folder_display_name);
-            let url = "https://cloudresourcemanager.googleapis.com/v3/folders";
+            status!("Creating Infrastructure Folder: {}...", folder_display_name);
+            let url = crate::endpoints::url("cloudresourcemanager", "v3/folders");
             let body = serde_json::json!({
                 "displayName": folder_display_name,
                 "parent": parent
@@ -255,10 +256,10 @@ pub async fn bootstrap(
             if res.status().is_success() {
                 let info: serde_json::Value = res.json().await?;
                 if let Some(op_name) = info.get("name").and_then(|v| v.as_str()) {
-                    println!("Folder creation in progress ({})...", op_name);
+                    status!("Folder creation in progress ({})...", op_name);
                     loop {
                         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                        let poll_res = client.get(format!("https://cloudresourcemanager.googleapis.com/v3/{}", op_name))
+                        let poll_res = client.get(format!("{}/v3/{}", crate::endpoints::base("cloudresourcemanager"), op_name))
                             .bearer_auth(&token.token)
                             .send()
                             .await?;
@@ -267,7 +268,7 @@ pub async fn bootstrap(
                             if let Some(response) = op_status.get("response") {
                                 if let Some(name) = response.get("name").and_then(|v| v.as_str()) {
                                     current_parent = name.to_string();
-                                    println!("Successfully created folder: {}.", current_parent);
+                                    status!("Successfully created folder: {}.", current_parent);
                                     break;
                                 }
                             }
@@ -276,7 +277,7 @@ pub async fn bootstrap(
                                 break;
                             }
                         }
-                        println!("Waiting for folder creation...");
+                        status!("Waiting for folder creation...");
                     }
                 }
             } else {
@@ -287,8 +288,8 @@ pub async fn bootstrap(
     }
 
     // 3. Create Project Shell
-    println!("Creating Project: {}...", project_id);
-    let url = "https://cloudresourcemanager.googleapis.com/v3/projects";
+    status!("Creating Project: {}...", project_id);
+    let url = crate::endpoints::url("cloudresourcemanager", "v3/projects");
     let body = serde_json::json!({
         "projectId": project_id,
         "displayName": project_id,
@@ -304,31 +305,31 @@ pub async fn bootstrap(
     if res.status().is_success() {
         let info: serde_json::Value = res.json().await?;
         if let Some(op_name) = info.get("name").and_then(|v| v.as_str()) {
-            println!("Project creation in progress ({})...", op_name);
+            status!("Project creation in progress ({})...", op_name);
             loop {
                 tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                let poll_res = client.get(format!("https://cloudresourcemanager.googleapis.com/v3/{}", op_name))
+                let poll_res = client.get(format!("{}/v3/{}", crate::endpoints::base("cloudresourcemanager"), op_name))
                     .bearer_auth(&token.token)
                     .send()
                     .await?;
                 let op_status: serde_json::Value = poll_res.json().await?;
                 if op_status.get("done").and_then(|v| v.as_bool()).unwrap_or(false) {
-                    println!("Project shell created.");
+                    status!("Project shell created.");
                     break;
                 }
-                println!("Waiting for project creation...");
+                status!("Waiting for project creation...");
             }
         }
     } else if res.status().as_u16() == 409 {
-        println!("Project already exists, skipping creation.");
+        status!("Project already exists, skipping creation.");
     } else {
         let err = res.text().await?;
         println!("Warning: Failed to create project: {}", err);
     }
 
     // 4. Link Billing Account
-    println!("Linking Billing Account: {}...", bid);
-    let url = format!("https://cloudbilling.googleapis.com/v1/projects/{}/billingInfo", project_id);
+    status!("Linking Billing Account: {}...", bid);
+    let url = format!("{}/v1/projects/{}/billingInfo", crate::endpoints::base("cloudbilling"), project_id);
     let body = serde_json::json!({
         "billingAccountName": format!("billingAccounts/{}", bid)
     });
@@ -340,7 +341,7 @@ pub async fn bootstrap(
         .await?;
 
     if res.status().is_success() {
-        println!("Successfully linked billing account.");
+        status!("Successfully linked billing account.");
     } else {
         let err = res.text().await?;
         println!("Warning: Failed to link billing: {}", err);
@@ -358,10 +359,10 @@ pub async fn bootstrap(
     ];
 
     for service in core_services {
-        println!("Enabling core service: {}...", service);
+        status!("Enabling core service: {}...", service);
         let url = format!(
-            "https://serviceusage.googleapis.com/v1/projects/{}/services/{}:enable",
-            project_id, service
+            "{}/v1/projects/{}/services/{}:enable",
+            crate::endpoints::base("serviceusage"), project_id, service
         );
 
         let res = client.post(&url)
@@ -371,7 +372,7 @@ pub async fn bootstrap(
             .await?;
 
         if res.status().is_success() {
-            println!("Successfully enabled {}.", service);
+            status!("Successfully enabled {}.", service);
         } else {
             let err_body = res.text().await?;
             println!("Warning: Failed to enable {}: {}", service, err_body);
@@ -379,44 +380,19 @@ pub async fn bootstrap(
     }
 
     // 6. Create GCS State Bucket
-    println!("Creating GCS State Bucket: {}...", bucket_name);
-    let url = format!("https://storage.googleapis.com/storage/v1/b?project={}", project_id);
-    let body = serde_json::json!({
-        "name": bucket_name,
-        "location": r,
-        "iamConfiguration": {
-            "uniformBucketLevelAccess": {
-                "enabled": true
-            }
-        },
-        "versioning": {
-            "enabled": true
-        }
-    });
-
-    let res = client.post(&url)
-        .bearer_auth(&token.token)
-        .json(&body)
-        .send()
-        .await?;
-
-    if res.status().is_success() {
-        println!("Successfully created state bucket.");
-    } else if res.status().as_u16() == 409 {
-        println!("Bucket already exists, skipping creation.");
-    } else {
-        let err = res.text().await?;
-        println!("Warning: Failed to create bucket: {}", err);
+    status!("Creating GCS State Bucket: {}...", bucket_name);
+    if let Err(e) = create_state_bucket(&client, &token.token, &project_id, &bucket_name, &r).await {
+        println!("Warning: Failed to create bucket: {}", e);
     }
 
-    println!("Bootstrap completed successfully.");
-    println!("Core Infrastructure (Folder, Project, Billing, Foundation APIs, State Bucket) is now ready.");
+    status!("Bootstrap completed successfully.");
+    status!("Core Infrastructure (Folder, Project, Billing, Foundation APIs, State Bucket) is now ready.");
 
     // 7. Automatic setup: Transpile -> Init -> Import
-    println!("Running automatic setup...");
+    status!("Running automatic setup...");
 
     // 7a. Transpile
-    println!("Transpiling YAML to HCL...");
+    status!("Transpiling YAML to HCL...");
     let exe = std::env::current_exe()?;
     let mut cmd = std::process::Command::new(&exe);
 
@@ -444,28 +420,29 @@ pub async fn bootstrap(
     // 7b. Init
     let target_hcl_dir = std::path::Path::new(&runtime_config.hcl_dir);
     if target_hcl_dir.exists() && target_hcl_dir.is_dir() {
-        println!("Initializing OpenTofu/Terraform in {}...", target_hcl_dir.display());
+        status!("Initializing OpenTofu/Terraform in {}...", target_hcl_dir.display());
         let status = std::process::Command::new(&runtime_config.tf_tool)
             .current_dir(target_hcl_dir)
             .arg("init")
+            .args(crate::output::backend_config_args(target_hcl_dir))
             .status()?;
 
         if !status.success() {
              return Err(format!("{} init failed. Cannot proceed with imports.", runtime_config.tf_tool).into());
         }
 
-        println!("Detected existing HCL directory at {}. Running automatic imports...", target_hcl_dir.display());
+        status!("Detected existing HCL directory at {}. Running automatic imports...", target_hcl_dir.display());
 
         // Import Folder
         if current_parent.starts_with("folders/") {
-            run_import(&runtime_config.tf_tool, &target_hcl_dir, "google_folder.infra_folder", &current_parent);
+            run_import(&runtime_config, &config_dir, target_hcl_dir, "google_folder.infra_folder", &current_parent).await?;
         }
 
         // Import Project
-        run_import(&runtime_config.tf_tool, &target_hcl_dir, "google_project.infra", &project_id);
+        run_import(&runtime_config, &config_dir, target_hcl_dir, "google_project.infra", &project_id).await?;
 
         // Import Bucket
-        run_import(&runtime_config.tf_tool, &target_hcl_dir, "google_storage_bucket.state", &bucket_name);
+        run_import(&runtime_config, &config_dir, target_hcl_dir, "google_storage_bucket.state", &bucket_name).await?;
     } else {
         println!("Warning: HCL directory not found after transpilation. Skipping imports.");
     }
@@ -473,8 +450,42 @@ pub async fn bootstrap(
     Ok(())
 }
 
-fn run_import(tf_tool: &str, working_dir: &std::path::Path, resource_address: &str, resource_id: &str) {
-    println!("Importing {} (ID: {})...", resource_address, resource_id);
+/// Creates the versioned, uniform-access state bucket; an existing bucket is not an error.
+pub async fn create_state_bucket(client: &reqwest::Client, token: &str, project_id: &str, bucket_name: &str, location: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let url = format!("{}/storage/v1/b?project={}", crate::endpoints::base("storage"), project_id);
+    let body = serde_json::json!({
+        "name": bucket_name,
+        "location": location,
+        "iamConfiguration": {
+            "uniformBucketLevelAccess": {
+                "enabled": true
+            }
+        },
+        "versioning": {
+            "enabled": true
+        }
+    });
+
+    let res = client.post(&url)
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await?;
+
+    if res.status().is_success() {
+        status!("Successfully created state bucket.");
+    } else if res.status().as_u16() == 409 {
+        status!("Bucket already exists, skipping creation.");
+    } else {
+        return Err(res.text().await?.into());
+    }
+    Ok(())
+}
+
+/// Imports one resource; attempts that reach the state are recorded in the audit log.
+async fn run_import(runtime_config: &crate::ToolConfig, config_dir: &std::path::Path, working_dir: &std::path::Path, resource_address: &str, resource_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let tf_tool = runtime_config.tf_tool.as_str();
+    status!("Importing {} (ID: {})...", resource_address, resource_id);
     let output = std::process::Command::new(tf_tool)
         .current_dir(working_dir)
         .arg("import")
@@ -482,21 +493,27 @@ fn run_import(tf_tool: &str, working_dir: &std::path::Path, resource_address: &s
         .arg(resource_id)
         .output();
 
-    match output {
+    let success = match output {
         Ok(out) => {
             if out.status.success() {
                 println!("- {}: Successfully imported.", resource_address);
+                true
             } else {
                 let stderr = String::from_utf8_lossy(&out.stderr);
                 if stderr.contains("Resource already managed by OpenTofu") {
                     println!("- {}: Already managed by OpenTofu.", resource_address);
-                } else {
-                    println!("- {}: Import failed or skipped. (stderr: {})", resource_address, stderr.trim());
+                    return Ok(());
                 }
+                println!("- {}: Import failed or skipped. (stderr: {})", resource_address, stderr.trim());
+                false
             }
         }
         Err(e) => {
             println!("- {}: Failed to execute {} import: {}", resource_address, tf_tool, e);
+            return Ok(());
         }
-    }
+    };
+
+    let record = crate::audit::AuditRecord::new(&[tf_tool, "import", resource_address, resource_id], working_dir, vec![resource_address.to_string()], success);
+    crate::audit::record(config_dir, &runtime_config.audit, &record).await
 }
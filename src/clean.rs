@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::manifest::Manifest;
+
+/// Terraform working files that `clean` never removes, even if listed.
+fn is_protected(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    name == ".terraform"
+        || name == ".terraform.lock.hcl"
+        || name.ends_with(".tfstate")
+        || name.ends_with(".tfstate.backup")
+}
+
+/// Removes the files recorded as generated in the manifest. With `all`, also the schema cache
+/// (`schema_dir/*.json`) and work directories left over by schema generation.
+pub fn clean(config_dir: &Path, schema_dir: &Path, all: bool, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut manifest = Manifest::load(config_dir)?;
+
+    let mut targets: Vec<PathBuf> = manifest.generated.iter()
+        .map(|p| config_dir.join(p))
+        .filter(|p| p.exists())
+        .collect();
+
+    if all {
+        if let Ok(entries) = fs::read_dir(schema_dir) {
+            let mut schemas: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            schemas.sort();
+            targets.extend(schemas);
+        }
+        let mut work_dirs: Vec<PathBuf> = [config_dir, Path::new(".")].iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_dir() && p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(".temp_schema_gen_")))
+            .collect();
+        work_dirs.sort();
+        work_dirs.dedup_by(|a, b| fs::canonicalize(&*a).ok() == fs::canonicalize(&*b).ok());
+        targets.extend(work_dirs);
+    }
+
+    if targets.is_empty() {
+        println!("Nothing to clean.");
+        return Ok(());
+    }
+
+    for path in &targets {
+        if is_protected(path) {
+            println!("Skipping {} (Terraform state or working files)", path.display());
+            continue;
+        }
+        if dry_run {
+            println!("Would remove {}", path.display());
+            continue;
+        }
+        let result = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+        result.map_err(|e| format!("Failed to remove '{}': {}", path.display(), e))?;
+        println!("Removed {}", path.display());
+    }
+
+    if !dry_run {
+        manifest.generated.clear();
+        manifest.save(config_dir)?;
+    }
+    Ok(())
+}
@@ -19,12 +19,41 @@ pub struct Config {
     #[serde(alias = "google_billing_account_iam_member", skip_serializing_if = "Option::is_none")]
     pub billing_account_iam_member: Option<serde_yaml::Value>,
 
+    // Labels merged into every resource that supports labels, or with `mode: provider` applied by
+    // the generated google providers (inherited by folders/projects)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_labels: Option<DefaultLabels>,
+    // Named billing accounts (name -> billing account ID), selectable by name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billing_accounts: Option<HashMap<String, String>>,
+    // Attribute defaults inherited by all folders, projects and resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<Defaults>,
+
+    // Local values (name -> value), referenced as `local.<name>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locals: Option<HashMap<String, serde_yaml::Value>>,
+
+    // Terraform modules (name -> source, version, providers and inputs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module: Option<HashMap<String, serde_yaml::Value>>,
+
+    // Outputs (name -> value expression, or value, description and sensitive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<HashMap<String, serde_yaml::Value>>,
+
     // Hierarchical Resources
     #[serde(skip_serializing_if = "Option::is_none")]
     pub folder: Option<HashMap<String, Folder>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<HashMap<String, Project>>,
 
+    // AWS Organizations hierarchy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organizational_unit: Option<HashMap<String, OrganizationalUnit>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<HashMap<String, Account>>,
+
     // Catch-all for other top level fields
     #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
     pub extra: HashMap<String, serde_yaml::Value>,
@@ -37,8 +66,29 @@ pub struct Folder {
     #[serde(rename = "import-id-comment", skip_serializing_if = "Option::is_none")]
     pub import_id_comment: Option<String>,
     pub display_name: String,
+    /// Parent of a top-level folder (`folders/123`, `organizations/456` or a `!ref`) instead of
+    /// the organization; nested folders are created in the folder they are nested in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<serde_yaml::Value>,
+    /// Deletion protection for this folder and everything below it (inherited)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub parent: Option<String>,
+    pub protect: Option<bool>,
+    /// Labels merged into every resource below this folder, or with `mode: provider` into the
+    /// providers of the projects below it (inherited, narrowest wins)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_labels: Option<DefaultLabels>,
+    /// Attribute defaults for everything below this folder (override the inherited ones)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<Defaults>,
+    /// Team(s) owning this folder and everything below it (inherited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Owners>,
+    /// How the IAM members of this folder are generated (not inherited)
+    #[serde(rename = "iam-mode", skip_serializing_if = "Option::is_none")]
+    pub iam_mode: Option<IamMode>,
+    /// Provider of everything below this folder instead of the root one (inherited)
+    #[serde(rename = "provider-alias", skip_serializing_if = "Option::is_none")]
+    pub provider_alias: Option<ProviderAlias>,
     // Recursive folder structure
     #[serde(skip_serializing_if = "Option::is_none")]
     pub folder: Option<HashMap<String, Folder>>,
@@ -67,6 +117,22 @@ pub struct Project {
     pub tags: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deletion_policy: Option<String>,
+    /// Deletion protection for this project and its resources (inherited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protect: Option<bool>,
+    /// Labels merged into the project and its resources, or with `mode: provider` into the
+    /// project's provider (inherited, narrowest wins)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_labels: Option<DefaultLabels>,
+    /// Team(s) owning this project and its resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Owners>,
+    /// How the IAM members of this project are generated
+    #[serde(rename = "iam-mode", skip_serializing_if = "Option::is_none")]
+    pub iam_mode: Option<IamMode>,
+    /// Provider of the project and its resources instead of the generated `project_<key>` one
+    #[serde(rename = "provider-alias", skip_serializing_if = "Option::is_none")]
+    pub provider_alias: Option<ProviderAlias>,
 
     // Project specific explicit fields (lists)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -77,7 +143,340 @@ pub struct Project {
     pub extra: HashMap<String, serde_yaml::Value>,
 }
 
+/// `aws_organizations_organizational_unit`, the AWS counterpart of [`Folder`].
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct OrganizationalUnit {
+    #[serde(rename = "import-id", skip_serializing_if = "Option::is_none")]
+    pub import_id: Option<String>,
+    #[serde(rename = "import-id-comment", skip_serializing_if = "Option::is_none")]
+    pub import_id_comment: Option<String>,
+    pub name: String,
+    /// Parent of a top-level unit (`ou-...`, `r-...` or a `!ref`) instead of the organization
+    /// root; nested units are created in the unit they are nested in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<serde_yaml::Value>,
+    /// Deletion protection for this unit and everything below it (inherited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protect: Option<bool>,
+    /// Tags merged into every unit and account below this unit (inherited, narrowest wins)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_labels: Option<HashMap<String, String>>,
+    /// Team(s) owning this unit and everything below it (inherited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Owners>,
+    /// Service control policies (IDs or `!ref`s) attached to this unit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policies: Option<Vec<serde_yaml::Value>>,
+    // Recursive unit structure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organizational_unit: Option<HashMap<String, OrganizationalUnit>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<HashMap<String, Account>>,
+
+    // Catch-all for other attributes of the unit
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_yaml::Value>,
+}
+
+/// `aws_organizations_account`, the AWS counterpart of [`Project`].
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Account {
+    #[serde(rename = "import-id", skip_serializing_if = "Option::is_none")]
+    pub import_id: Option<String>,
+    #[serde(rename = "import-id-comment", skip_serializing_if = "Option::is_none")]
+    pub import_id_comment: Option<String>,
+    /// Account name, the YAML key if not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub email: String,
+    /// Region of the provider for the resources of this account, else that of `providers.aws`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Deletion protection for this account and its resources (inherited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protect: Option<bool>,
+    /// Tags merged into the account and its resources (inherited, narrowest wins)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_labels: Option<HashMap<String, String>>,
+    /// Team(s) owning this account and its resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<Owners>,
+    /// Service control policies (IDs or `!ref`s) attached to this account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policies: Option<Vec<serde_yaml::Value>>,
+
+    // Catch-all for account attributes and the resources in the account
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_yaml::Value>,
+}
+
+/// Keys of the mapping a tagged value (`!expr ...`, `!dynamic ...`) is stored as while the
+/// config is deserialized: serde cannot buffer tagged values for the `extra` catch-alls.
+const TAG_KEY: &str = "cfg2hcl:tag";
+const TAGGED_VALUE_KEY: &str = "cfg2hcl:value";
+
+impl Config {
+    /// Deserializes a config, keeping the tagged values left after tag resolution.
+    pub fn from_value(mut value: serde_yaml::Value) -> Result<Config, serde_path_to_error::Error<serde_yaml::Error>> {
+        encode_tags(&mut value);
+        let mut config: Config = serde_path_to_error::deserialize(value)?;
+        config.restore_tags();
+        Ok(config)
+    }
+
+    fn restore_tags(&mut self) {
+        for value in self.terraform.iter_mut().chain(self.billing_account_iam_member.iter_mut()) {
+            decode_tags(value);
+        }
+        let maps = [&mut self.providers, &mut self.org_policy_policy, &mut self.google_organization_policy, &mut self.locals, &mut self.module, &mut self.outputs];
+        for value in maps.into_iter().flatten().flat_map(|m| m.values_mut()).chain(self.extra.values_mut()) {
+            decode_tags(value);
+        }
+        for value in self.organization_iam_member.iter_mut().flat_map(|m| m.values_mut()).flatten() {
+            decode_tags(value);
+        }
+        for folder in self.folder.iter_mut().flat_map(|f| f.values_mut()) {
+            folder.restore_tags();
+        }
+        for project in self.project.iter_mut().flat_map(|p| p.values_mut()) {
+            project.restore_tags();
+        }
+        for unit in self.organizational_unit.iter_mut().flat_map(|u| u.values_mut()) {
+            unit.restore_tags();
+        }
+        for account in self.account.iter_mut().flat_map(|a| a.values_mut()) {
+            account.restore_tags();
+        }
+    }
+}
+
+impl Folder {
+    fn restore_tags(&mut self) {
+        self.extra.values_mut().chain(self.parent.iter_mut()).for_each(decode_tags);
+        for folder in self.folder.iter_mut().flat_map(|f| f.values_mut()) {
+            folder.restore_tags();
+        }
+        for project in self.project.iter_mut().flat_map(|p| p.values_mut()) {
+            project.restore_tags();
+        }
+    }
+}
+
+impl Project {
+    fn restore_tags(&mut self) {
+        self.extra.values_mut().chain(self.project_service.iter_mut().flatten()).for_each(decode_tags);
+    }
+}
+
+impl OrganizationalUnit {
+    fn restore_tags(&mut self) {
+        self.extra.values_mut().chain(self.parent.iter_mut()).chain(self.policies.iter_mut().flatten()).for_each(decode_tags);
+        for unit in self.organizational_unit.iter_mut().flat_map(|u| u.values_mut()) {
+            unit.restore_tags();
+        }
+        for account in self.account.iter_mut().flat_map(|a| a.values_mut()) {
+            account.restore_tags();
+        }
+    }
+}
+
+impl Account {
+    fn restore_tags(&mut self) {
+        self.extra.values_mut().chain(self.policies.iter_mut().flatten()).for_each(decode_tags);
+    }
+}
+
+fn encode_tags(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Tagged(tagged) => {
+            let tag = tagged.tag.to_string();
+            let mut inner = std::mem::take(&mut tagged.value);
+            encode_tags(&mut inner);
+            let mut map = serde_yaml::Mapping::new();
+            map.insert(TAG_KEY.into(), tag.into());
+            map.insert(TAGGED_VALUE_KEY.into(), inner);
+            *value = serde_yaml::Value::Mapping(map);
+        }
+        serde_yaml::Value::Mapping(map) => {
+            // Keys can be tagged too (`!ref google_project.infra.project_id: [...]`)
+            *map = std::mem::take(map).into_iter()
+                .map(|(mut k, mut v)| {
+                    encode_tags(&mut k);
+                    encode_tags(&mut v);
+                    (k, v)
+                })
+                .collect();
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(encode_tags),
+        _ => {}
+    }
+}
+
+fn decode_tags(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(map) if map.len() == 2 && map.get(TAG_KEY).is_some_and(|t| t.is_string()) && map.contains_key(TAGGED_VALUE_KEY) => {
+            let tag = map.get(TAG_KEY).and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            let mut inner = map.remove(TAGGED_VALUE_KEY).unwrap_or_default();
+            decode_tags(&mut inner);
+            *value = serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+                tag: serde_yaml::value::Tag::new(tag),
+                value: inner,
+            }));
+        }
+        serde_yaml::Value::Mapping(map) => {
+            *map = std::mem::take(map).into_iter()
+                .map(|(mut k, mut v)| {
+                    decode_tags(&mut k);
+                    decode_tags(&mut v);
+                    (k, v)
+                })
+                .collect();
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(decode_tags),
+        _ => {}
+    }
+}
+
+/// `owner:` of the root, a folder or a project: one owner or a list, in CODEOWNERS syntax
+/// (`@org/team`, `@user` or an email address).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum Owners {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Owners {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            Owners::One(owner) => vec![owner.clone()],
+            Owners::Many(owners) => owners.clone(),
+        }
+    }
+}
+
+/// `default_labels:` of the root, a folder or a project: a mapping of labels, or the labels under
+/// `labels:` with an explicit `mode:`.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(try_from = "HashMap<String, serde_yaml::Value>")]
+pub struct DefaultLabels {
+    pub mode: LabelMode,
+    pub labels: HashMap<String, String>,
+}
+
+impl DefaultLabels {
+    /// The labels, if they are applied in `mode`.
+    pub fn in_mode(&self, mode: LabelMode) -> Option<&HashMap<String, String>> {
+        (self.mode == mode).then_some(&self.labels)
+    }
+}
+
+impl TryFrom<HashMap<String, serde_yaml::Value>> for DefaultLabels {
+    type Error = String;
+
+    /// Once `mode:` is given, only `labels:` may stand next to it, so that labels written next to
+    /// `mode:` (or a misspelled mode) are reported instead of being taken for labels.
+    fn try_from(mut map: HashMap<String, serde_yaml::Value>) -> Result<Self, String> {
+        let labels = |value: serde_yaml::Value| serde_yaml::from_value::<HashMap<String, String>>(value).map_err(|e| e.to_string());
+        let Some(mode) = map.remove("mode") else {
+            if map.contains_key("labels") {
+                return Err("`labels:` of `default_labels:` needs a `mode:` (merge or provider)".to_string());
+            }
+            let map: serde_yaml::Mapping = map.into_iter().map(|(k, v)| (k.into(), v)).collect();
+            return Ok(DefaultLabels { mode: LabelMode::Merge, labels: labels(map.into())? });
+        };
+        let mode = serde_yaml::from_value(mode).map_err(|_| "`mode:` of `default_labels:` must be merge or provider".to_string())?;
+        if let Some(key) = map.keys().find(|k| *k != "labels") {
+            return Err(format!("unknown key `{}` in `default_labels:` with a `mode:`; the labels go under `labels:`", key));
+        }
+        Ok(DefaultLabels { mode, labels: map.remove("labels").map(labels).transpose()?.unwrap_or_default() })
+    }
+}
+
+/// `mode:` of `default_labels:`: where the labels of the scope are applied.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMode {
+    /// Merged into the `labels` of every resource below the scope whose schema has `labels`
+    #[default]
+    Merge,
+    /// Set as the `default_labels` of the generated google providers below the scope
+    Provider,
+}
+
+/// `defaults:` block of the root or a folder. Explicit values on a project/resource always win.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Defaults {
+    /// Billing account (ID or name from `billing_accounts`) for projects and billing IAM
+    /// (before the `billing-account-infra` variable)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billing_account: Option<String>,
+    /// `deletion_policy` for projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deletion_policy: Option<String>,
+    /// `auto_create_network` for projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_create_network: Option<bool>,
+    /// Labels, merged like `default_labels`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<HashMap<String, String>>,
+    /// Region for the per-project provider and resources with a `region` attribute
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+impl Defaults {
+    /// Returns these defaults with the values of a nested `defaults:` block layered on top.
+    pub fn overlay(&self, child: Option<&Defaults>) -> Defaults {
+        let Some(child) = child else { return self.clone() };
+        Defaults {
+            billing_account: child.billing_account.clone().or_else(|| self.billing_account.clone()),
+            deletion_policy: child.deletion_policy.clone().or_else(|| self.deletion_policy.clone()),
+            auto_create_network: child.auto_create_network.or(self.auto_create_network),
+            labels: match (&self.labels, &child.labels) {
+                (Some(parent), Some(own)) => {
+                    let mut labels = parent.clone();
+                    labels.extend(own.iter().map(|(k, v)| (k.clone(), v.clone())));
+                    Some(labels)
+                }
+                (parent, own) => own.clone().or_else(|| parent.clone()),
+            },
+            region: child.region.clone().or_else(|| self.region.clone()),
+        }
+    }
+}
+
+/// `iam-mode:` of the root, a folder or a project: how the `*_iam_member` entries of that scope
+/// (not the ones of the folders and projects below it) are generated.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IamMode {
+    /// One `google_*_iam_member` per grant, leaving other grants alone
+    #[default]
+    Member,
+    /// One `google_*_iam_binding` per role, authoritative for the role
+    Binding,
+    /// One `google_*_iam_policy` for the scope, authoritative for its whole policy
+    Policy,
+}
+
+/// `provider-alias:` of a folder or project: one provider (`google-beta.euteam`) for all resources
+/// below it, or one per resource type, with `default` for the types not listed.
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum ProviderAlias {
+    All(String),
+    ByType(HashMap<String, String>),
+}
+
+/// Values of `content_type` in the discovery config: the Cloud Asset API content types
+/// `discover-from-organization` lists assets with.
+pub const DISCOVERY_CONTENT_TYPES: &[&str] = &["RESOURCE", "IAM_POLICY"];
+
+/// An entry of `resource_types` in the discovery config. Unknown keys are rejected, so that a
+/// misspelled key (`contenttype:`) does not silently keep a type from being imported.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct DiscoveryResourceConfig {
     pub description: String,
     pub import: bool,
@@ -93,9 +492,44 @@ pub struct DiscoveryResourceConfig {
     pub derive_yaml_key_from: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deprecated: Option<bool>,
+    /// Command mapping the assets to YAML resources instead of the built-in mapping, see
+    /// `discovery_mappers.rs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapper: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct DiscoveryConfig {
     pub resource_types: HashMap<String, DiscoveryResourceConfig>,
 }
+
+impl DiscoveryConfig {
+    /// Parses a discovery config; errors name the resource type and key they are about.
+    pub fn from_yaml(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: Self = serde_yaml::from_str(content)?;
+        let mut errors: Vec<String> = config.resource_types.iter()
+            .filter_map(|(tf_type, resource)| {
+                let content_type = resource.content_type.as_deref()?;
+                (!DISCOVERY_CONTENT_TYPES.contains(&content_type)).then(|| format!(
+                    "resource_types.{}.content_type: '{}' is not one of {}", tf_type, content_type, DISCOVERY_CONTENT_TYPES.join(", ")))
+            })
+            .collect();
+        if !errors.is_empty() {
+            errors.sort();
+            return Err(errors.join("\n").into());
+        }
+        Ok(config)
+    }
+
+    /// Imported types `discover-from-organization` cannot list because `asset_type` or
+    /// `content_type` is missing, sorted.
+    pub fn incomplete_types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.resource_types.iter()
+            .filter(|(_, r)| r.import && (r.asset_type.is_none() || r.content_type.is_none()))
+            .map(|(tf_type, _)| tf_type.as_str())
+            .collect();
+        types.sort();
+        types
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::BTreeSet;
+use serde_yaml::{Mapping, Value};
+use crate::yaml_edit::mapping_key;
+
+/// Style `convert-iam` rewrites IAM entries to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// `google_*_iam_member`: a compact map from member to roles
+    Member,
+    /// `google_*_iam_binding`: one entry per role with its `members`
+    Binding,
+}
+
+impl Style {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "member" => Ok(Self::Member),
+            "binding" => Ok(Self::Binding),
+            _ => Err(format!("Unknown --to '{}' (expected member or binding)", name)),
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Member => "_iam_member",
+            Self::Binding => "_iam_binding",
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::Member => Self::Binding,
+            Self::Binding => Self::Member,
+        }
+    }
+}
+
+/// Rewrites the IAM sections of the other style in a YAML text to `to` (`convert-iam`), see
+/// [`crate::yaml_edit::rewrite_sections`]. Bindings are keyed `<scope>_<role>` after the YAML key
+/// of the folder or project they are in (`organization` at the top level). Billing account IAM
+/// is left as it is. Returns `None` if there is nothing to convert.
+pub fn convert(content: &str, to: Style, notes: &mut Vec<String>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let from = to.other().suffix();
+    let types: BTreeSet<&str> = content.lines()
+        .filter_map(|line| match mapping_key(line) {
+            Some((key, "")) if key.ends_with(from) && !key.contains("billing_account") && !key.starts_with("CEX_") => Some(key),
+            _ => None,
+        })
+        .collect();
+    if types.is_empty() {
+        return Ok(None);
+    }
+    let types: Vec<&str> = types.into_iter().collect();
+    let new_type = |tf_type: &str| format!("{}{}", tf_type.strip_suffix(from).unwrap_or(tf_type), to.suffix());
+    let mut dropped_imports = false;
+    let converted = match to {
+        Style::Binding => crate::yaml_edit::rewrite_sections(content, &types, |tf_type, scope, entries| {
+            Ok((new_type(tf_type), to_bindings(scope.unwrap_or("organization"), entries, &mut dropped_imports)?))
+        }, merge_bindings)?,
+        Style::Member => crate::yaml_edit::rewrite_sections(content, &types, |tf_type, _, entries| {
+            Ok((new_type(tf_type), to_members(entries, &mut dropped_imports)?))
+        }, merge_members)?,
+    };
+    if dropped_imports {
+        notes.push("import-id entries were dropped; the migration file imports the converted resources".to_string());
+    }
+    Ok(converted)
+}
+
+/// `member: [role, {role: ~, condition: {...}}]` -> `<scope>_<role>: {role, members, condition}`
+fn to_bindings(scope: &str, entries: &Mapping, dropped_imports: &mut bool) -> Result<Mapping, String> {
+    let mut bindings = Mapping::new();
+    for (member, roles) in entries {
+        let Some(member) = member.as_str() else { continue };
+        let Value::Sequence(roles) = roles else {
+            return Err(format!("the roles of '{}' are no list", member));
+        };
+        for role in roles {
+            let (role, condition) = match role {
+                Value::String(role) => (role.clone(), None),
+                Value::Mapping(grant) => {
+                    *dropped_imports |= grant.contains_key("import-id");
+                    let role = grant.keys().filter_map(Value::as_str).find(|k| *k != "condition" && *k != "import-id");
+                    let Some(role) = role else { continue };
+                    (role.to_string(), grant.get("condition").cloned())
+                }
+                _ => return Err(format!("a role of '{}' is neither a string nor a mapping", member)),
+            };
+            let role_name = role.rsplit('/').next().unwrap_or(&role);
+            let mut key = format!("{}_{}", scope, role_name);
+            if let Some(condition) = &condition {
+                key.push('_');
+                key.push_str(condition.get("title").and_then(Value::as_str).unwrap_or("condition"));
+            }
+            let key = Value::String(crate::transpiler::resource_label(&key));
+            let binding = bindings.entry(key).or_insert_with(|| {
+                let mut binding = Mapping::new();
+                binding.insert("role".into(), role.clone().into());
+                binding.insert("members".into(), Value::Sequence(Vec::new()));
+                if let Some(condition) = &condition {
+                    binding.insert("condition".into(), condition.clone());
+                }
+                Value::Mapping(binding)
+            });
+            if binding.get("condition") != condition.as_ref() {
+                return Err(format!("'{}' has grants of {} with different conditions of the same title", member, role));
+            }
+            if let Some(Value::Sequence(members)) = binding.get_mut("members") {
+                if !members.iter().any(|m| m.as_str() == Some(member)) {
+                    members.push(member.into());
+                }
+            }
+        }
+    }
+    Ok(bindings)
+}
+
+/// `<key>: {role, members, condition}` -> `member: [role, {role: ~, condition: {...}}]`
+fn to_members(entries: &Mapping, dropped_imports: &mut bool) -> Result<Mapping, String> {
+    let mut members_map = Mapping::new();
+    for (key, binding) in entries {
+        let key = key.as_str().unwrap_or_default();
+        let Value::Mapping(binding) = binding else {
+            return Err(format!("'{}' is not a mapping", key));
+        };
+        if let Some(other) = binding.keys().filter_map(Value::as_str).find(|k| !["role", "members", "condition", "import-id", "import-id-comment"].contains(k)) {
+            return Err(format!("'{}' has '{}', which the member style cannot express; convert it by hand", key, other));
+        }
+        *dropped_imports |= binding.contains_key("import-id");
+        let Some(role) = binding.get("role").and_then(Value::as_str) else {
+            return Err(format!("'{}' has no role", key));
+        };
+        let Some(Value::Sequence(members)) = binding.get("members") else {
+            return Err(format!("'{}' has no list of members", key));
+        };
+        let grant = match binding.get("condition") {
+            Some(condition) => {
+                let mut grant = Mapping::new();
+                grant.insert(role.into(), Value::Null);
+                grant.insert("condition".into(), condition.clone());
+                Value::Mapping(grant)
+            }
+            None => Value::String(role.to_string()),
+        };
+        for member in members {
+            let Some(member) = member.as_str() else {
+                return Err(format!("'{}' has a member that is not a string", key));
+            };
+            let roles = members_map.entry(member.into()).or_insert_with(|| Value::Sequence(Vec::new()));
+            if let Value::Sequence(roles) = roles {
+                if !roles.contains(&grant) {
+                    roles.push(grant.clone());
+                }
+            }
+        }
+    }
+    Ok(members_map)
+}
+
+/// A binding of the same key next to the converted ones: its members are combined.
+fn merge_bindings(key: &str, existing: &mut Value, new: Value) -> Result<(), String> {
+    if existing.get("role") != new.get("role") || existing.get("condition") != new.get("condition") {
+        return Err(format!("'{}' exists already for another role or condition; rename one of them", key));
+    }
+    if let (Some(Value::Sequence(members)), Some(Value::Sequence(added))) = (existing.get_mut("members"), new.get("members")) {
+        for member in added {
+            if !members.contains(member) {
+                members.push(member.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A member with roles next to the converted ones: its roles are combined.
+fn merge_members(key: &str, existing: &mut Value, new: Value) -> Result<(), String> {
+    let (Value::Sequence(roles), Value::Sequence(added)) = (existing, new) else {
+        return Err(format!("the roles of '{}' are no list", key));
+    };
+    for role in added {
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+    }
+    Ok(())
+}
+
+/// The state migration of a conversion, for the HCL directory: a `removed` block (with
+/// `destroy = false`) for every IAM resource generated before but not after, and an `import`
+/// block for every one generated after but not before, with the import ID of its type
+/// (`<parent> <role> <member>` or `<parent> <role>`, and the title of its condition). Returns the
+/// file and the number of removed and imported resources.
+pub fn migration(old_main_tf: &str, new_main_tf: &str) -> Result<(String, usize, usize), Box<dyn std::error::Error>> {
+    let iam_blocks = |main_tf: &str| -> Result<Vec<hcl::Block>, hcl::Error> {
+        let body: hcl::Body = hcl::from_str(main_tf)?;
+        Ok(body.into_blocks()
+            .filter(|b| b.identifier() == "resource" && b.labels().first().is_some_and(|t| t.as_str().ends_with("_iam_member") || t.as_str().ends_with("_iam_binding")))
+            .collect())
+    };
+    let address = |block: &hcl::Block| block.labels().iter().map(|l| l.as_str()).collect::<Vec<_>>().join(".");
+    let (old, new) = (iam_blocks(old_main_tf)?, iam_blocks(new_main_tf)?);
+    let old_addresses: BTreeSet<String> = old.iter().map(address).collect();
+    let new_addresses: BTreeSet<String> = new.iter().map(address).collect();
+
+    let mut body = hcl::Body::builder();
+    let mut removed = 0;
+    for from in old_addresses.difference(&new_addresses) {
+        body = body.add_block(hcl::Block::builder("removed")
+            .add_attribute(("from", from.parse::<hcl::Expression>().map_err(|e| format!("Invalid resource address '{}': {}", from, e))?))
+            .add_block(hcl::Block::builder("lifecycle").add_attribute(("destroy", false)).build())
+            .build());
+        removed += 1;
+    }
+    let mut imported = 0;
+    for block in new.iter().filter(|b| !old_addresses.contains(&address(b))) {
+        for (to, id) in import_ids(block, &address(block)) {
+            body = body.add_block(hcl::Block::builder("import")
+                .add_attribute(("to", to.parse::<hcl::Expression>().map_err(|e| format!("Invalid resource address '{}': {}", to, e))?))
+                .add_attribute(("id", id))
+                .build());
+            imported += 1;
+        }
+    }
+    Ok((hcl::to_string(&body.build())?, removed, imported))
+}
+
+/// (address, import ID) of each instance of an IAM resource block; `for_each` members get one per
+/// key.
+fn import_ids(block: &hcl::Block, address: &str) -> Vec<(String, hcl::Expression)> {
+    let attribute = |key: &str| block.body.attributes().find(|a| a.key() == key).map(|a| a.expr().clone());
+    let text = |expr: Option<hcl::Expression>| match expr {
+        Some(hcl::Expression::String(s)) => s,
+        Some(other) => format!("${{{}}}", other),
+        None => String::new(),
+    };
+    let Some(parent) = block.body.attributes()
+        .find(|a| !["role", "member", "members", "provider", "for_each", "count"].contains(&a.key()))
+        .map(|a| a.expr().clone()) else { return Vec::new() };
+    let title = block.body.blocks()
+        .find(|b| b.identifier() == "condition")
+        .and_then(|b| b.body.attributes().find(|a| a.key() == "title"))
+        .map(|a| text(Some(a.expr().clone())));
+
+    let id = |parts: Vec<String>| {
+        let parent_is_literal = matches!(parent, hcl::Expression::String(_));
+        let id = std::iter::once(text(Some(parent.clone()))).chain(parts).chain(title.clone()).collect::<Vec<_>>().join(" ");
+        if parent_is_literal {
+            hcl::Expression::from(id)
+        } else {
+            hcl::Expression::TemplateExpr(Box::new(hcl::TemplateExpr::QuotedString(id)))
+        }
+    };
+    let role = text(attribute("role"));
+    match attribute("for_each") {
+        Some(hcl::Expression::Object(grants)) => grants.iter()
+            .map(|(key, grant)| {
+                let key = match key {
+                    hcl::ObjectKey::Expression(hcl::Expression::String(s)) => s.clone(),
+                    other => other.to_string(),
+                };
+                let field = |name: &str| match grant {
+                    hcl::Expression::Object(fields) => fields.iter()
+                        .find(|(k, _)| k.to_string() == name)
+                        .map(|(_, v)| text(Some(v.clone())))
+                        .unwrap_or_default(),
+                    _ => String::new(),
+                };
+                (format!("{}[\"{}\"]", address, key), id(vec![field("role"), field("member")]))
+            })
+            .collect(),
+        _ if block.labels().first().is_some_and(|t| t.as_str().ends_with("_iam_binding")) => vec![(address.to_string(), id(vec![role]))],
+        _ => vec![(address.to_string(), id(vec![role, text(attribute("member"))]))],
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// `[daemon]` table of config.toml.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct DaemonConfig {
+    /// HCL directories checked for drift (relative to the config directory); defaults to `hcl_dir`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<PathBuf>,
+    /// Time between two runs, e.g. `30m`, `6h`, `1d`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    /// Slack, Teams or generic HTTP webhook that receives drift reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+impl DaemonConfig {
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty() && self.interval.is_none() && self.webhook_url.is_none()
+    }
+}
+
+/// Parses `90s`, `30m`, `6h` or `1d` (a bare number is taken as seconds).
+pub fn parse_interval(value: &str) -> Result<Duration, Box<dyn std::error::Error>> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "s"),
+    };
+    let number: u64 = number.parse().map_err(|_| format!("Invalid interval '{}'", value))?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number * 60,
+        "h" => number * 3600,
+        "d" => number * 86400,
+        _ => return Err(format!("Invalid interval '{}' (use s, m, h or d)", value).into()),
+    };
+    if seconds == 0 {
+        return Err("Interval must be greater than zero".into());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Runs the drift check for every scope, posting to the webhook when differences are found
+/// or a check fails. Repeats every `interval` unless `once` is set.
+pub async fn run(tf_tool: &str, scopes: &[PathBuf], webhook_url: Option<&str>, interval: Duration, once: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if webhook_url.is_none() {
+        eprintln!("Warning: No webhook_url configured; drift is only printed.");
+    }
+    loop {
+        for scope in scopes {
+            check_scope(tf_tool, scope, webhook_url).await;
+        }
+        if once {
+            return Ok(());
+        }
+        status!("Next drift check in {}s.", interval.as_secs());
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn check_scope(tf_tool: &str, scope: &Path, webhook_url: Option<&str>) {
+    let report = crate::state_report::read_state(tf_tool, scope, None)
+        .and_then(|state| crate::state_report::drift(&state, scope));
+
+    let (text, details) = match report {
+        Ok(report) if report.differences() == 0 => {
+            println!("{}: in sync ({} resources)", scope.display(), report.state_count);
+            return;
+        }
+        Ok(report) => (report.text(scope), report.details(scope)),
+        Err(e) => {
+            let text = format!("Drift check for {} failed: {}", scope.display(), e);
+            let details = serde_json::json!({ "event": "drift-error", "scope": scope.display().to_string(), "error": e.to_string() });
+            (text, details)
+        }
+    };
+
+    println!("{}", text);
+    if let Some(url) = webhook_url {
+        if let Err(e) = crate::notify::post_webhook(url, &text, details).await {
+            eprintln!("Warning: {}", e);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::i18n::t;
+
+/// API a resource type needs, by type prefix (longest prefix wins).
+const REQUIRED_SERVICES: &[(&str, &str)] = &[
+    ("google_access_context_manager_", "accesscontextmanager.googleapis.com"),
+    ("google_artifact_registry_", "artifactregistry.googleapis.com"),
+    ("google_bigquery_", "bigquery.googleapis.com"),
+    ("google_bigtable_", "bigtableadmin.googleapis.com"),
+    ("google_certificate_manager_", "certificatemanager.googleapis.com"),
+    ("google_cloud_run_", "run.googleapis.com"),
+    ("google_cloud_scheduler_", "cloudscheduler.googleapis.com"),
+    ("google_cloudbuild_", "cloudbuild.googleapis.com"),
+    ("google_cloudfunctions_", "cloudfunctions.googleapis.com"),
+    ("google_cloudfunctions2_", "cloudfunctions.googleapis.com"),
+    ("google_compute_", "compute.googleapis.com"),
+    ("google_container_", "container.googleapis.com"),
+    ("google_dataproc_", "dataproc.googleapis.com"),
+    ("google_dns_", "dns.googleapis.com"),
+    ("google_filestore_", "file.googleapis.com"),
+    ("google_kms_", "cloudkms.googleapis.com"),
+    ("google_logging_project_", "logging.googleapis.com"),
+    ("google_monitoring_", "monitoring.googleapis.com"),
+    ("google_pubsub_", "pubsub.googleapis.com"),
+    ("google_redis_", "redis.googleapis.com"),
+    ("google_secret_manager_", "secretmanager.googleapis.com"),
+    ("google_service_account", "iam.googleapis.com"),
+    ("google_service_networking_", "servicenetworking.googleapis.com"),
+    ("google_sql_", "sqladmin.googleapis.com"),
+    ("google_storage_", "storage.googleapis.com"),
+    ("google_vpc_access_", "vpcaccess.googleapis.com"),
+];
+
+/// `(dependent type, attribute, dependency type, attribute)`: the dependent waits for every
+/// dependency whose attribute has the same value.
+const ORDERING_RULES: &[(&str, &str, &str, &str)] = &[
+    // Shared VPC: host project before service project attachment
+    ("google_compute_shared_vpc_service_project", "host_project", "google_compute_shared_vpc_host_project", "project"),
+    // Workloads in a service project use the host network only once attached
+    ("google_container_cluster", "project", "google_compute_shared_vpc_service_project", "service_project"),
+    ("google_compute_instance", "project", "google_compute_shared_vpc_service_project", "service_project"),
+    ("google_compute_instance_template", "project", "google_compute_shared_vpc_service_project", "service_project"),
+    ("google_vpc_access_connector", "project", "google_compute_shared_vpc_service_project", "service_project"),
+];
+
+/// Org policy constraints that change what happens when a project is created, so projects below
+/// the policy (in the organization or the same folder) are created after it.
+const PROJECT_CREATION_CONSTRAINTS: &[&str] = &[
+    "compute.skipDefaultNetworkCreation",
+    "iam.automaticIamGrantsForDefaultServiceAccounts",
+];
+
+/// Adds `depends_on` for the orderings Terraform cannot infer from references: the
+/// `google_project_service` a resource needs, the [`ORDERING_RULES`] and org policies that
+/// affect project creation. Explicit `depends_on` entries are kept.
+pub fn apply(blocks: &mut [hcl::Block]) {
+    let resources: Vec<(String, String)> = blocks.iter()
+        .filter(|b| b.identifier() == "resource" && b.labels.len() == 2)
+        .map(|b| (b.labels[0].as_str().to_string(), b.labels[1].as_str().to_string()))
+        .collect();
+    let attrs: Vec<HashMap<String, String>> = blocks.iter()
+        .filter(|b| b.identifier() == "resource" && b.labels.len() == 2)
+        .map(|b| b.body.attributes().map(|a| (a.key.to_string(), expr_key(&a.expr))).collect())
+        .collect();
+
+    // (project, service) -> google_project_service address
+    let mut services: HashMap<(String, String), String> = HashMap::new();
+    for ((tf_type, label), a) in resources.iter().zip(&attrs) {
+        if tf_type == "google_project_service" {
+            if let (Some(project), Some(service)) = (a.get("project"), a.get("service")) {
+                services.insert((project.clone(), service.clone()), format!("{}.{}", tf_type, label));
+            }
+        }
+    }
+    let projects_with_services: BTreeSet<&String> = services.keys().map(|(p, _)| p).collect();
+
+    let mut idx = 0;
+    for block in blocks.iter_mut() {
+        if block.identifier() != "resource" || block.labels.len() != 2 {
+            continue;
+        }
+        let (tf_type, label) = &resources[idx];
+        let own = &attrs[idx];
+        idx += 1;
+        let mut deps = BTreeSet::new();
+
+        if let (Some(service), Some(project)) = (required_service(tf_type), own.get("project")) {
+            if let Some(address) = services.get(&(project.clone(), service.to_string())) {
+                deps.insert(address.clone());
+            } else if projects_with_services.contains(project) {
+                let project = project.trim_end_matches(".project_id");
+                let address = format!("{}.{}", tf_type, label);
+                crate::warnings::warn(t("service-not-enabled-group", &[("type", tf_type), ("service", &service), ("project", &project)]), address.clone(),
+                    t("service-not-enabled", &[("resource", &address), ("service", &service), ("project", &project)]));
+            }
+        }
+
+        for (dependent, attr, dependency, dep_attr) in ORDERING_RULES {
+            if tf_type != dependent {
+                continue;
+            }
+            let Some(value) = own.get(*attr) else { continue };
+            for ((other_type, other_label), other) in resources.iter().zip(&attrs) {
+                if other_type == dependency && other.get(*dep_attr) == Some(value) {
+                    deps.insert(format!("{}.{}", other_type, other_label));
+                }
+            }
+        }
+
+        if tf_type == "google_project" {
+            for ((other_type, other_label), other) in resources.iter().zip(&attrs) {
+                if other_type != "google_org_policy_policy" {
+                    continue;
+                }
+                let constraint = other.get("name").and_then(|n| n.rsplit_once("/policies/")).map(|(_, c)| c);
+                if !constraint.is_some_and(|c| PROJECT_CREATION_CONSTRAINTS.contains(&c)) {
+                    continue;
+                }
+                let parent = other.get("parent");
+                let applies = parent.is_some_and(|p| p.starts_with("organizations/")) || (parent.is_some() && parent == own.get("folder_id"));
+                if applies {
+                    deps.insert(format!("{}.{}", other_type, other_label));
+                }
+            }
+        }
+
+        add_depends_on(block, deps);
+    }
+}
+
+fn required_service(tf_type: &str) -> Option<&'static str> {
+    REQUIRED_SERVICES.iter()
+        .filter(|(prefix, _)| tf_type.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, service)| *service)
+}
+
+/// Comparable form of an attribute: the content of plain strings, the HCL text otherwise.
+fn expr_key(expr: &hcl::Expression) -> String {
+    match expr {
+        hcl::Expression::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn add_depends_on(block: &mut hcl::Block, deps: BTreeSet<String>) {
+    if deps.is_empty() {
+        return;
+    }
+    match block.body.attributes_mut().find(|a| a.key.as_str() == "depends_on") {
+        Some(attr) => {
+            // Explicit depends_on that is not a list is left alone
+            let hcl::Expression::Array(items) = &mut attr.expr else { return };
+            let present: BTreeSet<String> = items.iter().map(expr_key).collect();
+            items.extend(deps.iter().filter(|d| !present.contains(*d)).filter_map(|d| d.parse().ok()));
+        }
+        None => {
+            let items: Vec<hcl::Expression> = deps.iter().filter_map(|d| d.parse().ok()).collect();
+            // Attributes go before nested blocks such as lifecycle
+            let pos = block.body.0.iter().position(|s| s.is_block()).unwrap_or(block.body.0.len());
+            block.body.0.insert(pos, hcl::Structure::Attribute(hcl::Attribute::new("depends_on", items)));
+        }
+    }
+}
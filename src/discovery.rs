@@ -6,6 +6,11 @@ use google_cloud_asset_v1::client::AssetService;
 use google_cloud_asset_v1::model::{Asset, ContentType};
 use google_cloud_gax::paginator::ItemPaginator;
 
+/// Scope key under which IAM bindings of deleted principals are parked. Bindings of
+/// `deleted:...` members can never be applied, so they are kept out of the IAM resources;
+/// the transpiler skips this key.
+pub const DELETED_PRINCIPALS_KEY: &str = "_deleted_principals";
+
 pub struct Discoverer {
     pub state: Value,
     pub registry: Option<ResourceRegistry>,
@@ -44,6 +49,30 @@ impl Discoverer {
         }
     }
 
+    /// Records the binding of a deleted principal (`deleted:user:...?uid=...`) under
+    /// `_deleted_principals: {member: {tf_type: [roles]}}` and returns true; other members are
+    /// left to the caller.
+    fn park_deleted_member(extra: &mut HashMap<String, serde_yaml::Value>, tf_type: &str, member: &str, role: &str) -> bool {
+        if !member.starts_with("deleted:") {
+            return false;
+        }
+        let section = extra.entry(DELETED_PRINCIPALS_KEY.to_string())
+            .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+        if let serde_yaml::Value::Mapping(members) = section {
+            let types = members.entry(serde_yaml::Value::String(member.to_string()))
+                .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+            if let serde_yaml::Value::Mapping(types) = types {
+                let roles = types.entry(serde_yaml::Value::String(tf_type.to_string()))
+                    .or_insert_with(|| serde_yaml::Value::Sequence(Vec::new()));
+                if let serde_yaml::Value::Sequence(roles) = roles {
+                    let role_val = serde_yaml::Value::String(role.to_string());
+                    if !roles.contains(&role_val) { roles.push(role_val); }
+                }
+            }
+        }
+        true
+    }
+
     fn is_type_enabled(&self, tf_type: &str) -> bool {
         match &self.enabled_types {
             Some(enabled) => enabled.contains(tf_type),
@@ -377,6 +406,9 @@ impl Discoverer {
         if tf_type.ends_with("_iam_member") {
             let role = values["role"].as_str().unwrap_or("unknown_role").to_string();
             let member = values["member"].as_str().unwrap_or("unknown_member").to_string();
+            if Self::park_deleted_member(&mut p.extra, tf_type, &member, &role) {
+                return;
+            }
             if p.extra.get(tf_type).is_none() { p.extra.insert(tf_type.to_string(), serde_yaml::Value::Mapping(serde_yaml::Mapping::new())); }
             if let Some(serde_yaml::Value::Mapping(members_map)) = p.extra.get_mut(tf_type) {
                 let member_key = serde_yaml::Value::String(member);
@@ -404,6 +436,9 @@ impl Discoverer {
         if tf_type.ends_with("_iam_member") {
             let role = values["role"].as_str().unwrap_or("unknown_role").to_string();
             let member = values["member"].as_str().unwrap_or("unknown_member").to_string();
+            if Self::park_deleted_member(&mut f.extra, tf_type, &member, &role) {
+                return;
+            }
             if f.extra.get(tf_type).is_none() { f.extra.insert(tf_type.to_string(), serde_yaml::Value::Mapping(serde_yaml::Mapping::new())); }
             if let Some(serde_yaml::Value::Mapping(members_map)) = f.extra.get_mut(tf_type) {
                 let member_key = serde_yaml::Value::String(member);
@@ -426,6 +461,9 @@ impl Discoverer {
         if tf_type.ends_with("_iam_member") {
             let role = values["role"].as_str().unwrap_or("unknown_role").to_string();
             let member = values["member"].as_str().unwrap_or("unknown_member").to_string();
+            if Self::park_deleted_member(&mut c.extra, tf_type, &member, &role) {
+                return;
+            }
 
             if tf_type == "google_organization_iam_member" {
                 if c.organization_iam_member.is_none() { c.organization_iam_member = Some(HashMap::new()); }
@@ -507,7 +545,10 @@ impl Discoverer {
         registry: Option<ResourceRegistry>,
     ) -> Result<Config, Box<dyn std::error::Error>> {
         
-        let client = AssetService::builder().build().await?;
+        let client = match crate::endpoints::get("cloudasset") {
+            Some(endpoint) => AssetService::builder().with_endpoint(endpoint).build().await?,
+            None => AssetService::builder().build().await?,
+        };
         
         let mut type_map: BTreeMap<u32, std::collections::BTreeSet<String>> = BTreeMap::new();
         
@@ -551,7 +592,7 @@ impl Discoverer {
                         asset_type.split('/').last().unwrap_or(&asset_type).to_string()
                     };
                  
-                 println!("Fetching assets for type: {} (Content: {:?})", display_type, ctype);
+                 status!("Fetching assets for type: {} (Content: {:?})", display_type, ctype);
 
                  let mut stream = client.list_assets()
                     .set_parent(format!("organizations/{}", org_id))
@@ -615,9 +656,7 @@ impl Discoverer {
              println!("{:<width$}: {}\n", total_label, all_assets.len(), width = max_len);
         }
 
-        let config = Self::construct_config_from_assets(all_assets, verbose, add_import_id, add_import_id_as_comment, registry.as_ref(), discovery_config.as_ref());
-
-        Ok(config)
+        Self::construct_config_from_assets(all_assets, verbose, add_import_id, add_import_id_as_comment, registry.as_ref(), discovery_config.as_ref())
     }
 
     fn construct_config_from_assets(
@@ -627,7 +666,7 @@ impl Discoverer {
         add_import_id_as_comment: bool,
         registry: Option<&ResourceRegistry>,
         discovery_config: Option<&DiscoveryConfig>,
-    ) -> Config {
+    ) -> Result<Config, Box<dyn std::error::Error>> {
         let mut config = Config::default();
         let mut deprecated_seen = HashSet::new();
         let mut folder_map: HashMap<String, Folder> = HashMap::new(); 
@@ -688,6 +727,7 @@ impl Discoverer {
         }
 
         // Pass 2: Process all other resources (IAM, Policies, Services, Generic)
+        let mut mapped: BTreeMap<&str, (&Vec<String>, Vec<crate::discovery_mappers::MapperInput>)> = BTreeMap::new();
         for asset in &assets {
              if (asset.asset_type == "cloudresourcemanager.googleapis.com/Folder" || 
                  asset.asset_type == "cloudresourcemanager.googleapis.com/Project") && asset.resource.is_some() {
@@ -729,6 +769,12 @@ impl Discoverer {
                  deprecated_seen.insert(tf_type.to_string());
              }
 
+             // Mapped in one batch per type once all scopes are known
+             if let Some(mapper) = &res_config.mapper {
+                 mapped.entry(tf_type.as_str()).or_insert_with(|| (mapper, Vec::new())).1.push((asset, scope, scope_id));
+                 continue;
+             }
+
              if tf_type.contains("organization_policy") || tf_type == "google_org_policy_policy" {
                  Self::discover_organization_policy(tf_type, asset, res_config, registry, add_import_id, add_import_id_as_comment, &scope, &scope_id, &mut config, &mut folder_map, &mut project_map, &gcp_id_to_yaml_name);
              } else if asset.iam_policy.is_some() {
@@ -740,6 +786,17 @@ impl Discoverer {
              }
         }
         
+        for (tf_type, (mapper, inputs)) in &mapped {
+            for resource in crate::discovery_mappers::run(mapper, tf_type, inputs)? {
+                let (_, scope, scope_id) = &inputs[resource.asset];
+                let target_type = resource.tf_type.as_deref().unwrap_or(tf_type);
+                let values = serde_yaml::to_value(&resource.values)?;
+                if let Some(extra) = Self::scope_extra(scope, scope_id, &mut config, &mut folder_map, &mut project_map, &gcp_id_to_yaml_name) {
+                    Self::insert_resource(extra, target_type, &Self::sanitize_yaml_key(&resource.key), values);
+                }
+            }
+        }
+
         // Link Projects to Folders
         let project_ids: Vec<String> = project_id_to_parent.keys().cloned().collect();
         for p_id in project_ids {
@@ -782,7 +839,7 @@ impl Discoverer {
             eprintln!("Warning: Resource type '{}' is deprecated.", deprecated_type);
         }
 
-        config
+        Ok(config)
     }
 
     fn discover_google_folder(
@@ -823,7 +880,7 @@ impl Discoverer {
 
          let folder = Folder {
              display_name: display_name,
-             parent: parent_string.clone(),
+             parent: parent_string.clone().map(serde_yaml::Value::String),
              import_id: if add_import_id { Some(folder_id.clone()) } else { None },
              import_id_comment: if add_import_id_as_comment { Some(folder_id.clone()) } else { None },
              ..Default::default()
@@ -1114,6 +1171,17 @@ impl Discoverer {
                  if !binding.members.is_empty() {
                      for member in &binding.members {
                          let role = &binding.role;
+                         let parked = match scope {
+                             "organization" => Self::park_deleted_member(&mut config.extra, tf_type, member, role),
+                             "folder" => gcp_id_to_yaml_name.get(scope_id).and_then(|f_yaml| folder_map.get_mut(f_yaml))
+                                 .is_some_and(|f| Self::park_deleted_member(&mut f.extra, tf_type, member, role)),
+                             "project" => gcp_id_to_yaml_name.get(scope_id).and_then(|p_yaml| project_map.get_mut(p_yaml))
+                                 .is_some_and(|p| Self::park_deleted_member(&mut p.extra, tf_type, member, role)),
+                             _ => false,
+                         };
+                         if parked {
+                             continue;
+                         }
                          if scope == "organization" {
                              if tf_type == "google_organization_iam_member" {
                                  if config.organization_iam_member.is_none() { config.organization_iam_member = Some(HashMap::new()); }
@@ -1211,41 +1279,61 @@ impl Discoverer {
           }
           
           if resource_val.is_empty() { return; }
-          let policy_map_val = serde_yaml::Value::Mapping(resource_val);
+          if let Some(extra) = Self::scope_extra(scope, scope_id, config, folder_map, project_map, gcp_id_to_yaml_name) {
+               Self::insert_resource(extra, tf_type, &sanitized_key, serde_yaml::Value::Mapping(resource_val));
+          }
+    }
 
-          if scope == "organization" {
-               config.extra.entry(tf_type.to_string()).or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
-               if let Some(serde_yaml::Value::Mapping(m)) = config.extra.get_mut(tf_type) {
-                    m.insert(serde_yaml::Value::String(sanitized_key.clone()), policy_map_val);
-               }
-          } else if scope == "folder" {
-                if let Some(f_yaml) = gcp_id_to_yaml_name.get(scope_id) {
-                    if let Some(f) = folder_map.get_mut(f_yaml) {
-                        f.extra.entry(tf_type.to_string()).or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
-                        if let Some(serde_yaml::Value::Mapping(m)) = f.extra.get_mut(tf_type) {
-                            m.insert(serde_yaml::Value::String(sanitized_key.clone()), policy_map_val);
-                        }
-                    }
-                }
-          } else if scope == "project" {
-                if let Some(p_yaml) = gcp_id_to_yaml_name.get(scope_id) {
-                    if let Some(p) = project_map.get_mut(p_yaml) {
-                         p.extra.entry(tf_type.to_string()).or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
-                         if let Some(serde_yaml::Value::Mapping(m)) = p.extra.get_mut(tf_type) {
-                             m.insert(serde_yaml::Value::String(sanitized_key.clone()), policy_map_val);
-                         }
-                    }
-                }
+    /// The organization, folder or project (by their `extra` resources) an asset belongs to.
+    fn scope_extra<'a>(
+         scope: &str,
+         scope_id: &str,
+         config: &'a mut Config,
+         folder_map: &'a mut HashMap<String, Folder>,
+         project_map: &'a mut HashMap<String, Project>,
+         gcp_id_to_yaml_name: &HashMap<String, String>,
+    ) -> Option<&'a mut HashMap<String, serde_yaml::Value>> {
+          match scope {
+               "organization" => Some(&mut config.extra),
+               "folder" => gcp_id_to_yaml_name.get(scope_id).and_then(|f_yaml| folder_map.get_mut(f_yaml)).map(|f| &mut f.extra),
+               "project" => gcp_id_to_yaml_name.get(scope_id).and_then(|p_yaml| project_map.get_mut(p_yaml)).map(|p| &mut p.extra),
+               _ => None,
+          }
+    }
+
+    fn insert_resource(extra: &mut HashMap<String, serde_yaml::Value>, tf_type: &str, key: &str, value: serde_yaml::Value) {
+          let entry = extra.entry(tf_type.to_string()).or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+          if let serde_yaml::Value::Mapping(m) = entry {
+               m.insert(serde_yaml::Value::String(key.to_string()), value);
           }
     }
-    
 
     pub fn print_summary(config: &Config, filtered_count: Option<usize>) {
         println!("\n=== Configuration Summary ===");
         if let Some(count) = filtered_count {
             println!("Filtered resources: {}", count);
         }
+
+        let mut stats = Self::resource_stats(config);
+        stats.remove(DELETED_PRINCIPALS_KEY);
+        let mut sorted_stats: Vec<_> = stats.iter().collect();
+        sorted_stats.sort_by_key(|a| a.0);
         
+        for (k, v) in sorted_stats {
+            println!("{:<30}: {}", k, v);
+        }
+    }
+
+    /// Warns about the IAM bindings parked under `_deleted_principals:`.
+    pub fn report_deleted_principals(config: &Config) {
+        let count = Self::resource_stats(config).get(DELETED_PRINCIPALS_KEY).copied().unwrap_or(0);
+        if count > 0 {
+            eprintln!("Warning: IAM bindings of {} deleted principal(s) were not turned into resources; they are listed under '{}:'. Remove the bindings in GCP, then delete those sections.", count, DELETED_PRINCIPALS_KEY);
+        }
+    }
+
+    /// Number of entries per resource type, over all scopes.
+    fn resource_stats(config: &Config) -> HashMap<String, usize> {
         let mut stats: HashMap<String, usize> = HashMap::new();
         
         
@@ -1275,12 +1363,7 @@ impl Discoverer {
             }
         }
 
-        let mut sorted_stats: Vec<_> = stats.iter().collect();
-        sorted_stats.sort_by_key(|a| a.0);
-        
-        for (k, v) in sorted_stats {
-            println!("{:<30}: {}", k, v);
-        }
+        stats
     }
 
     fn count_folder_resources(f: &Folder, stats: &mut HashMap<String, usize>) {
@@ -1375,6 +1458,7 @@ impl Discoverer {
 
          } else {
              // Legacy types
+             crate::org_policy::from_asset_data(&mut data_map);
              data_map.insert("constraint".to_string(), serde_json::Value::String(constraint.to_string()));
 
              if tf_type == "google_organization_policy" {
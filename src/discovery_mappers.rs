@@ -0,0 +1,86 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+use google_cloud_asset_v1::model::Asset;
+
+/// An asset handed to a mapper with its scope (`organization`, `folder` or `project`) and scope ID.
+pub type MapperInput<'a> = (&'a Asset, String, String);
+
+#[derive(Serialize)]
+struct Request<'a> {
+    /// Resource type of the discovery config entry
+    tf_type: &'a str,
+    assets: Vec<RequestAsset<'a>>,
+}
+
+#[derive(Serialize)]
+struct RequestAsset<'a> {
+    name: &'a str,
+    asset_type: &'a str,
+    scope: &'a str,
+    scope_id: &'a str,
+    ancestors: &'a [String],
+    /// `resource.data` of the Cloud Asset API
+    data: Option<&'a serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    resources: Vec<MappedResource>,
+}
+
+/// A YAML resource returned by a mapper; it is placed in the scope of the asset it came from.
+#[derive(Deserialize)]
+pub struct MappedResource {
+    /// Index of the source asset in the request
+    pub asset: usize,
+    /// Resource type key in the YAML, defaults to the discovery config entry
+    #[serde(default, rename = "type")]
+    pub tf_type: Option<String>,
+    /// YAML key of the resource (sanitized like discovered names)
+    pub key: String,
+    pub values: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Runs the `mapper` command of a discovery config entry with all assets of that entry. The
+/// mapper reads `{"tf_type": ..., "assets": [...]}` on stdin and prints
+/// `{"resources": [{"asset": 0, "key": ..., "values": {...}}]}`; an asset may map to any number
+/// of resources, including none.
+pub fn run(command: &[String], tf_type: &str, inputs: &[MapperInput]) -> Result<Vec<MappedResource>, Box<dyn std::error::Error>> {
+    let Some(program) = command.first() else {
+        return Err(format!("Discovery mapper of '{}' has an empty command", tf_type).into());
+    };
+    status!("Mapping {} asset(s) of {} with {}", inputs.len(), tf_type, program);
+
+    let assets = inputs.iter().map(|(asset, scope, scope_id)| RequestAsset {
+        name: &asset.name,
+        asset_type: &asset.asset_type,
+        scope,
+        scope_id,
+        ancestors: &asset.ancestors,
+        data: asset.resource.as_ref().and_then(|r| r.data.as_ref()),
+    }).collect();
+    let request = serde_json::to_vec(&Request { tf_type, assets })?;
+
+    let mut child = Command::new(program)
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run discovery mapper '{}' for {}: {}", program, tf_type, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&request)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!("Discovery mapper '{}' for {} failed ({}): {}", program, tf_type, output.status, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    let response: Response = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Discovery mapper '{}' for {} returned invalid JSON: {}", program, tf_type, e))?;
+
+    if let Some(bad) = response.resources.iter().find(|r| r.asset >= inputs.len()) {
+        return Err(format!("Discovery mapper '{}' for {} returned resource '{}' for unknown asset {}", program, tf_type, bad.key, bad.asset).into());
+    }
+    Ok(response.resources)
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Services that can be redirected: API host name (`<service>.googleapis.com`), the provider's
+/// `<attribute>_custom_endpoint`, and the API path the provider expects in it (google, google-beta).
+const SERVICES: &[(&str, &str, &str, &str)] = &[
+    ("bigquery", "big_query", "bigquery/v2/", "bigquery/v2/"),
+    ("cloudasset", "cloud_asset", "v1/", "v1/"),
+    ("cloudbilling", "cloud_billing", "v1/", "v1/"),
+    ("cloudidentity", "cloud_identity", "v1/", "v1beta1/"),
+    ("cloudkms", "kms", "v1/", "v1/"),
+    ("cloudresourcemanager", "resource_manager", "v1/", "v1/"),
+    ("cloudresourcemanager", "resource_manager_v3", "v3/", "v3/"),
+    ("compute", "compute", "compute/v1/", "compute/beta/"),
+    ("container", "container", "v1/", "v1beta1/"),
+    ("dns", "dns", "dns/v1/", "dns/v1beta2/"),
+    ("essentialcontacts", "essential_contacts", "v1/", "v1/"),
+    ("iam", "iam", "v1/", "v1/"),
+    ("iamcredentials", "iam_credentials", "v1/", "v1/"),
+    ("logging", "logging", "v2/", "v2/"),
+    ("orgpolicy", "org_policy", "v2/", "v2/"),
+    ("pubsub", "pubsub", "v1/", "v1/"),
+    ("secretmanager", "secret_manager", "v1/", "v1/"),
+    ("serviceusage", "service_usage", "v1/", "v1beta1/"),
+    ("storage", "storage", "storage/v1/", "storage/v1/"),
+];
+
+static OVERRIDES: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+/// Sets the endpoint overrides (`service = "base URL"`, e.g. `storage = "http://localhost:4443"`):
+/// `[endpoint_overrides]` of config.toml, with the entries of the `--endpoint-overrides` file on top.
+pub fn init(configured: &BTreeMap<String, String>, file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut overrides = configured.clone();
+    if let Some(file) = file {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read endpoint overrides '{}': {}", file.display(), e))?;
+        let from_file: BTreeMap<String, String> = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse endpoint overrides '{}': {}", file.display(), e))?;
+        overrides.extend(from_file);
+    }
+    for service in overrides.keys() {
+        if !SERVICES.iter().any(|(name, ..)| name == service) {
+            let mut known: Vec<&str> = SERVICES.iter().map(|(name, ..)| *name).collect();
+            known.dedup();
+            return Err(format!("Unknown service '{}' in endpoint overrides (known: {})", service, known.join(", ")).into());
+        }
+    }
+    if !overrides.is_empty() {
+        eprintln!("Warning: using endpoint overrides for {}", overrides.keys().cloned().collect::<Vec<_>>().join(", "));
+    }
+    let _ = OVERRIDES.set(overrides);
+    Ok(())
+}
+
+/// Base URL of a Google API, e.g. `https://storage.googleapis.com` unless overridden.
+pub fn base(service: &str) -> String {
+    match OVERRIDES.get().and_then(|o| o.get(service)) {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => format!("https://{}.googleapis.com", service),
+    }
+}
+
+/// URL of an API path, e.g. `url("cloudresourcemanager", "v3/folders")`.
+pub fn url(service: &str, path: &str) -> String {
+    format!("{}/{}", base(service), path.trim_start_matches('/'))
+}
+
+/// The overridden base URL of a service, for clients that only need it when it differs.
+pub fn get(service: &str) -> Option<&'static str> {
+    OVERRIDES.get().and_then(|o| o.get(service)).map(String::as_str)
+}
+
+/// `*_custom_endpoint` attributes for a generated `google` or `google-beta` provider block.
+pub fn provider_attributes(provider: &str) -> Vec<(String, String)> {
+    let Some(overrides) = OVERRIDES.get().filter(|_| provider == "google" || provider == "google-beta") else {
+        return Vec::new();
+    };
+    SERVICES.iter()
+        .filter_map(|(service, attribute, ga_path, beta_path)| {
+            let url = overrides.get(*service)?;
+            let path = if provider == "google-beta" { beta_path } else { ga_path };
+            Some((format!("{}_custom_endpoint", attribute), format!("{}/{}", url.trim_end_matches('/'), path)))
+        })
+        .collect()
+}
@@ -0,0 +1,626 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::transpiler::resource_label;
+
+/// Roots of traversals that are no references to other blocks.
+const BUILTIN_ROOTS: &[&str] = &["each", "count", "self", "path", "terraform"];
+
+/// A local Terraform module made from the resources of one or more YAML subtrees
+/// (`extract-module`). The first subtree is the pattern; values that differ between the
+/// subtrees, and values that refer to anything outside them, become module variables.
+pub struct Extraction {
+    /// Files of `modules/<name>`: (file name, content)
+    pub files: Vec<(String, String)>,
+    /// `moved` blocks from the current addresses into the module instances
+    pub moved: Vec<hcl::Block>,
+    /// One per subtree
+    pub instances: Vec<Instance>,
+    /// Blocks outside the subtrees that refer to extracted resources: (block, referenced address,
+    /// module output to refer to instead)
+    pub outside_references: Vec<(String, String, String)>,
+}
+
+/// The call of the module that replaces one subtree.
+pub struct Instance {
+    pub name: String,
+    /// The module's local provider name -> the configuration passed in
+    pub providers: BTreeMap<String, String>,
+    /// Variable -> its value for this subtree
+    pub inputs: BTreeMap<String, hcl::Expression>,
+    /// `depends_on` entries outside the subtree
+    pub depends_on: Vec<hcl::Expression>,
+    /// Current address of each extracted resource -> `module.<name>.<output>`
+    pub outputs: BTreeMap<String, String>,
+}
+
+impl Extraction {
+    /// The `module:` entries that replace the subtrees in the YAML.
+    pub fn module_yaml(&self, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let mut modules = serde_yaml::Mapping::new();
+        for instance in &self.instances {
+            let mut entry = serde_yaml::Mapping::new();
+            entry.insert("source".into(), format!("./modules/{}", name).into());
+            if !instance.providers.is_empty() {
+                let providers: serde_yaml::Mapping = instance.providers.iter()
+                    .map(|(local, configured)| (local.as_str().into(), configured.as_str().into()))
+                    .collect();
+                entry.insert("providers".into(), providers.into());
+            }
+            for (variable, value) in &instance.inputs {
+                entry.insert(variable.as_str().into(), yaml_value(value));
+            }
+            if !instance.depends_on.is_empty() {
+                entry.insert("depends_on".into(), serde_yaml::Value::Sequence(instance.depends_on.iter().map(|e| e.to_string().into()).collect()));
+            }
+            modules.insert(instance.name.as_str().into(), entry.into());
+        }
+        let mut module_yaml = serde_yaml::Mapping::new();
+        module_yaml.insert("module".into(), modules.into());
+        Ok(serde_yaml::to_string(&module_yaml)?)
+    }
+}
+
+/// What is collected while merging the subtrees into the module's blocks.
+struct Merge<'a> {
+    /// Per subtree: current address -> address in the module
+    internal: Vec<HashMap<String, String>>,
+    module_addresses: BTreeSet<String>,
+    /// Per subtree: its provider configuration -> the module's local name for it
+    providers: Vec<BTreeMap<String, String>>,
+    key: &'a str,
+    /// Variable name -> its value per subtree
+    variables: BTreeMap<String, Vec<hcl::Expression>>,
+    /// `depends_on` entries outside the subtrees, per subtree
+    depends_on: Vec<Vec<hcl::Expression>>,
+}
+
+/// `body` and `outputs` are the generated `main.tf` and `outputs.tf`, `sources` maps their
+/// addresses to YAML paths.
+pub fn extract(body: &hcl::Body, outputs_body: &hcl::Body, sources: &BTreeMap<String, String>, paths: &[String], name: &str, provider_sources: &HashMap<String, String>) -> Result<Extraction, Box<dyn std::error::Error>> {
+    if hcl::Identifier::new(name).is_err() {
+        return Err(format!("'{}' is no valid module name (letters, digits, '_' and '-')", name).into());
+    }
+    let resources: Vec<(String, &hcl::Block)> = body.blocks()
+        .filter(|b| b.identifier() == "resource" && b.labels().len() == 2)
+        .map(|b| (format!("{}.{}", b.labels()[0].as_str(), b.labels()[1].as_str()), b))
+        .collect();
+
+    // The resources of each subtree, ordered by type and YAML path within the subtree
+    let mut instances: Vec<Vec<(String, &hcl::Block)>> = Vec::new();
+    for path in paths {
+        let prefix = format!("{}.", path);
+        let mut blocks: Vec<(String, String, &hcl::Block)> = resources.iter()
+            .filter_map(|(address, block)| {
+                let yaml_path = sources.get(address)?;
+                let relative = if yaml_path == path { "" } else { yaml_path.strip_prefix(&prefix)? };
+                Some((relative.to_string(), address.clone(), *block))
+            })
+            .collect();
+        if blocks.is_empty() {
+            return Err(format!("No resources are generated from '{}'", path).into());
+        }
+        blocks.sort_by(|(p1, _, b1), (p2, _, b2)| (b1.labels()[0].as_str(), p1).cmp(&(b2.labels()[0].as_str(), p2)));
+        instances.push(blocks.into_iter().map(|(_, address, block)| (address, block)).collect());
+    }
+    let types = |blocks: &[(String, &hcl::Block)]| blocks.iter().map(|(_, b)| b.labels()[0].as_str().to_string()).collect::<Vec<_>>();
+    for (path, blocks) in paths.iter().zip(&instances).skip(1) {
+        if types(blocks) != types(&instances[0]) {
+            return Err(format!("'{}' does not generate the same resources as '{}' ({} vs. {})", path, paths[0], types(blocks).join(", "), types(&instances[0]).join(", ")).into());
+        }
+    }
+
+    // Labels in the module: with several subtrees, the key of the first one becomes `this`
+    let keys: Vec<String> = paths.iter().map(|p| resource_label(p.rsplit('.').next().unwrap_or(p))).collect();
+    let key = if paths.len() > 1 { keys[0].as_str() } else { "" };
+    let mut labels: Vec<String> = instances[0].iter().map(|(_, b)| generalize(b.labels()[1].as_str(), key)).collect();
+    let module_addresses: Vec<String> = instances[0].iter().zip(&labels).map(|((_, b), l)| format!("{}.{}", b.labels()[0].as_str(), l)).collect();
+    if module_addresses.iter().collect::<BTreeSet<_>>().len() != module_addresses.len() {
+        labels = instances[0].iter().map(|(_, b)| b.labels()[1].as_str().to_string()).collect();
+    }
+    let module_addresses: Vec<String> = instances[0].iter().zip(&labels).map(|((_, b), l)| format!("{}.{}", b.labels()[0].as_str(), l)).collect();
+
+    let mut merge = Merge {
+        internal: instances.iter().map(|blocks| blocks.iter().map(|(a, _)| a.clone()).zip(module_addresses.iter().cloned()).collect()).collect(),
+        module_addresses: module_addresses.iter().cloned().collect(),
+        providers: vec![BTreeMap::new(); paths.len()],
+        key,
+        variables: BTreeMap::new(),
+        depends_on: vec![Vec::new(); paths.len()],
+    };
+
+    let mut main = hcl::Body::builder();
+    let mut outputs = hcl::Body::builder();
+    for (j, module_address) in module_addresses.iter().enumerate() {
+        let (tf_type, label) = module_address.split_once('.').unwrap_or_default();
+        let bodies: Vec<&hcl::Body> = instances.iter().map(|blocks| &blocks[j].1.body).collect();
+        let prefix = if label == "this" { short_type(tf_type) } else { format!("{}_{}", short_type(tf_type), label) };
+        let merged = merge.body(&prefix, &bodies, &instances.iter().map(|b| b[j].0.as_str()).collect::<Vec<_>>())?;
+        main = main.add_block(hcl::Block::builder("resource").add_label(tf_type).add_label(label).add_structures(merged).build());
+        outputs = outputs.add_block(hcl::Block::builder("output").add_label(&prefix)
+            .add_attribute(("value", module_address.parse::<hcl::Expression>()?))
+            .build());
+    }
+
+    let mut variables = hcl::Body::builder();
+    for name in merge.variables.keys() {
+        variables = variables.add_block(hcl::Block::builder("variable").add_label(name).build());
+    }
+
+    // Aliased provider configurations the module receives from its callers
+    let mut aliases: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for local in merge.providers.iter().flat_map(|p| p.values()) {
+        if let Some((provider, _)) = local.split_once('.') {
+            aliases.entry(provider.to_string()).or_default().insert(local.clone());
+        }
+    }
+    let mut required_providers = hcl::Block::builder("required_providers");
+    for (provider, locals) in &aliases {
+        let mut object = hcl::Object::new();
+        if let Some(source) = provider_sources.get(provider) {
+            object.insert(hcl::ObjectKey::Identifier(hcl::Identifier::unchecked("source")), hcl::Expression::from(source.clone()));
+        }
+        let locals: Result<Vec<hcl::Expression>, _> = locals.iter().map(|l| l.parse::<hcl::Expression>()).collect();
+        object.insert(hcl::ObjectKey::Identifier(hcl::Identifier::unchecked("configuration_aliases")), hcl::Expression::Array(locals?));
+        required_providers = required_providers.add_attribute((provider.as_str(), hcl::Expression::Object(object)));
+    }
+    let versions = hcl::Body::builder()
+        .add_block(hcl::Block::builder("terraform").add_block(required_providers.build()).build())
+        .build();
+
+    let files = vec![
+        ("main.tf".to_string(), hcl::to_string(&main.build())?),
+        ("variables.tf".to_string(), hcl::to_string(&variables.build())?),
+        ("outputs.tf".to_string(), hcl::to_string(&outputs.build())?),
+        ("versions.tf".to_string(), hcl::to_string(&versions)?),
+    ];
+
+    // One module instance per subtree
+    let instance_names: Vec<String> = if paths.len() == 1 {
+        vec![name.to_string()]
+    } else {
+        keys.iter().map(|k| format!("{}_{}", name, k)).collect()
+    };
+    let mut moved = Vec::new();
+    let mut module_instances = Vec::new();
+    for (i, instance) in instance_names.iter().enumerate() {
+        let mut outputs = BTreeMap::new();
+        for ((address, _), module_address) in instances[i].iter().zip(&module_addresses) {
+            moved.push(hcl::Block::builder("moved")
+                .add_attribute(("from", address.parse::<hcl::Expression>()?))
+                .add_attribute(("to", format!("module.{}.{}", instance, module_address).parse::<hcl::Expression>()?))
+                .build());
+            let (tf_type, label) = module_address.split_once('.').unwrap_or_default();
+            let output = if label == "this" { short_type(tf_type) } else { format!("{}_{}", short_type(tf_type), label) };
+            outputs.insert(address.clone(), format!("module.{}.{}", instance, output));
+        }
+        module_instances.push(Instance {
+            name: instance.clone(),
+            providers: merge.providers[i].iter().map(|(configured, local)| (local.clone(), configured.clone())).collect(),
+            inputs: merge.variables.iter().map(|(variable, values)| (variable.clone(), values[i].clone())).collect(),
+            depends_on: merge.depends_on[i].clone(),
+            outputs,
+        });
+    }
+
+    // References into the subtrees from the rest of the configuration
+    let mut outside_references = Vec::new();
+    for block in body.blocks().chain(outputs_body.blocks()) {
+        let address = std::iter::once(block.identifier()).chain(block.labels().iter().map(|l| l.as_str())).collect::<Vec<_>>().join(".");
+        let address = address.strip_prefix("resource.").unwrap_or(&address).to_string();
+        if merge.internal.iter().any(|m| m.contains_key(&address)) {
+            continue;
+        }
+        let mut referenced = BTreeSet::new();
+        body_references(&block.body, &mut referenced);
+        for reference in referenced {
+            for instance in &module_instances {
+                if let Some(output) = instance.outputs.get(&reference) {
+                    outside_references.push((address.clone(), reference.clone(), output.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(Extraction { files, moved, instances: module_instances, outside_references })
+}
+
+impl Merge<'_> {
+    /// The module's version of a block body from its version in each subtree.
+    fn body(&mut self, prefix: &str, bodies: &[&hcl::Body], addresses: &[&str]) -> Result<Vec<hcl::Structure>, Box<dyn std::error::Error>> {
+        let shape = |body: &hcl::Body| body.iter().map(|s| match s {
+            hcl::Structure::Attribute(a) => a.key().to_string(),
+            hcl::Structure::Block(b) => format!("{} {{}}", b.identifier()),
+        }).collect::<Vec<_>>();
+        for (body, address) in bodies.iter().zip(addresses).skip(1) {
+            if shape(body) != shape(bodies[0]) {
+                return Err(format!("{} is not built like {} ({} vs. {})", address, addresses[0], shape(body).join(", "), shape(bodies[0]).join(", ")).into());
+            }
+        }
+
+        let mut structures = Vec::new();
+        for (idx, structure) in bodies[0].iter().enumerate() {
+            let nth = |body: &&hcl::Body| body.iter().nth(idx).cloned();
+            let versions: Vec<hcl::Structure> = bodies.iter().filter_map(nth).collect();
+            match structure {
+                hcl::Structure::Attribute(attr) => {
+                    let exprs: Vec<&hcl::Expression> = versions.iter().filter_map(|s| s.as_attribute()).map(|a| a.expr()).collect();
+                    let expr = match attr.key() {
+                        "provider" => self.provider(&exprs, addresses)?,
+                        "depends_on" => self.depends_on(&exprs),
+                        key => self.value(&format!("{}_{}", prefix, key), &exprs, addresses)?,
+                    };
+                    structures.push(hcl::Structure::Attribute(hcl::Attribute::new(attr.key(), expr)));
+                }
+                // Nothing in lifecycle refers to other blocks
+                hcl::Structure::Block(block) if block.identifier() == "lifecycle" => structures.push(structure.clone()),
+                hcl::Structure::Block(block) => {
+                    let nested: Vec<&hcl::Body> = versions.iter().filter_map(|s| s.as_block()).map(|b| &b.body).collect();
+                    let merged = self.body(&format!("{}_{}", prefix, block.identifier()), &nested, addresses)?;
+                    let mut builder = hcl::Block::builder(block.identifier());
+                    for label in block.labels() {
+                        builder = builder.add_label(label.clone());
+                    }
+                    structures.push(hcl::Structure::Block(builder.add_structures(merged).build()));
+                }
+            }
+        }
+        Ok(structures)
+    }
+
+    /// A value that is the same in all subtrees and only refers to the module's own resources is
+    /// kept; anything else becomes a variable.
+    fn value(&mut self, name: &str, exprs: &[&hcl::Expression], addresses: &[&str]) -> Result<hcl::Expression, Box<dyn std::error::Error>> {
+        let rewritten: Vec<hcl::Expression> = exprs.iter().zip(&self.internal).map(|(e, internal)| rewrite(e, internal)).collect();
+        let mut references = BTreeSet::new();
+        expression_references(&rewritten[0], &mut references);
+        if rewritten.iter().all(|e| *e == rewritten[0]) && references.is_subset(&self.module_addresses) {
+            return Ok(rewritten[0].clone());
+        }
+        for ((expr, internal), address) in exprs.iter().zip(&self.internal).zip(addresses) {
+            let mut references = BTreeSet::new();
+            expression_references(expr, &mut references);
+            if let Some(reference) = references.iter().find(|r| internal.contains_key(*r)) {
+                return Err(format!("{} refers to {} in a way that differs between the subtrees; align them first", address, reference).into());
+            }
+        }
+        let mut variable = hcl::Identifier::sanitized(name).to_string();
+        let mut n = 2;
+        while self.variables.contains_key(&variable) {
+            variable = format!("{}_{}", hcl::Identifier::sanitized(name), n);
+            n += 1;
+        }
+        self.variables.insert(variable.clone(), exprs.iter().map(|e| (*e).clone()).collect());
+        Ok(format!("var.{}", variable).parse()?)
+    }
+
+    /// `provider = google.project_team_a` becomes an aliased configuration passed in by the caller.
+    fn provider(&mut self, exprs: &[&hcl::Expression], addresses: &[&str]) -> Result<hcl::Expression, Box<dyn std::error::Error>> {
+        let local = generalize(&exprs[0].to_string(), self.key);
+        for ((expr, providers), address) in exprs.iter().zip(self.providers.iter_mut()).zip(addresses) {
+            let configured = expr.to_string();
+            if let Some(other) = providers.iter().find(|(c, l)| **l == local && **c != configured).map(|(c, _)| c) {
+                return Err(format!("{} uses {} where the other resources of its subtree use {}", address, configured, other).into());
+            }
+            providers.insert(configured, local.clone());
+        }
+        Ok(local.parse()?)
+    }
+
+    /// Entries within the subtrees are kept, the others move to the module instance.
+    fn depends_on(&mut self, exprs: &[&hcl::Expression]) -> hcl::Expression {
+        let mut kept = Vec::new();
+        for (i, expr) in exprs.iter().enumerate() {
+            let hcl::Expression::Array(items) = expr else { continue };
+            for item in items {
+                let mut references = BTreeSet::new();
+                expression_references(item, &mut references);
+                if references.iter().any(|r| self.internal[i].contains_key(r)) {
+                    if i == 0 {
+                        kept.push(rewrite(item, &self.internal[0]));
+                    }
+                } else if !self.depends_on[i].contains(item) {
+                    self.depends_on[i].push(item.clone());
+                }
+            }
+        }
+        hcl::Expression::Array(kept)
+    }
+}
+
+/// `team_a_logs` -> `this_logs` for the key `team_a`.
+fn generalize(label: &str, key: &str) -> String {
+    if key.is_empty() {
+        label.to_string()
+    } else {
+        label.replace(key, "this")
+    }
+}
+
+/// `google_storage_bucket` -> `storage_bucket`
+fn short_type(tf_type: &str) -> String {
+    tf_type.split_once('_').map_or(tf_type, |(_, rest)| rest).to_string()
+}
+
+/// A module input in the YAML: literals as they are, anything else as `!expr`.
+fn yaml_value(expr: &hcl::Expression) -> serde_yaml::Value {
+    match expr {
+        hcl::Expression::Null => serde_yaml::Value::Null,
+        hcl::Expression::Bool(b) => serde_yaml::Value::Bool(*b),
+        hcl::Expression::String(s) => serde_yaml::Value::String(s.clone()),
+        hcl::Expression::Number(n) => serde_yaml::to_value(n).unwrap_or(serde_yaml::Value::Null),
+        other => serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+            tag: serde_yaml::value::Tag::new("expr"),
+            value: serde_yaml::Value::String(hcl::format::to_string(other).unwrap_or_default()),
+        })),
+    }
+}
+
+/// Addresses referenced in a body: `google_folder.x`, `var.x`, `local.x`, `module.x`, `data.t.x`.
+fn body_references(body: &hcl::Body, references: &mut BTreeSet<String>) {
+    for structure in body.iter() {
+        match structure {
+            hcl::Structure::Attribute(attr) => expression_references(attr.expr(), references),
+            hcl::Structure::Block(block) if block.identifier() != "lifecycle" => body_references(&block.body, references),
+            hcl::Structure::Block(_) => {}
+        }
+    }
+}
+
+fn expression_references(expr: &hcl::Expression, references: &mut BTreeSet<String>) {
+    for_each_child(expr, &mut |child| expression_references(child, references));
+    match expr {
+        hcl::Expression::Traversal(traversal) => {
+            if let Some(address) = traversal_address(traversal) {
+                references.insert(address);
+            }
+        }
+        hcl::Expression::TemplateExpr(template) => {
+            // A template that cannot be read counts as referring to something outside
+            match hcl::Template::from_expr(template) {
+                Ok(template) => template_references(template.elements(), references),
+                Err(_) => { references.insert(String::new()); }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn template_references(elements: &[hcl::template::Element], references: &mut BTreeSet<String>) {
+    for element in elements {
+        match element {
+            hcl::template::Element::Literal(_) => {}
+            hcl::template::Element::Interpolation(interpolation) => expression_references(&interpolation.expr, references),
+            hcl::template::Element::Directive(_) => { references.insert(String::new()); }
+        }
+    }
+}
+
+/// `google_folder.x.name` -> `google_folder.x`, `data.google_project.p.id` -> `data.google_project.p`
+fn traversal_address(traversal: &hcl::expr::Traversal) -> Option<String> {
+    let hcl::Expression::Variable(root) = &traversal.expr else { return None };
+    if BUILTIN_ROOTS.contains(&root.as_str()) {
+        return None;
+    }
+    let attrs: Vec<&str> = traversal.operators.iter()
+        .map_while(|op| match op {
+            hcl::expr::TraversalOperator::GetAttr(ident) => Some(ident.as_str()),
+            _ => None,
+        })
+        .collect();
+    let take = if root.as_str() == "data" { 2 } else { 1 };
+    (attrs.len() >= take).then(|| std::iter::once(root.as_str()).chain(attrs[..take].iter().copied()).collect::<Vec<_>>().join("."))
+}
+
+/// An expression with the references to the subtree's resources pointing to the module's.
+fn rewrite(expr: &hcl::Expression, internal: &HashMap<String, String>) -> hcl::Expression {
+    let mut expr = expr.clone();
+    rewrite_in_place(&mut expr, internal);
+    expr
+}
+
+/// Replaces the referenced addresses by what they map to: `google_folder.team_a.name` ->
+/// `google_folder.this.name` or `module.team_a.folder_team_a.name`.
+fn rewrite_in_place(expr: &mut hcl::Expression, internal: &HashMap<String, String>) {
+    match expr {
+        hcl::Expression::Traversal(traversal) => {
+            if let Some(address) = traversal_address(traversal) {
+                if let Some(Ok(hcl::Expression::Traversal(replacement))) = internal.get(&address).map(|r| r.parse::<hcl::Expression>()) {
+                    let consumed = address.split('.').count() - 1;
+                    let mut operators = replacement.operators;
+                    operators.extend(traversal.operators.drain(consumed..));
+                    traversal.expr = replacement.expr;
+                    traversal.operators = operators;
+                }
+            }
+            for op in traversal.operators.iter_mut() {
+                if let hcl::expr::TraversalOperator::Index(index) = op {
+                    rewrite_in_place(index, internal);
+                }
+            }
+        }
+        hcl::Expression::TemplateExpr(template) => {
+            let text = match template.as_mut() {
+                hcl::expr::TemplateExpr::QuotedString(s) => s,
+                hcl::expr::TemplateExpr::Heredoc(heredoc) => &mut heredoc.template,
+            };
+            for (from, to) in internal {
+                if let Ok(pattern) = regex::Regex::new(&format!(r"\b{}\b", regex::escape(from))) {
+                    *text = pattern.replace_all(text, regex::NoExpand(to)).into_owned();
+                }
+            }
+        }
+        _ => for_each_child_mut(expr, &mut |child| rewrite_in_place(child, internal)),
+    }
+}
+
+/// `split_output = "module"`: the resources of each top-level folder go into their own module
+/// under `modules/<key>`, called from the root `main.tf` with the providers and values they
+/// need. Returns the files of the root module and of the folder modules; references from the
+/// root's blocks, outputs and imports are pointed to the module outputs and addresses.
+pub fn module_layout(main_blocks: &[hcl::Block], outputs: &mut [hcl::Block], imports: &mut [hcl::Block], sources: &BTreeMap<String, String>, provider_sources: &HashMap<String, String>) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let body = hcl::Body(main_blocks.iter().cloned().map(hcl::Structure::Block).collect());
+    let outputs_body = hcl::Body(outputs.iter().cloned().map(hcl::Structure::Block).collect());
+    let folders: BTreeSet<&str> = sources.iter()
+        .filter(|(address, _)| !address.starts_with("module."))
+        .filter_map(|(_, yaml_path)| yaml_path.strip_prefix("folder.")?.split('.').next())
+        .collect();
+
+    let mut files = Vec::new();
+    let mut extractions = Vec::new();
+    for key in folders {
+        let name = resource_label(key);
+        if main_blocks.iter().any(|b| b.identifier() == "module" && b.labels().first().is_some_and(|l| l.as_str() == name)) {
+            return Err(format!("The module of folder '{}' would replace the YAML module '{}'; rename one of them", key, name).into());
+        }
+        let extraction = extract(&body, &outputs_body, sources, &[format!("folder.{}", key)], &name, provider_sources)?;
+        for (file, content) in &extraction.files {
+            files.push((format!("modules/{}/{}", name, file), content.clone()));
+        }
+        extractions.push(extraction);
+    }
+
+    // Current address -> module output for references, the module itself for `depends_on` and
+    // the address in the module for imports
+    let mut to_output = HashMap::new();
+    let mut to_module = HashMap::new();
+    for instance in extractions.iter().flat_map(|e| &e.instances) {
+        for (address, output) in &instance.outputs {
+            to_output.insert(address.clone(), output.clone());
+            to_module.insert(address.clone(), format!("module.{}", instance.name));
+        }
+    }
+
+    let mut root = hcl::Body::builder();
+    for block in main_blocks {
+        let address = format!("{}.{}", block.labels().first().map_or("", |l| l.as_str()), block.labels().get(1).map_or("", |l| l.as_str()));
+        if block.identifier() == "resource" && to_module.contains_key(&address) {
+            continue;
+        }
+        let mut block = block.clone();
+        // Chains of moved blocks are followed, the old addresses stay as they are
+        if block.identifier() != "moved" {
+            rewrite_body(&mut block.body, &to_output, &to_module);
+        }
+        root = root.add_block(block);
+    }
+    for instance in extractions.iter().flat_map(|e| &e.instances) {
+        let mut module = hcl::Block::builder("module")
+            .add_label(&instance.name)
+            .add_attribute(("source", format!("./modules/{}", instance.name)));
+        if !instance.providers.is_empty() {
+            let mut providers = hcl::Object::new();
+            for (local, configured) in &instance.providers {
+                providers.insert(hcl::ObjectKey::Expression(local.parse()?), configured.parse::<hcl::Expression>()?);
+            }
+            module = module.add_attribute(("providers", hcl::Expression::Object(providers)));
+        }
+        for (variable, value) in &instance.inputs {
+            module = module.add_attribute((variable.as_str(), rewrite(value, &to_output)));
+        }
+        let mut module_body = module.build();
+        if !instance.depends_on.is_empty() {
+            module_body.body.0.push(hcl::Structure::Attribute(hcl::Attribute::new("depends_on", hcl::Expression::Array(instance.depends_on.clone()))));
+            rewrite_body(&mut module_body.body, &to_output, &to_module);
+        }
+        root = root.add_block(module_body);
+    }
+    for block in extractions.into_iter().flat_map(|e| e.moved) {
+        root = root.add_block(block);
+    }
+    files.insert(0, ("main.tf".to_string(), hcl::to_string(&root.build())?));
+
+    for output in outputs.iter_mut() {
+        rewrite_body(&mut output.body, &to_output, &to_module);
+    }
+    for import in imports.iter_mut() {
+        for structure in import.body.0.iter_mut() {
+            if let hcl::Structure::Attribute(attr) = structure {
+                let hcl::Expression::Traversal(traversal) = &attr.expr else { continue };
+                if attr.key() != "to" {
+                    continue;
+                }
+                if let Some(module) = traversal_address(traversal).and_then(|a| to_module.get(&a)) {
+                    attr.expr = format!("{}.{}", module, attr.expr).parse()?;
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// References to moved resources in a block outside the modules: `depends_on` entries name the
+/// module, anything else its outputs.
+fn rewrite_body(body: &mut hcl::Body, to_output: &HashMap<String, String>, to_module: &HashMap<String, String>) {
+    for structure in body.0.iter_mut() {
+        match structure {
+            hcl::Structure::Attribute(attr) if attr.key() == "depends_on" => {
+                let hcl::Expression::Array(items) = &mut attr.expr else { continue };
+                let mut entries: Vec<hcl::Expression> = Vec::new();
+                for item in items.drain(..) {
+                    let module = match &item {
+                        hcl::Expression::Traversal(traversal) => traversal_address(traversal).and_then(|a| to_module.get(&a)),
+                        _ => None,
+                    };
+                    let entry = match module.map(|m| m.parse::<hcl::Expression>()) {
+                        Some(Ok(module)) => module,
+                        _ => item,
+                    };
+                    if !entries.contains(&entry) {
+                        entries.push(entry);
+                    }
+                }
+                *items = entries;
+            }
+            hcl::Structure::Attribute(attr) => rewrite_in_place(&mut attr.expr, to_output),
+            hcl::Structure::Block(block) if block.identifier() != "lifecycle" => rewrite_body(&mut block.body, to_output, to_module),
+            hcl::Structure::Block(_) => {}
+        }
+    }
+}
+
+fn for_each_child(expr: &hcl::Expression, f: &mut dyn FnMut(&hcl::Expression)) {
+    match expr {
+        hcl::Expression::Array(items) => items.iter().for_each(f),
+        hcl::Expression::Object(object) => object.values().for_each(f),
+        hcl::Expression::FuncCall(call) => call.args.iter().for_each(f),
+        hcl::Expression::Parenthesis(inner) => f(inner),
+        hcl::Expression::Conditional(c) => [&c.cond_expr, &c.true_expr, &c.false_expr].into_iter().for_each(f),
+        hcl::Expression::Operation(op) => match op.as_ref() {
+            hcl::expr::Operation::Unary(u) => f(&u.expr),
+            hcl::expr::Operation::Binary(b) => [&b.lhs_expr, &b.rhs_expr].into_iter().for_each(f),
+        },
+        hcl::Expression::Traversal(traversal) => {
+            for op in &traversal.operators {
+                if let hcl::expr::TraversalOperator::Index(index) = op {
+                    f(index);
+                }
+            }
+        }
+        // The loop variables are no references; only the collection is looked at
+        hcl::Expression::ForExpr(for_expr) => f(&for_expr.collection_expr),
+        _ => {}
+    }
+}
+
+/// Calls `f` for the direct sub-expressions of `expr` (not for traversal indexes).
+pub fn for_each_child_mut(expr: &mut hcl::Expression, f: &mut dyn FnMut(&mut hcl::Expression)) {
+    match expr {
+        hcl::Expression::Array(items) => items.iter_mut().for_each(f),
+        hcl::Expression::Object(object) => object.values_mut().for_each(f),
+        hcl::Expression::FuncCall(call) => call.args.iter_mut().for_each(f),
+        hcl::Expression::Parenthesis(inner) => f(inner),
+        hcl::Expression::Conditional(c) => {
+            f(&mut c.cond_expr);
+            f(&mut c.true_expr);
+            f(&mut c.false_expr);
+        }
+        hcl::Expression::Operation(op) => match op.as_mut() {
+            hcl::expr::Operation::Unary(u) => f(&mut u.expr),
+            hcl::expr::Operation::Binary(b) => {
+                f(&mut b.lhs_expr);
+                f(&mut b.rhs_expr);
+            }
+        },
+        hcl::Expression::ForExpr(for_expr) => f(&mut for_expr.collection_expr),
+        _ => {}
+    }
+}
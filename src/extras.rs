@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::manifest::{ExtraEntry, Manifest, EXTRAS_APPLIED, STATE_DIR};
+
+/// A YAML entry without Terraform support (`unsupported: true`, or a type without schema that
+/// defines `apply:`). It is applied with the given shell (gcloud/REST) commands instead.
+#[derive(Debug, Clone)]
+pub struct UnsupportedResource {
+    /// `<type>.<name>`, used as key in the manifest
+    pub address: String,
+    pub apply: Vec<String>,
+    pub destroy: Vec<String>,
+}
+
+/// gcloud command groups of settings without a Terraform resource, by YAML type (without
+/// `google_`), and whether the group has a `delete` command. Entries of these types need no
+/// `apply:`; the commands are derived from their attributes.
+const GCLOUD_GROUPS: &[(&str, &str, bool)] = &[
+    ("access_approval_settings", "access-approval settings", true),
+    ("logging_settings", "logging settings", false),
+];
+
+/// Keys of an entry that configure the extras script instead of being gcloud flags.
+const EXTRAS_KEYS: &[&str] = &["unsupported", "apply", "destroy", "gcloud"];
+
+/// Returns true if a resource instance should be handled by the extras script.
+pub fn is_unsupported(attrs: &serde_yaml::Mapping, has_schema: bool) -> bool {
+    attrs.get("unsupported").and_then(|v| v.as_bool()) == Some(true)
+        || (!has_schema && (attrs.contains_key("apply") || attrs.contains_key("gcloud")))
+}
+
+/// Returns true if every instance of a resource type without schema is handled by the extras script.
+pub fn is_unsupported_group(value: &serde_yaml::Value) -> bool {
+    value.as_mapping().is_some_and(|m| {
+        !m.is_empty() && m.values().all(|v| v.as_mapping().is_some_and(|attrs| is_unsupported(attrs, false)))
+    })
+}
+
+/// Reads the `apply:` / `destroy:` commands (a string or a list of strings) of an entry and
+/// substitutes `{name}`, `{project}`, `{org}` and `{<attribute>}` for scalar attributes, each
+/// value shell-quoted.
+pub fn commands(attrs: &serde_yaml::Mapping, key: &str, placeholders: &BTreeMap<String, String>) -> Vec<String> {
+    let raw: Vec<String> = match attrs.get(key) {
+        Some(serde_yaml::Value::String(s)) => vec![s.clone()],
+        Some(serde_yaml::Value::Sequence(seq)) => seq.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect(),
+        _ => Vec::new(),
+    };
+    raw.into_iter()
+        .map(|cmd| {
+            placeholders.iter().fold(cmd, |acc, (k, v)| acc.replace(&format!("{{{}}}", k), &shell_quote(v)))
+        })
+        .collect()
+}
+
+/// The gcloud commands of an entry without `apply:`, from its `gcloud:` command group or the one
+/// known for its type: `gcloud <group> update <scope> --<attribute>=<value>...` and
+/// `gcloud <group> delete <scope>` (none for groups known to have no `delete`). Attributes become
+/// flags under their YAML key; lists are joined with commas and booleans become `--<attribute>` /
+/// `--no-<attribute>`. The scope is `--project=<project>`, else `--organization=<org>`. `None`
+/// if the group is not known.
+pub fn derived_commands(tf_type: &str, attrs: &serde_yaml::Mapping, project: Option<&str>, org: Option<&str>) -> Option<(Vec<String>, Vec<String>)> {
+    let (group, deletable) = match attrs.get("gcloud").and_then(|v| v.as_str()) {
+        Some(group) => (group, true),
+        None => {
+            let tf_type = tf_type.strip_prefix("google_").unwrap_or(tf_type);
+            GCLOUD_GROUPS.iter().find(|(t, _, _)| *t == tf_type).map(|(_, group, deletable)| (*group, *deletable))?
+        }
+    };
+    let scope = match (project, org) {
+        (Some(project), _) => format!(" --project={}", shell_quote(project)),
+        (None, Some(org)) => format!(" --organization={}", shell_quote(org)),
+        (None, None) => String::new(),
+    };
+
+    let mut flags = String::new();
+    for (k, v) in attrs {
+        let Some(k) = k.as_str().filter(|k| !EXTRAS_KEYS.contains(k)) else { continue };
+        match v {
+            serde_yaml::Value::Bool(true) => flags.push_str(&format!(" --{}", k)),
+            serde_yaml::Value::Bool(false) => flags.push_str(&format!(" --no-{}", k)),
+            serde_yaml::Value::Sequence(items) => {
+                let items: Vec<String> = items.iter().filter_map(scalar).collect();
+                flags.push_str(&format!(" --{}={}", k, shell_quote(&items.join(","))));
+            }
+            _ => {
+                if let Some(value) = scalar(v) {
+                    flags.push_str(&format!(" --{}={}", k, shell_quote(&value)));
+                }
+            }
+        }
+    }
+    let apply = vec![format!("gcloud {} update{}{}", group, scope, flags)];
+    let destroy = deletable.then(|| format!("gcloud {} delete{}", group, scope)).into_iter().collect();
+    Some((apply, destroy))
+}
+
+fn scalar(v: &serde_yaml::Value) -> Option<String> {
+    match v {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// `value` as one shell word: as it is if it only has characters the shell leaves alone, else in
+/// single quotes.
+pub fn shell_quote(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Writes `scripts/apply-extras.sh` for the current unsupported resources and updates the
+/// manifest. Resources recorded in the manifest but no longer in the YAML get their destroy
+/// commands in the apply section, until the apply section of a generated script has run.
+pub fn write_extras_script(config_dir: &Path, input_name: &str, resources: &[UnsupportedResource]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut manifest = Manifest::load(config_dir)?;
+
+    let current: BTreeMap<String, ExtraEntry> = resources.iter()
+        .map(|r| (r.address.clone(), ExtraEntry { apply: r.apply.clone(), destroy: r.destroy.clone() }))
+        .collect();
+    // The removals of the previous script are done once its apply section has run
+    let applied_path = config_dir.join(STATE_DIR).join(EXTRAS_APPLIED);
+    let applied = fs::read_to_string(&applied_path).ok().and_then(|s| s.trim().parse::<u64>().ok());
+    let mut removed = match applied == Some(manifest.extras_generation) {
+        true => BTreeMap::new(),
+        false => std::mem::take(&mut manifest.removed_extras),
+    };
+    removed.extend(manifest.extras.iter()
+        .filter(|(address, _)| !current.contains_key(*address))
+        .map(|(address, entry)| (address.clone(), entry.clone())));
+    removed.retain(|address, _| !current.contains_key(address));
+    let generation = manifest.extras_generation + 1;
+
+    let mut script = String::new();
+    script.push_str("#!/bin/sh\n");
+    script.push_str(&format!("# Generated by cfg2hcl from {} - do not edit.\n", input_name));
+    script.push_str("# Resources without Terraform support. Usage: apply-extras.sh [apply|destroy]\n");
+    script.push_str("set -eu\n\n");
+
+    script.push_str("apply() {\n    :\n");
+    for (address, entry) in &removed {
+        script.push_str(&format!("    # {} (removed from YAML)\n", address));
+        for cmd in &entry.destroy {
+            script.push_str(&format!("    {}\n", cmd));
+        }
+    }
+    for (address, entry) in &current {
+        script.push_str(&format!("    # {}\n", address));
+        for cmd in &entry.apply {
+            script.push_str(&format!("    {}\n", cmd));
+        }
+    }
+    script.push_str(&format!("    # Confirms to cfg2hcl that the removed resources are gone\n    mkdir -p \"$(dirname \"$0\")/../{}\"\n", STATE_DIR));
+    script.push_str(&format!("    echo {} > \"$(dirname \"$0\")/../{}/{}\"\n", generation, STATE_DIR, EXTRAS_APPLIED));
+    script.push_str("}\n\n");
+
+    script.push_str("destroy() {\n    :\n");
+    for (address, entry) in current.iter().rev() {
+        script.push_str(&format!("    # {}\n", address));
+        for cmd in &entry.destroy {
+            script.push_str(&format!("    {}\n", cmd));
+        }
+    }
+    script.push_str("}\n\n");
+
+    script.push_str("case \"${1:-apply}\" in\n");
+    script.push_str("    apply) apply ;;\n");
+    script.push_str("    destroy) destroy ;;\n");
+    script.push_str("    *) echo \"Usage: $0 [apply|destroy]\" >&2; exit 1 ;;\n");
+    script.push_str("esac\n");
+
+    let scripts_dir = config_dir.join("scripts");
+    fs::create_dir_all(&scripts_dir)?;
+    let script_path = scripts_dir.join("apply-extras.sh");
+    fs::write(&script_path, script)
+        .map_err(|e| format!("Failed to write '{}': {}", script_path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))?;
+    }
+    println!("Created {}", script_path.display());
+
+    for address in removed.keys() {
+        println!("Note: {} was removed from the YAML; its destroy commands run with the next apply-extras.sh.", address);
+    }
+
+    manifest.extras = current;
+    manifest.removed_extras = removed;
+    manifest.extras_generation = generation;
+    manifest.record_generated(config_dir, &script_path, true);
+    manifest.save(config_dir)
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use serde_yaml::Value;
+
+/// How the file of a `!file` value ends up in the HCL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// The contents as a heredoc
+    Inline,
+    /// `file("${path.root}/...")`, read by Terraform
+    Reference,
+}
+
+/// Replaces every `!file` value by an `!expr`: `!file path` (or `!file [path, inline]`) by a
+/// heredoc with the contents of the file, `!file [path, reference]` by a `file()` call relative
+/// to `path.root`, which stays valid when resources are moved into child modules. Relative paths
+/// are resolved against `yaml_dir`; the file must exist in both modes.
+pub fn apply(value: &mut Value, yaml_dir: &Path, hcl_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    match value {
+        Value::Mapping(map) => map.values_mut().try_for_each(|v| apply(v, yaml_dir, hcl_dir)),
+        Value::Sequence(seq) => seq.iter_mut().try_for_each(|v| apply(v, yaml_dir, hcl_dir)),
+        Value::Tagged(tagged) if tagged.tag == "!file" => {
+            let (path, mode) = parse(&tagged.value)?;
+            let file = yaml_dir.join(path);
+            let expr = match mode {
+                Mode::Inline => {
+                    let content = fs::read_to_string(&file)
+                        .map_err(|e| format!("Failed to read '{}' of !file {}: {}", file.display(), path, e))?;
+                    heredoc(&content)
+                }
+                Mode::Reference => {
+                    if !file.is_file() {
+                        return Err(format!("File '{}' of !file {} does not exist", file.display(), path).into());
+                    }
+                    let relative = relative_path(&file, hcl_dir)?;
+                    format!("file(\"${{path.root}}/{}\")", relative.replace('\\', "/").replace('"', "\\\""))
+                }
+            };
+            *value = Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+                tag: serde_yaml::value::Tag::new("!expr"),
+                value: Value::String(expr),
+            }));
+            Ok(())
+        }
+        Value::Tagged(tagged) => apply(&mut tagged.value, yaml_dir, hcl_dir),
+        _ => Ok(()),
+    }
+}
+
+/// Path and mode of `path` or `[path, inline|reference]`.
+fn parse(value: &Value) -> Result<(&str, Mode), Box<dyn std::error::Error>> {
+    const USAGE: &str = "!file takes a path or [path, inline|reference]";
+    match value {
+        Value::String(path) => Ok((path, Mode::Inline)),
+        Value::Sequence(items) => match items.as_slice() {
+            [Value::String(path), Value::String(mode)] => match mode.as_str() {
+                "inline" => Ok((path, Mode::Inline)),
+                "reference" => Ok((path, Mode::Reference)),
+                other => Err(format!("Unknown !file mode '{}': {}", other, USAGE).into()),
+            },
+            _ => Err(USAGE.into()),
+        },
+        _ => Err(USAGE.into()),
+    }
+}
+
+/// `content` as a heredoc whose end marker does not occur in it. `${` and `%{` are escaped, so
+/// scripts keep their shell variables.
+fn heredoc(content: &str) -> String {
+    let mut marker = "EOT".to_string();
+    while content.lines().any(|line| line.trim() == marker) {
+        marker.push('_');
+    }
+    let mut body = content.replace("${", "$${").replace("%{", "%%{");
+    if !body.is_empty() && !body.ends_with('\n') {
+        body.push('\n');
+    }
+    format!("<<{}\n{}{}", marker, body, marker)
+}
+
+/// `path` relative to the directory `base`, e.g. `../yaml/scripts/startup.sh`.
+fn relative_path(path: &Path, base: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let path = path.canonicalize()?;
+    let base = match base.canonicalize() {
+        Ok(base) => base,
+        // hcl_dir may not exist before the first transpile
+        Err(_) => std::env::current_dir()?.join(base).components().fold(PathBuf::new(), |mut acc, c| {
+            match c {
+                Component::CurDir => {}
+                Component::ParentDir => { acc.pop(); }
+                c => acc.push(c),
+            }
+            acc
+        }),
+    };
+    let common = path.components().zip(base.components()).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(path.components().skip(common));
+    Ok(relative.display().to_string())
+}
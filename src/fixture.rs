@@ -0,0 +1,284 @@
+use serde_json::{json, Value};
+use serde_yaml::Mapping;
+
+/// Organization, customer and billing account of every fixture (obviously fake values).
+const ORG_ID: &str = "123456789012";
+const CUSTOMER_ID: &str = "C0fixture";
+const DOMAIN: &str = "example.com";
+const BILLING_ACCOUNT: &str = "000000-000000-000000";
+const REGION: &str = "europe-west3";
+
+const ADJECTIVES: &[&str] = &["amber", "bold", "calm", "dusty", "eager", "fuzzy", "gentle", "hazy", "icy", "jolly", "keen", "lucky", "misty", "noble", "olive", "proud", "quiet", "rapid", "silver", "tidy"];
+const NOUNS: &[&str] = &["badger", "cedar", "delta", "ember", "falcon", "glacier", "harbor", "island", "juniper", "kestrel", "lagoon", "meadow", "nebula", "orchid", "pebble", "quartz", "river", "summit", "tundra", "willow"];
+const ENVS: &[&str] = &["dev", "test", "prod"];
+const LOCATIONS: &[&str] = &["EU", "US", "europe-west3", "europe-west1", "us-central1"];
+
+/// Size and seed of a fixture; the same values always give the same files.
+pub struct FixtureSpec {
+    pub folders: usize,
+    pub projects: usize,
+    pub resources: usize,
+    pub seed: u64,
+}
+
+/// A generated YAML config and a `show -json` state with the resources it would create.
+pub struct Fixture {
+    pub yaml: String,
+    pub state: Value,
+}
+
+/// SplitMix64: tiny, fast and stable across releases (unlike the algorithms of random crates).
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// A numeric ID like those of folders and projects.
+    fn number(&mut self) -> String {
+        format!("{}", 100_000_000_000 + self.next() % 900_000_000_000)
+    }
+}
+
+struct GenFolder {
+    key: String,
+    display_name: String,
+    id: String,
+    parent: Option<usize>,
+    env: &'static str,
+}
+
+struct GenProject {
+    key: String,
+    project_id: String,
+    number: String,
+    folder: Option<usize>,
+    env: &'static str,
+}
+
+struct GenResource {
+    tf_type: &'static str,
+    key: String,
+    project: usize,
+    attributes: Mapping,
+}
+
+/// Builds a synthetic organization: folders nested below each other or the organization,
+/// projects spread over the folders and resources spread over the projects.
+pub fn generate(spec: &FixtureSpec) -> Result<Fixture, Box<dyn std::error::Error>> {
+    if spec.resources > 0 && spec.projects == 0 {
+        return Err("--resources needs at least one project".into());
+    }
+    let mut rng = Rng(spec.seed);
+
+    let mut folders = Vec::new();
+    for i in 0..spec.folders {
+        let noun = rng.pick(NOUNS);
+        // About a third of the folders are nested, always below an earlier one
+        let parent = (i > 0 && rng.below(3) == 0).then(|| rng.below(i));
+        folders.push(GenFolder {
+            key: format!("{}_{:03}", noun, i + 1),
+            display_name: format!("{}{} {}", noun[..1].to_uppercase(), &noun[1..], i + 1),
+            id: rng.number(),
+            parent,
+            env: rng.pick(ENVS),
+        });
+    }
+
+    let mut projects = Vec::new();
+    for i in 0..spec.projects {
+        let folder = (!folders.is_empty()).then(|| rng.below(folders.len()));
+        let project_id = format!("fx-{}-{}-{:04}", rng.pick(ADJECTIVES), rng.pick(NOUNS), i + 1);
+        projects.push(GenProject {
+            key: project_id.replace('-', "_"),
+            project_id,
+            number: rng.number(),
+            folder,
+            env: folder.map_or_else(|| rng.pick(ENVS), |f| folders[f].env),
+        });
+    }
+
+    let mut resources = Vec::new();
+    for i in 0..spec.resources {
+        let project = rng.below(spec.projects.max(1));
+        let word = rng.pick(NOUNS);
+        let mut attributes = Mapping::new();
+        let tf_type = match rng.below(3) {
+            0 => {
+                attributes.insert("name".into(), format!("{}-{}-{:04}", projects[project].project_id, word, i + 1).into());
+                attributes.insert("location".into(), rng.pick(LOCATIONS).into());
+                attributes.insert("uniform_bucket_level_access".into(), true.into());
+                let mut labels = Mapping::new();
+                labels.insert("env".into(), projects[project].env.into());
+                labels.insert("purpose".into(), word.into());
+                attributes.insert("labels".into(), labels.into());
+                "google_storage_bucket"
+            }
+            1 => {
+                attributes.insert("account_id".into(), format!("sa-{}-{:04}", word, i + 1).into());
+                attributes.insert("display_name".into(), format!("{} service account {}", word, i + 1).into());
+                "google_service_account"
+            }
+            _ => {
+                attributes.insert("name".into(), format!("net-{}-{:04}", word, i + 1).into());
+                attributes.insert("auto_create_subnetworks".into(), (rng.below(2) == 0).into());
+                "google_compute_network"
+            }
+        };
+        resources.push(GenResource { tf_type, key: format!("{}_{:04}", word, i + 1), project, attributes });
+    }
+
+    let yaml = render_yaml(spec, &folders, &projects, &resources)?;
+    let state = render_state(&folders, &projects, &resources);
+    Ok(Fixture { yaml, state })
+}
+
+fn render_yaml(spec: &FixtureSpec, folders: &[GenFolder], projects: &[GenProject], resources: &[GenResource]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut root = Mapping::new();
+    let mut variables = Mapping::new();
+    variables.insert("customer-id".into(), CUSTOMER_ID.into());
+    variables.insert("customer-organization-id".into(), ORG_ID.into());
+    variables.insert("customer-domain".into(), DOMAIN.into());
+    variables.insert("billing-account-infra".into(), BILLING_ACCOUNT.into());
+    variables.insert("default-region".into(), REGION.into());
+    root.insert("variables".into(), variables.into());
+    root.insert("terraform".into(), serde_yaml::from_str::<serde_yaml::Value>("backend:\n  local:\n    path: terraform.tfstate\n")?);
+    root.insert("providers".into(), serde_yaml::from_str::<serde_yaml::Value>(&format!("google:\n  region: {}\n", REGION))?);
+
+    let project_entry = |p: usize| -> Mapping {
+        let project = &projects[p];
+        let mut entry = Mapping::new();
+        entry.insert("project_id".into(), project.project_id.clone().into());
+        let mut labels = Mapping::new();
+        labels.insert("env".into(), project.env.into());
+        entry.insert("labels".into(), labels.into());
+        for resource in resources.iter().filter(|r| r.project == p) {
+            let by_type = entry.entry(resource.tf_type.into()).or_insert_with(|| Mapping::new().into());
+            if let serde_yaml::Value::Mapping(by_type) = by_type {
+                by_type.insert(resource.key.clone().into(), resource.attributes.clone().into());
+            }
+        }
+        entry
+    };
+    let projects_in = |folder: Option<usize>| -> Mapping {
+        (0..projects.len()).filter(|p| projects[*p].folder == folder)
+            .map(|p| (projects[p].key.clone().into(), project_entry(p).into()))
+            .collect()
+    };
+    fn folder_entry(f: usize, folders: &[GenFolder], projects_in: &dyn Fn(Option<usize>) -> Mapping) -> Mapping {
+        let mut entry = Mapping::new();
+        entry.insert("display_name".into(), folders[f].display_name.clone().into());
+        let children: Mapping = (0..folders.len()).filter(|c| folders[*c].parent == Some(f))
+            .map(|c| (folders[c].key.clone().into(), folder_entry(c, folders, projects_in).into()))
+            .collect();
+        if !children.is_empty() {
+            entry.insert("folder".into(), children.into());
+        }
+        let projects = projects_in(Some(f));
+        if !projects.is_empty() {
+            entry.insert("project".into(), projects.into());
+        }
+        entry
+    }
+
+    let top_folders: Mapping = (0..folders.len()).filter(|f| folders[*f].parent.is_none())
+        .map(|f| (folders[f].key.clone().into(), folder_entry(f, folders, &projects_in).into()))
+        .collect();
+    if !top_folders.is_empty() {
+        root.insert("folder".into(), top_folders.into());
+    }
+    let top_projects = projects_in(None);
+    if !top_projects.is_empty() {
+        root.insert("project".into(), top_projects.into());
+    }
+
+    let header = format!(
+        "# Synthetic fixture, generated by `cfg2hcl gen-fixture --folders {} --projects {} --resources {} --seed {}`\n",
+        spec.folders, spec.projects, spec.resources, spec.seed,
+    );
+    Ok(header + &serde_yaml::to_string(&root)?)
+}
+
+/// State as written by `show -json`, with the attributes the provider would report after apply.
+fn render_state(folders: &[GenFolder], projects: &[GenProject], resources: &[GenResource]) -> Value {
+    let mut entries = Vec::new();
+    let resource = |tf_type: &str, name: &str, values: Value| json!({
+        "address": format!("{}.{}", tf_type, name),
+        "mode": "managed",
+        "type": tf_type,
+        "name": name,
+        "provider_name": "registry.terraform.io/hashicorp/google",
+        "schema_version": 0,
+        "values": values,
+        "sensitive_values": {},
+    });
+
+    for folder in folders {
+        let parent = folder.parent.map_or_else(|| format!("organizations/{}", ORG_ID), |p| format!("folders/{}", folders[p].id));
+        entries.push(resource("google_folder", &folder.key, json!({
+            "display_name": folder.display_name,
+            "folder_id": folder.id,
+            "id": format!("folders/{}", folder.id),
+            "name": format!("folders/{}", folder.id),
+            "parent": parent,
+        })));
+    }
+    for project in projects {
+        entries.push(resource("google_project", &project.key, json!({
+            "billing_account": BILLING_ACCOUNT,
+            "folder_id": project.folder.map(|f| folders[f].id.clone()),
+            "id": format!("projects/{}", project.project_id),
+            "labels": { "env": project.env },
+            "name": project.project_id,
+            "number": project.number,
+            "org_id": if project.folder.is_none() { Some(ORG_ID) } else { None },
+            "project_id": project.project_id,
+        })));
+    }
+    for res in resources {
+        let project_id = &projects[res.project].project_id;
+        let mut values = serde_json::to_value(&res.attributes).unwrap_or_else(|_| json!({}));
+        values["project"] = json!(project_id);
+        match res.tf_type {
+            "google_storage_bucket" => {
+                let name = values["name"].as_str().unwrap_or_default().to_string();
+                values["location"] = json!(values["location"].as_str().unwrap_or_default().to_uppercase());
+                values["id"] = json!(name);
+                values["self_link"] = json!(format!("https://www.googleapis.com/storage/v1/b/{}", name));
+                values["url"] = json!(format!("gs://{}", name));
+            }
+            "google_service_account" => {
+                let email = format!("{}@{}.iam.gserviceaccount.com", values["account_id"].as_str().unwrap_or_default(), project_id);
+                values["id"] = json!(format!("projects/{}/serviceAccounts/{}", project_id, email));
+                values["name"] = values["id"].clone();
+                values["email"] = json!(email);
+                values["member"] = json!(format!("serviceAccount:{}", email));
+            }
+            _ => {
+                let name = values["name"].as_str().unwrap_or_default().to_string();
+                values["id"] = json!(format!("projects/{}/global/networks/{}", project_id, name));
+                values["self_link"] = json!(format!("https://www.googleapis.com/compute/v1/projects/{}/global/networks/{}", project_id, name));
+            }
+        }
+        entries.push(resource(res.tf_type, &res.key, values));
+    }
+
+    json!({
+        "format_version": "1.0",
+        "terraform_version": "1.9.0",
+        "values": { "root_module": { "resources": entries } },
+    })
+}
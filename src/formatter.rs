@@ -0,0 +1,183 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Formats generated HCL the way `terraform fmt` does, without running it: the `=` of
+/// attributes on consecutive lines of the same body are aligned, blocks are separated by one
+/// blank line, blank lines at the start and end of a body are dropped and trailing whitespace is
+/// removed. With `sort_attributes` the attributes of each top-level block are sorted, see
+/// [`sort_block`]. Comments are kept where they are.
+pub fn format(content: &str, sort_attributes: bool) -> String {
+    let content = if sort_attributes { sort_blocks(content) } else { content.to_string() };
+    let lines = align(&normalize_blank_lines(&content));
+    if lines.is_empty() {
+        return String::new();
+    }
+    lines.join("\n") + "\n"
+}
+
+/// `  key = value`, `  "key" = value` and `  google.alias = value`: (indentation, key)
+fn attribute_line(line: &str) -> Option<(usize, &str)> {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let attribute = ATTRIBUTE.get_or_init(|| Regex::new(r#"^(\s*)([A-Za-z_][A-Za-z0-9_-]*(?:\.[A-Za-z_][A-Za-z0-9_-]*)*|"(?:[^"\\]|\\.)*")\s*=[^=]"#).unwrap());
+    let captures = attribute.captures(line)?;
+    Some((captures[1].len(), captures.get(2)?.as_str()))
+}
+
+/// The end marker of a heredoc opened on the line: `<<EOT` -> `EOT`
+fn heredoc_marker(line: &str) -> Option<String> {
+    static HEREDOC: OnceLock<Regex> = OnceLock::new();
+    let heredoc = HEREDOC.get_or_init(|| Regex::new(r"<<-?([A-Za-z_][A-Za-z0-9_]*)$").unwrap());
+    heredoc.captures(line.trim_end()).map(|c| c[1].to_string())
+}
+
+/// Lines with trailing whitespace removed, runs of blank lines collapsed and no blank lines
+/// after `{`/`[` or before `}`/`]`; heredoc content is left as it is.
+fn normalize_blank_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut heredoc: Option<String> = None;
+    for line in content.lines() {
+        if let Some(marker) = &heredoc {
+            if line.trim() == marker {
+                heredoc = None;
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        let line = line.trim_end();
+        heredoc = heredoc_marker(line);
+        if line.is_empty() {
+            let after_open = lines.last().is_none_or(|l| l.is_empty() || l.ends_with('{') || l.ends_with('['));
+            if !after_open {
+                lines.push(String::new());
+            }
+            continue;
+        }
+        let closing = line.trim_start().starts_with('}') || line.trim_start().starts_with(']');
+        if closing && lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        // One blank line between top-level blocks
+        let top_level_start = !line.starts_with(char::is_whitespace) && !line.starts_with('}');
+        if top_level_start && lines.last().is_some_and(|l| l == "}") {
+            lines.push(String::new());
+        }
+        lines.push(line.to_string());
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Pads the keys of single-line attributes on consecutive lines with the same indentation to the
+/// longest.
+fn align(lines: &[String]) -> Vec<String> {
+    let mut aligned: Vec<String> = lines.to_vec();
+    let mut group: Vec<(usize, usize)> = Vec::new();
+    let mut heredoc: Option<String> = None;
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(marker) = &heredoc {
+            if line.trim() == marker {
+                heredoc = None;
+            }
+            continue;
+        }
+        // Attributes with values over several lines are not aligned and end the group
+        let indentation = |l: &String| l.len() - l.trim_start().len();
+        let multi_line = lines.get(idx + 1).is_some_and(|next| !next.is_empty() && indentation(next) > indentation(line));
+        match attribute_line(line).filter(|_| !multi_line) {
+            Some((indent, key)) if group.last().is_some_and(|(i, _)| *i + 1 == idx && lines[*i].len() - lines[*i].trim_start().len() == indent) => {
+                group.push((idx, key.len()));
+            }
+            Some((_, key)) => {
+                pad(&mut aligned, &group);
+                group = vec![(idx, key.len())];
+            }
+            None => {
+                pad(&mut aligned, &group);
+                group.clear();
+            }
+        }
+        heredoc = heredoc_marker(line);
+    }
+    pad(&mut aligned, &group);
+    aligned
+}
+
+fn pad(lines: &mut [String], group: &[(usize, usize)]) {
+    let width = group.iter().map(|(_, len)| *len).max().unwrap_or_default();
+    for (idx, len) in group {
+        let line = &lines[*idx];
+        let indent = line.len() - line.trim_start().len();
+        let (key, rest) = line.split_at(indent + len);
+        lines[*idx] = format!("{}{}{}", key, " ".repeat(width - len + 1), rest.trim_start());
+    }
+}
+
+/// Each top-level block parsed and written again with sorted attributes; comments between the
+/// blocks stay. Blocks that cannot be parsed are kept as they are.
+fn sort_blocks(content: &str) -> String {
+    let mut result = String::new();
+    let mut chunk = String::new();
+    let mut heredoc: Option<String> = None;
+    for line in content.lines() {
+        if let Some(marker) = &heredoc {
+            if line.trim() == marker {
+                heredoc = None;
+            }
+            chunk.push_str(line);
+            chunk.push('\n');
+            continue;
+        }
+        let in_block = !chunk.is_empty();
+        if !in_block && (line.trim().is_empty() || line.starts_with('#') || line.starts_with(char::is_whitespace)) {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+        chunk.push_str(line);
+        chunk.push('\n');
+        heredoc = heredoc_marker(line);
+        let single_line = !in_block && !line.trim_end().ends_with('{');
+        if single_line || (in_block && line == "}") {
+            result.push_str(&sorted_chunk(&chunk));
+            chunk.clear();
+        }
+    }
+    result.push_str(&chunk);
+    result
+}
+
+fn sorted_chunk(chunk: &str) -> String {
+    match hcl::from_str::<hcl::Body>(chunk) {
+        Ok(mut body) => {
+            for structure in body.0.iter_mut() {
+                if let hcl::Structure::Block(block) = structure {
+                    sort_block(block);
+                }
+            }
+            hcl::to_string(&body).unwrap_or_else(|_| chunk.to_string())
+        }
+        Err(_) => chunk.to_string(),
+    }
+}
+
+/// Meta-arguments (`count`, `for_each`, `provider`, `alias`, `source`, `version`) first, then
+/// the other attributes by name, nested blocks in their order, `depends_on` and `lifecycle` last;
+/// the same in every nested block.
+fn sort_block(block: &mut hcl::Block) {
+    const META_ARGUMENTS: &[&str] = &["count", "for_each", "provider", "alias", "source", "version"];
+    let rank = |structure: &hcl::Structure| match structure {
+        hcl::Structure::Attribute(a) if META_ARGUMENTS.contains(&a.key()) => (0, String::new()),
+        hcl::Structure::Attribute(a) if a.key() == "depends_on" => (3, String::new()),
+        hcl::Structure::Attribute(a) => (1, a.key().to_string()),
+        hcl::Structure::Block(b) if b.identifier() == "lifecycle" => (4, String::new()),
+        hcl::Structure::Block(_) => (2, String::new()),
+    };
+    block.body.0.sort_by_key(rank);
+    for structure in block.body.0.iter_mut() {
+        if let hcl::Structure::Block(nested) = structure {
+            sort_block(nested);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use serde_yaml::{Mapping, Value};
+
+/// Replaces IAM members that name a declared `cloud_identity_group` by its key
+/// (`group:platform-admins`, or `- group: platform-admins` in a list) by the group's email
+/// (`group:platform-admins@<customer-domain>`, or its `id`/`email`), wherever they are: member
+/// map keys, `member`/`members` attributes, billing members and group memberships. Fails for a
+/// `group:` member without `@` that names no declared group.
+pub fn apply(value: &mut Value) -> Result<(), String> {
+    let domain = value.get("customer-domain").and_then(Value::as_str).unwrap_or_default().to_string();
+    let mut groups = BTreeMap::new();
+    collect_groups(value, &domain, &mut groups);
+    let mut errors = Vec::new();
+    resolve(value, &groups, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Group key -> email of every `cloud_identity_group` in any scope.
+fn collect_groups(value: &Value, domain: &str, groups: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Mapping(map) => {
+            for (k, v) in map {
+                if let (Some("cloud_identity_group"), Value::Mapping(declared)) = (k.as_str(), v) {
+                    for (name, attrs) in declared {
+                        let Some(name) = name.as_str() else { continue };
+                        let email = attrs.get("id").or_else(|| attrs.get("email")).and_then(Value::as_str)
+                            .map(|e| e.to_string())
+                            .unwrap_or_else(|| format!("{}@{}", name, domain));
+                        groups.insert(name.to_string(), email);
+                    }
+                }
+                collect_groups(v, domain, groups);
+            }
+        }
+        Value::Sequence(seq) => seq.iter().for_each(|v| collect_groups(v, domain, groups)),
+        _ => {}
+    }
+}
+
+fn resolve(value: &mut Value, groups: &BTreeMap<String, String>, yaml_path: &str, errors: &mut Vec<String>) {
+    match value {
+        Value::Mapping(map) => {
+            *map = std::mem::take(map).into_iter()
+                .map(|(mut k, mut v)| {
+                    let key_path = join(yaml_path, k.as_str().unwrap_or_default());
+                    if let Value::String(key) = &mut k {
+                        resolve_member(key, groups, yaml_path, errors);
+                    }
+                    resolve(&mut v, groups, &key_path, errors);
+                    (k, v)
+                })
+                .collect::<Mapping>();
+        }
+        Value::Sequence(seq) => {
+            for (i, item) in seq.iter_mut().enumerate() {
+                // `- group: platform-admins`
+                let short = item.as_mapping()
+                    .filter(|m| m.len() == 1)
+                    .and_then(|m| m.get("group"))
+                    .and_then(Value::as_str)
+                    .filter(|name| !name.contains('@'))
+                    .map(|name| format!("group:{}", name));
+                if let Some(member) = short {
+                    *item = Value::String(member);
+                }
+                resolve(item, groups, &format!("{}[{}]", yaml_path, i), errors);
+            }
+        }
+        Value::String(s) => resolve_member(s, groups, yaml_path, errors),
+        _ => {}
+    }
+}
+
+fn resolve_member(member: &mut String, groups: &BTreeMap<String, String>, yaml_path: &str, errors: &mut Vec<String>) {
+    let Some(name) = member.strip_prefix("group:").map(str::trim) else { return };
+    if name.contains('@') || name.contains("${") || name.is_empty() {
+        return;
+    }
+    match groups.get(name) {
+        Some(email) => *member = format!("group:{}", email),
+        None => errors.push(format!("IAM member '{}' at '{}' names no declared cloud_identity_group", member, yaml_path)),
+    }
+}
+
+fn join(parent: &str, child: &str) -> String {
+    if parent.is_empty() { child.to_string() } else { format!("{}.{}", parent, child) }
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+/// `[guardrails]` table of config.toml. Unset limits are not checked.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct GuardrailConfig {
+    /// Maximum number of resources generated by one transpile run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_resources: Option<usize>,
+    /// Maximum number of IAM member resources targeting the same scope (project, folder, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iam_members_per_scope: Option<usize>,
+    /// Maximum number of destroys in a plan scanned by `scan-plan` (moved resources excluded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_destroys: Option<usize>,
+}
+
+impl GuardrailConfig {
+    pub fn is_empty(&self) -> bool {
+        self.max_resources.is_none() && self.max_iam_members_per_scope.is_none() && self.max_destroys.is_none()
+    }
+}
+
+/// Checks the generated `main.tf` against the resource count limits.
+pub fn check_generated(main_tf: &str, guardrails: &GuardrailConfig) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut violations = Vec::new();
+    if guardrails.max_resources.is_none() && guardrails.max_iam_members_per_scope.is_none() {
+        return Ok(violations);
+    }
+
+    let body: hcl::Body = hcl::from_str(main_tf)?;
+    let resources: Vec<&hcl::Block> = body.blocks().filter(|b| b.identifier() == "resource").collect();
+
+    if let Some(max) = guardrails.max_resources {
+        let total: usize = resources.iter().map(|block| instances(block)).sum();
+        if total > max {
+            violations.push(format!("{} resources generated, limit is {} (max_resources)", total, max));
+        }
+    }
+
+    if let Some(max) = guardrails.max_iam_members_per_scope {
+        let mut per_scope: BTreeMap<String, usize> = BTreeMap::new();
+        for block in &resources {
+            let Some(tf_type) = block.labels().first().map(|l| l.as_str()) else { continue };
+            let Some(kind) = tf_type.strip_suffix("_iam_member") else { continue };
+
+            // Every attribute except the grant itself and its instances identifies the scope
+            let scope: Vec<String> = block.body.attributes()
+                .filter(|a| !matches!(a.key(), "member" | "role" | "provider" | "condition" | "for_each" | "count"))
+                .map(|a| format!("{}={}", a.key(), a.expr()))
+                .collect();
+            *per_scope.entry(format!("{} [{}]", kind, scope.join(", "))).or_default() += instances(block);
+        }
+        for (scope, count) in per_scope {
+            if count > max {
+                violations.push(format!("{} IAM members on {}, limit is {} (max_iam_members_per_scope)", count, scope, max));
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Number of resources a block creates: the entries of a literal `for_each` (object, list or
+/// `toset([...])`), a literal `count` (the larger branch of a conditional one), else one.
+fn instances(block: &hcl::Block) -> usize {
+    fn entries(expr: &hcl::Expression) -> Option<usize> {
+        match expr {
+            hcl::Expression::Object(object) => Some(object.len()),
+            hcl::Expression::Array(items) => Some(items.len()),
+            hcl::Expression::FuncCall(call) if call.args.len() == 1 => entries(&call.args[0]),
+            _ => None,
+        }
+    }
+    fn count(expr: &hcl::Expression) -> Option<usize> {
+        match expr {
+            hcl::Expression::Number(n) => n.as_u64().map(|n| n as usize),
+            hcl::Expression::Conditional(cond) => Some(count(&cond.true_expr)?.max(count(&cond.false_expr)?)),
+            _ => None,
+        }
+    }
+    block.body.attributes()
+        .find_map(|a| match a.key() {
+            "for_each" => entries(a.expr()),
+            "count" => count(a.expr()),
+            _ => None,
+        })
+        .unwrap_or(1)
+}
+
+/// Checks the resources a plan would destroy against `max_destroys`.
+pub fn check_destroys(destroys: &[String], guardrails: &GuardrailConfig) -> Vec<String> {
+    match guardrails.max_destroys {
+        Some(max) if destroys.len() > max => {
+            let mut message = format!("plan destroys {} resources, limit is {} (max_destroys)", destroys.len(), max);
+            for address in destroys.iter().take(10) {
+                message.push_str(&format!("\n    - {}", address));
+            }
+            if destroys.len() > 10 {
+                message.push_str(&format!("\n    ... and {} more", destroys.len() - 10));
+            }
+            vec![message]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Turns guardrail violations into an error unless `--override-guardrail` was given.
+pub fn enforce(violations: &[String], override_guardrail: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    for violation in violations {
+        if override_guardrail {
+            eprintln!("Warning: Guardrail overridden: {}", violation);
+        } else {
+            eprintln!("Error: Guardrail violated: {}", violation);
+        }
+    }
+    if override_guardrail {
+        return Ok(());
+    }
+    Err(format!("{} guardrail violation(s). Re-run with --override-guardrail if this is intended.", violations.len()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_each_and_count_instances_are_counted() {
+        let main_tf = r#"
+resource "google_project_iam_member" "iam" {
+  for_each = {
+    "user:a@example.com roles/viewer" = { member = "user:a@example.com", role = "roles/viewer" }
+    "user:b@example.com roles/viewer" = { member = "user:b@example.com", role = "roles/viewer" }
+  }
+  role    = each.value.role
+  member  = each.value.member
+  project = "p-1"
+}
+
+resource "google_project_iam_member" "extra" {
+  role    = "roles/browser"
+  member  = "user:c@example.com"
+  project = "p-1"
+}
+
+resource "google_storage_bucket" "logs" {
+  count = var.enable_logs ? 1 : 0
+  name  = "logs"
+}
+
+resource "google_storage_bucket" "data" {
+  for_each = toset(["a", "b", "c"])
+  name     = each.key
+}
+"#;
+        let guardrails = GuardrailConfig { max_resources: Some(6), max_iam_members_per_scope: Some(2), max_destroys: None };
+        let violations = check_generated(main_tf, &guardrails).unwrap();
+        assert_eq!(violations, [
+            "7 resources generated, limit is 6 (max_resources)",
+            "3 IAM members on google_project [project=\"p-1\"], limit is 2 (max_iam_members_per_scope)",
+        ]);
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use git2::{Oid, Repository, Sort, Tree, TreeWalkMode, TreeWalkResult};
+use serde::Serialize;
+use crate::schema::ResourceRegistry;
+use crate::transpiler::Transpiler;
+use crate::ToolConfig;
+
+/// What a commit did to a generated resource.
+#[derive(Debug, Serialize)]
+pub struct Change {
+    /// `added`, `changed` or `removed`
+    pub kind: &'static str,
+    pub commit: String,
+    /// Commit date, `YYYY-MM-DD`
+    pub date: String,
+    pub author: String,
+    pub summary: String,
+}
+
+/// The changelog of one resource address, oldest change first.
+#[derive(Debug, Serialize)]
+pub struct ResourceHistory {
+    pub address: String,
+    /// YAML path of the entry in the latest revision that had the resource
+    pub yaml_path: String,
+    pub changes: Vec<Change>,
+}
+
+/// A generated resource: its YAML path and the HCL of its block.
+type Generated = BTreeMap<String, (String, String)>;
+
+/// Transpiles the input at every commit (first-parent history of HEAD) that touched the YAML,
+/// include or override directories, and reports per resource address when it was added, changed
+/// or removed. Commits that fail to transpile are skipped with a warning.
+pub fn history(input_path: &Path, runtime_config: &ToolConfig, registry: Arc<ResourceRegistry>) -> Result<Vec<ResourceHistory>, Box<dyn std::error::Error>> {
+    let input_path = input_path.canonicalize()
+        .map_err(|e| format!("Failed to read '{}': {}", input_path.display(), e))?;
+    let repo = Repository::discover(input_path.parent().unwrap_or(Path::new(".")))
+        .map_err(|e| format!("'{}' is not in a git repository: {}", input_path.display(), e))?;
+    let workdir = repo.workdir().ok_or("history needs a git repository with a working tree")?.canonicalize()?;
+    let relative = |path: &Path| path.canonicalize().ok().and_then(|p| p.strip_prefix(&workdir).ok().map(Path::to_path_buf));
+
+    // Directories read by the transpile, relative to the repository (those outside it are used as they are)
+    let input_rel = relative(&input_path).ok_or_else(|| format!("'{}' is outside of the git repository", input_path.display()))?;
+    let mut tracked: Vec<PathBuf> = vec![input_rel.parent().map(Path::to_path_buf).unwrap_or_default()];
+    tracked.extend(runtime_config.include_dirs.iter().chain(&runtime_config.override_dirs).filter_map(|d| relative(d)));
+    tracked.sort();
+    tracked.dedup();
+
+    let checkout = std::env::temp_dir().join(format!("cfg2hcl-history-{}", std::process::id()));
+    let mut config = runtime_config.clone();
+    let remap = |dirs: &[PathBuf]| dirs.iter().map(|d| relative(d).map_or_else(|| d.clone(), |r| checkout.join(r))).collect::<Vec<_>>();
+    config.include_dirs = remap(&runtime_config.include_dirs);
+    config.override_dirs = remap(&runtime_config.override_dirs);
+    config.yaml_dir = checkout.join(input_rel.parent().unwrap_or(Path::new("")));
+
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.simplify_first_parent()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+    let mut histories: BTreeMap<String, ResourceHistory> = BTreeMap::new();
+    let mut previous: Generated = BTreeMap::new();
+    let mut previous_ids: Vec<Option<Oid>> = Vec::new();
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        for oid in walk {
+            let commit = repo.find_commit(oid?)?;
+            let tree = commit.tree()?;
+            let ids: Vec<Option<Oid>> = tracked.iter().map(|dir| subtree_id(&tree, dir)).collect();
+            if ids == previous_ids {
+                continue;
+            }
+            previous_ids = ids;
+
+            let short = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+            if checkout.exists() {
+                fs::remove_dir_all(&checkout)?;
+            }
+            materialize(&repo, &tree, &tracked, &checkout)?;
+            let generated = match transpile(&checkout.join(&input_rel), &config, registry.clone()) {
+                Ok(generated) => generated,
+                Err(e) => {
+                    eprintln!("Warning: skipping commit {} ({}): {}", short, commit.summary().unwrap_or_default(), e);
+                    continue;
+                }
+            };
+
+            let author = commit.author();
+            let change = |kind| Change {
+                kind,
+                commit: short.clone(),
+                date: date(commit.time().seconds()),
+                author: author.name().unwrap_or_default().to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            };
+            for (address, (yaml_path, block)) in &generated {
+                let kind = match previous.get(address) {
+                    None => "added",
+                    Some((_, old)) if old != block => "changed",
+                    Some(_) => continue,
+                };
+                let entry = histories.entry(address.clone()).or_insert_with(|| ResourceHistory {
+                    address: address.clone(),
+                    yaml_path: String::new(),
+                    changes: Vec::new(),
+                });
+                entry.yaml_path = yaml_path.clone();
+                entry.changes.push(change(kind));
+            }
+            for address in previous.keys().filter(|a| !generated.contains_key(*a)) {
+                if let Some(entry) = histories.get_mut(address) {
+                    entry.changes.push(change("removed"));
+                }
+            }
+            previous = generated;
+            // Warnings of old revisions are not what the user asked about
+            crate::warnings::clear();
+        }
+        Ok(())
+    })();
+    if checkout.exists() {
+        let _ = fs::remove_dir_all(&checkout);
+    }
+    result?;
+    Ok(histories.into_values().collect())
+}
+
+/// ID of the tree (or blob) at `dir` in a commit, `None` if it does not exist there.
+fn subtree_id(tree: &Tree, dir: &Path) -> Option<Oid> {
+    if dir.as_os_str().is_empty() {
+        return Some(tree.id());
+    }
+    tree.get_path(dir).ok().map(|entry| entry.id())
+}
+
+/// Writes the files below the tracked directories of a commit to `target`.
+fn materialize(repo: &Repository, tree: &Tree, tracked: &[PathBuf], target: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    for dir in tracked {
+        let subtree = if dir.as_os_str().is_empty() {
+            tree.clone()
+        } else {
+            match tree.get_path(dir).ok().and_then(|e| e.to_object(repo).ok()).and_then(|o| o.into_tree().ok()) {
+                Some(subtree) => subtree,
+                None => continue,
+            }
+        };
+        let mut error = None;
+        subtree.walk(TreeWalkMode::PreOrder, |parent, entry| {
+            let Some(blob) = entry.to_object(repo).ok().and_then(|o| o.into_blob().ok()) else {
+                return TreeWalkResult::Ok;
+            };
+            let path = target.join(dir).join(parent).join(entry.name().unwrap_or_default());
+            let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&path, blob.content()));
+            match written {
+                Ok(()) => TreeWalkResult::Ok,
+                Err(e) => {
+                    error = Some(format!("Failed to write '{}': {}", path.display(), e));
+                    TreeWalkResult::Abort
+                }
+            }
+        })?;
+        if let Some(error) = error {
+            return Err(error.into());
+        }
+    }
+    Ok(())
+}
+
+/// Resources generated from one revision of the input.
+fn transpile(input_path: &Path, config: &ToolConfig, registry: Arc<ResourceRegistry>) -> Result<Generated, Box<dyn std::error::Error>> {
+    let loaded = crate::load_input(input_path, config)?;
+    let (provider_sources, provider_versions) = crate::provider_settings(config);
+    let project = Transpiler::new(
+        &loaded.config,
+        Some(registry),
+        config.auto_explode.clone(),
+        "none".to_string(),
+        crate::extract_variables(&loaded.raw_value)?,
+        provider_sources,
+        provider_versions,
+    ).with_required_labels(config.required_labels.clone())
+        .with_implicit_dependencies(config.implicit_dependencies)
+        .with_implicit_references(config.implicit_references()?)
+        .with_service_label_template(config.service_label_template.clone(), Vec::new())
+        .with_resource_aliases(config.resource_aliases())
+        .transpile()?;
+
+    let body: hcl::Body = hcl::from_str(&project.resources_tf())?;
+    Ok(body.blocks()
+        .filter(|b| b.identifier() == "resource" && b.labels().len() == 2)
+        .map(|b| {
+            let address = format!("{}.{}", b.labels()[0].as_str(), b.labels()[1].as_str());
+            let yaml_path = project.sources.get(&address).cloned().unwrap_or_default();
+            (address, (yaml_path, hcl::to_string(b).unwrap_or_default()))
+        })
+        .collect())
+}
+
+/// `YYYY-MM-DD` of a Unix timestamp (UTC).
+pub fn date(seconds: i64) -> String {
+    // Civil from days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = seconds.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Prints the changelog resource by resource.
+pub fn print_history(histories: &[ResourceHistory]) {
+    for history in histories {
+        println!("{} ({})", history.address, history.yaml_path);
+        for change in &history.changes {
+            println!("  {}  {}  {:<8} {}  {}", change.date, change.commit, change.kind, change.author, change.summary);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+/// `[hooks]` table of config.toml: shell commands run around `transpile` and `apply`.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct HooksConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_transpile: Vec<String>,
+    /// e.g. `["tflint --chdir hcl", "checkov -d hcl --quiet"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_transpile: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_apply: Vec<String>,
+    /// Also run when the apply failed (see `CFG2HCL_APPLY_EXIT_CODE`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_apply: Vec<String>,
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_transpile.is_empty() && self.post_transpile.is_empty() && self.pre_apply.is_empty() && self.post_apply.is_empty()
+    }
+}
+
+/// Runs the commands of one hook in order from the config file directory, with `CFG2HCL_HOOK`
+/// and the given variables in the environment. The first failing command fails the hook.
+pub fn run(hook: &str, commands: &[String], config_dir: &Path, env: &[(&str, String)]) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = if config_dir.as_os_str().is_empty() { Path::new(".") } else { config_dir };
+    for command in commands {
+        status!("Running {} hook: {}", hook, command);
+        let status = shell(command)
+            .current_dir(dir)
+            .env("CFG2HCL_HOOK", hook)
+            .envs(env.iter().map(|(k, v)| (*k, v)))
+            .status()
+            .map_err(|e| format!("Failed to run {} hook '{}': {}", hook, command, e))?;
+        if !status.success() {
+            return Err(format!("{} hook '{}' failed ({})", hook, command, status).into());
+        }
+    }
+    Ok(())
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt::Display;
+use std::sync::OnceLock;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+
+/// Message catalogs in Fluent syntax (`locales/*.ftl`), English first as the fallback.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+type Catalog = FluentBundle<FluentResource>;
+
+static SELECTED: OnceLock<(Catalog, Catalog)> = OnceLock::new();
+
+/// Selects the language of user-facing messages: `language` from config.toml, otherwise
+/// `LC_ALL`, `LC_MESSAGES` or `LANG` (e.g. `de_DE.UTF-8`). Unknown languages fall back to English.
+pub fn init(configured: Option<&str>) {
+    let requested = configured.filter(|l| !l.is_empty() && *l != "auto").map(str::to_string).or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+    });
+    let language = requested.as_deref()
+        .map(|l| l.split(['_', '-', '.', '@']).next().unwrap_or(l).to_lowercase())
+        .unwrap_or_default();
+    let (code, source) = match LOCALES.iter().find(|(code, _)| *code == language) {
+        Some(locale) => *locale,
+        None => {
+            if let Some(configured) = configured.filter(|l| !l.is_empty() && *l != "auto") {
+                eprintln!("Warning: language '{}' is not available, using English (available: {})",
+                    configured, LOCALES.iter().map(|(code, _)| *code).collect::<Vec<_>>().join(", "));
+            }
+            LOCALES[0]
+        }
+    };
+    let _ = SELECTED.set((catalog(code, source), catalog(LOCALES[0].0, LOCALES[0].1)));
+}
+
+/// Looks up a message and fills in its variables. Messages missing in the selected language
+/// come from the English catalog, unknown keys are returned as is. Arguments that read as numbers
+/// are passed as Fluent numbers, so selectors like `{ $count -> [one] ... *[other] ... }` work.
+pub fn t(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let (selected, fallback) = SELECTED.get_or_init(|| (catalog(LOCALES[0].0, LOCALES[0].1), catalog(LOCALES[0].0, LOCALES[0].1)));
+    let Some((catalog, pattern)) = [selected, fallback].into_iter()
+        .find_map(|c| c.get_message(key).and_then(|m| m.value()).map(|p| (c, p))) else {
+        return key.to_string();
+    };
+
+    let values: Vec<(&str, String)> = args.iter().map(|(name, value)| (*name, value.to_string())).collect();
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in &values {
+        // Only numbers that are written back unchanged, so IDs like `007` stay strings
+        let number = FluentValue::try_number(value);
+        let is_number = matches!(&number, FluentValue::Number(n) if n.as_string() == value.as_str());
+        fluent_args.set(*name, if is_number { number } else { FluentValue::from(value.as_str()) });
+    }
+    let mut errors = Vec::new();
+    catalog.format_pattern(pattern, Some(&fluent_args), &mut errors).into_owned()
+}
+
+/// Loads a catalog. Syntax errors leave out the broken entries; the tests make sure the shipped
+/// catalogs have none.
+fn catalog(code: &str, source: &str) -> Catalog {
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, _)| resource);
+    let mut bundle = FluentBundle::new_concurrent(vec![code.parse().expect("locale codes are valid")]);
+    // No Unicode isolation marks around placeables; the messages go to a terminal
+    bundle.set_use_isolating(false);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogs_parse_and_translate_every_message() {
+        let mut catalogs = Vec::new();
+        for (code, source) in LOCALES {
+            let resource = FluentResource::try_new(source.to_string())
+                .unwrap_or_else(|(_, errors)| panic!("locales/{}.ftl: {:?}", code, errors));
+            let mut bundle: Catalog = FluentBundle::new_concurrent(vec![code.parse().unwrap()]);
+            bundle.add_resource(resource).unwrap_or_else(|errors| panic!("locales/{}.ftl: {:?}", code, errors));
+            catalogs.push((code, bundle));
+        }
+        let english = LOCALES[0].1.lines()
+            .filter(|l| l.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|l| l.split_once(" =").map(|(key, _)| key));
+        for key in english {
+            for (code, bundle) in &catalogs[1..] {
+                assert!(bundle.has_message(key), "locales/{}.ftl lacks '{}'", code, key);
+            }
+        }
+    }
+
+    #[test]
+    fn arguments_are_filled_in() {
+        let catalog = catalog("en", "a = { $n ->\n    [one] one file\n   *[other] { $n } files\n} in { $dir }\nb = {\"{\"}{ $x }{\"}\"}\n");
+        let format = |key: &str, args: &FluentArgs| {
+            let pattern = catalog.get_message(key).unwrap().value().unwrap();
+            catalog.format_pattern(pattern, Some(args), &mut Vec::new()).into_owned()
+        };
+        let mut args = FluentArgs::new();
+        args.set("n", FluentValue::try_number("1"));
+        args.set("dir", "hcl");
+        assert_eq!(format("a", &args), "one file in hcl");
+        args.set("n", FluentValue::try_number("3"));
+        assert_eq!(format("a", &args), "3 files in hcl");
+        args.set("x", "project");
+        assert_eq!(format("b", &args), "{project}");
+        assert_eq!(t("warning", &[("message", &"007")]), "Warning: 007");
+    }
+}
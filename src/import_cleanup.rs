@@ -0,0 +1,94 @@
+use std::collections::BTreeSet;
+
+/// An `import-id` entry removed (or to be removed) from a YAML file.
+#[derive(Debug, Clone)]
+pub struct RemovedImportId {
+    /// 1-based line number in the original file
+    pub line: usize,
+    /// Terraform address of the resource the entry belongs to, if it could be determined
+    pub address: Option<String>,
+}
+
+/// Removes `import-id:` / `import-id-comment:` entries and `# import-id:` comments from YAML
+/// text. Works line by line so that formatting, comments and anchors are preserved.
+///
+/// With `in_state`, only entries of resources whose address is in the set are removed; the
+/// address is derived from the enclosing keys (`<type>.<name>`, `folder`/`project` map to
+/// `google_folder`/`google_project`).
+pub fn strip_import_ids(content: &str, in_state: Option<&BTreeSet<String>>) -> (String, Vec<RemovedImportId>) {
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    // (indent, key) of the mapping keys enclosing the current line
+    let mut stack: Vec<(usize, String)> = Vec::new();
+
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if trimmed.is_empty() {
+            kept.push(line);
+            continue;
+        }
+
+        let is_import_entry = trimmed.starts_with("import-id:")
+            || trimmed.starts_with("import-id-comment:")
+            || trimmed.starts_with("# import-id:");
+
+        if trimmed.starts_with('#') && !is_import_entry {
+            kept.push(line);
+            continue;
+        }
+
+        while stack.last().is_some_and(|(i, _)| *i >= indent) {
+            stack.pop();
+        }
+
+        if is_import_entry {
+            let address = resource_address(&stack);
+            let remove = match in_state {
+                None => true,
+                Some(state) => address.as_ref().is_some_and(|a| state.contains(a)),
+            };
+            if remove {
+                removed.push(RemovedImportId { line: idx + 1, address });
+                continue;
+            }
+            kept.push(line);
+            continue;
+        }
+
+        // Sequence items open a new level; their keys are not resource names
+        let (item_indent, item) = match trimmed.strip_prefix("- ") {
+            Some(rest) => (indent + 2, rest),
+            None => (indent, trimmed),
+        };
+        if let Some((key, _)) = item.split_once(':') {
+            if item_indent != indent {
+                stack.push((indent, String::new()));
+            }
+            stack.push((item_indent, key.trim().trim_matches(|c| c == '"' || c == '\'').to_string()));
+        }
+        kept.push(line);
+    }
+
+    let mut result = kept.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    (result, removed)
+}
+
+/// `[..., "google_storage_bucket", "state"]` -> `google_storage_bucket.state`
+fn resource_address(stack: &[(usize, String)]) -> Option<String> {
+    let [.., (_, tf_type), (_, name)] = stack else { return None };
+    if name.is_empty() || tf_type.is_empty() {
+        return None;
+    }
+    let tf_type = match tf_type.as_str() {
+        "folder" => "google_folder".to_string(),
+        "project" => "google_project".to_string(),
+        t if t.starts_with("google_") => t.to_string(),
+        t => format!("google_{}", t),
+    };
+    Some(format!("{}.{}", tf_type, crate::transpiler::resource_label(name)))
+}
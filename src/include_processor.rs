@@ -6,82 +6,252 @@ use std::path::{Path, PathBuf};
 /// file define a `variables:` block. The variable extractor recognises this prefix.
 pub const INCLUDE_VARS_PREFIX: &str = "_cfg2hcl_include_vars_";
 
-pub fn process_includes(file_path: &Path, include_paths: &[PathBuf]) -> Result<String, Box<dyn std::error::Error>> {
-    let mut counter = 0usize;
-    process_includes_inner(file_path, include_paths, &mut counter)
-}
-
-fn process_includes_inner(file_path: &Path, include_paths: &[PathBuf], counter: &mut usize) -> Result<String, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(file_path)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to read file '{}': {}", file_path.display(), e)))?;
-    let mut result = Vec::new();
-    let parent_dir = file_path.parent().unwrap_or(Path::new("."));
-
-    for line in content.lines() {
-        if let Some(caps) = find_include(line) {
-            let (indent, key, include_file) = caps;
-            let resolved_path = resolve_include_path(parent_dir, include_file, include_paths)
-                .ok_or_else(|| format!("Could not resolve include file: {}", include_file))?;
-
-            let included_content = process_includes_inner(&resolved_path, include_paths, counter)?;
-
-            let content_indent = if key.is_some() { indent + 2 } else { indent };
-            let prefix = " ".repeat(content_indent);
-
-            if let Some(key_str) = key {
-                // Form B: content is indented under a key — no top-level key conflicts possible
-                result.push(format!("{}{}:", " ".repeat(indent), key_str));
-                for inc_line in included_content.lines() {
-                    if inc_line.trim().is_empty() {
-                        result.push(String::new());
-                    } else {
-                        result.push(format!("{}{}", prefix, inc_line));
-                    }
+/// Include-expanded YAML and the file and line each of its lines came from.
+pub struct Expanded {
+    pub content: String,
+    pub source_map: SourceMap,
+}
+
+impl Expanded {
+    /// YAML without includes, e.g. a redacted copy written to `root`.
+    pub fn plain(content: String, root: &Path) -> Self {
+        Self { content, source_map: SourceMap::new(root) }
+    }
+}
+
+/// Maps lines of include-expanded YAML back to their files. Lines are recorded in runs of
+/// consecutive lines of one file, so the map stays small however large the files are.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    /// Input file first, then the included files in the order they are included
+    files: Vec<PathBuf>,
+    /// Sorted by `start`; lines before the first run are lines of the input file
+    runs: Vec<Run>,
+    /// Expanded line index where the content of an included file starts -> its index in `files`
+    includes: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    /// 0-based line index in the expanded content
+    start: usize,
+    file: usize,
+    /// 1-based line in `file` of the first line of the run
+    line: usize,
+}
+
+impl SourceMap {
+    fn new(root: &Path) -> Self {
+        Self { files: vec![root.to_path_buf()], runs: Vec::new(), includes: Vec::new() }
+    }
+
+    /// The input file.
+    pub fn root(&self) -> &Path {
+        &self.files[0]
+    }
+
+    /// File and 1-based line of a 0-based line index in the expanded content.
+    pub fn location(&self, line_idx: usize) -> (&Path, usize) {
+        match self.runs.partition_point(|run| run.start <= line_idx).checked_sub(1) {
+            Some(idx) => {
+                let run = self.runs[idx];
+                (&self.files[run.file], run.line + line_idx - run.start)
+            }
+            None => (self.root(), line_idx + 1),
+        }
+    }
+
+    /// Every include as the expanded line index where the included content starts and the file.
+    pub fn includes(&self) -> impl Iterator<Item = (usize, &Path)> + '_ {
+        self.includes.iter().map(|(start, file)| (*start, self.files[*file].as_path()))
+    }
+
+    fn add_file(&mut self, path: &Path) -> usize {
+        self.files.push(path.to_path_buf());
+        self.files.len() - 1
+    }
+
+    /// Records that expanded line `line_idx` is line `line` (1-based) of `file`.
+    fn record(&mut self, line_idx: usize, file: usize, line: usize) {
+        let continues = self.runs.last().map_or(file == 0 && line == line_idx + 1, |run| run.file == file && run.line + line_idx - run.start == line);
+        if !continues {
+            self.runs.push(Run { start: line_idx, file, line });
+        }
+    }
+}
+
+/// Expands the includes of `file_path` into one YAML document. The document is kept whole
+/// instead of parsing each file and merging the trees: an anchor may be defined in one file and
+/// used in another, and comments, provenance, redaction and CODEOWNERS work on its text. Each
+/// file is read and copied once; the source map keeps the file boundaries for error reporting.
+pub fn process_includes(file_path: &Path, include_paths: &[PathBuf]) -> Result<Expanded, Box<dyn std::error::Error>> {
+    let _span = crate::timings::span("include processing");
+    let mut expander = Expander::new(include_paths, file_path, None);
+    let content = read_file(file_path)?;
+    expander.stack.push(canonical(file_path));
+    expander.expand(&content, file_path.parent().unwrap_or(Path::new(".")), 0, "", None)?;
+    Ok(expander.finish())
+}
+
+/// Like `process_includes` for YAML that does not come from a file; relative includes are
+/// resolved against `base_dir` and `root` names the content in the source map. With `confined`
+/// (content from the network), every include has to be a file below `base_dir` or one of the
+/// `include_paths`, so that no absolute path or `../` reaches other files of the host.
+pub fn process_includes_content(content: &str, root: &Path, base_dir: &Path, include_paths: &[PathBuf], confined: bool) -> Result<Expanded, Box<dyn std::error::Error>> {
+    let _span = crate::timings::span("include processing");
+    let allowed_dirs = confined.then(|| {
+        std::iter::once(base_dir).chain(include_paths.iter().map(PathBuf::as_path))
+            .filter_map(|dir| dir.canonicalize().ok())
+            .collect()
+    });
+    let mut expander = Expander::new(include_paths, root, allowed_dirs);
+    expander.expand(content, base_dir, 0, "", None)?;
+    Ok(expander.finish())
+}
+
+/// `path` with symlinks and `..` resolved, as it is if it cannot be resolved.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn read_file(file_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(fs::read_to_string(file_path)
+        .map_err(|e| std::io::Error::other(format!("Failed to read file '{}': {}", file_path.display(), e)))?)
+}
+
+/// Writes the include-expanded YAML line by line into one buffer. Every line of an included
+/// file gets the indentation of all its includes at once, so the content of a file is read
+/// once and copied once however deep it is included; a file's text is dropped as soon as it
+/// has been expanded. The source map records where each line came from.
+struct Expander<'a> {
+    include_paths: &'a [PathBuf],
+    out: String,
+    /// Lines written to `out` so far
+    lines: usize,
+    source_map: SourceMap,
+    /// Number of Form A includes so far, for the unique renamed `variables:` keys
+    counter: usize,
+    /// Canonical directories included files must be in; `None` allows any file
+    allowed_dirs: Option<Vec<PathBuf>>,
+    /// Canonical paths of the files being expanded, outermost first, to detect include cycles
+    stack: Vec<PathBuf>,
+}
+
+impl<'a> Expander<'a> {
+    fn new(include_paths: &'a [PathBuf], root: &Path, allowed_dirs: Option<Vec<PathBuf>>) -> Self {
+        Self { include_paths, out: String::new(), lines: 0, source_map: SourceMap::new(root), counter: 0, allowed_dirs, stack: Vec::new() }
+    }
+
+    /// Fails if `path` (after following symlinks) is outside the allowed directories.
+    fn check_allowed(&self, path: &Path, include_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(allowed_dirs) = &self.allowed_dirs else { return Ok(()) };
+        let canonical = path.canonicalize()
+            .map_err(|e| format!("Failed to resolve include file '{}': {}", include_file, e))?;
+        if !allowed_dirs.iter().any(|dir| canonical.starts_with(dir)) {
+            return Err(format!("Include file '{}' is outside yaml_dir and include_dirs", include_file).into());
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Expanded {
+        Expanded { content: self.out, source_map: self.source_map }
+    }
+
+    /// Expands an included file with every line indented by `prefix`; `vars_key` replaces its
+    /// top-level `variables:` key. Fails if the file is already being expanded (an include cycle).
+    fn expand_file(&mut self, file_path: &Path, prefix: &str, vars_key: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let path = canonical(file_path);
+        if let Some(start) = self.stack.iter().position(|p| *p == path) {
+            let cycle: Vec<String> = self.stack[start..].iter().chain([&path]).map(|p| p.display().to_string()).collect();
+            return Err(format!("Include cycle: {}", cycle.join(" -> ")).into());
+        }
+        let content = read_file(file_path)?;
+        let file = self.source_map.add_file(file_path);
+        self.source_map.includes.push((self.lines, file));
+        self.stack.push(path);
+        self.expand(&content, file_path.parent().unwrap_or(Path::new(".")), file, prefix, vars_key)?;
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Expands the content of `file` (its index in the source map).
+    fn expand(&mut self, content: &str, parent_dir: &Path, file: usize, prefix: &str, vars_key: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        for (idx, line) in content.lines().enumerate() {
+            let line_number = idx + 1;
+            if let Some((indent, key, include_file)) = find_include(line) {
+                let resolved_path = resolve_include_path(parent_dir, include_file, self.include_paths)
+                    .ok_or_else(|| format!("Could not resolve include file: {}", include_file))?;
+                self.check_allowed(&resolved_path, include_file)?;
+
+                let content_indent = if key.is_some() { indent + 2 } else { indent };
+                let content_prefix = format!("{}{}", prefix, " ".repeat(content_indent));
+
+                if let Some(key_str) = key {
+                    // Form B: content is indented under a key — no top-level key conflicts possible
+                    let key_str = match vars_key {
+                        Some(vars_key) if indent == 0 && key_str == "variables" => vars_key,
+                        _ => key_str,
+                    };
+                    self.push_line(&format!("{}{}{}:", prefix, " ".repeat(indent), key_str), file, line_number);
+                    self.expand_file(&resolved_path, &content_prefix, None)?;
+                } else {
+                    // Form A: content is inserted at the same indent level as the parent.
+                    // Rename any top-level `variables:` block in the included file to a unique
+                    // internal key to prevent duplicate-key errors when both files define variables.
+                    let renamed_key = format!("{}{}", INCLUDE_VARS_PREFIX, self.counter);
+                    self.counter += 1;
+                    self.expand_file(&resolved_path, &content_prefix, Some(&renamed_key))?;
                 }
+            } else if prefix.is_empty() && vars_key.is_none() {
+                self.push_line(line, file, line_number);
+            } else if line.trim().is_empty() {
+                self.push_line("", file, line_number);
             } else {
-                // Form A: content is inserted at the same indent level as the parent.
-                // Rename any top-level `variables:` block in the included file to a unique
-                // internal key to prevent duplicate-key errors when both files define variables.
-                let idx = *counter;
-                *counter += 1;
-                let renamed = rename_top_level_variables(&included_content, idx);
-
-                // Source annotation is visible in YAML error context output
-                result.push(format!("# cfg2hcl:source: {}", resolved_path.display()));
-                for inc_line in renamed.lines() {
-                    if inc_line.trim().is_empty() {
-                        result.push(String::new());
-                    } else {
-                        result.push(format!("{}{}", prefix, inc_line));
-                    }
+                match vars_key.and_then(|vars_key| rename_top_level_variables(line, vars_key)) {
+                    Some(renamed) => self.push_line(&format!("{}{}", prefix, renamed), file, line_number),
+                    None => self.push_line(&format!("{}{}", prefix, line), file, line_number),
                 }
-                result.push(format!("# cfg2hcl:source-end: {}", resolved_path.display()));
             }
-        } else {
-            result.push(line.to_string());
         }
+        Ok(())
     }
 
-    Ok(result.join("\n"))
+    fn push_line(&mut self, text: &str, file: usize, line: usize) {
+        self.source_map.record(self.lines, file, line);
+        self.out.push_str(text);
+        self.out.push('\n');
+        self.lines += 1;
+    }
 }
 
-/// Renames the top-level `variables:` key in an included file's content to a
-/// unique internal key so it can coexist with the parent file's `variables:` block.
-fn rename_top_level_variables(content: &str, idx: usize) -> String {
-    let new_key = format!("{}{}", INCLUDE_VARS_PREFIX, idx);
-    content.lines()
-        .map(|line| {
-            let trimmed = line.trim_start();
-            let indent = line.len() - trimmed.len();
-            if indent == 0 && (trimmed == "variables:" || trimmed.starts_with("variables: ")) {
-                let rest = &trimmed["variables".len()..];
-                format!("{}{}", new_key, rest)
-            } else {
-                line.to_string()
+/// Returns `file_path` followed by every file it includes, recursively, without duplicates.
+pub fn included_files(file_path: &Path, include_paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = vec![file_path.to_path_buf()];
+    let mut idx = 0;
+    while idx < files.len() {
+        let current = files[idx].clone();
+        let content = fs::read_to_string(&current)
+            .map_err(|e| format!("Failed to read file '{}': {}", current.display(), e))?;
+        let parent_dir = current.parent().unwrap_or(Path::new("."));
+        for line in content.lines() {
+            if let Some((_, _, include_file)) = find_include(line) {
+                let resolved = resolve_include_path(parent_dir, include_file, include_paths)
+                    .ok_or_else(|| format!("Could not resolve include file: {}", include_file))?;
+                if !files.contains(&resolved) {
+                    files.push(resolved);
+                }
             }
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+        }
+        idx += 1;
+    }
+    Ok(files)
+}
+
+/// A top-level `variables:` line of an included file with the key renamed to `vars_key`, so the
+/// block can coexist with the parent file's `variables:` block.
+fn rename_top_level_variables(line: &str, vars_key: &str) -> Option<String> {
+    let rest = line.strip_prefix("variables")?;
+    (rest == ":" || rest.starts_with(": ")).then(|| format!("{}{}", vars_key, rest))
 }
 
 fn find_include(line: &str) -> Option<(usize, Option<&str>, &str)> {
@@ -108,6 +278,12 @@ fn find_include(line: &str) -> Option<(usize, Option<&str>, &str)> {
 }
 
 fn resolve_include_path(current_dir: &Path, include_file: &str, search_paths: &[PathBuf]) -> Option<PathBuf> {
+    // 0. Presets: the project-local preset directories first, then the downloaded ones
+    let preset = include_file.strip_prefix("presets/");
+    if let Some(path) = preset.and_then(crate::presets::find) {
+        return Some(path);
+    }
+
     // 1. Try relative to current file
     let rel_path = current_dir.join(include_file);
     if rel_path.exists() {
@@ -122,5 +298,106 @@ fn resolve_include_path(current_dir: &Path, include_file: &str, search_paths: &[
         }
     }
 
-    None
+    // 3. Presets shipped with the binary
+    preset.and_then(crate::presets::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory with the given files, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("cfg2hcl-{}-{}", name, std::process::id()));
+            for (file, content) in files {
+                fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+                fs::write(dir.join(file), content).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn nested_includes_keep_their_source_lines() {
+        let dir = TempDir::new("includes", &[
+            ("main.yaml", "variables:\n  region: eu\nfolder:\n  infra: !include infra.yaml\n!include shared.yaml\n"),
+            ("infra.yaml", "display_name: Infra\nproject:\n  p: !include lib/project.yaml\n"),
+            ("lib/project.yaml", "project_id: p-1\nname: p\n"),
+            ("shared.yaml", "variables:\n  owner: ops\n"),
+        ]);
+        let expanded = process_includes(&dir.0.join("main.yaml"), &[]).unwrap();
+
+        let expected = format!(
+            "variables:\n  region: eu\nfolder:\n  infra:\n    display_name: Infra\n    project:\n      p:\n        project_id: p-1\n        name: p\n{}0:\n  owner: ops\n",
+            INCLUDE_VARS_PREFIX
+        );
+        assert_eq!(expanded.content, expected);
+
+        let location = |idx| {
+            let (file, line) = expanded.source_map.location(idx);
+            (file.strip_prefix(&dir.0).unwrap().to_string_lossy().into_owned(), line)
+        };
+        assert_eq!(location(3), ("main.yaml".to_string(), 4));
+        assert_eq!(location(4), ("infra.yaml".to_string(), 1));
+        assert_eq!(location(8), ("lib/project.yaml".to_string(), 2));
+        assert_eq!(location(9), ("shared.yaml".to_string(), 1));
+
+        let includes: Vec<usize> = expanded.source_map.includes().map(|(start, _)| start).collect();
+        assert_eq!(includes, [4, 7, 9]);
+    }
+
+    #[test]
+    fn anchors_can_be_used_across_files() {
+        let dir = TempDir::new("anchors", &[
+            ("main.yaml", "variables:\n  billing: &billing 000000-111111-222222\nproject:\n  p: !include project.yaml\n"),
+            ("project.yaml", "project_id: p-1\nbilling_account: *billing\n"),
+        ]);
+        let expanded = process_includes(&dir.0.join("main.yaml"), &[]).unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&expanded.content).unwrap();
+        assert_eq!(value["project"]["p"]["billing_account"].as_str(), Some("000000-111111-222222"));
+    }
+
+    #[test]
+    fn confined_includes_stay_in_the_base_dir() {
+        let dir = TempDir::new("confined", &[("yaml/ok.yaml", "a: 1\n"), ("secret.yaml", "b: 2\n")]);
+        let base = dir.0.join("yaml");
+        let root = base.join("request.yaml");
+        assert!(process_includes_content("x: !include ok.yaml\n", &root, &base, &[], true).is_ok());
+        for include in ["../secret.yaml", &dir.0.join("secret.yaml").display().to_string()] {
+            let content = format!("x: !include {}\n", include);
+            assert!(process_includes_content(&content, &root, &base, &[], false).is_ok());
+            let err = process_includes_content(&content, &root, &base, &[], true).err().unwrap();
+            assert!(err.to_string().contains("outside yaml_dir"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = TempDir::new("cycle", &[
+            ("main.yaml", "folder:\n  a: !include a.yaml\n"),
+            ("a.yaml", "display_name: A\nfolder:\n  b: !include sub/b.yaml\n"),
+            ("sub/b.yaml", "display_name: B\nfolder:\n  a: !include ../a.yaml\n"),
+        ]);
+        let err = process_includes(&dir.0.join("main.yaml"), &[]).err().unwrap().to_string();
+        let dir_path = dir.0.canonicalize().unwrap();
+        let cycle = [dir_path.join("a.yaml"), dir_path.join("sub/b.yaml"), dir_path.join("a.yaml")];
+        let cycle: Vec<String> = cycle.iter().map(|p| p.display().to_string()).collect();
+        assert_eq!(err, format!("Include cycle: {}", cycle.join(" -> ")));
+
+        // The same file included twice, but not within itself, is no cycle
+        let dir = TempDir::new("diamond", &[
+            ("main.yaml", "folder:\n  a: !include shared.yaml\n  b: !include shared.yaml\n"),
+            ("shared.yaml", "display_name: Shared\n"),
+        ]);
+        assert!(process_includes(&dir.0.join("main.yaml"), &[]).is_ok());
+    }
 }
@@ -1,3 +1,12 @@
+/// Progress output, left out with `--quiet` (see `term.rs`).
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::term::quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
 mod config;
 mod schema;
 mod transpiler;
@@ -5,28 +14,87 @@ mod state_migration;
 mod discovery;
 mod template;
 mod bootstrap;
-
-use clap::{Parser, Subcommand, CommandFactory};
+mod validation;
+mod preflight;
+mod naming;
+mod guardrails;
+mod state_report;
+mod import_cleanup;
+mod manifest;
+mod extras;
+mod output;
+mod clean;
+mod notify;
+mod daemon;
+mod serve;
+mod run_report;
+mod overrides;
+mod audit;
+mod migrate;
+mod dependencies;
+mod warnings;
+mod term;
+mod i18n;
+mod tag_plugins;
+mod discovery_mappers;
+mod hooks;
+mod provenance;
+mod scan;
+mod history;
+mod blame;
+mod owners;
+mod endpoints;
+mod fixture;
+mod redact;
+mod support_bundle;
+mod yaml_comments;
+mod tfvars;
+mod removed;
+mod split;
+mod providers_tf;
+mod extract_module;
+mod formatter;
+mod promote_policy;
+mod yaml_edit;
+mod upgrade_provider;
+mod timings;
+mod file_tag;
+mod aliases;
+mod secret_tag;
+mod model;
+mod org_policy;
+mod convert_iam;
+mod group_refs;
+mod presets;
+
+use clap::{Parser, Subcommand, CommandFactory, FromArgMatches};
 use clap_complete::Shell as CompletionShell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use crate::schema::ResourceRegistry;
 use crate::transpiler::Transpiler;
 use crate::config::{Config, DiscoveryConfig};
+use crate::i18n::t;
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ToolConfig {
     #[serde(default = "default_yaml_dir")]
-    pub yaml_dir: String,
+    pub yaml_dir: PathBuf,
     #[serde(default = "default_hcl_dir")]
-    pub hcl_dir: String,
+    pub hcl_dir: PathBuf,
     #[serde(default = "default_include_dirs")]
-    pub include_dirs: Vec<String>,
+    pub include_dirs: Vec<PathBuf>,
+    /// Directories with override files applied to every input, see `overrides.rs`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub override_dirs: Vec<PathBuf>,
+    /// Directories searched for `presets/...` includes before `yaml_dir/presets`, see `presets.rs`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preset_dirs: Vec<PathBuf>,
     #[serde(default = "default_schema_dir")]
-    pub schema_dir: String,
+    pub schema_dir: PathBuf,
     #[serde(default = "default_tf_tool")]
     pub tf_tool: String,
     #[serde(default)]
@@ -43,11 +111,87 @@ pub struct ToolConfig {
     pub auto_explode: Vec<String>,
     #[serde(default = "default_validation_level")]
     pub validation_level: String,
+    /// Language of validation messages (`en`, `de`); unset follows `LANG`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
     #[serde(default)]
     pub discovery_config: Option<String>,
+    #[serde(default)]
+    pub enforce_member_domain: bool,
+    #[serde(default)]
+    pub allowed_member_domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billing_project_quota: Option<usize>,
+    #[serde(default)]
+    pub required_labels: Vec<String>,
+    /// Add `depends_on` for required services and known orderings (see `dependencies.rs`)
+    #[serde(default = "default_implicit_dependencies")]
+    pub implicit_dependencies: bool,
+    /// Reference-like plain strings in reference contexts: `warn` (still references, with a
+    /// warning) or `off` (strings; references need `!ref`/`!expr`)
+    #[serde(default = "default_implicit_references")]
+    pub implicit_references: String,
+    /// Label of exploded `google_project_service` resources, see `transpiler::service_label`
+    #[serde(default = "default_service_label_template")]
+    pub service_label_template: String,
+    /// Format of the variable values: `hcl` (terraform.tfvars) or `json` (terraform.tfvars.json)
+    #[serde(default = "default_tfvars_format")]
+    pub tfvars_format: String,
+    /// Files the resources are written to: `none` (main.tf), `type`, `folder` or `module`, see `split.rs`
+    #[serde(default = "default_split_output")]
+    pub split_output: String,
+    /// Sort the attributes of generated blocks by name, see `formatter::sort_block`
+    #[serde(default)]
+    pub sort_attributes: bool,
+    /// Write the backend settings to backend.hcl (for `init -backend-config`) and leave an empty
+    /// `backend` block in providers.tf, so the HCL can be initialized against other state buckets
+    #[serde(default)]
+    pub backend_config_file: bool,
+    /// Override directory per environment (`prod = "overrides/prod"`); transpile writes the
+    /// variables of each to `<environment>.tfvars`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environments: BTreeMap<String, PathBuf>,
+    #[serde(default, skip_serializing_if = "crate::naming::NamingConfig::is_empty")]
+    pub naming: crate::naming::NamingConfig,
+    #[serde(default, skip_serializing_if = "crate::guardrails::GuardrailConfig::is_empty")]
+    pub guardrails: crate::guardrails::GuardrailConfig,
+    #[serde(default, skip_serializing_if = "crate::daemon::DaemonConfig::is_empty")]
+    pub daemon: crate::daemon::DaemonConfig,
+    #[serde(default, skip_serializing_if = "crate::notify::NotificationConfig::is_empty")]
+    pub notifications: crate::notify::NotificationConfig,
+    #[serde(default, skip_serializing_if = "crate::audit::AuditConfig::is_empty")]
+    pub audit: crate::audit::AuditConfig,
+    #[serde(default, skip_serializing_if = "crate::hooks::HooksConfig::is_empty")]
+    pub hooks: crate::hooks::HooksConfig,
+    #[serde(default, skip_serializing_if = "crate::scan::ScanConfig::is_default")]
+    pub scan: crate::scan::ScanConfig,
+    /// API base URLs per service (`storage = "http://localhost:4443"`), e.g. for emulators
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub endpoint_overrides: BTreeMap<String, String>,
+    /// Custom YAML tags resolved by external commands (`[tags.vault]` handles `!vault`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, crate::tag_plugins::TagPlugin>,
+    /// Short YAML keys of resource types (`vm = "google_compute_instance"`) on top of the
+    /// built-in ones, see `aliases.rs`; an empty type removes a built-in alias
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resource_aliases: BTreeMap<String, String>,
 }
 
 impl ToolConfig {
+    /// Whether `implicit_references` is the compatibility mode.
+    pub fn implicit_references(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        match self.implicit_references.as_str() {
+            "warn" => Ok(true),
+            "off" => Ok(false),
+            other => Err(format!("Unknown implicit_references '{}' (expected warn or off)", other).into()),
+        }
+    }
+
+    /// The built-in resource aliases with the configured ones on top.
+    pub fn resource_aliases(&self) -> BTreeMap<String, String> {
+        crate::aliases::resource_aliases(&self.resource_aliases)
+    }
+
     pub fn all_providers(&self) -> Vec<String> {
         let mut providers = Vec::new();
         providers.extend(self.google_providers.iter().map(|p| ToolConfig::parse_provider_string(p).0));
@@ -91,10 +235,10 @@ impl ToolConfig {
     }
 }
 
-fn default_yaml_dir() -> String { "yaml".to_string() }
-fn default_hcl_dir() -> String { "hcl".to_string() }
-fn default_include_dirs() -> Vec<String> { vec!["yaml".to_string()] }
-fn default_schema_dir() -> String { "schemas".to_string() }
+fn default_yaml_dir() -> PathBuf { PathBuf::from("yaml") }
+fn default_hcl_dir() -> PathBuf { PathBuf::from("hcl") }
+fn default_include_dirs() -> Vec<PathBuf> { vec![PathBuf::from("yaml")] }
+fn default_schema_dir() -> PathBuf { PathBuf::from("schemas") }
 fn default_tf_tool() -> String { "tofu".to_string() }
 fn default_google_providers() -> Vec<String> { vec!["google".to_string(), "google-beta".to_string()] }
 fn default_version() -> String { "7.12.0".to_string() }
@@ -105,6 +249,11 @@ fn default_auto_explode() -> Vec<String> {
     ]
 }
 fn default_validation_level() -> String { "warn".to_string() }
+fn default_implicit_dependencies() -> bool { true }
+fn default_implicit_references() -> String { "warn".to_string() }
+fn default_service_label_template() -> String { crate::transpiler::DEFAULT_SERVICE_LABEL_TEMPLATE.to_string() }
+fn default_tfvars_format() -> String { "hcl".to_string() }
+fn default_split_output() -> String { "none".to_string() }
 
 mod include_processor;
 
@@ -123,6 +272,40 @@ struct Cli {
     #[arg(long, global = true)]
     verbose: bool,
 
+    /// Proceed even if a guardrail limit (see [guardrails] in config.toml) is exceeded
+    #[arg(long, global = true)]
+    override_guardrail: bool,
+
+    /// Print every validation warning instead of grouping repeated ones
+    #[arg(long, global = true)]
+    show_all: bool,
+
+    /// Fail (after printing them) if there are validation warnings; transpile then writes nothing
+    #[arg(long, global = true)]
+    warnings_as_errors: bool,
+
+    /// Print only errors and what the command produces (no progress, no warning summary)
+    #[arg(long, short, global = true)]
+    quiet: bool,
+
+    /// Plain text instead of emoji (also with NO_COLOR set or TERM=dumb)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// TOML file with API endpoints (`storage = "http://localhost:4443"`) for emulators, used by
+    /// the generated providers and cfg2hcl itself; on top of [endpoint_overrides] in config.toml
+    #[arg(long, global = true)]
+    endpoint_overrides: Option<PathBuf>,
+
+    /// Print the time spent per phase (include processing, YAML parse, schema load, validation,
+    /// HCL generation, file IO) to stderr when the command is done
+    #[arg(long, global = true)]
+    timings: bool,
+
+    /// Write the time per phase as folded stacks to FILE, to render a flamegraph from
+    #[arg(long, global = true, value_name = "FILE")]
+    profile_run: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -131,17 +314,49 @@ struct Cli {
 enum Commands {
     /// Transpile YAML config to HCL
     Transpile {
-        /// Name of the input file (inside yaml_dir if relative)
+        /// Name of the input file (inside yaml_dir if relative), `-` for stdin
         input: String,
         /// Name of the output file (inside hcl_dir if relative)
         #[arg(long)]
         output: Option<String>,
+        /// Where the result goes: files (hcl_dir), stdout (concatenated HCL) or json (bundle of files on stdout)
+        #[arg(long, default_value = "files")]
+        emit: String,
+        /// Also pack the generated files and the run report into an archive (.tar.gz, .tgz or .tar)
+        #[arg(long)]
+        bundle: Option<PathBuf>,
+        /// Also write the generated blocks with their addresses and YAML paths as JSON, for tools that post-process the result
+        #[arg(long)]
+        emit_model: Option<PathBuf>,
+        /// Directory with override files applied after the ones of `override_dirs` (repeatable)
+        #[arg(long = "overrides")]
+        overrides: Vec<PathBuf>,
         /// Schema directory containing provider JSON files
         #[arg(long)]
         schema_dir: Option<PathBuf>,
         /// Print all resolved variables as YAML to stdout after transpilation
         #[arg(long)]
         print_variables: bool,
+        /// Leave out import blocks for resources already in state (runs `show -json` in the output directory)
+        #[arg(long)]
+        prune_imports: bool,
+        /// Write scripts/apply-extras.sh with the commands of resources marked `unsupported: true`
+        #[arg(long)]
+        extras_script: bool,
+        /// Write removed.tf with `removed` blocks for resources dropped from the YAML (kept, not destroyed)
+        #[arg(long)]
+        removed_blocks: bool,
+        /// Spread the resources over files: none (main.tf), type (iam.tf, projects.tf, ...), folder or module (default: split_output of config.toml)
+        #[arg(long)]
+        split_by: Option<String>,
+    },
+    /// Check that the current credentials hold the permissions needed to apply a YAML config
+    Preflight {
+        /// Name of the input file (inside yaml_dir if relative)
+        input: String,
+        /// Only list the permissions that would be checked
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Scan Tofu plan JSON for resource renames
     ScanPlan {
@@ -156,9 +371,71 @@ enum Commands {
         /// Path to mapping YAML file
         #[arg(default_value = "mapping.yaml")]
         mapping: PathBuf,
-        /// Output shell script path
-        #[arg(long, default_value = "migrate.sh")]
-        output: PathBuf,
+        /// Output path (default: migrate.sh, or moved.tf in hcl_dir for moved-blocks)
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// script (`state mv` commands) or moved-blocks (`moved` blocks, planned and applied with the HCL)
+        #[arg(long, default_value = "script")]
+        format: String,
+    },
+    /// Report resources in state that are missing from the YAML model and vice versa
+    StateReport {
+        /// Path to a state JSON file (output of `show -json`); runs `show -json` in hcl_dir if omitted
+        #[arg(long)]
+        state_json: Option<PathBuf>,
+        /// Exit with an error if any difference is found
+        #[arg(long)]
+        fail_on_drift: bool,
+    },
+    /// Find import blocks in imports.tf that target resources already in state or no longer generated
+    CheckImports {
+        /// Path to a state JSON file (output of `show -json`); runs `show -json` in hcl_dir if omitted
+        #[arg(long)]
+        state_json: Option<PathBuf>,
+        /// Remove the stale import blocks from imports.tf
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Remove import-id entries from a YAML file and its includes after a successful import
+    StripImportIds {
+        /// Name of the input file (inside yaml_dir if relative)
+        input: String,
+        /// Only remove entries of resources that are present in state
+        #[arg(long)]
+        only_in_state: bool,
+        /// Path to a state JSON file (output of `show -json`); runs `show -json` in hcl_dir if omitted
+        #[arg(long)]
+        state_json: Option<PathBuf>,
+        /// Show what would be removed without changing any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Periodically check the configured scopes for drift and report it to a webhook
+    Daemon {
+        /// Time between checks, e.g. 30m, 6h, 1d (default: [daemon] interval or 6h)
+        #[arg(long)]
+        interval: Option<String>,
+        /// Webhook receiving drift reports (default: [daemon] webhook_url)
+        #[arg(long)]
+        webhook_url: Option<String>,
+        /// Run a single check and exit
+        #[arg(long)]
+        once: bool,
+    },
+    /// Serve POST /transpile and POST /validate over HTTP for self-service portals
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+    },
+    /// Remove generated HCL files and scripts (never state or .terraform)
+    Clean {
+        /// Also remove the schema cache and leftover schema generation directories
+        #[arg(long)]
+        all: bool,
+        /// List what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Initialize project structure and config
     Init {
@@ -215,6 +492,23 @@ enum Commands {
         #[arg(long)]
         tf_tool: Option<String>,
     },
+    /// Move a provider to a new version: config.toml, schema, breaking changes and validation of the YAML
+    UpgradeProvider {
+        /// Provider as configured, e.g. google or google-beta
+        provider: String,
+        /// New version or constraint; 8.x stands for ~> 8.0
+        #[arg(long)]
+        to: String,
+        /// Input YAML files to validate (default: all YAML files in yaml_dir)
+        #[arg(long)]
+        input: Vec<String>,
+        /// Migration rules file with attribute renames to apply to the YAML
+        #[arg(long)]
+        rules: Option<PathBuf>,
+        /// Transpile the inputs once they pass the validation
+        #[arg(long)]
+        regenerate: bool,
+    },
     /// Discover infrastructure and generate YAML config from Terraform state
     DiscoverFromState {
         /// Path to Terraform state JSON file
@@ -251,13 +545,148 @@ enum Commands {
         #[arg(long)]
         discovery_config: Option<PathBuf>,
     },
-    /// Migrate state and configuration between local and cloud modes
+    /// Run `<tf_tool> apply` in hcl_dir with the pre_apply/post_apply hooks
+    Apply {
+        /// Arguments passed on to apply, e.g. `-- -auto-approve`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Run tflint, checkov and trivy on hcl_dir and trace their findings back to the YAML
+    Scan {
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+        /// Exit with an error if there are findings
+        #[arg(long)]
+        fail_on_findings: bool,
+    },
+    /// Show when generated resources were added, changed or removed, from the git history of the YAML
+    History {
+        /// Input YAML file (in yaml_dir)
+        input: String,
+        /// Only resources whose address or YAML path contains this text
+        #[arg(long)]
+        resource: Option<String>,
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+    /// Move the resources of YAML subtrees into a local module in hcl_dir/modules, with `moved` blocks for the state
+    ExtractModule {
+        /// Input YAML file (in yaml_dir)
+        input: String,
+        /// YAML paths of the subtrees, e.g. folder.team-a; more than one extracts a pattern they share
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Name of the module (directory and instance names)
+        #[arg(long)]
+        name: String,
+    },
+    /// Move an org policy's rules from dry_run_spec to spec in the YAML and show the diff
+    PromotePolicy {
+        /// Input YAML file (in yaml_dir); the files it includes are searched as well
+        input: String,
+        /// YAML key or constraint name of the policy, e.g. gcp.resourceLocations
+        policy: String,
+        /// Show the diff without changing any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rewrite legacy organization policies (google_*organization_policy) in the YAML to google_org_policy_policy and show the diff
+    ConvertOrgPolicy {
+        /// Input YAML file (in yaml_dir); the files it includes are converted as well
+        input: String,
+        /// Show the diff without changing any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rewrite IAM entries in the YAML between the member and binding styles, show the diff and write the state migration
+    ConvertIam {
+        /// Input YAML file (in yaml_dir); the files it includes are converted as well
+        input: String,
+        /// Style to convert to: member (google_*_iam_member) or binding (google_*_iam_binding)
+        #[arg(long)]
+        to: String,
+        /// File with the removed and import blocks of the migration, relative to hcl_dir
+        #[arg(long, default_value = "iam_migration.tf")]
+        output: PathBuf,
+        /// Show the diff without changing any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the YAML entry and last commit behind each resource block of a generated file
+    Blame {
+        /// Generated HCL file, e.g. hcl/main.tf
+        file: PathBuf,
+        /// Only the block containing this line
+        #[arg(long)]
+        line: Option<usize>,
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+    /// Write the CODEOWNERS rules for the YAML files and generated HCL from the `owner:` entries
+    Codeowners {
+        /// Input YAML file (in yaml_dir)
+        input: String,
+        /// CODEOWNERS file to update, relative to the config directory
+        #[arg(long, default_value = ".github/CODEOWNERS")]
+        output: PathBuf,
+    },
+    /// Generate a synthetic YAML config and matching state JSON for load tests, demos and bug reports
+    GenFixture {
+        /// Number of folders
+        #[arg(long, default_value_t = 5)]
+        folders: usize,
+        /// Number of projects
+        #[arg(long, default_value_t = 20)]
+        projects: usize,
+        /// Number of resources in the projects
+        #[arg(long, default_value_t = 200)]
+        resources: usize,
+        /// Seed; the same seed and sizes always give the same files
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Path to output YAML file
+        #[arg(long, default_value = "fixture.yaml")]
+        output: PathBuf,
+        /// Path to output state JSON file (as written by `show -json`)
+        #[arg(long, default_value = "fixture-state.json")]
+        state_output: PathBuf,
+    },
+    /// Write a copy of a YAML config (with its includes) with fake IDs, emails and domains, e.g. for bug reports
+    Redact {
+        /// Input YAML file (in yaml_dir)
+        input: String,
+        /// Path to output YAML file
+        #[arg(short, long, default_value = "redacted.yaml")]
+        output: PathBuf,
+    },
+    /// Pack version info, redacted config.toml, schema versions, the last run report and recent logs for a bug report
+    SupportBundle {
+        /// Archive to write (.tar.gz, .tgz or .tar)
+        #[arg(long, default_value = "cfg2hcl-support.tar.gz")]
+        output: PathBuf,
+        /// Also add this YAML file (in yaml_dir), redacted like `cfg2hcl redact`
+        #[arg(long)]
+        input: Option<String>,
+    },
+    /// Migrate state and configuration between local and cloud modes, or to another state bucket
     Migrate {
         /// Name of the input file
         input: String,
         /// Target mode (local or cloud)
         #[arg(long)]
         mode: Option<String>,
+        /// Move the cloud state to this GCS bucket (instead of switching modes)
+        #[arg(long)]
+        to_bucket: Option<String>,
+        /// State prefix in the new bucket (default: keep the current prefix)
+        #[arg(long)]
+        to_prefix: Option<String>,
+        /// Create the state bucket if it does not exist (same settings as bootstrap)
+        #[arg(long)]
+        create_bucket: bool,
     },
     /// Check for and install new releases from GitHub
     SelfUpdate {
@@ -274,8 +703,15 @@ enum Commands {
         #[arg(long)]
         skip_checksum: bool,
     },
-    /// Download the presets folder from the repo into yaml_dir/presets
-    GetPresets,
+    /// Download the presets folder of the latest release into yaml_dir/presets
+    GetPresets {
+        /// Only show which local presets differ from upstream; change nothing
+        #[arg(long, conflicts_with = "update")]
+        check: bool,
+        /// Apply the upstream changes with a diff, keeping locally modified presets
+        #[arg(long)]
+        update: bool,
+    },
     /// Download and open the latest README from the repository
     OpenReadme,
     /// Generate shell completion script
@@ -309,6 +745,22 @@ struct GlobalSettings {
     /// Falls back to $EDITOR env var, then the OS default app.
     #[serde(skip_serializing_if = "Option::is_none")]
     preferred_editor: Option<String>,
+    /// Repository (`owner/name`) that releases, presets and the README come from, e.g. a fork on
+    /// GitHub Enterprise or an internal mirror. Default `tjirsch/rs-cfg2hcl`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_repo: Option<String>,
+    /// Base URL of the repositories API (`https://<host>/api/v3/repos` on GitHub Enterprise).
+    /// Default `https://api.github.com/repos`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_base: Option<String>,
+    /// Base URL of raw file contents (`https://<host>/raw` on GitHub Enterprise).
+    /// Default `https://raw.githubusercontent.com`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_base: Option<String>,
+    /// Token sent as `Authorization: Bearer` to the API and raw hosts, for private forks and
+    /// mirrors and a higher API rate limit. `CFG2HCL_AUTH_TOKEN` and `GITHUB_TOKEN` take precedence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth_token: Option<String>,
 }
 
 impl Default for GlobalSettings {
@@ -317,6 +769,10 @@ impl Default for GlobalSettings {
             self_update_frequency: default_self_update_frequency(),
             last_update_check: None,
             preferred_editor: None,
+            update_repo: None,
+            api_base: None,
+            raw_base: None,
+            auth_token: None,
         }
     }
 }
@@ -340,12 +796,12 @@ fn load_global_settings() -> GlobalSettings {
         let content = match fs::read_to_string(&path) {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("⚠️  Warning: Could not read {}: {}", path.display(), e);
+                eprintln!("{}Warning: Could not read {}: {}", crate::term::icon("⚠️  ", ""), path.display(), e);
                 return GlobalSettings::default();
             }
         };
         return toml::from_str(&content).unwrap_or_else(|e| {
-            eprintln!("⚠️  Warning: Could not parse {}: {}", path.display(), e);
+            eprintln!("{}Warning: Could not parse {}: {}", crate::term::icon("⚠️  ", ""), path.display(), e);
             eprintln!("   String values must be quoted, e.g.  preferred_editor = \"zed\"");
             GlobalSettings::default()
         });
@@ -371,8 +827,18 @@ fn save_global_settings(settings: &GlobalSettings) -> Result<(), Box<dyn std::er
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("cfg2hcl v{} (built {})", env!("CARGO_PKG_VERSION"), env!("BUILD_DATE"));
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    crate::term::init(cli.quiet, cli.no_color);
+    // The recording is dropped last, after the span of the command
+    let _recording = crate::timings::record(cli.timings, cli.profile_run.clone());
+    let _command_span = crate::timings::span(matches.subcommand_name().unwrap_or("cfg2hcl"));
+    // Keep stdout clean for `transpile --emit stdout|json` and `scan --format json`
+    let emits_to_stdout = matches!(&cli.command, Some(Commands::Transpile { emit, .. }) if emit != "files")
+        || matches!(&cli.command, Some(Commands::Scan { format, .. } | Commands::History { format, .. } | Commands::Blame { format, .. }) if format == "json");
+    if !emits_to_stdout {
+        status!("cfg2hcl v{} (built {})", env!("CARGO_PKG_VERSION"), env!("BUILD_DATE"));
+    }
 
     // Load/create global settings on first run (creates ~/.config/cfg2hcl/cfg2hcl.toml with defaults)
     let mut global_settings = load_global_settings();
@@ -401,10 +867,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         } else {
             // Config is mandatory for Transpile and other commands that need it
             match cmd_choice {
-                Commands::Transpile { .. } | Commands::ScanPlan { .. } | Commands::GenerateMigration { .. } | Commands::UpdateSchema { .. } | Commands::DiscoverFromState { .. } | Commands::DiscoverFromOrganization { .. } | Commands::Migrate { .. } | Commands::Bootstrap { .. } | Commands::GetPresets => {
+                Commands::Transpile { .. } | Commands::Preflight { .. } | Commands::ScanPlan { .. } | Commands::GenerateMigration { .. } | Commands::StateReport { .. } | Commands::CheckImports { .. } | Commands::StripImportIds { .. } | Commands::Clean { .. } | Commands::Daemon { .. } | Commands::Serve { .. } | Commands::UpdateSchema { .. } | Commands::UpgradeProvider { .. } | Commands::DiscoverFromState { .. } | Commands::DiscoverFromOrganization { .. } | Commands::Migrate { .. } | Commands::Apply { .. } | Commands::Scan { .. } | Commands::History { .. } | Commands::ExtractModule { .. } | Commands::PromotePolicy { .. } | Commands::ConvertOrgPolicy { .. } | Commands::ConvertIam { .. } | Commands::Blame { .. } | Commands::Codeowners { .. } | Commands::Redact { .. } | Commands::Bootstrap { .. } | Commands::GetPresets { .. } => {
                     return Err("Config file 'config.toml' not found in current directory. Please provide it or specify --config <PATH>.".into());
                 }
-                Commands::Init { .. } | Commands::SelfUpdate { .. } | Commands::Completion { .. } | Commands::OpenReadme | Commands::SetPreferredEditor { .. } => {
+                Commands::Init { .. } | Commands::SelfUpdate { .. } | Commands::Completion { .. } | Commands::OpenReadme | Commands::SetPreferredEditor { .. } | Commands::GenFixture { .. } | Commands::SupportBundle { .. } => {
                     // These commands can proceed without a config file
                     PathBuf::from("config.toml")
                 }
@@ -413,8 +879,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // Optional: check for updates per global settings (skip for SelfUpdate and Init)
-    if !matches!(cmd_choice, Commands::SelfUpdate { .. } | Commands::Init { .. } | Commands::SetPreferredEditor { .. }) {
-        let _ = maybe_check_for_updates(&mut global_settings).await;
+    let mut update_available = None;
+    if !emits_to_stdout && !matches!(cmd_choice, Commands::SelfUpdate { .. } | Commands::Init { .. } | Commands::SetPreferredEditor { .. }) {
+        update_available = maybe_check_for_updates(&mut global_settings).await.ok().flatten();
     }
 
     let config_dir = config_file_path.parent().unwrap_or(Path::new(".")).to_path_buf();
@@ -428,6 +895,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             yaml_dir: default_yaml_dir(),
             hcl_dir: default_hcl_dir(),
             include_dirs: default_include_dirs(),
+            override_dirs: Vec::new(),
+            preset_dirs: Vec::new(),
             schema_dir: default_schema_dir(),
             tf_tool: default_tf_tool(),
             google_providers: default_google_providers(),
@@ -437,132 +906,194 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             provider_version: default_version(),
             auto_explode: default_auto_explode(),
             validation_level: default_validation_level(),
+            language: None,
             discovery_config: None,
+            enforce_member_domain: false,
+            allowed_member_domains: Vec::new(),
+            billing_project_quota: None,
+            required_labels: Vec::new(),
+            implicit_dependencies: default_implicit_dependencies(),
+            implicit_references: default_implicit_references(),
+            service_label_template: default_service_label_template(),
+            tfvars_format: default_tfvars_format(),
+            split_output: default_split_output(),
+            sort_attributes: false,
+            backend_config_file: false,
+            environments: BTreeMap::new(),
+            naming: Default::default(),
+            guardrails: Default::default(),
+            daemon: Default::default(),
+            notifications: Default::default(),
+            audit: Default::default(),
+            hooks: Default::default(),
+            scan: Default::default(),
+            endpoint_overrides: BTreeMap::new(),
+            tags: BTreeMap::new(),
+            resource_aliases: BTreeMap::new(),
         }
     };
 
+    crate::i18n::init(tool_config.language.as_deref());
+    crate::endpoints::init(&tool_config.endpoint_overrides, cli.endpoint_overrides.as_deref())?;
+
     // Create a copy for runtime use with resolved paths
     let mut runtime_config = tool_config.clone();
 
     // Resolve relative paths in runtime_config relative to the config file directory
-    if Path::new(&runtime_config.yaml_dir).is_relative() {
-        runtime_config.yaml_dir = config_dir.join(&runtime_config.yaml_dir).to_str().unwrap().to_string();
+    if runtime_config.yaml_dir.is_relative() {
+        runtime_config.yaml_dir = config_dir.join(&runtime_config.yaml_dir);
     }
-    if Path::new(&runtime_config.hcl_dir).is_relative() {
-        runtime_config.hcl_dir = config_dir.join(&runtime_config.hcl_dir).to_str().unwrap().to_string();
+    if runtime_config.hcl_dir.is_relative() {
+        runtime_config.hcl_dir = config_dir.join(&runtime_config.hcl_dir);
     }
-    if Path::new(&runtime_config.schema_dir).is_relative() {
-        runtime_config.schema_dir = config_dir.join(&runtime_config.schema_dir).to_str().unwrap().to_string();
+    if runtime_config.schema_dir.is_relative() {
+        runtime_config.schema_dir = config_dir.join(&runtime_config.schema_dir);
     }
     runtime_config.include_dirs = runtime_config.include_dirs.into_iter().map(|d| {
-        if Path::new(&d).is_relative() {
-            config_dir.join(d).to_str().unwrap().to_string()
+        if d.is_relative() {
+            config_dir.join(d)
         } else {
             d
         }
     }).collect();
+    runtime_config.override_dirs = runtime_config.override_dirs.into_iter()
+        .map(|d| if d.is_relative() { config_dir.join(d) } else { d })
+        .collect();
+    runtime_config.preset_dirs = runtime_config.preset_dirs.into_iter()
+        .map(|d| if d.is_relative() { config_dir.join(d) } else { d })
+        .collect();
+    crate::presets::init(&runtime_config.preset_dirs, &runtime_config.yaml_dir);
+    for dir in runtime_config.environments.values_mut() {
+        if dir.is_relative() {
+            *dir = config_dir.join(&*dir);
+        }
+    }
+    for plugin in runtime_config.tags.values_mut() {
+        if let Some(program) = plugin.command.first_mut() {
+            if program.contains('/') && Path::new(program.as_str()).is_relative() {
+                *program = config_dir.join(&*program).to_string_lossy().into_owned();
+            }
+        }
+    }
+
+    tool_config.notifications.warn_unknown_events();
+    if let Some((version, url)) = update_available {
+        let text = format!("cfg2hcl {} is available (current: {}): {}", version, env!("CARGO_PKG_VERSION"), url);
+        let details = serde_json::json!({ "event": "update-available", "version": version, "current": env!("CARGO_PKG_VERSION"), "url": url });
+        crate::notify::notify(&tool_config.notifications, "update-available", &text, details).await;
+    }
 
+    // Validation warnings are grouped and printed when the command is done (also when it fails);
+    // the long-running commands print them right away
+    let _collecting = (!matches!(cmd_choice, Commands::Daemon { .. } | Commands::Serve { .. }))
+        .then(|| crate::warnings::collect(cli.show_all));
+    let (show_all, warnings_as_errors) = (cli.show_all, cli.warnings_as_errors);
 
     match cmd_choice {
-        Commands::Transpile { input, output, schema_dir, print_variables } => {
+        Commands::Transpile { input, output, emit, bundle, emit_model, overrides, schema_dir, print_variables, prune_imports, extras_script, removed_blocks, split_by } => {
             let validation_level = cli.validation.unwrap_or(tool_config.validation_level.clone());
+            runtime_config.override_dirs.extend(overrides);
 
-            let input_path = if Path::new(&input).is_absolute() {
-                PathBuf::from(&input)
-            } else {
-                PathBuf::from(&runtime_config.yaml_dir).join(&input)
+            // With stdout/json nothing is written; only the existing schema cache is read
+            let to_stdout = match emit.as_str() {
+                "files" => false,
+                "stdout" | "json" => true,
+                other => return Err(format!("Unknown --emit '{}' (expected files, stdout or json)", other).into()),
             };
+            if to_stdout && (output.is_some() || bundle.is_some() || emit_model.is_some() || print_variables || prune_imports || extras_script || removed_blocks) {
+                return Err(format!("--emit {} cannot be combined with --output, --bundle, --emit-model, --print-variables, --prune-imports, --extras-script or --removed-blocks", emit).into());
+            }
+            if let Some(bundle) = &bundle {
+                crate::output::bundle_is_gzip(bundle)?;
+            }
+            // Hooks only run when files are written, so that stdout stays clean
+            if !to_stdout {
+                let hook_env = [("CFG2HCL_INPUT", input.clone()), ("CFG2HCL_HCL_DIR", runtime_config.hcl_dir.display().to_string())];
+                crate::hooks::run("pre_transpile", &tool_config.hooks.pre_transpile, &config_dir, &hook_env)?;
+            }
 
-            let include_paths: Vec<PathBuf> = runtime_config.include_dirs.iter().map(PathBuf::from).collect();
-            let processed_content = include_processor::process_includes(&input_path, &include_paths)?;
-            let raw_value: serde_yaml::Value = serde_yaml::from_str::<serde_yaml::Value>(&processed_content).map_err(|e| {
-                print_yaml_error_context(&processed_content, &e);
-                e
-            })?;
-            let raw_value_for_vars = raw_value.clone();
-            let merged_value = merge_variables(raw_value);
-            let processed_value = resolve_yaml_custom_tags(merged_value);
-
-            let config: Config = {
-                serde_path_to_error::deserialize::<_, Config>(processed_value).map_err(|e: serde_path_to_error::Error<serde_yaml::Error>| {
-                    let path = e.path().to_string();
-                    format!("Error at '{}': {}", path, e.into_inner())
-                })?
+            let loaded = if input == "-" {
+                let mut content = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut content)
+                    .map_err(|e| format!("Failed to read YAML from stdin: {}", e))?;
+                let expanded = include_processor::process_includes_content(&content, Path::new("<stdin>"), &runtime_config.yaml_dir, &runtime_config.include_dirs, false)?;
+                parse_input(expanded, &runtime_config, false)?
+            } else {
+                load_input(&resolve_input_path(&input, &runtime_config), &runtime_config)?
             };
+            check_input(&loaded, &runtime_config, &validation_level)?;
+            let LoadedInput { raw_value: raw_value_for_vars, config, processed_content, source_map, .. } = loaded;
 
             // Sync schemas based on providers in YAML
-            if let Some(providers) = &config.providers {
+            if let Some(providers) = config.providers.as_ref().filter(|_| !to_stdout) {
                 let provider_names: Vec<String> = providers.keys().cloned().collect();
                 sync_schemas(&mut tool_config, &runtime_config, &provider_names, &config_file_path)?;
             }
 
-            let s_dir = schema_dir.unwrap_or_else(|| PathBuf::from(&runtime_config.schema_dir));
-            if !s_dir.exists() {
+            let s_dir = schema_dir.unwrap_or_else(|| runtime_config.schema_dir.clone());
+            if !s_dir.exists() && !to_stdout {
                 fs::create_dir_all(&s_dir)
                     .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create schema directory '{}': {}", s_dir.display(), e)))?;
             }
-            let registry = ResourceRegistry::load_all(s_dir.to_str().unwrap_or("schemas"))?;
+            let registry = ResourceRegistry::load_all(&s_dir)?;
 
-            let variables = extract_variables(&raw_value_for_vars);
+            let tfvars_format = crate::tfvars::TfvarsFormat::parse(&tool_config.tfvars_format)?;
+            let split_by = crate::split::SplitBy::parse(split_by.as_deref().unwrap_or(&tool_config.split_output))?;
+            let variables = extract_variables(&raw_value_for_vars)?;
+            let declarations = variable_declarations(&raw_value_for_vars)?;
             let variables_snapshot = if print_variables { Some(variables.clone()) } else { None };
+            let sensitive: BTreeSet<String> = declarations.iter().filter(|(_, d)| d.sensitive).map(|(name, _)| name.clone()).collect();
 
-            let mut provider_sources = HashMap::new();
-            let mut provider_versions = HashMap::new();
-            
-            // Populate sources and versions from parsed config
-            // Note: We need to handle source logic specifically per cloud type if possible,
-            // but here we are iterating generally.
-            // Let's iterate the original lists to know the "type" (google, aws, etc) or infer from name.
-            
-            let def_ver = tool_config.provider_version.clone();
-
-            for p in &tool_config.google_providers {
-                let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
-                let source = if name.contains('/') { name.clone() } else { format!("hashicorp/{}", name) };
-                provider_sources.insert(name.clone(), source);
-                provider_versions.insert(name, ver);
-            }
-             for p in &tool_config.aws_providers {
-                let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
-                let source = if name.contains('/') { name.clone() } else { format!("hashicorp/{}", name) };
-                provider_sources.insert(name.clone(), source);
-                provider_versions.insert(name, ver);
-             }
-             for p in &tool_config.azure_providers {
-                let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
-                let source = if name.contains('/') { name.clone() } else {
-                     let base = if name.starts_with("azurerm") { "azurerm" } else { "azurerm" }; 
-                     format!("hashicorp/{}", base)
-                };
-                provider_sources.insert(name.clone(), source);
-                provider_versions.insert(name, ver);
-             }
-             for p in &tool_config.alibaba_providers {
-                let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
-                provider_sources.insert(name.clone(), "aliyun/alicloud".to_string());
-                provider_versions.insert(name, ver);
-             }
+            let (provider_sources, provider_versions) = provider_settings(&tool_config);
+            let mut manifest = crate::manifest::Manifest::load(&config_dir)?;
+            let previous_label_templates = manifest.previous_service_label_templates(&tool_config.service_label_template);
 
             let transpiler = Transpiler::new(
                 &config,
-                Some(registry),
+                Some(std::sync::Arc::new(registry)),
                 runtime_config.auto_explode.clone(),
                 validation_level,
                 variables,
                 provider_sources,
                 provider_versions
-            );
-            let project = transpiler.transpile()?;
+            ).with_required_labels(tool_config.required_labels.clone())
+                .with_implicit_dependencies(tool_config.implicit_dependencies)
+                .with_implicit_references(tool_config.implicit_references()?)
+                .with_service_label_template(tool_config.service_label_template.clone(), previous_label_templates.clone())
+                .with_variable_descriptions(crate::yaml_comments::variable_descriptions(&processed_content))
+                .with_variable_declarations(declarations)
+                .with_resource_aliases(tool_config.resource_aliases())
+                .with_tfvars(tfvars_format, environment_variables(&processed_content, &runtime_config)?)
+                .with_split_by(split_by)
+                .with_backend_config_file(tool_config.backend_config_file);
+            let mut project = transpiler.transpile()?;
+            project.annotate(&processed_content);
+            project.format(tool_config.sort_attributes);
+
+            let violations = crate::guardrails::check_generated(&project.resources_tf(), &tool_config.guardrails)?;
+            crate::guardrails::enforce(&violations, cli.override_guardrail)?;
+            if warnings_as_errors && crate::warnings::count() > 0 {
+                return Err(t("warnings-no-files-written", &[("count", &crate::warnings::count())]).into());
+            }
+
+            if emit == "stdout" {
+                print!("{}", crate::output::concatenated(&project));
+                return Ok(());
+            }
+            if emit == "json" {
+                println!("{}", serde_json::to_string_pretty(&crate::output::bundle(&project))?);
+                return Ok(());
+            }
 
             // The user wants HCL files created directly in the hcl_dir
             let base_output_path = if let Some(out) = output {
                 if Path::new(&out).is_absolute() {
                     PathBuf::from(out)
                 } else {
-                    PathBuf::from(&runtime_config.hcl_dir).join(out)
+                    runtime_config.hcl_dir.clone().join(out)
                 }
             } else {
-                PathBuf::from(&runtime_config.hcl_dir)
+                runtime_config.hcl_dir.clone()
             };
 
             // Ensure the output directory exists
@@ -571,37 +1102,136 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create output directory '{}': {}", base_output_path.display(), e)))?;
             }
 
-            let imports_path = base_output_path.join("imports.tf");
-            if imports_path.exists() {
-                fs::remove_file(&imports_path)
-                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to delete old imports.tf: {}", e)))?;
+            let mut imports_tf = project.file("imports.tf").to_string();
+            if prune_imports && !imports_tf.trim().is_empty() {
+                let state = crate::state_report::read_state(&tool_config.tf_tool, &base_output_path, None)?;
+                let in_state = crate::state_report::state_resources(&state).into_iter().map(|r| r.instance).collect();
+                let (kept, stale) = crate::state_report::find_stale_imports(&imports_tf, None, &in_state)?;
+                crate::state_report::print_stale_imports(&stale, true);
+                imports_tf = crate::formatter::format(&kept, tool_config.sort_attributes);
             }
 
-            let write_file = |filename: &str, content: &str| -> std::io::Result<()> {
-                if content.trim().is_empty() { return Ok(()); }
-                let p = base_output_path.join(filename);
-                fs::write(&p, content)
-                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to write file '{}': {}", p.display(), e)))?;
-                println!("Created {}", p.display());
-                Ok(())
+            // .tf files of the previous run, which may have been split differently
+            let relative_output_path = base_output_path.strip_prefix(&config_dir).unwrap_or(&base_output_path);
+            let mut previous_tf_files: BTreeSet<String> = manifest.generated.iter()
+                .filter(|p| p.extension().is_some_and(|e| e == "tf"))
+                .filter_map(|p| p.strip_prefix(relative_output_path).ok())
+                .filter(|p| p.parent() == Some(Path::new("")) || p.starts_with("modules"))
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+            previous_tf_files.insert("main.tf".to_string());
+
+            // Resources of the previous run that are no longer generated
+            let removed_tf = if removed_blocks {
+                let previous = |name: &str| fs::read_to_string(base_output_path.join(name)).ok();
+                let previous_resources: Vec<(String, String)> = previous_tf_files.iter()
+                    .filter(|name| *name != "removed.tf" && crate::transpiler::GeneratedProject::is_resource_file(name))
+                    .filter_map(|name| previous(name).map(|content| (name.clone(), content)))
+                    .collect();
+                let resource_files: Vec<(&str, &str)> = project.files().into_iter()
+                    .filter(|(name, _)| crate::transpiler::GeneratedProject::is_resource_file(name))
+                    .collect();
+                let removed_tf = crate::removed::removed_blocks(&previous_resources, previous("removed.tf").as_deref(), &resource_files)?;
+                Some(crate::formatter::format(&removed_tf, tool_config.sort_attributes))
+            } else {
+                None
             };
 
-            write_file("main.tf", &project.main_tf)?;
-            write_file("providers.tf", &project.providers_tf)?;
-            write_file("variables.tf", &project.variables_tf)?;
-            write_file("terraform.tfvars", &project.tfvars)?;
-            write_file("imports.tf", &project.imports_tf)?;
+            // Empty outputs keep the previous file, except imports.tf, outputs.tf and backend.hcl which
+            // must not outlive their entries and the tfvars file of the other format, which Terraform would still load
+            let non_empty = |content: &str| (!content.trim().is_empty()).then(|| content.to_string());
+            let removed_with_entries = ["outputs.tf", "imports.tf", crate::output::BACKEND_CONFIG_FILE];
+            let mut files: Vec<(&str, Option<String>)> = project.files().into_iter()
+                .filter(|(name, _)| !removed_with_entries.contains(name))
+                .map(|(name, content)| (name, Some(content.to_string())))
+                .collect();
+            files.push(("outputs.tf", non_empty(project.file("outputs.tf"))));
+            files.push(("imports.tf", non_empty(&imports_tf)));
+            files.push((crate::output::BACKEND_CONFIG_FILE, non_empty(project.file(crate::output::BACKEND_CONFIG_FILE))));
+            if let Some(removed_tf) = &removed_tf {
+                files.push(("removed.tf", non_empty(removed_tf)));
+            }
+            let other_tfvars = match tfvars_format {
+                crate::tfvars::TfvarsFormat::Hcl => crate::tfvars::TfvarsFormat::Json,
+                crate::tfvars::TfvarsFormat::Json => crate::tfvars::TfvarsFormat::Hcl,
+            }.file_name("terraform");
+            files.push((&other_tfvars, None));
+            // Resource files of another split
+            let stale: Vec<String> = previous_tf_files.into_iter()
+                .filter(|name| !files.iter().any(|(n, _)| n == name) && !["outputs.tf", "imports.tf", "removed.tf"].contains(&name.as_str()))
+                .collect();
+            files.extend(stale.iter().map(|name| (name.as_str(), None)));
+
+            let files: Vec<(&str, Option<&str>)> = files.iter().map(|(name, c)| (*name, c.as_deref())).collect();
+            let written = crate::output::write_atomically(&base_output_path, &files)?;
+            for (name, _) in &files {
+                let path = base_output_path.join(name);
+                manifest.record_generated(&config_dir, &path, written.contains(&path));
+            }
+            manifest.service_label_templates = previous_label_templates;
+            manifest.service_label_templates.push(tool_config.service_label_template.clone());
+            manifest.save(&config_dir)?;
+            for path in &written {
+                println!("Created {}", path.display());
+            }
+
+            let generated: Vec<(&str, &str)> = files.iter().filter_map(|(name, c)| c.map(|c| (*name, c))).collect();
+            let provenance = crate::provenance::Provenance::new(&input, &project, &processed_content, &source_map);
+            let report = crate::run_report::RunReport::new(&input, &generated, &project.unsupported, provenance.owner_counts());
+            report.save(&config_dir)?;
+            provenance.save(&config_dir)?;
+            if let Some(bundle) = bundle {
+                let bundle_path = if bundle.is_absolute() { bundle } else { config_dir.join(bundle) };
+                crate::output::write_bundle(&bundle_path, &generated, &report)?;
+                println!("Created bundle {}", bundle_path.display());
+            }
+            if let Some(model_path) = emit_model {
+                let model_path = if model_path.is_absolute() { model_path } else { config_dir.join(model_path) };
+                project.model()?.save(&model_path)?;
+                println!("Created model {}", model_path.display());
+            }
+
+            if extras_script {
+                crate::extras::write_extras_script(&config_dir, &input, &project.unsupported)?;
+            } else if !project.unsupported.is_empty() {
+                eprintln!("Warning: {} unsupported resource(s) skipped. Use --extras-script to generate scripts/apply-extras.sh.", project.unsupported.len());
+            }
 
             if let Some(vars) = variables_snapshot {
                 let vars_map: serde_yaml::Mapping = vars
                     .into_iter()
-                    .map(|(k, v)| (serde_yaml::Value::String(k), v))
+                    .map(|(k, v)| {
+                        let v = if sensitive.contains(&k) { serde_yaml::Value::String("(sensitive)".to_string()) } else { v };
+                        (serde_yaml::Value::String(k), v)
+                    })
                     .collect();
                 print!("{}", serde_yaml::to_string(&serde_yaml::Value::Mapping(vars_map))?);
             }
 
+            let hook_env = [
+                ("CFG2HCL_INPUT", input),
+                ("CFG2HCL_HCL_DIR", base_output_path.display().to_string()),
+                ("CFG2HCL_FILES", std::env::join_paths(&written)?.to_string_lossy().into_owned()),
+                ("CFG2HCL_RUN_REPORT", crate::run_report::RunReport::path(&config_dir).display().to_string()),
+            ];
+            crate::hooks::run("post_transpile", &tool_config.hooks.post_transpile, &config_dir, &hook_env)?;
+
             Ok::<(), Box<dyn std::error::Error>>(())
         }
+        Commands::Preflight { input, dry_run } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let loaded = load_input(&input_path, &runtime_config)?;
+            // Projects of the last generated HCL, to tell which ones the next apply deletes
+            let generated_projects = if runtime_config.hcl_dir.is_dir() {
+                crate::state_report::generated_resources(&runtime_config.hcl_dir)?.into_iter()
+                    .filter(|r| r.address.rsplit('.').nth(1) == Some("google_project"))
+                    .filter_map(|r| r.project)
+                    .collect()
+            } else {
+                Default::default()
+            };
+            crate::preflight::preflight(&loaded.config, &generated_projects, dry_run, tool_config.billing_project_quota).await
+        }
         Commands::Init {
             defaults,
             providers,
@@ -651,15 +1281,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let dirs = vec![&tool_config.yaml_dir, &tool_config.hcl_dir, &tool_config.schema_dir];
             for d in dirs {
                 fs::create_dir_all(d)?;
-                println!("Created directory: {}", d);
+                println!("Created directory: {}", d.display());
             }
 
             // 2. Generate config.toml if missing
             if !Path::new("config.toml").exists() {
                 let mut config_lines = vec![
-                    format!("schema_dir = \"{}\"", tool_config.schema_dir),
-                    format!("yaml_dir = \"{}\"", tool_config.yaml_dir),
-                    format!("hcl_dir = \"{}\"", tool_config.hcl_dir),
+                    format!("schema_dir = \"{}\"", tool_config.schema_dir.display()),
+                    format!("yaml_dir = \"{}\"", tool_config.yaml_dir.display()),
+                    format!("hcl_dir = \"{}\"", tool_config.hcl_dir.display()),
                     "include_dirs = [\".\", \"yaml\"]".to_string(),
                     format!("tf_tool = \"{}\"", tool),
                 ];
@@ -705,7 +1335,7 @@ Thumbs.db
 
             // 4. Generate template YAML if customer_id provided
             if let Some(c_id) = customer_id {
-                let yaml_path = PathBuf::from(&runtime_config.yaml_dir).join(format!("{}.yaml", c_id));
+                let yaml_path = runtime_config.yaml_dir.join(format!("{}.yaml", c_id));
                 if !yaml_path.exists() {
                     let args = crate::template::TemplateArgs {
                         customer_id: c_id.clone(),
@@ -733,16 +1363,16 @@ Thumbs.db
 
             if !all_provs.is_empty() {
                 for p in all_provs {
-                    println!("Fetching schema for {}...", p);
+                    status!("Fetching schema for {}...", p);
                     crate::schema::ResourceRegistry::generate_schema(
                         &tool,
                         &p,
                         &runtime_config.provider_version,
-                        &format!("{}/{}.json", runtime_config.schema_dir, p)
+                        &runtime_config.schema_dir.join(format!("{}.json", p))
                     )?;
                 }
             }
-            println!("Initialization complete.");
+            status!("Initialization complete.");
             Ok(())
         }
         Commands::UpdateSchema { providers, version, tf_tool } => {
@@ -755,21 +1385,122 @@ Thumbs.db
                  let def_ver = version.unwrap_or_else(|| tool_config.provider_version.clone());
                  for prov in p_list {
                      let (p_name, p_ver) = ToolConfig::parse_provider_string_with_default(&prov, &def_ver);
-                     let out = PathBuf::from(format!("{}/{}.json", runtime_config.schema_dir, p_name.split('/').last().unwrap_or(&p_name)));
-                     println!("Updating schema for {} version {} using {}...", p_name, p_ver, tool);
-                     ResourceRegistry::generate_schema(&tool, &p_name, &p_ver, out.to_str().unwrap())?;
+                     let out = runtime_config.schema_dir.join(format!("{}.json", p_name.split('/').next_back().unwrap_or(&p_name)));
+                     status!("Updating schema for {} version {} using {}...", p_name, p_ver, tool);
+                     ResourceRegistry::generate_schema(&tool, &p_name, &p_ver, &out)?;
                  }
             } else {
                  // Use parsed config
                  for (p_name, p_ver) in tool_config.parsed_providers() {
                       // Override if version passed (unlikely for bulk update but possible)
                       let usage_ver = version.clone().unwrap_or(p_ver);
-                      let out = PathBuf::from(format!("{}/{}.json", runtime_config.schema_dir, p_name.split('/').last().unwrap_or(&p_name)));
-                      println!("Updating schema for {} version {} using {}...", p_name, usage_ver, tool);
-                      ResourceRegistry::generate_schema(&tool, &p_name, &usage_ver, out.to_str().unwrap())?;
+                      let out = runtime_config.schema_dir.join(format!("{}.json", p_name.split('/').next_back().unwrap_or(&p_name)));
+                      status!("Updating schema for {} version {} using {}...", p_name, usage_ver, tool);
+                      ResourceRegistry::generate_schema(&tool, &p_name, &usage_ver, &out)?;
                  }
             }
-            println!("Done.");
+            status!("Done.");
+            Ok(())
+        }
+        Commands::UpgradeProvider { provider, to, input, rules, regenerate } => {
+            let constraint = crate::upgrade_provider::version_constraint(&to);
+            let default_version = tool_config.provider_version.clone();
+            let lists = [&mut tool_config.google_providers, &mut tool_config.aws_providers, &mut tool_config.azure_providers, &mut tool_config.alibaba_providers];
+            let entry = lists.into_iter().flat_map(|list| list.iter_mut())
+                .find(|p| ToolConfig::parse_provider_string(p).0 == provider)
+                .ok_or_else(|| format!("Provider '{}' is not configured in config.toml", provider))?;
+            let (_, previous_version) = ToolConfig::parse_provider_string_with_default(entry, &default_version);
+            *entry = format!("{}|{}", provider, constraint);
+            let rules = rules.map(|path| crate::upgrade_provider::load_rules(&path)).transpose()?.unwrap_or_default();
+
+            // The previous schema is compared with the new one
+            let schema_path = runtime_config.schema_dir.join(format!("{}.json", provider.split('/').next_back().unwrap_or(&provider)));
+            let old_schemas = match crate::upgrade_provider::load_resource_schemas(&schema_path) {
+                Ok(schemas) => schemas,
+                Err(e) => {
+                    eprintln!("Warning: {}; breaking changes cannot be reported", e);
+                    HashMap::new()
+                }
+            };
+            status!("Updating schema for {} from {} to {} using {}...", provider, previous_version, constraint, tool_config.tf_tool);
+            ResourceRegistry::generate_schema(&tool_config.tf_tool, &provider, &constraint, &schema_path)?;
+            tool_config.save(&config_file_path)?;
+            println!("Updated {} to {} in {}.", provider, constraint, config_file_path.display());
+            let new_schemas = crate::upgrade_provider::load_resource_schemas(&schema_path)?;
+
+            // What the last transpile generated
+            let manifest = crate::manifest::Manifest::load(&config_dir)?;
+            let generated: Vec<String> = manifest.generated.iter()
+                .filter(|p| p.extension().is_some_and(|e| e == "tf"))
+                .filter_map(|p| fs::read_to_string(config_dir.join(p)).ok())
+                .collect();
+            let used = crate::upgrade_provider::used_attributes(&generated);
+            let findings = crate::upgrade_provider::breaking_changes(&old_schemas, &new_schemas, &used, &rules);
+            for finding in &findings {
+                let subject = if finding.path.is_empty() { finding.resource_type.clone() } else { format!("{}.{}", finding.resource_type, finding.path) };
+                match &finding.renamed_to {
+                    Some(new_name) => println!("{}: {} (renamed to {} by the migration rules)", subject, finding.message, new_name),
+                    None => println!("{}: {}", subject, finding.message),
+                }
+            }
+            let open = findings.iter().filter(|f| f.renamed_to.is_none()).count();
+            println!("{} breaking change(s) in the generated resources, {} covered by the migration rules.", findings.len(), findings.len() - open);
+
+            let inputs: Vec<PathBuf> = if input.is_empty() {
+                let mut files: Vec<PathBuf> = fs::read_dir(&runtime_config.yaml_dir)?.flatten().map(|e| e.path())
+                    .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "yaml" || e == "yml"))
+                    .collect();
+                files.sort();
+                files
+            } else {
+                input.iter().map(|i| resolve_input_path(i, &runtime_config)).collect()
+            };
+            if !rules.is_empty() {
+                for input_path in &inputs {
+                    for file in include_processor::included_files(input_path, &runtime_config.include_dirs)? {
+                        let content = fs::read_to_string(&file)?;
+                        let (renamed, changes) = crate::upgrade_provider::apply_renames(&content, &rules);
+                        for (line, change) in &changes {
+                            println!("{}:{}: {}", file.display(), line, change);
+                        }
+                        if !changes.is_empty() {
+                            fs::write(&file, renamed).map_err(|e| format!("Failed to write '{}': {}", file.display(), e))?;
+                        }
+                    }
+                }
+            }
+
+            // Each input is transpiled without writing files; with --regenerate again for real
+            let mut global_args: Vec<std::ffi::OsString> = Vec::new();
+            if let Some(config_path) = &cli.config {
+                global_args.extend(["--config".into(), config_path.into()]);
+            }
+            global_args.extend(["--validation".into(), cli.validation.clone().unwrap_or_else(|| "error".to_string()).into()]);
+            let mut failed = Vec::new();
+            for input_path in &inputs {
+                // Absolute, so that transpile does not resolve it relative to yaml_dir again
+                let absolute = std::path::absolute(input_path)?;
+                let valid = std::process::Command::new(std::env::current_exe()?)
+                    .args(&global_args)
+                    .arg("transpile").arg(&absolute).args(["--emit", "json"])
+                    .stdout(std::process::Stdio::null())
+                    .status()?
+                    .success();
+                if !valid {
+                    failed.push(input_path.display().to_string());
+                    continue;
+                }
+                println!("{} is valid for {} {}.", input_path.display(), provider, constraint);
+                if regenerate {
+                    let status = std::process::Command::new(std::env::current_exe()?).args(&global_args).arg("transpile").arg(&absolute).status()?;
+                    if !status.success() {
+                        failed.push(input_path.display().to_string());
+                    }
+                }
+            }
+            if !failed.is_empty() {
+                return Err(format!("{} input(s) fail with {} {}: {}", failed.len(), provider, constraint, failed.join(", ")).into());
+            }
             Ok(())
         }
         Commands::ScanPlan { plan_json, output } => {
@@ -777,27 +1508,172 @@ Thumbs.db
             let mapping = crate::state_migration::scan_plan(&p_json)?;
             let yaml = serde_yaml::to_string(&mapping)?;
 
+            // Renamed resources are moved, not destroyed
+            let destroys: Vec<String> = crate::state_migration::planned_destroys(&p_json)?
+                .into_iter()
+                .filter(|address| !mapping.contains_key(address))
+                .collect();
+            let violations = crate::guardrails::check_destroys(&destroys, &tool_config.guardrails);
+            crate::guardrails::enforce(&violations, cli.override_guardrail)?;
+
             let final_output = if output.is_absolute() { output } else { config_dir.join(output) };
             fs::write(&final_output, yaml)?;
             println!("Mapping generated: {}", final_output.display());
+
+            let summary = crate::state_migration::plan_summary(&p_json)?;
+            let text = format!(
+                "Plan {}: {} to create, {} to update, {} to replace, {} to destroy, {} renamed (moved)",
+                p_json.display(), summary.create, summary.update, summary.replace, summary.delete, mapping.len()
+            );
+            let details = serde_json::json!({
+                "event": "plan-summary",
+                "plan": p_json.display().to_string(),
+                "create": summary.create,
+                "update": summary.update,
+                "replace": summary.replace,
+                "delete": summary.delete,
+                "moved": mapping.len(),
+                "destroys": destroys,
+            });
+            crate::notify::notify(&tool_config.notifications, "plan-summary", &text, details).await;
             Ok(())
         }
-        Commands::GenerateMigration { mapping, output } => {
+        Commands::GenerateMigration { mapping, output, format } => {
             let m_path = if mapping.is_absolute() { mapping } else { config_dir.join(mapping) };
-            let final_output = if output.is_absolute() { output } else { config_dir.join(output) };
-            crate::state_migration::generate_migration(&m_path, &final_output, &tool_config.tf_tool)?;
-            println!("Migration script generated: {}", final_output.display());
+            let resolve = |output: PathBuf| if output.is_absolute() { output } else { config_dir.join(output) };
+            match format.as_str() {
+                "script" => {
+                    let final_output = resolve(output.unwrap_or_else(|| PathBuf::from("migrate.sh")));
+                    crate::state_migration::generate_migration(&m_path, &final_output, &tool_config.tf_tool)?;
+                    println!("Migration script generated: {}", final_output.display());
+                }
+                "moved-blocks" => {
+                    let final_output = output.map(resolve).unwrap_or_else(|| runtime_config.hcl_dir.join("moved.tf"));
+                    crate::state_migration::generate_moved_blocks(&m_path, &final_output)?;
+                    println!("Moved blocks generated: {}", final_output.display());
+                }
+                other => return Err(format!("Unknown --format '{}' (expected script or moved-blocks)", other).into()),
+            }
+            Ok(())
+        }
+        Commands::StateReport { state_json, fail_on_drift } => {
+            let hcl_dir = runtime_config.hcl_dir.clone();
+            let state = crate::state_report::read_state(&tool_config.tf_tool, &hcl_dir, state_json.as_deref())?;
+            let report = crate::state_report::state_report(&state, &hcl_dir)?;
+            let differences = report.differences();
+            if differences > 0 {
+                crate::notify::notify(&tool_config.notifications, "drift", &report.text(&hcl_dir), report.details(&hcl_dir)).await;
+            }
+            if fail_on_drift && differences > 0 {
+                return Err(format!("{} difference(s) between state and YAML model", differences).into());
+            }
+            Ok(())
+        }
+        Commands::CheckImports { state_json, prune } => {
+            let hcl_dir = runtime_config.hcl_dir.clone();
+            let imports_path = hcl_dir.join("imports.tf");
+            if !imports_path.exists() {
+                println!("No imports.tf found in {}", hcl_dir.display());
+                return Ok(());
+            }
+            let content = fs::read_to_string(&imports_path)?;
+
+            let state = crate::state_report::read_state(&tool_config.tf_tool, &hcl_dir, state_json.as_deref())?;
+            let in_state = crate::state_report::state_resources(&state).into_iter().map(|r| r.instance).collect();
+            let generated = crate::state_report::generated_resources(&hcl_dir)?.into_iter().map(|r| r.address).collect();
+            let (kept, stale) = crate::state_report::find_stale_imports(&content, Some(&generated), &in_state)?;
+
+            if stale.is_empty() {
+                println!("{}No stale import blocks in {}", crate::term::icon("✅ ", ""), imports_path.display());
+                return Ok(());
+            }
+            crate::state_report::print_stale_imports(&stale, prune);
+            if !prune {
+                println!("Run with --prune to remove {} stale import block(s).", stale.len());
+            } else if kept.trim().is_empty() {
+                fs::remove_file(&imports_path)?;
+                println!("Removed {} (no import blocks left)", imports_path.display());
+            } else {
+                fs::write(&imports_path, kept)?;
+                println!("Updated {}", imports_path.display());
+            }
+            Ok(())
+        }
+        Commands::StripImportIds { input, only_in_state, state_json, dry_run } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let in_state: Option<std::collections::BTreeSet<String>> = if only_in_state {
+                let state = crate::state_report::read_state(&tool_config.tf_tool, &runtime_config.hcl_dir, state_json.as_deref())?;
+                Some(crate::state_report::state_resources(&state).into_iter().map(|r| r.address).collect())
+            } else {
+                None
+            };
+
+            let include_paths: Vec<PathBuf> = runtime_config.include_dirs.clone();
+            let mut total = 0;
+            for file in include_processor::included_files(&input_path, &include_paths)? {
+                let content = fs::read_to_string(&file)?;
+                let (stripped, removed) = crate::import_cleanup::strip_import_ids(&content, in_state.as_ref());
+                if removed.is_empty() {
+                    continue;
+                }
+                for entry in &removed {
+                    let address = entry.address.as_deref().unwrap_or("?");
+                    println!("{}:{}: import-id of {}", file.display(), entry.line, address);
+                }
+                if !dry_run {
+                    fs::write(&file, stripped)?;
+                }
+                total += removed.len();
+            }
+
+            if dry_run {
+                println!("{} import-id entries would be removed (dry run).", total);
+            } else {
+                println!("Removed {} import-id entries.", total);
+            }
             Ok(())
         }
+        Commands::Daemon { interval, webhook_url, once } => {
+            let interval = crate::daemon::parse_interval(
+                interval.as_deref().or(tool_config.daemon.interval.as_deref()).unwrap_or("6h"),
+            )?;
+            let scopes: Vec<PathBuf> = if tool_config.daemon.scopes.is_empty() {
+                vec![runtime_config.hcl_dir.clone()]
+            } else {
+                tool_config.daemon.scopes.iter().map(|s| config_dir.join(s)).collect()
+            };
+            let webhook_url = webhook_url
+                .or_else(|| tool_config.daemon.webhook_url.clone())
+                .or_else(|| tool_config.notifications.webhook_for("drift").map(String::from));
+            crate::daemon::run(&tool_config.tf_tool, &scopes, webhook_url.as_deref(), interval, once).await
+        }
+        Commands::Serve { listen } => {
+            // Schemas are loaded once; run `update-schema` and restart to pick up new ones
+            let registry = ResourceRegistry::load_all(&runtime_config.schema_dir)?;
+            let context = crate::serve::ServeContext {
+                validation_level: cli.validation.unwrap_or(tool_config.validation_level.clone()),
+                runtime_config,
+                registry: std::sync::Arc::new(registry),
+            };
+            crate::serve::serve(&listen, context).await
+        }
+        Commands::Clean { all, dry_run } => {
+            crate::clean::clean(&config_dir, &runtime_config.schema_dir, all, dry_run)
+        }
         Commands::DiscoverFromState { state_json, output, add_import_id, add_import_id_as_comment, discovery_config } => {
             let discovery_config_obj = load_discovery_config(discovery_config, &tool_config)?
                 .ok_or_else(|| {
                     let err: Box<dyn std::error::Error> = "Discovery configuration not found. Please provide --discovery-config or ensure 'presets/discovery-config.yaml' exists and is correctly configured in config.toml.".into();
                      err
                 })?;
+            let s_dir = runtime_config.schema_dir.clone();
+            let registry = ResourceRegistry::load_all(&s_dir).ok();
+            if let Some(registry) = &registry {
+                warn_unknown_discovery_types(&discovery_config_obj, registry);
+            }
             let enabled_types = Some(discovery_config_obj.resource_types.into_iter().filter(|(_,v)| v.import).map(|(k,_)| k).collect());
 
-            println!("Reading infrastructure state...");
+            status!("Reading infrastructure state...");
             let state_val: serde_json::Value = if let Some(path) = state_json {
                 let content = fs::read_to_string(&path)
                     .map_err(|e| format!("Failed to read state file '{}': {}", path.display(), e))?;
@@ -814,9 +1690,6 @@ Thumbs.db
                 serde_json::from_slice(&output.stdout)?
             };
 
-            let s_dir = PathBuf::from(&runtime_config.schema_dir);
-            let registry = ResourceRegistry::load_all(s_dir.to_str().unwrap_or("schemas")).ok();
-
             let discoverer = crate::discovery::Discoverer::new(state_val, registry, cli.verbose, add_import_id, add_import_id_as_comment, enabled_types);
             let config = discoverer.discover()?;
 
@@ -843,7 +1716,7 @@ Thumbs.db
             let final_output = if output.is_absolute() {
                 output
             } else {
-                PathBuf::from(&runtime_config.yaml_dir).join(output)
+                runtime_config.yaml_dir.join(output)
             };
 
             if let Some(parent) = final_output.parent() {
@@ -852,14 +1725,15 @@ Thumbs.db
             }
             fs::write(&final_output, yaml)
                 .map_err(|e| format!("Failed to write output file '{}': {}", final_output.display(), e))?;
+            crate::discovery::Discoverer::report_deleted_principals(&config);
             if cli.verbose {
                 crate::discovery::Discoverer::print_summary(&config, Some(discoverer.filtered_count.get()));
             }
             Ok(())
         }
         Commands::DiscoverFromOrganization { customer_organization_id, output, add_import_id, add_import_id_as_comment, discovery_config } => {
-            let s_dir = PathBuf::from(&tool_config.schema_dir);
-            let registry = ResourceRegistry::load_all(s_dir.to_str().unwrap_or("schemas"))
+            let s_dir = tool_config.schema_dir.clone();
+            let registry = ResourceRegistry::load_all(&s_dir)
                 .map_err(|e| format!("Failed to load resource registry from {}: {}", s_dir.display(), e))?;
 
             let discovery_config_obj = load_discovery_config(discovery_config, &tool_config)?
@@ -867,6 +1741,7 @@ Thumbs.db
                     let err: Box<dyn std::error::Error> = "Discovery configuration not found. Please provide --discovery-config or ensure 'presets/discovery-config.yaml' exists and is correctly configured in config.toml.".into();
                      err
                 })?;
+            warn_unknown_discovery_types(&discovery_config_obj, &registry);
             let config = crate::discovery::Discoverer::discover_from_org(&customer_organization_id, cli.verbose, add_import_id, add_import_id_as_comment, Some(discovery_config_obj), Some(registry)).await?;
             let mut yaml = serde_yaml::to_string(&config)?;
 
@@ -891,7 +1766,7 @@ Thumbs.db
             let final_output = if output.is_absolute() {
                 output
             } else {
-                PathBuf::from(&runtime_config.yaml_dir).join(output)
+                runtime_config.yaml_dir.join(output)
             };
 
             if let Some(parent) = final_output.parent() {
@@ -900,6 +1775,7 @@ Thumbs.db
             }
             fs::write(&final_output, yaml)
                  .map_err(|e| format!("Failed to write output file '{}': {}", final_output.display(), e))?;
+            crate::discovery::Discoverer::report_deleted_principals(&config);
             if cli.verbose {
                 crate::discovery::Discoverer::print_summary(&config, None);
             }
@@ -909,111 +1785,409 @@ Thumbs.db
             let config_path = if config_file.is_absolute() {
                 config_file
             } else {
-                PathBuf::from(&runtime_config.yaml_dir).join(config_file)
+                runtime_config.yaml_dir.join(config_file)
             };
+            let input_name = config_path.display().to_string();
             crate::bootstrap::bootstrap(
                 config_path,
                 dry_run,
                 runtime_config,
+                config_dir.clone(),
                 cli.config.clone(),
                 cli.validation.clone(),
                 cli.verbose,
             )
             .await?;
+            if !dry_run {
+                let text = format!("Bootstrap of {} completed", input_name);
+                let details = serde_json::json!({ "event": "bootstrap-complete", "input": input_name });
+                crate::notify::notify(&tool_config.notifications, "bootstrap-complete", &text, details).await;
+            }
             Ok(())
         }
-        Commands::Migrate { input, mode } => {
-            let input_path = if Path::new(&input).is_absolute() {
-                PathBuf::from(&input)
+        Commands::Apply { args } => {
+            let hcl_dir = &runtime_config.hcl_dir;
+            let mut hook_env = vec![("CFG2HCL_HCL_DIR", hcl_dir.display().to_string())];
+            let report = crate::run_report::RunReport::path(&config_dir);
+            if report.exists() {
+                hook_env.push(("CFG2HCL_RUN_REPORT", report.display().to_string()));
+            }
+            crate::hooks::run("pre_apply", &tool_config.hooks.pre_apply, &config_dir, &hook_env)?;
+
+            // The state before and after the apply gives the addresses it changed for the audit
+            // record; an unreadable state (e.g. none yet) counts as empty.
+            let read_state = || crate::state_report::read_state(&tool_config.tf_tool, hcl_dir, None).unwrap_or(serde_json::Value::Null);
+            let before = read_state();
+            let status = std::process::Command::new(&tool_config.tf_tool)
+                .current_dir(hcl_dir)
+                .arg("apply")
+                .args(&args)
+                .status()
+                .map_err(|e| format!("Failed to run {} apply: {}", tool_config.tf_tool, e))?;
+            let mut command = vec![tool_config.tf_tool.as_str(), "apply"];
+            command.extend(args.iter().map(String::as_str));
+            let changed = crate::state_report::changed_instances(&before, &read_state());
+            let record = crate::audit::AuditRecord::new(&command, hcl_dir, changed, status.success());
+            crate::audit::record(&config_dir, &tool_config.audit, &record).await?;
+
+            hook_env.push(("CFG2HCL_APPLY_EXIT_CODE", status.code().unwrap_or(-1).to_string()));
+            crate::hooks::run("post_apply", &tool_config.hooks.post_apply, &config_dir, &hook_env)?;
+            if !status.success() {
+                return Err(format!("{} apply failed ({})", tool_config.tf_tool, status).into());
+            }
+            Ok(())
+        }
+        Commands::Scan { format, fail_on_findings } => {
+            if format != "text" && format != "json" {
+                return Err(format!("Unknown --format '{}' (expected text or json)", format).into());
+            }
+            let provenance = crate::provenance::Provenance::load(&config_dir)?;
+            if provenance.is_none() {
+                eprintln!("Warning: no provenance from a previous transpile, findings are not traced back to the YAML");
+            }
+            let findings = crate::scan::run(&tool_config.scan, &runtime_config.hcl_dir, provenance.as_ref())?;
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&findings)?);
+            } else if findings.is_empty() {
+                println!("{}No findings", crate::term::icon("✅ ", ""));
             } else {
-                PathBuf::from(&runtime_config.yaml_dir).join(&input)
+                crate::scan::print_findings(&findings);
+                println!("{} finding(s)", findings.len());
+            }
+            if fail_on_findings && !findings.is_empty() {
+                return Err(format!("{} scanner finding(s)", findings.len()).into());
+            }
+            Ok(())
+        }
+        Commands::History { input, resource, format } => {
+            if format != "text" && format != "json" {
+                return Err(format!("Unknown --format '{}' (expected text or json)", format).into());
+            }
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let registry = ResourceRegistry::load_all(&runtime_config.schema_dir)?;
+            let mut histories = crate::history::history(&input_path, &runtime_config, std::sync::Arc::new(registry))?;
+            if let Some(filter) = &resource {
+                histories.retain(|h| h.address.contains(filter.as_str()) || h.yaml_path.contains(filter.as_str()));
+            }
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&histories)?);
+            } else {
+                crate::history::print_history(&histories);
+            }
+            Ok(())
+        }
+        Commands::ExtractModule { input, paths, name } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let loaded = load_input(&input_path, &runtime_config)?;
+            let registry = ResourceRegistry::load_all(&runtime_config.schema_dir)?;
+            let (provider_sources, provider_versions) = provider_settings(&tool_config);
+            let project = Transpiler::new(
+                &loaded.config,
+                Some(std::sync::Arc::new(registry)),
+                runtime_config.auto_explode.clone(),
+                "none".to_string(),
+                extract_variables(&loaded.raw_value)?,
+                provider_sources.clone(),
+                provider_versions,
+            ).with_required_labels(tool_config.required_labels.clone())
+                .with_implicit_dependencies(tool_config.implicit_dependencies)
+                .with_implicit_references(tool_config.implicit_references()?)
+                .with_service_label_template(tool_config.service_label_template.clone(), Vec::new())
+                .with_resource_aliases(tool_config.resource_aliases())
+                .transpile()?;
+            let main_body: hcl::Body = hcl::from_str(&project.resources_tf())?;
+            let outputs_body: hcl::Body = hcl::from_str(project.file("outputs.tf"))?;
+            let extraction = crate::extract_module::extract(&main_body, &outputs_body, &project.sources, &paths, &name, &provider_sources)?;
+
+            let module_dir = runtime_config.hcl_dir.join("modules").join(&name);
+            if module_dir.exists() {
+                return Err(format!("'{}' already exists; remove it or choose another --name", module_dir.display()).into());
+            }
+            fs::create_dir_all(&module_dir)
+                .map_err(|e| format!("Failed to create '{}': {}", module_dir.display(), e))?;
+            for (file, content) in &extraction.files {
+                let path = module_dir.join(file);
+                fs::write(&path, content).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+                println!("Created {}", path.display());
+            }
+
+            // Next to the moved blocks of generate-migration, kept until every workspace applied them
+            let moved_path = runtime_config.hcl_dir.join("moved.tf");
+            let mut moved = match fs::read_to_string(&moved_path) {
+                Ok(content) => hcl::from_str::<hcl::Body>(&content).map_err(|e| format!("Failed to parse '{}': {}", moved_path.display(), e))?,
+                Err(_) => hcl::Body::default(),
             };
+            moved.0.extend(extraction.moved.iter().cloned().map(hcl::Structure::Block));
+            fs::write(&moved_path, hcl::to_string(&moved)?).map_err(|e| format!("Failed to write '{}': {}", moved_path.display(), e))?;
+            println!("Updated {}", moved_path.display());
 
-            if !input_path.exists() {
-                return Err(format!("Input file not found: {}", input_path.display()).into());
+            println!("\nReplace {} in {} with:\n\n{}", paths.join(", "), input_path.display(), extraction.module_yaml(&name)?);
+            for (block, reference, output) in &extraction.outside_references {
+                println!("Warning: {} refers to {}; refer to {} instead.", block, reference, output);
+            }
+            Ok(())
+        }
+        Commands::PromotePolicy { input, policy, dry_run } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let include_paths: Vec<PathBuf> = runtime_config.include_dirs.clone();
+            let mut promoted = Vec::new();
+            for file in include_processor::included_files(&input_path, &include_paths)? {
+                let content = fs::read_to_string(&file)?;
+                if let Some(result) = crate::promote_policy::promote(&content, &policy)? {
+                    promoted.push((file, content, result));
+                }
             }
+            let (file, content, result) = match promoted.len() {
+                0 => return Err(format!("No google_org_policy_policy '{}' in {} or the files it includes", policy, input_path.display()).into()),
+                1 => promoted.remove(0),
+                _ => return Err(format!("'{}' is defined in several files: {}", policy, promoted.iter().map(|(f, _, _)| f.display().to_string()).collect::<Vec<_>>().join(", ")).into()),
+            };
 
-            let content = fs::read_to_string(&input_path)
-                .map_err(|e| format!("Failed to read input file '{}': {}", input_path.display(), e))?;
+            print!("{}", crate::yaml_edit::diff(&file.display().to_string(), &content, &result));
+            if dry_run {
+                println!("Policy '{}' would be promoted (dry run).", policy);
+            } else {
+                fs::write(&file, result).map_err(|e| format!("Failed to write '{}': {}", file.display(), e))?;
+                println!("Promoted the dry-run rules of '{}' to spec in {}.", policy, file.display());
+            }
+            Ok(())
+        }
+        Commands::ConvertOrgPolicy { input, dry_run } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let include_paths: Vec<PathBuf> = runtime_config.include_dirs.clone();
+            let mut notes = Vec::new();
+            let mut converted = Vec::new();
+            for file in include_processor::included_files(&input_path, &include_paths)? {
+                let content = fs::read_to_string(&file)?;
+                if let Some(result) = crate::org_policy::convert(&content, &mut notes)
+                    .map_err(|e| format!("{}: {}", file.display(), e))? {
+                    converted.push((file, content, result));
+                }
+            }
+            if converted.is_empty() {
+                println!("No legacy organization policies in {} or the files it includes.", input_path.display());
+                return Ok(());
+            }
 
-            // Detect current mode
-            let re_cloud = regex::Regex::new(r"deployment-mode:\s+&deployment-mode\s+cloud").unwrap();
-            let current_mode = if re_cloud.is_match(&content) {
-                "cloud"
+            for (file, content, result) in &converted {
+                print!("{}", crate::yaml_edit::diff(&file.display().to_string(), content, result));
+                if !dry_run {
+                    fs::write(file, result).map_err(|e| format!("Failed to write '{}': {}", file.display(), e))?;
+                }
+            }
+            for note in &notes {
+                eprintln!("Note: {}", note);
+            }
+            if dry_run {
+                println!("{} file(s) would be converted (dry run).", converted.len());
             } else {
-                "local"
-            };
+                println!("Converted the legacy organization policies in {} file(s). Run transpile with --removed-blocks to drop the legacy policies from the state without deleting them.", converted.len());
+            }
+            Ok(())
+        }
+        Commands::ConvertIam { input, to, output, dry_run } => {
+            let style = crate::convert_iam::Style::parse(&to)?;
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let include_paths: Vec<PathBuf> = runtime_config.include_dirs.clone();
+            let mut notes = Vec::new();
+            let mut converted = Vec::new();
+            for file in include_processor::included_files(&input_path, &include_paths)? {
+                let content = fs::read_to_string(&file)?;
+                if let Some(result) = crate::convert_iam::convert(&content, style, &mut notes)
+                    .map_err(|e| format!("{}: {}", file.display(), e))? {
+                    converted.push((file, content, result));
+                }
+            }
+            if converted.is_empty() {
+                println!("No IAM entries to convert to the {} style in {} or the files it includes.", to, input_path.display());
+                return Ok(());
+            }
 
-            let target_mode = match mode {
-                Some(m) => m,
-                None => if current_mode == "local" { "cloud".to_string() } else { "local".to_string() }
+            // The migration compares what the YAML generates before and after the conversion
+            let loaded = load_input(&input_path, &runtime_config)?;
+            let expanded = include_processor::process_includes(&input_path, &include_paths)?;
+            let content = crate::convert_iam::convert(&expanded.content, style, &mut Vec::new())?.unwrap_or(expanded.content);
+            let converted_input = parse_input(include_processor::Expanded { content, source_map: expanded.source_map }, &runtime_config, false)?;
+            let registry = std::sync::Arc::new(ResourceRegistry::load_all(&runtime_config.schema_dir)?);
+            let (provider_sources, provider_versions) = provider_settings(&tool_config);
+            let transpile = |loaded: &LoadedInput| -> Result<String, Box<dyn std::error::Error>> {
+                Ok(Transpiler::new(
+                    &loaded.config,
+                    Some(registry.clone()),
+                    runtime_config.auto_explode.clone(),
+                    "none".to_string(),
+                    extract_variables(&loaded.raw_value)?,
+                    provider_sources.clone(),
+                    provider_versions.clone(),
+                ).with_required_labels(tool_config.required_labels.clone())
+                    .with_implicit_dependencies(tool_config.implicit_dependencies)
+                    .with_implicit_references(tool_config.implicit_references()?)
+                    .with_service_label_template(tool_config.service_label_template.clone(), Vec::new())
+                    .with_resource_aliases(tool_config.resource_aliases())
+                    .transpile()?
+                    .resources_tf())
             };
+            let (migration, removed, imported) = crate::convert_iam::migration(&transpile(&loaded)?, &transpile(&converted_input)?)?;
 
-            if current_mode == target_mode {
-                println!("Already in {} mode. No changes needed.", target_mode);
+            for (file, content, result) in &converted {
+                print!("{}", crate::yaml_edit::diff(&file.display().to_string(), content, result));
+            }
+            for note in &notes {
+                eprintln!("Note: {}", note);
+            }
+            let output = if output.is_absolute() { output } else { runtime_config.hcl_dir.join(output) };
+            if dry_run {
+                println!("{} file(s) would be converted; the migration would remove {} and import {} IAM resource(s) (dry run).", converted.len(), removed, imported);
                 return Ok(());
             }
+            for (file, _, result) in &converted {
+                fs::write(file, result).map_err(|e| format!("Failed to write '{}': {}", file.display(), e))?;
+            }
+            if let Some(parent) = output.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&output, migration).map_err(|e| format!("Failed to write '{}': {}", output.display(), e))?;
+            println!("Converted {} file(s) to the {} style. {} removes {} and imports {} IAM resource(s) with the next apply; delete it once every workspace has applied it.", converted.len(), to, output.display(), removed, imported);
+            Ok(())
+        }
+        Commands::Blame { file, line, format } => {
+            if format != "text" && format != "json" {
+                return Err(format!("Unknown --format '{}' (expected text or json)", format).into());
+            }
+            let provenance = crate::provenance::Provenance::load(&config_dir)?
+                .ok_or("No provenance found; run transpile first")?;
+            let file = if file.is_absolute() { file } else { config_dir.join(file) };
+            let annotations = crate::blame::blame(&file, &provenance, line)?;
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&annotations)?);
+            } else {
+                crate::blame::print_annotations(&annotations);
+            }
+            Ok(())
+        }
+        Commands::Codeowners { input, output } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let loaded = load_input(&input_path, &runtime_config)?;
+            // Paths in CODEOWNERS are relative to the repository root
+            let dir = if config_dir.as_os_str().is_empty() { Path::new(".") } else { config_dir.as_path() };
+            let repo_root = git2::Repository::discover(dir).ok()
+                .and_then(|repo| repo.workdir().map(Path::to_path_buf))
+                .unwrap_or_else(|| dir.to_path_buf());
+            let rules = crate::owners::codeowners_rules(&input_path, &loaded.processed_content, &loaded.source_map, &loaded.config, &runtime_config.hcl_dir, &repo_root);
+            if rules.is_empty() {
+                eprintln!("Warning: no owner: entries in {}, the cfg2hcl section will be empty", input);
+            }
+            let output = if output.is_absolute() { output } else { config_dir.join(output) };
+            crate::owners::write_codeowners(&output, &input, &rules)?;
+            println!("Updated {} ({} rule(s))", output.display(), rules.len());
+            Ok(())
+        }
+        Commands::GenFixture { folders, projects, resources, seed, output, state_output } => {
+            let spec = crate::fixture::FixtureSpec { folders, projects, resources, seed };
+            let fixture = crate::fixture::generate(&spec)?;
+            for (path, content) in [(&output, fixture.yaml), (&state_output, serde_json::to_string_pretty(&fixture.state)? + "\n")] {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create output directory '{}': {}", parent.display(), e))?;
+                }
+                fs::write(path, content)
+                    .map_err(|e| format!("Failed to write output file '{}': {}", path.display(), e))?;
+                status!("Created {}", path.display());
+            }
+            Ok(())
+        }
+        Commands::Redact { input, output } => {
+            let input_path = resolve_input_path(&input, &runtime_config);
+            let loaded = load_input(&input_path, &runtime_config)?;
+            let redacted = crate::redact::redact(&loaded.processed_content, &loaded.raw_value);
+            if let Err(e) = parse_input(include_processor::Expanded::plain(redacted.content.clone(), &output), &runtime_config, false) {
+                eprintln!("Warning: the redacted config does not load any more: {}", e);
+            }
+            fs::write(&output, &redacted.content)
+                .map_err(|e| format!("Failed to write output file '{}': {}", output.display(), e))?;
+            for (kind, count) in &redacted.counts {
+                status!("  {} {}", count, kind.describe());
+            }
+            status!("Created {}; review it before sharing, names and descriptions are kept", output.display());
+            Ok(())
+        }
+        Commands::SupportBundle { output, input } => {
+            let loaded = match input {
+                Some(input) => Some(load_input(&resolve_input_path(&input, &runtime_config), &runtime_config)?),
+                None => None,
+            };
+            let input = loaded.as_ref().map(|loaded| (loaded.processed_content.as_str(), &loaded.raw_value));
+            let entries = crate::support_bundle::collect(&config_file_path, &config_dir, &runtime_config, input);
+            let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+            let files: Vec<(&str, &str)> = entries.iter().map(|(name, content)| (name.as_str(), content.as_str())).collect();
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            crate::output::write_archive(&output, &files, now)?;
+            status!("Created {} ({})", output.display(), names.join(", "));
+            status!("Review the files before attaching them to an issue");
+            Ok(())
+        }
+        Commands::Migrate { input, mode, to_bucket, to_prefix, create_bucket } => {
+            let input_path = if Path::new(&input).is_absolute() {
+                PathBuf::from(&input)
+            } else {
+                runtime_config.yaml_dir.join(&input)
+            };
 
-            println!("Migrating from {} to {} mode...", current_mode, target_mode);
-
-            // Update YAML while preserving formatting and anchors
-            let re = regex::Regex::new(r"(?m)^\s*deployment-mode:\s+&deployment-mode\s+\w+.*$").unwrap();
-            let new_content = re.replace(&content, format!("  deployment-mode: &deployment-mode {} # switch by command", target_mode)).to_string();
-            fs::write(&input_path, new_content)
-                .map_err(|e| format!("Failed to write updated YAML to '{}': {}", input_path.display(), e))?;
-            println!("Updated YAML: {}", input_path.display());
+            if !input_path.exists() {
+                return Err(format!("Input file not found: {}", input_path.display()).into());
+            }
 
-            // Transpile
-            println!("Regenerating HCL...");
-            let mut cmd = std::process::Command::new(std::env::current_exe()?);
+            let mut global_args: Vec<std::ffi::OsString> = Vec::new();
             if let Some(config_path) = &cli.config {
-                cmd.arg("--config").arg(config_path);
+                global_args.extend(["--config".into(), config_path.into()]);
             }
             if let Some(validation) = &cli.validation {
-                cmd.arg("--validation").arg(validation);
+                global_args.extend(["--validation".into(), validation.into()]);
             }
             if cli.verbose {
-                cmd.arg("--verbose");
-            }
-            let res = cmd.arg("transpile")
-                .arg(&input)
-                .status()?;
-
-            if !res.success() {
-                return Err("Failed to regenerate HCL".into());
+                global_args.push("--verbose".into());
             }
 
-            // Run Init with migrate-state
-            println!("Running {} init -migrate-state...", tool_config.tf_tool);
-            let res = std::process::Command::new(&tool_config.tf_tool)
-                .current_dir(&runtime_config.hcl_dir)
-                .arg("init")
-                .arg("-migrate-state")
-                .arg("-force-copy") // Automate the "yes" for state copy
-                .status()?;
-
-            if !res.success() {
-                return Err(format!("Failed to migrate state using {}", tool_config.tf_tool).into());
+            let ctx = crate::migrate::MigrateContext {
+                tf_tool: &tool_config.tf_tool,
+                config_dir: &config_dir,
+                hcl_dir: &runtime_config.hcl_dir,
+                input: &input,
+                input_path: &input_path,
+                global_args,
+                audit: &tool_config.audit,
+            };
+            if to_bucket.is_some() || to_prefix.is_some() {
+                if mode.is_some() {
+                    return Err("--mode cannot be combined with --to-bucket/--to-prefix".into());
+                }
+                crate::migrate::migrate_backend(&ctx, to_bucket, to_prefix, create_bucket).await
+            } else {
+                crate::migrate::migrate(&ctx, mode, create_bucket).await
             }
-
-            println!("Migration to {} mode complete.", target_mode);
-            Ok(())
         }
         Commands::SelfUpdate { no_download_readme, no_open_readme, check_only, skip_checksum } => {
-            run_self_update(!no_download_readme, !no_open_readme, check_only, skip_checksum, global_settings.preferred_editor.as_deref()).await
+            run_self_update(&ReleaseSource::from_settings(&global_settings), !no_download_readme, !no_open_readme, check_only, skip_checksum, global_settings.preferred_editor.as_deref()).await
+        }
+        Commands::GetPresets { check, update } => {
+            let mode = match (check, update) {
+                (true, _) => crate::presets::Mode::Check,
+                (_, true) => crate::presets::Mode::Update,
+                _ => crate::presets::Mode::Download,
+            };
+            run_get_presets(&runtime_config.yaml_dir, &ReleaseSource::from_settings(&global_settings), mode).await
         }
-        Commands::GetPresets => run_get_presets(&runtime_config.yaml_dir).await,
-        Commands::OpenReadme => run_open_readme(global_settings.preferred_editor.as_deref()).await,
+        Commands::OpenReadme => run_open_readme(&ReleaseSource::from_settings(&global_settings), global_settings.preferred_editor.as_deref()).await,
         Commands::Completion { shell, install } => run_completion(&shell, install),
         Commands::SetPreferredEditor { editor, clear } => {
             if clear {
                 global_settings.preferred_editor = None;
                 save_global_settings(&global_settings)?;
-                println!("✅ preferred_editor cleared (will fall back to $EDITOR / OS default).");
+                println!("{}preferred_editor cleared (will fall back to $EDITOR / OS default).", crate::term::icon("✅ ", ""));
             } else if let Some(e) = editor {
                 global_settings.preferred_editor = Some(e.clone());
                 save_global_settings(&global_settings)?;
-                println!("✅ preferred_editor set to \"{}\".", e);
+                println!("{}preferred_editor set to \"{}\".", crate::term::icon("✅ ", ""), e);
             } else {
                 match &global_settings.preferred_editor {
                     Some(e) => println!("preferred_editor = \"{}\"", e),
@@ -1024,13 +2198,34 @@ Thumbs.db
         }
     }?;
 
-    Ok(())
+    crate::warnings::finish(show_all, warnings_as_errors)
+}
+
+/// Variable values with their `!env`, `!join` and `!format` tags resolved; variables in the
+/// declaration form (see [`crate::tfvars::Declaration`]) give their `value` or `default`.
+fn extract_variables(value: &serde_yaml::Value) -> Result<HashMap<String, serde_yaml::Value>, Box<dyn std::error::Error>> {
+    let mut vars = HashMap::new();
+    collect_variables_recursive(value, &mut vars);
+    vars.into_iter()
+        .map(|(k, v)| {
+            let v = resolve_yaml_custom_tags(v).map_err(|e| format!("Variable '{}': {}", k, e))?;
+            Ok((k, crate::tfvars::variable_value(&v)))
+        })
+        .collect()
 }
 
-fn extract_variables(value: &serde_yaml::Value) -> HashMap<String, serde_yaml::Value> {
+/// The variables written in the declaration form.
+fn variable_declarations(value: &serde_yaml::Value) -> Result<HashMap<String, crate::tfvars::Declaration>, Box<dyn std::error::Error>> {
     let mut vars = HashMap::new();
     collect_variables_recursive(value, &mut vars);
-    vars
+    let mut declarations = HashMap::new();
+    for (name, value) in vars {
+        let declaration = crate::tfvars::declaration(&value).map_err(|e| format!("Invalid declaration of variable '{}': {}", name, e))?;
+        if let Some(declaration) = declaration {
+            declarations.insert(name, declaration);
+        }
+    }
+    Ok(declarations)
 }
 
 fn is_variables_key(k: &serde_yaml::Value) -> bool {
@@ -1112,7 +2307,7 @@ fn merge_variables(value: serde_yaml::Value) -> serde_yaml::Value {
         if let Some(variables) = top_level_vars {
             for (k, v) in variables {
                 if !map.contains_key(&k) {
-                    map.insert(k, v);
+                    map.insert(k, crate::tfvars::variable_value(&v));
                 }
             }
         }
@@ -1122,14 +2317,14 @@ fn merge_variables(value: serde_yaml::Value) -> serde_yaml::Value {
     }
 }
 
-fn resolve_yaml_custom_tags(value: serde_yaml::Value) -> serde_yaml::Value {
+fn resolve_yaml_custom_tags(value: serde_yaml::Value) -> Result<serde_yaml::Value, Box<dyn std::error::Error>> {
     match value {
         serde_yaml::Value::Mapping(map) => {
             let mut new_map = serde_yaml::Mapping::new();
             for (k, v) in map {
-                let processed_k = resolve_yaml_custom_tags(k);
+                let processed_k = resolve_yaml_custom_tags(k)?;
                 let key_str = processed_k.as_str().unwrap_or("").to_string();
-                let mut processed_v = resolve_yaml_custom_tags(v);
+                let mut processed_v = resolve_yaml_custom_tags(v)?;
 
                 // Coerce known string fields if they are numbers
                 if matches!(key_str.as_str(), "customer-organization-id" | "infra-bucket-name" | "project_id" | "org_id" | "folder_id") {
@@ -1140,20 +2335,26 @@ fn resolve_yaml_custom_tags(value: serde_yaml::Value) -> serde_yaml::Value {
 
                 new_map.insert(processed_k, processed_v);
             }
-            serde_yaml::Value::Mapping(new_map)
+            Ok(serde_yaml::Value::Mapping(new_map))
         }
         serde_yaml::Value::Sequence(seq) => {
-            serde_yaml::Value::Sequence(seq.into_iter().map(resolve_yaml_custom_tags).collect())
+            Ok(serde_yaml::Value::Sequence(seq.into_iter().map(resolve_yaml_custom_tags).collect::<Result<_, _>>()?))
         }
         serde_yaml::Value::Tagged(tagged) => {
             if tagged.tag == "!expr" {
-                return serde_yaml::Value::Tagged(tagged);
+                return Ok(serde_yaml::Value::Tagged(tagged));
+            }
+            if tagged.tag == "!env" {
+                return resolve_env_tag(tagged.value);
+            }
+            if tagged.tag == "!merge" {
+                return resolve_merge_tag(tagged.value);
             }
             if tagged.tag == "!join" {
                 if let serde_yaml::Value::Sequence(items) = tagged.value {
                     let mut result = String::new();
                     for item in items {
-                        let inner = resolve_yaml_custom_tags(item);
+                        let inner = resolve_yaml_custom_tags(item)?;
                         match inner {
                             serde_yaml::Value::String(s) => result.push_str(&s),
                             serde_yaml::Value::Number(n) => result.push_str(&n.to_string()),
@@ -1161,28 +2362,28 @@ fn resolve_yaml_custom_tags(value: serde_yaml::Value) -> serde_yaml::Value {
                             _ => {}
                         }
                     }
-                    return serde_yaml::Value::String(result);
+                    return Ok(serde_yaml::Value::String(result));
                 } else {
-                    let inner = resolve_yaml_custom_tags(tagged.value);
-                    return match inner {
+                    let inner = resolve_yaml_custom_tags(tagged.value)?;
+                    return Ok(match inner {
                         serde_yaml::Value::String(s) => serde_yaml::Value::String(s),
                         serde_yaml::Value::Number(n) => serde_yaml::Value::String(n.to_string()),
                         _ => serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
                             tag: tagged.tag,
                             value: inner,
                         }))
-                    };
+                    });
                 }
             } else if tagged.tag == "!format" {
                 if let serde_yaml::Value::Sequence(items) = tagged.value {
-                    if items.is_empty() { return serde_yaml::Value::Null; }
-                    let fmt_v = resolve_yaml_custom_tags(items[0].clone());
+                    if items.is_empty() { return Ok(serde_yaml::Value::Null); }
+                    let fmt_v = resolve_yaml_custom_tags(items[0].clone())?;
                     let mut fmt = match fmt_v {
                         serde_yaml::Value::String(s) => s,
-                        _ => return serde_yaml::Value::Null,
+                        _ => return Ok(serde_yaml::Value::Null),
                     };
                     for i in 1..items.len() {
-                        let arg = resolve_yaml_custom_tags(items[i].clone());
+                        let arg = resolve_yaml_custom_tags(items[i].clone())?;
                         let arg_str = match arg {
                             serde_yaml::Value::String(s) => s,
                             serde_yaml::Value::Number(n) => n.to_string(),
@@ -1191,18 +2392,368 @@ fn resolve_yaml_custom_tags(value: serde_yaml::Value) -> serde_yaml::Value {
                         };
                         fmt = fmt.replacen("{}", &arg_str, 1);
                     }
-                    return serde_yaml::Value::String(fmt);
+                    return Ok(serde_yaml::Value::String(fmt));
                 }
             }
-            serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+            Ok(serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
                 tag: tagged.tag,
-                value: resolve_yaml_custom_tags(tagged.value),
-            }))
+                value: resolve_yaml_custom_tags(tagged.value)?,
+            })))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// `!env NAME` is the value of the environment variable `NAME`, `!env [NAME, fallback]` falls
+/// back to `fallback` if it is not set.
+fn resolve_env_tag(value: serde_yaml::Value) -> Result<serde_yaml::Value, Box<dyn std::error::Error>> {
+    let (name, fallback) = match value {
+        serde_yaml::Value::String(name) => (name, None),
+        serde_yaml::Value::Sequence(mut items) if items.len() == 2 => {
+            let fallback = items.pop();
+            match items.pop() {
+                Some(serde_yaml::Value::String(name)) => (name, fallback),
+                _ => return Err("!env takes a variable name or [NAME, fallback]".into()),
+            }
         }
-        _ => value,
+        _ => return Err("!env takes a variable name or [NAME, fallback]".into()),
+    };
+    match std::env::var(&name) {
+        Ok(value) => Ok(serde_yaml::Value::String(value)),
+        Err(std::env::VarError::NotPresent) => match fallback {
+            Some(fallback) => resolve_yaml_custom_tags(fallback),
+            None => Err(format!("Environment variable '{}' of !env is not set (use !env [{}, fallback] for a default)", name, name).into()),
+        },
+        Err(std::env::VarError::NotUnicode(_)) => Err(format!("Environment variable '{}' of !env is not valid UTF-8", name).into()),
     }
 }
 
+/// `!merge [base, overrides, ...]` is the mappings merged recursively, later ones winning; `null`
+/// removes a key of an earlier mapping.
+fn resolve_merge_tag(value: serde_yaml::Value) -> Result<serde_yaml::Value, Box<dyn std::error::Error>> {
+    const USAGE: &str = "!merge takes a list of mappings, e.g. !merge [*base, {key: value}]";
+    let serde_yaml::Value::Sequence(items) = value else {
+        return Err(USAGE.into());
+    };
+    let mut merged = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+    for item in items {
+        match resolve_yaml_custom_tags(item)? {
+            item @ serde_yaml::Value::Mapping(_) => crate::overrides::merge(&mut merged, item),
+            serde_yaml::Value::Null => {}
+            _ => return Err(USAGE.into()),
+        }
+    }
+    Ok(merged)
+}
+
+/// Parses every `!expr` and `!ref` value with the HCL expression parser, so that a typo fails
+/// the run with its location instead of silently dropping the attribute.
+fn check_expressions(value: &serde_yaml::Value, content: &str, source_map: &include_processor::SourceMap) -> Result<(), Box<dyn std::error::Error>> {
+    let errors = invalid_expression_errors(value, content, source_map);
+    for error in &errors {
+        eprintln!("Error: {}", error);
+    }
+    if !errors.is_empty() {
+        return Err(format!("{} invalid !expr/!ref/!secret value(s)", errors.len()).into());
+    }
+    Ok(())
+}
+
+/// One message per invalid value, with the file and line it comes from.
+fn invalid_expression_errors(value: &serde_yaml::Value, content: &str, source_map: &include_processor::SourceMap) -> Vec<String> {
+    let mut invalid = Vec::new();
+    collect_invalid_expressions(value, "", &mut invalid);
+    invalid.iter().map(|entry| {
+        let location = expression_line(content, &entry.tag, &entry.expr)
+            .map(|idx| source_map.location(idx))
+            .map(|(file, line)| format!("{}:{} ", file.display(), line))
+            .unwrap_or_default();
+        format!("Invalid {} '{}' at {}('{}'): {}", entry.tag, entry.expr, location, entry.path, entry.error)
+    }).collect()
+}
+
+/// A `!expr` or `!ref` value that is not valid HCL (or not a reference), or a `!secret` that is
+/// not a secret name.
+struct InvalidExpression {
+    path: String,
+    tag: String,
+    expr: String,
+    error: String,
+}
+
+fn collect_invalid_expressions(value: &serde_yaml::Value, path: &str, invalid: &mut Vec<InvalidExpression>) {
+    match value {
+        serde_yaml::Value::Tagged(tagged) if tagged.tag == "!expr" || tagged.tag == "!ref" => {
+            let tag = tagged.tag.to_string();
+            let error = match &tagged.value {
+                serde_yaml::Value::String(expr) => match expr.parse::<hcl::Expression>() {
+                    Ok(hcl::Expression::Traversal(_)) => None,
+                    // A YAML key (`!ref infra`)
+                    Ok(hcl::Expression::Variable(_)) if tag == "!ref" => None,
+                    Ok(_) if tag == "!ref" => Some("not a reference like google_project.infra.project_id or infra.project_id (use !expr for other expressions)".to_string()),
+                    Ok(_) => None,
+                    // The parser reports positions within the expression, which mean little on their own
+                    Err(e) => Some(e.to_string().lines().last().unwrap_or_default().trim_start_matches([' ', '=']).to_string()),
+                },
+                _ => Some(format!("{} needs a string", tag)),
+            };
+            if let Some(error) = error {
+                let expr = tagged.value.as_str().map(str::to_string).unwrap_or_else(|| format!("{:?}", tagged.value));
+                invalid.push(InvalidExpression { path: path.to_string(), tag, expr, error });
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) if tagged.tag == "!secret" => {
+            let error = match &tagged.value {
+                serde_yaml::Value::String(name) => crate::secret_tag::SecretRef::parse(name).err(),
+                _ => Some("!secret needs a string".to_string()),
+            };
+            if let Some(error) = error {
+                let expr = tagged.value.as_str().map(str::to_string).unwrap_or_else(|| format!("{:?}", tagged.value));
+                invalid.push(InvalidExpression { path: path.to_string(), tag: tagged.tag.to_string(), expr, error });
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => collect_invalid_expressions(&tagged.value, path, invalid),
+        serde_yaml::Value::Mapping(map) => {
+            for (k, v) in map {
+                let key = match k {
+                    serde_yaml::Value::Tagged(tagged) => tagged.value.as_str().map(str::to_string),
+                    k => k.as_str().map(str::to_string),
+                }.unwrap_or_else(|| format!("{:?}", k));
+                let child = if path.is_empty() { key } else { format!("{}.{}", path, key) };
+                // Keys can be references too (`!ref google_project.infra.project_id: [...]`)
+                collect_invalid_expressions(k, &child, invalid);
+                collect_invalid_expressions(v, &child, invalid);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_invalid_expressions(item, &format!("{}[{}]", path, i), invalid);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Line index of the tag of an expression: the expression follows it on the same line or, for
+/// block scalars, on the next one.
+fn expression_line(content: &str, tag: &str, expr: &str) -> Option<usize> {
+    let first = expr.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let lines: Vec<&str> = content.lines().collect();
+    lines.iter().enumerate()
+        .filter(|(_, line)| line.contains(tag))
+        .find(|(idx, line)| line.contains(first) || lines.get(idx + 1).is_some_and(|next| next.contains(first)))
+        .map(|(idx, _)| idx)
+}
+
+/// Resolves an input file name relative to yaml_dir unless it is absolute.
+fn resolve_input_path(input: &str, runtime_config: &ToolConfig) -> PathBuf {
+    if Path::new(input).is_absolute() {
+        PathBuf::from(input)
+    } else {
+        runtime_config.yaml_dir.join(input)
+    }
+}
+
+/// A YAML input file after include processing, variable merging and tag resolution.
+struct LoadedInput {
+    /// Include-expanded YAML text (used to locate findings in source files)
+    processed_content: String,
+    /// Source file and line of each line of `processed_content`
+    source_map: include_processor::SourceMap,
+    /// Parsed YAML before variables are merged (used for variable extraction)
+    raw_value: serde_yaml::Value,
+    config: Config,
+    /// Names that violate (or could not be generated from) the `[naming]` patterns
+    naming_findings: Vec<crate::naming::NamingFinding>,
+}
+
+/// Loads a YAML input file the same way `transpile` does.
+fn load_input(input_path: &Path, runtime_config: &ToolConfig) -> Result<LoadedInput, Box<dyn std::error::Error>> {
+    let include_paths: Vec<PathBuf> = runtime_config.include_dirs.clone();
+    parse_input(include_processor::process_includes(input_path, &include_paths)?, runtime_config, false)
+}
+
+/// Parses include-expanded YAML; its source map is used for error locations.
+/// With `restricted` (input from the network, see `serve`), tags that read from the host
+/// (`!env`, `!file` and tag plugins) are rejected.
+fn parse_input(expanded: include_processor::Expanded, runtime_config: &ToolConfig, restricted: bool) -> Result<LoadedInput, Box<dyn std::error::Error>> {
+    let include_processor::Expanded { content: processed_content, source_map } = expanded;
+    let _span = crate::timings::span("yaml parse");
+    let mut raw_value: serde_yaml::Value = serde_yaml::from_str::<serde_yaml::Value>(&processed_content).inspect_err(|e| {
+        print_yaml_error_context(&processed_content, e, &source_map);
+    })?;
+    if restricted {
+        reject_host_tags(&raw_value, &runtime_config.tags)?;
+    }
+    crate::overrides::apply_overrides(&mut raw_value, &runtime_config.override_dirs)?;
+    crate::file_tag::apply(&mut raw_value, &runtime_config.yaml_dir, &runtime_config.hcl_dir)?;
+    let mut merged_value = merge_variables(raw_value.clone());
+    crate::tag_plugins::apply(&mut merged_value, &runtime_config.tags)?;
+    check_expressions(&merged_value, &processed_content, &source_map)?;
+    let mut processed_value = resolve_yaml_custom_tags(merged_value)?;
+    crate::group_refs::apply(&mut processed_value)?;
+    let naming_findings = crate::naming::apply_naming(&mut processed_value, &runtime_config.naming);
+
+    let config = Config::from_value(processed_value).map_err(|e: serde_path_to_error::Error<serde_yaml::Error>| {
+        let path = e.path().to_string();
+        format!("Error at '{}': {}", path, e.into_inner())
+    })?;
+
+    Ok(LoadedInput { processed_content, source_map, raw_value, config, naming_findings })
+}
+
+/// Fails on the first `!env`, `!file` or tag plugin value.
+fn reject_host_tags(value: &serde_yaml::Value, plugins: &BTreeMap<String, crate::tag_plugins::TagPlugin>) -> Result<(), Box<dyn std::error::Error>> {
+    match value {
+        serde_yaml::Value::Mapping(map) => map.values().try_for_each(|v| reject_host_tags(v, plugins)),
+        serde_yaml::Value::Sequence(seq) => seq.iter().try_for_each(|v| reject_host_tags(v, plugins)),
+        serde_yaml::Value::Tagged(tagged) => {
+            let name = tagged.tag.to_string();
+            let name = name.trim_start_matches('!');
+            if name == "env" || name == "file" || plugins.contains_key(name) {
+                return Err(format!("!{} is not allowed in requests to the server", name).into());
+            }
+            reject_host_tags(&tagged.value, plugins)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Variables of each environment of `[environments]`: the input with the common override
+/// directories and then the one of the environment applied.
+fn environment_variables(processed_content: &str, runtime_config: &ToolConfig) -> Result<BTreeMap<String, HashMap<String, serde_yaml::Value>>, Box<dyn std::error::Error>> {
+    let mut environments = BTreeMap::new();
+    for (name, dir) in &runtime_config.environments {
+        let mut raw_value: serde_yaml::Value = serde_yaml::from_str(processed_content)?;
+        let dirs: Vec<PathBuf> = runtime_config.override_dirs.iter().chain([dir]).cloned().collect();
+        crate::overrides::apply_overrides(&mut raw_value, &dirs)
+            .map_err(|e| format!("Environment '{}': {}", name, e))?;
+        crate::file_tag::apply(&mut raw_value, &runtime_config.yaml_dir, &runtime_config.hcl_dir)?;
+        environments.insert(name.clone(), extract_variables(&raw_value)?);
+    }
+    Ok(environments)
+}
+
+/// Runs all checks on a loaded input that do not need the schemas.
+fn check_input(loaded: &LoadedInput, runtime_config: &ToolConfig, validation_level: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = crate::timings::span("validation");
+    check_config(&loaded.config, validation_level)?;
+    report_naming_findings(&loaded.naming_findings, validation_level)?;
+    check_iam_members(&loaded.config, runtime_config, validation_level, &loaded.processed_content, &loaded.source_map)
+}
+
+/// Provider sources and versions from the provider lists in config.toml.
+fn provider_settings(tool_config: &ToolConfig) -> (HashMap<String, String>, HashMap<String, String>) {
+    let mut provider_sources = HashMap::new();
+    let mut provider_versions = HashMap::new();
+    let def_ver = tool_config.provider_version.clone();
+
+    for p in tool_config.google_providers.iter().chain(&tool_config.aws_providers) {
+        let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
+        let source = if name.contains('/') { name.clone() } else { format!("hashicorp/{}", name) };
+        provider_sources.insert(name.clone(), source);
+        provider_versions.insert(name, ver);
+    }
+    for p in &tool_config.azure_providers {
+        let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
+        let source = if name.contains('/') { name.clone() } else { "hashicorp/azurerm".to_string() };
+        provider_sources.insert(name.clone(), source);
+        provider_versions.insert(name, ver);
+    }
+    for p in &tool_config.alibaba_providers {
+        let (name, ver) = ToolConfig::parse_provider_string_with_default(p, &def_ver);
+        provider_sources.insert(name.clone(), "aliyun/alicloud".to_string());
+        provider_versions.insert(name, ver);
+    }
+    (provider_sources, provider_versions)
+}
+
+/// Runs the semantic configuration checks. Fatal findings always fail, the others follow
+/// the validation level.
+fn check_config(config: &Config, validation_level: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut errors = 0;
+    for finding in crate::validation::check_config(config) {
+        let msg = t("at-path", &[("message", &finding.message), ("path", &finding.yaml_path)]);
+        if finding.fatal || validation_level == "error" {
+            errors += 1;
+            eprintln!("{}", t("error", &[("message", &msg)]));
+        } else if validation_level != "none" {
+            crate::warnings::warn(finding.message.clone(), finding.yaml_path.clone(), msg);
+        }
+    }
+    if errors > 0 {
+        return Err(t("config-validation-failed", &[("count", &errors)]).into());
+    }
+    Ok(())
+}
+
+/// Reports `[naming]` pattern violations according to the validation level.
+fn report_naming_findings(findings: &[crate::naming::NamingFinding], validation_level: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if validation_level == "none" || findings.is_empty() {
+        return Ok(());
+    }
+    for finding in findings {
+        let msg = t("at-path", &[("message", &finding.message), ("path", &finding.yaml_path)]);
+        if validation_level == "error" {
+            eprintln!("{}", t("error", &[("message", &msg)]));
+        } else {
+            crate::warnings::warn(finding.message.clone(), finding.yaml_path.clone(), msg);
+        }
+    }
+    if validation_level == "error" {
+        return Err(t("naming-validation-failed", &[("count", &findings.len())]).into());
+    }
+    Ok(())
+}
+
+/// Validates IAM principals and, if `enforce_member_domain` is set, that user/group/domain
+/// principals belong to the customer domain. Syntax problems follow the validation level,
+/// domain violations always fail.
+fn check_iam_members(config: &Config, tool_config: &ToolConfig, validation_level: &str, content: &str, source_map: &include_processor::SourceMap) -> Result<(), Box<dyn std::error::Error>> {
+    let mut allowed_domains = Vec::new();
+    if tool_config.enforce_member_domain {
+        if let Some(domain) = config.extra.get("customer-domain").and_then(|v| v.as_str()) {
+            if !domain.is_empty() {
+                allowed_domains.push(domain.to_string());
+            }
+        }
+        allowed_domains.extend(tool_config.allowed_member_domains.iter().cloned());
+        if allowed_domains.is_empty() {
+            return Err("enforce_member_domain is set but neither customer-domain nor allowed_member_domains is configured".into());
+        }
+    }
+
+    let mut errors = 0;
+    for finding in crate::validation::check_members(config, &allowed_domains) {
+        if !finding.domain_violation && validation_level == "none" {
+            continue;
+        }
+
+        // Point at the first line mentioning the member, mapped back to its source file
+        let location = content
+            .lines()
+            .position(|l| l.contains(&finding.member))
+            .map(|idx| {
+                let (file, line) = source_map.location(idx);
+                format!("{}:{}: ", file.display(), line)
+            })
+            .unwrap_or_default();
+
+        let msg = format!("{}{}", location, t("at-path", &[("message", &finding.message), ("path", &finding.yaml_path)]));
+        if finding.domain_violation || validation_level == "error" {
+            errors += 1;
+            eprintln!("{}", t("error", &[("message", &msg)]));
+        } else {
+            crate::warnings::warn(finding.message.clone(), finding.yaml_path.clone(), msg);
+        }
+    }
+
+    if errors > 0 {
+        return Err(t("iam-validation-failed", &[("count", &errors)]).into());
+    }
+    Ok(())
+}
+
 fn sync_schemas(tool_config: &mut ToolConfig, runtime_config: &ToolConfig, provider_names: &[String], config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     let mut updated = false;
     let all_known = tool_config.all_providers(); // Just names
@@ -1245,14 +2796,14 @@ fn sync_schemas(tool_config: &mut ToolConfig, runtime_config: &ToolConfig, provi
              .unwrap_or_else(|| ToolConfig::parse_provider_string_with_default(p, &tool_config.provider_version));
 
         let out_name = p_name_resolved.split('/').last().unwrap_or(&p_name_resolved);
-        let schema_path = PathBuf::from(&runtime_config.schema_dir).join(format!("{}.json", out_name));
+        let schema_path = runtime_config.schema_dir.clone().join(format!("{}.json", out_name));
         if !schema_path.exists() {
             // Ensure schema directory exists
             fs::create_dir_all(&runtime_config.schema_dir)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create schema directory '{}': {}", runtime_config.schema_dir, e)))?;
+                .map_err(|e| std::io::Error::other(format!("Failed to create schema directory '{}': {}", runtime_config.schema_dir.display(), e)))?;
 
-            println!("Generating schema for provider: {} version {}...", p_name_resolved, p_ver_resolved);
-            ResourceRegistry::generate_schema(&runtime_config.tf_tool, &p_name_resolved, &p_ver_resolved, schema_path.to_str().unwrap())?;
+            status!("Generating schema for provider: {} version {}...", p_name_resolved, p_ver_resolved);
+            ResourceRegistry::generate_schema(&runtime_config.tf_tool, &p_name_resolved, &p_ver_resolved, &schema_path)?;
             updated = true;
         }
     }
@@ -1272,7 +2823,9 @@ fn load_discovery_config(path: Option<PathBuf>, tool_config: &ToolConfig) -> Res
         PathBuf::from(p_str)
     } else {
         let default = PathBuf::from("presets/discovery-config.yaml");
-        if default.exists() {
+        if let Some(preset) = crate::presets::find("discovery-config.yaml") {
+            preset
+        } else if default.exists() {
             default
         } else {
             return Ok(None);
@@ -1285,15 +2838,56 @@ fn load_discovery_config(path: Option<PathBuf>, tool_config: &ToolConfig) -> Res
 
     let content = fs::read_to_string(&config_path)
         .map_err(|e| format!("Failed to read discovery config '{}': {}", config_path.display(), e))?;
-    let config: DiscoveryConfig = serde_yaml::from_str(&content)?;
+    let mut config = DiscoveryConfig::from_yaml(&content)
+        .map_err(|e| format!("Invalid discovery config '{}': {}", config_path.display(), e))?;
+
+    // Mapper programs given as relative paths live next to the discovery config
+    let base = config_path.parent().unwrap_or(Path::new("."));
+    for program in config.resource_types.values_mut().filter_map(|r| r.mapper.as_mut()?.first_mut()) {
+        if program.contains('/') && Path::new(program.as_str()).is_relative() {
+            *program = base.join(&*program).to_string_lossy().into_owned();
+        }
+    }
+
+    // Resource types may be given by their alias, e.g. `bucket:`
+    let aliases = tool_config.resource_aliases();
+    for (alias, tf_type) in &aliases {
+        if let Some(resource) = config.resource_types.remove(alias) {
+            if config.resource_types.contains_key(tf_type) {
+                return Err(format!("Invalid discovery config '{}': '{}' and its alias '{}' are both configured", config_path.display(), tf_type, alias).into());
+            }
+            config.resource_types.insert(tf_type.clone(), resource);
+        }
+    }
 
     let total_types = config.resource_types.len();
     let enabled_types = config.resource_types.values().filter(|v| v.import).count();
-    println!("Loaded {} resource types from discovery config file '{}' ({} enabled for import).", total_types, config_path.display(), enabled_types);
+    status!("Loaded {} resource types from discovery config file '{}' ({} enabled for import).", total_types, config_path.display(), enabled_types);
+    for tf_type in config.incomplete_types() {
+        crate::warnings::warn("Imported discovery type without asset_type or content_type", tf_type,
+            format!("Discovery type '{}' is imported but has no asset_type or content_type; discover-from-organization skips it", tf_type));
+    }
 
     Ok(Some(config))
 }
 
+/// Warns about imported discovery types that are not resources of the loaded provider schemas
+/// (misspelled or from a provider that is not configured); they are kept, but never match.
+fn warn_unknown_discovery_types(config: &DiscoveryConfig, registry: &ResourceRegistry) {
+    if registry.is_empty() {
+        return;
+    }
+    let mut unknown: Vec<&String> = config.resource_types.iter()
+        .filter(|(tf_type, r)| r.import && registry.find_resource(tf_type).is_none())
+        .map(|(tf_type, _)| tf_type)
+        .collect();
+    unknown.sort();
+    for tf_type in unknown {
+        crate::warnings::warn("Unknown resource type in discovery config", tf_type.as_str(),
+            format!("Discovery type '{}' is not a resource type of the provider schemas", tf_type));
+    }
+}
+
 fn print_recursive_help(cmd: &mut clap::Command) {
     let _ = cmd.print_help();
     println!("\n");
@@ -1319,11 +2913,91 @@ fn print_recursive_help(cmd: &mut clap::Command) {
 
 const REPO: &str = "tjirsch/rs-cfg2hcl";
 const API_URL: &str = "https://api.github.com/repos";
+const RAW_URL: &str = "https://raw.githubusercontent.com";
+
+/// Where releases, presets and the README are fetched from: github.com, or the repository, API and
+/// raw hosts of the `update_repo`, `api_base` and `raw_base` user settings.
+struct ReleaseSource {
+    repo: String,
+    api_base: String,
+    raw_base: String,
+    auth_token: Option<String>,
+}
+
+impl ReleaseSource {
+    fn from_settings(settings: &GlobalSettings) -> Self {
+        let base = |value: &Option<String>, default: &str| value.as_deref().unwrap_or(default).trim_end_matches('/').to_string();
+        Self {
+            repo: settings.update_repo.clone().unwrap_or_else(|| REPO.to_string()),
+            api_base: base(&settings.api_base, API_URL),
+            raw_base: base(&settings.raw_base, RAW_URL),
+            auth_token: ["CFG2HCL_AUTH_TOKEN", "GITHUB_TOKEN"].iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|t| !t.is_empty())
+                .or_else(|| settings.auth_token.clone()),
+        }
+    }
+
+    /// `<api_base>/<repo>/<path>`
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/{}/{}", self.api_base, self.repo, path)
+    }
+
+    /// `<raw_base>/<repo>/main/<path>`
+    fn raw_url(&self, path: &str) -> String {
+        format!("{}/{}/main/{}", self.raw_base, self.repo, path)
+    }
+
+    fn is_default(&self) -> bool {
+        self.repo == REPO && self.api_base == API_URL && self.raw_base == RAW_URL
+    }
+
+    /// GET of an API URL. A rate-limited response (403/429 with no requests left) becomes an error
+    /// that says when the limit resets and how to raise it, instead of failing to decode its body.
+    async fn api_get(&self, client: &reqwest::Client, url: &str) -> Result<reqwest::Response, Box<dyn std::error::Error>> {
+        let response = client.get(url).send().await?;
+        let status = response.status();
+        if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+        let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<u64>().ok());
+        let wait = if let Some(retry_after) = header("retry-after") {
+            Some(retry_after)
+        } else if header("x-ratelimit-remaining") == Some(0) {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+            Some(header("x-ratelimit-reset").unwrap_or(now).saturating_sub(now))
+        } else {
+            None
+        };
+        let Some(wait) = wait else { return Ok(response) };
+        let hint = if self.auth_token.is_some() {
+            "The token's limit is used up as well."
+        } else {
+            "Unauthenticated requests share a low limit per IP address; set GITHUB_TOKEN (or auth_token in ~/.config/cfg2hcl/cfg2hcl.toml) to raise it."
+        };
+        Err(format!("API rate limit exceeded for {} (resets in {} min). {}", url, wait.div_ceil(60), hint).into())
+    }
+
+    /// HTTP client that sends the auth token, if any, with every request (reqwest drops it on
+    /// redirects to other hosts, e.g. asset storage).
+    fn client(&self, user_agent: &str) -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+        let mut builder = reqwest::Client::builder().user_agent(user_agent);
+        if let Some(token) = &self.auth_token {
+            let mut headers = reqwest::header::HeaderMap::new();
+            let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| "auth_token contains characters not allowed in an HTTP header")?;
+            value.set_sensitive(true);
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+            builder = builder.default_headers(headers);
+        }
+        Ok(builder.build()?)
+    }
+}
 
 /// Fetches latest release from GitHub and returns (latest_version, html_url) if an update is available.
-async fn check_update_available(client: &reqwest::Client) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
-    let url = format!("{}/{}/releases/latest", API_URL, REPO);
-    let response = client.get(&url).send().await?;
+async fn check_update_available(client: &reqwest::Client, source: &ReleaseSource) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+    let url = source.api_url("releases/latest");
+    let response = source.api_get(client, &url).await?;
     if !response.status().is_success() {
         return Ok(None);
     }
@@ -1343,10 +3017,11 @@ async fn check_update_available(client: &reqwest::Client) -> Result<Option<(Stri
 }
 
 /// If global settings say so, run a check-only update check and optionally persist last_update_check (daily).
-async fn maybe_check_for_updates(settings: &mut GlobalSettings) -> Result<(), Box<dyn std::error::Error>> {
+/// Returns the newer version and its URL if an update is available.
+async fn maybe_check_for_updates(settings: &mut GlobalSettings) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
     let freq = settings.self_update_frequency.as_str();
     if freq == "never" {
-        return Ok(());
+        return Ok(None);
     }
     if freq == "daily" {
         if let Some(ref last) = settings.last_update_check {
@@ -1356,14 +3031,13 @@ async fn maybe_check_for_updates(settings: &mut GlobalSettings) -> Result<(), Bo
                 .unwrap_or_default()
                 .as_secs();
             if now.saturating_sub(last_ts) < 86400 {
-                return Ok(());
+                return Ok(None);
             }
         }
     }
-    let client = reqwest::Client::builder()
-        .user_agent("cfg2hcl-update-checker")
-        .build()?;
-    let update = check_update_available(&client).await?;
+    let source = ReleaseSource::from_settings(settings);
+    let client = source.client("cfg2hcl-update-checker")?;
+    let update = check_update_available(&client, &source).await?;
     if freq == "daily" {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1372,67 +3046,72 @@ async fn maybe_check_for_updates(settings: &mut GlobalSettings) -> Result<(), Bo
         settings.last_update_check = Some(now.to_string());
         let _ = save_global_settings(settings);
     }
-    if let Some((version, url)) = update {
-        println!("⚠️  Update available: {} (current: {}). Run `cfg2hcl self-update` to install. {}", version, env!("CARGO_PKG_VERSION"), url);
+    if let Some((version, url)) = &update {
+        status!("{}Update available: {} (current: {}). Run `cfg2hcl self-update` to install. {}", crate::term::icon("⚠️  ", ""), version, env!("CARGO_PKG_VERSION"), url);
     }
-    Ok(())
+    Ok(update)
 }
 
-/// Download the presets folder from the repo into yaml_dir/presets (creates subdirs as needed).
-async fn run_get_presets(yaml_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder()
-        .user_agent("cfg2hcl-get-presets")
-        .build()?;
-    let presets_base = PathBuf::from(yaml_dir).join("presets");
-    std::fs::create_dir_all(&presets_base)?;
-    let mut count = 0u32;
-    let mut queue: Vec<(String, PathBuf)> = vec![("presets".to_string(), presets_base.clone())];
-    while let Some((api_path, local_base)) = queue.pop() {
-        let url = format!("{}/{}/contents/{}?ref=main", API_URL, REPO, api_path);
-        let items: Vec<ContentItem> = client.get(&url).send().await?.json().await?;
+/// Fetch the presets folder of the latest release (of `main` without releases) and sync it into
+/// yaml_dir/presets, see [`crate::presets::sync`].
+async fn run_get_presets(yaml_dir: &Path, source: &ReleaseSource, mode: crate::presets::Mode) -> Result<(), Box<dyn std::error::Error>> {
+    let client = source.client("cfg2hcl-get-presets")?;
+    #[derive(Deserialize)]
+    struct Release {
+        tag_name: String,
+    }
+    let response = source.api_get(&client, &source.api_url("releases/latest")).await?;
+    let version = if response.status().is_success() {
+        response.json::<Release>().await?.tag_name
+    } else {
+        "main".to_string()
+    };
+
+    let mut upstream = BTreeMap::new();
+    let mut queue: Vec<String> = vec!["presets".to_string()];
+    while let Some(api_path) = queue.pop() {
+        let url = source.api_url(&format!("contents/{}?ref={}", api_path, version));
+        let response = source.api_get(&client, &url).await?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to list {} in {}: {}", api_path, source.repo, response.status()).into());
+        }
+        let items: Vec<ContentItem> = response.json().await?;
         for item in items {
             if item.typ == "file" {
                 if let Some(download_url) = &item.download_url {
                     let content = client.get(download_url).send().await?.bytes().await?;
-                    let dest = local_base.join(&item.name);
-                    if let Some(p) = dest.parent() {
-                        std::fs::create_dir_all(p)?;
-                    }
-                    std::fs::write(&dest, &content)?;
-                    count += 1;
+                    let relative = item.path.strip_prefix("presets/").unwrap_or(&item.path).to_string();
+                    upstream.insert(relative, content.to_vec());
                 }
             } else if item.typ == "dir" {
-                let sub_base = local_base.join(&item.name);
-                std::fs::create_dir_all(&sub_base)?;
-                queue.push((item.path, sub_base));
+                queue.push(item.path);
             }
         }
     }
-    println!("Downloaded {} preset file(s) to {}", count, presets_base.display());
-    Ok(())
+
+    let presets_base = yaml_dir.join("presets");
+    std::fs::create_dir_all(&presets_base)?;
+    crate::presets::sync(&presets_base, &version, &upstream, mode)
 }
 
 #[derive(Deserialize)]
 struct ContentItem {
     #[serde(rename = "type")]
     typ: String,
-    name: String,
     path: String,
     #[serde(default)]
     download_url: Option<String>,
 }
 
-async fn run_self_update(download_readme: bool, open_readme: bool, check_only: bool, skip_checksum: bool, preferred_editor: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+async fn run_self_update(source: &ReleaseSource, download_readme: bool, open_readme: bool, check_only: bool, skip_checksum: bool, preferred_editor: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
 
     let current_version = env!("CARGO_PKG_VERSION");
     println!("Current version: {}", current_version);
 
-    let client = reqwest::Client::builder()
-        .user_agent("cfg2hcl-update-checker")
-        .build()?;
+    let client = source.client("cfg2hcl-update-checker")?;
 
-    let url = format!("{}/{}/releases/latest", API_URL, REPO);
-    let response = client.get(&url).send().await?;
+    let url = source.api_url("releases/latest");
+    let response = source.api_get(&client, &url).await?;
 
     if !response.status().is_success() {
         return Err(format!("Failed to fetch release info: {}", response.status()).into());
@@ -1457,7 +3136,7 @@ async fn run_self_update(download_readme: bool, open_readme: bool, check_only: b
     println!("Latest version: {}", latest_version);
 
     if compare_versions(current_version, latest_version) < 0 {
-        println!("\n⚠️  A new version is available!");
+        println!("\n{}A new version is available!", crate::term::icon("⚠️  ", ""));
         println!("   Current: {}", current_version);
         println!("   Latest:  {}", latest_version);
         println!("   Release: {}", release.html_url);
@@ -1465,9 +3144,13 @@ async fn run_self_update(download_readme: bool, open_readme: bool, check_only: b
             println!("\nRun `cfg2hcl self-update` to install.");
             return Ok(());
         }
-        println!("\n📥 Installing update...");
+        println!("\n{}Installing update...", crate::term::icon("📥 ", ""));
 
-        let installer_url = format!("https://github.com/{}/releases/latest/download/cfg2hcl-installer.sh", REPO);
+        // The installer asset of the release; older releases only have it under the tag's download URL
+        let installer_url = release.assets.iter()
+            .find(|a| a.name == "cfg2hcl-installer.sh")
+            .map(|a| a.browser_download_url.clone())
+            .unwrap_or_else(|| format!("{}/cfg2hcl-installer.sh", release.html_url.replacen("/releases/tag/", "/releases/download/", 1)));
 
         // Download installer as bytes for checksum verification
         let installer_bytes = client.get(&installer_url).send().await?.bytes().await?;
@@ -1491,12 +3174,13 @@ async fn run_self_update(download_readme: bool, open_readme: bool, check_only: b
                         expected, actual, release.html_url
                     ).into());
                 }
-                println!("✅ Checksum verified");
+                println!("{}Checksum verified", crate::term::icon("✅ ", ""));
             }
             None if skip_checksum => {
                 eprintln!(
-                    "⚠️  No checksum file found in this release. \
-                     Proceeding without verification (--skip-checksum)."
+                    "{}No checksum file found in this release. \
+                     Proceeding without verification (--skip-checksum).",
+                    crate::term::icon("⚠️  ", "Warning: ")
                 );
             }
             None => {
@@ -1518,20 +3202,26 @@ async fn run_self_update(download_readme: bool, open_readme: bool, check_only: b
             use std::os::unix::fs::PermissionsExt;
             std::fs::set_permissions(&temp_file, std::fs::Permissions::from_mode(0o755))?;
 
-            let status = std::process::Command::new("sh")
-                .arg(&temp_file)
-                .status()?;
+            let mut installer = std::process::Command::new("sh");
+            installer.arg(&temp_file);
+            if !source.is_default() {
+                // Let the installer fetch the binaries from the same host as the release
+                if let Some(host) = release.html_url.split(&format!("/{}/", source.repo)).next() {
+                    installer.env("CFG2HCL_INSTALLER_GHE_BASE_URL", host);
+                }
+            }
+            let status = installer.status()?;
             let _ = std::fs::remove_file(&temp_file);
 
             if status.success() {
-                println!("✅ Update installed successfully!");
+                println!("{}Update installed successfully!", crate::term::icon("✅ ", ""));
                 println!("   Please restart your terminal or run: source ~/.profile");
 
                 if download_readme {
-                    match download_and_open_readme(&client, REPO, &latest_version, open_readme, preferred_editor).await {
+                    match download_and_open_readme(&client, source, latest_version, open_readme, preferred_editor).await {
                         Ok(Some(path)) => println!("README: {}", path.display()),
                         Ok(None) => {}
-                        Err(e) => eprintln!("⚠️  Warning: Could not download README: {}", e),
+                        Err(e) => eprintln!("{}Warning: Could not download README: {}", crate::term::icon("⚠️  ", ""), e),
                     }
                 }
             } else {
@@ -1544,7 +3234,7 @@ async fn run_self_update(download_readme: bool, open_readme: bool, check_only: b
             return Err("Automatic installation on Windows is not yet supported. Please download and run the installer manually.".into());
         }
     } else {
-        println!("✅ You are running the latest version!");
+        println!("{}You are running the latest version!", crate::term::icon("✅ ", ""));
     }
 
     Ok(())
@@ -1552,16 +3242,20 @@ async fn run_self_update(download_readme: bool, open_readme: bool, check_only: b
 
 async fn download_and_open_readme(
     client: &reqwest::Client,
-    repo: &str,
+    source: &ReleaseSource,
     version: &str,
     open_after_download: bool,
     preferred_editor: Option<&str>,
 ) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
     let download_dir = get_download_dir()?;
     let readme_path = download_dir.join(format!("cfg2hcl-{}-README.md", version));
-    let readme_url = format!("https://raw.githubusercontent.com/{}/main/README.md", repo);
-    println!("\n📄 Downloading README to '{}'...", readme_path.display());
-    let readme_content = client.get(&readme_url).send().await?.text().await?;
+    let readme_url = source.raw_url("README.md");
+    status!("\n{}Downloading README to '{}'...", crate::term::icon("📄 ", ""), readme_path.display());
+    let response = client.get(&readme_url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {}: {}", readme_url, response.status()).into());
+    }
+    let readme_content = response.text().await?;
     std::fs::write(&readme_path, &readme_content)
         .map_err(|e| format!("Failed to write '{}': {}", readme_path.display(), e))?;
     if open_after_download {
@@ -1602,14 +3296,12 @@ fn get_download_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
 }
 
 fn open_file(path: &Path, preferred_editor: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-    let path_str = path.to_str()
-        .ok_or_else(|| format!("File path {:?} contains non-UTF-8 characters", path))?;
 
     let editor_env = std::env::var("EDITOR").ok();
     let editor = preferred_editor.or_else(|| editor_env.as_deref());
 
     if let Some(editor) = editor {
-        println!("   Opening '{}' with '{}'...", path_str, editor);
+        println!("   Opening '{}' with '{}'...", path.display(), editor);
         // Try direct invocation first — works when the editor binary is in PATH
         let result = std::process::Command::new(editor).arg(path).status();
         match result {
@@ -1621,7 +3313,7 @@ fn open_file(path: &Path, preferred_editor: Option<&str>) -> Result<(), Box<dyn
                 #[cfg(target_os = "macos")]
                 {
                     let open_result = std::process::Command::new("open")
-                        .args(["-a", editor, path_str])
+                        .arg("-a").arg(editor).arg(path)
                         .status();
                     if open_result.map(|s| s.success()).unwrap_or(false) {
                         return Ok(());
@@ -1641,29 +3333,29 @@ fn open_file(path: &Path, preferred_editor: Option<&str>) -> Result<(), Box<dyn
     // No editor configured — use OS default
     #[cfg(target_os = "macos")]
     {
-        println!("   Opening '{}' with system default app...", path_str);
+        println!("   Opening '{}' with system default app...", path.display());
         std::process::Command::new("open")
-            .arg(path_str)
+            .arg(path)
             .status()
-            .map_err(|e| format!("Failed to open '{}' with 'open': {}", path_str, e))?;
+            .map_err(|e| format!("Failed to open '{}' with 'open': {}", path.display(), e))?;
     }
     #[cfg(target_os = "linux")]
     {
-        println!("   Opening '{}' with xdg-open...", path_str);
-        if std::process::Command::new("xdg-open").arg(path_str).status().is_err() {
+        println!("   Opening '{}' with xdg-open...", path.display());
+        if std::process::Command::new("xdg-open").arg(path).status().is_err() {
             return Err(format!(
                 "Could not open '{}': xdg-open failed and neither preferred_editor nor $EDITOR is set",
-                path_str
+                path.display()
             ).into());
         }
     }
     #[cfg(target_os = "windows")]
     {
-        println!("   Opening '{}' with system default app...", path_str);
+        println!("   Opening '{}' with system default app...", path.display());
         std::process::Command::new("cmd")
-            .args(["/C", "start", "", path_str])
+            .args(["/C", "start", ""]).arg(path)
             .status()
-            .map_err(|e| format!("Failed to open '{}': {}", path_str, e))?;
+            .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
     }
     Ok(())
 }
@@ -1694,20 +3386,16 @@ fn compare_versions(v1: &str, v2: &str) -> i32 {
     0
 }
 
-fn print_yaml_error_context(content: &str, err: &serde_yaml::Error) {
+fn print_yaml_error_context(content: &str, err: &serde_yaml::Error, source_map: &include_processor::SourceMap) {
     if let Some(location) = err.location() {
         let line_idx = location.line() - 1;
         let lines: Vec<&str> = content.lines().collect();
 
         if line_idx < lines.len() {
-            // Scan backward from the error line to find the nearest cfg2hcl:source: annotation
-            let source_file = lines[..=line_idx]
-                .iter()
-                .rev()
-                .find_map(|l| l.trim().strip_prefix("# cfg2hcl:source: "));
-
-            if let Some(src) = source_file {
-                eprintln!("\nError in included file: {}", src);
+            // Map the error line back to the file it came from
+            let (source_file, source_line) = source_map.location(line_idx);
+            if source_file != source_map.root() {
+                eprintln!("\nError in included file: {}:{}", source_file.display(), source_line);
             }
 
             eprintln!("\nError context (line {}):", line_idx + 1);
@@ -1754,11 +3442,9 @@ fn run_completion(shell_str: &str, install: bool) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
-async fn run_open_readme(preferred_editor: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder()
-        .user_agent("cfg2hcl-open-readme")
-        .build()?;
-    match download_and_open_readme(&client, REPO, "latest", true, preferred_editor).await {
+async fn run_open_readme(source: &ReleaseSource, preferred_editor: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let client = source.client("cfg2hcl-open-readme")?;
+    match download_and_open_readme(&client, source, "latest", true, preferred_editor).await {
         Ok(Some(path)) => println!("README saved to: {}", path.display()),
         Ok(None) => {}
         Err(e) => return Err(e),
@@ -1768,28 +3454,78 @@ async fn run_open_readme(preferred_editor: Option<&str>) -> Result<(), Box<dyn s
 
 fn completion_install_path(shell: CompletionShell) -> Result<(PathBuf, Option<String>), Box<dyn std::error::Error>> {
     use clap_complete::Shell;
-    let home = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
+    let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("~"));
     let (path, msg): (PathBuf, Option<String>) = match shell {
         Shell::Bash => (
-            PathBuf::from(format!("{}/.local/share/bash-completion/completions/cfg2hcl", home)),
+            home.join(".local/share/bash-completion/completions/cfg2hcl"),
             Some("Ensure bash-completion is installed and sourced in your ~/.bashrc".to_string()),
         ),
         Shell::Zsh => (
-            PathBuf::from(format!("{}/.zsh/completions/_cfg2hcl", home)),
+            home.join(".zsh/completions/_cfg2hcl"),
             Some("Ensure ~/.zsh/completions is in your fpath — add to ~/.zshrc:\n  fpath=(~/.zsh/completions $fpath)\n  autoload -Uz compinit && compinit".to_string()),
         ),
         Shell::Fish => (
-            PathBuf::from(format!("{}/.config/fish/completions/cfg2hcl.fish", home)),
+            home.join(".config/fish/completions/cfg2hcl.fish"),
             None,
         ),
         Shell::PowerShell => {
-            let userprofile = std::env::var("USERPROFILE").unwrap_or_else(|_| home.clone());
+            let userprofile = std::env::var_os("USERPROFILE").map(PathBuf::from).unwrap_or_else(|| home.clone());
             (
-                PathBuf::from(format!(r"{}\Documents\PowerShell\Completions\cfg2hcl.ps1", userprofile)),
+                userprofile.join("Documents").join("PowerShell").join("Completions").join("cfg2hcl.ps1"),
                 Some("Add to your $PROFILE:\n  . \"$env:USERPROFILE\\Documents\\PowerShell\\Completions\\cfg2hcl.ps1\"".to_string()),
             )
         },
         _ => return Err(format!("Unsupported shell: {:?}", shell).into()),
     };
     Ok((path, msg))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(yaml: &str) -> Vec<InvalidExpression> {
+        let value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        let mut invalid = Vec::new();
+        collect_invalid_expressions(&value, "", &mut invalid);
+        invalid
+    }
+
+    #[test]
+    fn expressions_accept_references_and_hcl() {
+        let yaml = r#"
+a: !ref var.x[0]
+b: !ref google_x.y["k"].id
+c: !expr lookup(var.m, "k", null)
+d: !expr "var.a ? 1 : 2"
+e: !expr '"${var.p}-suffix"'
+f: !ref infra
+"#;
+        assert!(invalid(yaml).is_empty());
+    }
+
+    #[test]
+    fn ref_rejects_expressions_that_are_no_reference() {
+        let found = invalid("a:\n  b: !ref lookup(var.m, \"k\", null)\n  c: !ref \"var.a ? 1 : 2\"\n");
+        assert_eq!(found.iter().map(|e| e.path.as_str()).collect::<Vec<_>>(), ["a.b", "a.c"]);
+        assert!(found.iter().all(|e| e.error.starts_with("not a reference")));
+    }
+
+    #[test]
+    fn invalid_expression_reports_file_and_line() {
+        let dir = std::env::temp_dir().join(format!("cfg2hcl-expr-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.yaml"), "project:\n  p:\n    labels: !include labels.yaml\n").unwrap();
+        fs::write(dir.join("labels.yaml"), "team: a\nowner: !expr var.owner[\nenv: !ref var.env\n").unwrap();
+
+        let expanded = include_processor::process_includes(&dir.join("main.yaml"), &[]).unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&expanded.content).unwrap();
+        let errors = invalid_expression_errors(&value, &expanded.content, &expanded.source_map);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        let location = format!("at {}:2 ('project.p.labels.owner')", dir.join("labels.yaml").display());
+        assert!(errors[0].starts_with("Invalid !expr 'var.owner['"), "{}", errors[0]);
+        assert!(errors[0].contains(&location), "{}", errors[0]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// Directory next to config.toml where cfg2hcl keeps its own bookkeeping.
+pub const STATE_DIR: &str = ".cfg2hcl";
+
+/// File in `STATE_DIR` where `apply-extras.sh apply` records the generation it ran.
+pub const EXTRAS_APPLIED: &str = "extras-applied";
+
+/// Records what cfg2hcl generated outside of Terraform, so that later runs can detect
+/// entries that were removed from the YAML. Stored as `.cfg2hcl/manifest.json`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    /// Unsupported resources emitted to `scripts/apply-extras.sh`, keyed by address
+    #[serde(default)]
+    pub extras: BTreeMap<String, ExtraEntry>,
+    /// Unsupported resources removed from the YAML whose destroy commands have not run yet; they
+    /// stay in the apply section of every generated script until a run of it is confirmed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub removed_extras: BTreeMap<String, ExtraEntry>,
+    /// Number of the latest `apply-extras.sh`; its apply section writes it to `EXTRAS_APPLIED`
+    #[serde(default)]
+    pub extras_generation: u64,
+    /// Files written by cfg2hcl (relative to the config directory), removed by `clean`
+    #[serde(default)]
+    pub generated: BTreeSet<PathBuf>,
+    /// `service_label_template` values used so far, oldest first; the last one is current
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service_label_templates: Vec<String>,
+}
+
+impl Manifest {
+    /// Templates used before `current` (oldest first), to emit `moved` blocks from. Projects
+    /// from before the history was recorded used the default template.
+    pub fn previous_service_label_templates(&self, current: &str) -> Vec<String> {
+        let mut previous = self.service_label_templates.clone();
+        if previous.is_empty() {
+            previous.push(crate::transpiler::DEFAULT_SERVICE_LABEL_TEMPLATE.to_string());
+        }
+        previous.retain(|t| t != current);
+        previous
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExtraEntry {
+    pub apply: Vec<String>,
+    pub destroy: Vec<String>,
+}
+
+impl Manifest {
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(STATE_DIR).join("manifest.json")
+    }
+
+    /// Loads the manifest; a missing file yields an empty manifest.
+    pub fn load(config_dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path(config_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read manifest '{}': {}", path.display(), e))?;
+        Ok(serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse manifest '{}': {}", path.display(), e))?)
+    }
+
+    /// Records a written (`true`) or removed (`false`) file.
+    pub fn record_generated(&mut self, config_dir: &Path, path: &Path, exists: bool) {
+        let relative = path.strip_prefix(config_dir).unwrap_or(path).to_path_buf();
+        if exists {
+            self.generated.insert(relative);
+        } else {
+            self.generated.remove(&relative);
+        }
+    }
+
+    pub fn save(&self, config_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path(config_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .map_err(|e| format!("Failed to write manifest '{}': {}", path.display(), e))?;
+        Ok(())
+    }
+}
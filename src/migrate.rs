@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use google_cloud_auth::credentials::Builder;
+use crate::manifest::STATE_DIR;
+
+/// What `migrate` works on, resolved by the command.
+pub struct MigrateContext<'a> {
+    pub tf_tool: &'a str,
+    pub config_dir: &'a Path,
+    pub hcl_dir: &'a Path,
+    /// Input as given on the command line (passed on to `transpile`)
+    pub input: &'a str,
+    pub input_path: &'a Path,
+    /// Global flags (`--config`, `--validation`, `--verbose`) forwarded to `transpile`
+    pub global_args: Vec<OsString>,
+    pub audit: &'a crate::audit::AuditConfig,
+}
+
+/// Switches `deployment-mode` between local and cloud and moves the state along.
+pub async fn migrate(ctx: &MigrateContext<'_>, mode: Option<String>, create_bucket: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(ctx.input_path)
+        .map_err(|e| format!("Failed to read input file '{}': {}", ctx.input_path.display(), e))?;
+
+    // Detect current mode
+    let re_cloud = regex::Regex::new(r"deployment-mode:\s+&deployment-mode\s+cloud").unwrap();
+    let current_mode = if re_cloud.is_match(&content) { "cloud" } else { "local" };
+
+    let target_mode = match mode {
+        Some(m) if m == "local" || m == "cloud" => m,
+        Some(m) => return Err(format!("Unknown mode '{}' (expected local or cloud)", m).into()),
+        None => if current_mode == "local" { "cloud".to_string() } else { "local".to_string() }
+    };
+
+    if current_mode == target_mode {
+        println!("Already in {} mode. No changes needed.", target_mode);
+        return Ok(());
+    }
+
+    status!("Migrating from {} to {} mode...", current_mode, target_mode);
+
+    // Update YAML while preserving formatting and anchors
+    let re = regex::Regex::new(r"(?m)^\s*deployment-mode:\s+&deployment-mode\s+\w+.*$").unwrap();
+    let updated = re.replace(&content, format!("  deployment-mode: &deployment-mode {} # switch by command", target_mode)).to_string();
+
+    if target_mode == "cloud" {
+        ensure_state_bucket(&updated, create_bucket).await?;
+    }
+
+    run(ctx, &content, &updated, current_mode, format!("deployment-mode {} -> {}", current_mode, target_mode)).await?;
+    println!("Migration to {} mode complete.", target_mode);
+    Ok(())
+}
+
+/// Moves the cloud state to another GCS bucket and/or prefix by rewriting
+/// `terraform.backend.gcs` in the input, e.g. when the state bucket must move to another project.
+pub async fn migrate_backend(ctx: &MigrateContext<'_>, to_bucket: Option<String>, to_prefix: Option<String>, create_bucket: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(ctx.input_path)
+        .map_err(|e| format!("Failed to read input file '{}': {}", ctx.input_path.display(), e))?;
+
+    let re_cloud = regex::Regex::new(r"deployment-mode:\s+&deployment-mode\s+cloud").unwrap();
+    if !re_cloud.is_match(&content) {
+        return Err("--to-bucket/--to-prefix move the state of the gcs backend, but the input is in local mode. Edit terraform.backend.gcs directly, or migrate --mode cloud afterwards.".into());
+    }
+
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&content)?;
+    let gcs = yaml.get("terraform")
+        .and_then(|t| t.get("backend"))
+        .and_then(|b| b.get("gcs"))
+        .ok_or("No terraform.backend.gcs found in the input")?;
+    let current_bucket = gcs.get("bucket").and_then(|b| b.as_str()).unwrap_or_default().to_string();
+    let current_prefix = gcs.get("prefix").and_then(|p| p.as_str()).unwrap_or_default().to_string();
+    let target_bucket = to_bucket.unwrap_or_else(|| current_bucket.clone());
+    let target_prefix = to_prefix.unwrap_or_else(|| current_prefix.clone());
+
+    if target_bucket == current_bucket && target_prefix == current_prefix {
+        println!("State is already in gs://{}/{}. No changes needed.", current_bucket, current_prefix);
+        return Ok(());
+    }
+
+    status!("Migrating state from gs://{}/{} to gs://{}/{}...", current_bucket, current_prefix, target_bucket, target_prefix);
+
+    let mut updated = set_backend_value(&content, "bucket", &target_bucket)
+        .ok_or("Cannot locate terraform.backend.gcs.bucket in the input")?;
+    if target_prefix != current_prefix {
+        updated = set_backend_value(&updated, "prefix", &target_prefix)
+            .ok_or("Cannot locate terraform.backend.gcs in the input")?;
+    }
+
+    ensure_state_bucket(&updated, create_bucket).await?;
+
+    let note = format!("backend gs://{}/{} -> gs://{}/{}", current_bucket, current_prefix, target_bucket, target_prefix);
+    run(ctx, &content, &updated, "cloud", note).await?;
+    println!("Migration to gs://{}/{} complete.", target_bucket, target_prefix);
+    Ok(())
+}
+
+/// Sets `terraform.backend.gcs.<key>` to a literal string, leaving the rest of the file (anchors,
+/// comments) untouched. A missing key is added after `bucket`. `None` if the block is not found.
+fn set_backend_value(content: &str, key: &str, value: &str) -> Option<String> {
+    let re_key = regex::Regex::new(r"^(\s*)([\w.-]+):").unwrap();
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut bucket_line = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let Some(caps) = re_key.captures(line) else { continue };
+        let indent = caps[1].len();
+        while stack.last().is_some_and(|(i, _)| *i >= indent) {
+            stack.pop();
+        }
+        stack.push((indent, caps[2].to_string()));
+        let path: Vec<&str> = stack.iter().map(|(_, k)| k.as_str()).collect();
+        if path.len() == 4 && path[..3] == ["terraform", "backend", "gcs"] {
+            if path[3] == key {
+                lines[idx] = format!("{}{}: {}", &caps[1], key, serde_json::to_string(value).ok()?);
+                return Some(lines.join("\n") + "\n");
+            }
+            if path[3] == "bucket" {
+                bucket_line = Some((idx, caps[1].to_string()));
+            }
+        }
+    }
+    let (idx, indent) = bucket_line?;
+    lines.insert(idx + 1, format!("{}{}: {}", indent, key, serde_json::to_string(value).ok()?));
+    Some(lines.join("\n") + "\n")
+}
+
+/// Snapshots the state, writes the updated YAML, regenerates the HCL and runs
+/// `init -migrate-state`. The YAML (and the backend of the working directory) is restored if
+/// any step fails or the migrated state does not contain the same resources.
+async fn run(ctx: &MigrateContext<'_>, original: &str, updated: &str, source: &str, note: String) -> Result<(), Box<dyn std::error::Error>> {
+    let before = snapshot(ctx, source)?;
+
+    fs::write(ctx.input_path, updated)
+        .map_err(|e| format!("Failed to write updated YAML to '{}': {}", ctx.input_path.display(), e))?;
+    status!("Updated YAML: {}", ctx.input_path.display());
+
+    status!("Regenerating HCL...");
+    if let Err(e) = regenerate(ctx) {
+        rollback(ctx, original, false);
+        return Err(e);
+    }
+
+    status!("Running {} init -migrate-state...", ctx.tf_tool);
+    let success = Command::new(ctx.tf_tool)
+        .current_dir(ctx.hcl_dir)
+        .arg("init")
+        .arg("-migrate-state")
+        .arg("-force-copy") // Automate the "yes" for state copy
+        .args(crate::output::backend_config_args(ctx.hcl_dir))
+        .status()
+        .is_ok_and(|s| s.success());
+
+    let after = if success { state_addresses(ctx.tf_tool, ctx.hcl_dir).ok() } else { None };
+    let record = crate::audit::AuditRecord::new(&[ctx.tf_tool, "init", "-migrate-state", "-force-copy"], ctx.hcl_dir, after.iter().flatten().cloned().collect(), success)
+        .with_note(note);
+    crate::audit::record(ctx.config_dir, ctx.audit, &record).await?;
+
+    if !success {
+        rollback(ctx, original, true);
+        return Err(format!("Failed to migrate state using {}", ctx.tf_tool).into());
+    }
+
+    if let Some((snapshot_path, before)) = before {
+        let after = after.unwrap_or_default();
+        let missing: Vec<&String> = before.difference(&after).collect();
+        let added: Vec<&String> = after.difference(&before).collect();
+        if !missing.is_empty() || !added.is_empty() {
+            for address in &missing {
+                eprintln!("  - {} (missing after migration)", address);
+            }
+            for address in &added {
+                eprintln!("  + {} (not in the previous state)", address);
+            }
+            rollback(ctx, original, true);
+            return Err(format!(
+                "Migrated state does not match the previous one ({} resources before, {} after). The previous backend is unchanged; snapshot: {}",
+                before.len(), after.len(), snapshot_path.display()
+            ).into());
+        }
+        println!("Verified: {} resource(s) in the migrated state.", after.len());
+    }
+    Ok(())
+}
+
+/// Snapshot file and the resource addresses it contains
+type Snapshot = (PathBuf, BTreeSet<String>);
+
+/// Saves the current state (`state pull`) to `.cfg2hcl/state-snapshots/` and returns its
+/// path and addresses. `None` if there is no state yet.
+fn snapshot(ctx: &MigrateContext<'_>, source: &str) -> Result<Option<Snapshot>, Box<dyn std::error::Error>> {
+    let output = Command::new(ctx.tf_tool)
+        .current_dir(ctx.hcl_dir)
+        .arg("state")
+        .arg("pull")
+        .output()
+        .map_err(|e| format!("Failed to run {} state pull: {}", ctx.tf_tool, e))?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Cannot snapshot the current state ({} state pull failed): {}", ctx.tf_tool, err.trim()).into());
+    }
+    if output.stdout.iter().all(|b| b.is_ascii_whitespace()) {
+        status!("No existing state found; nothing to verify.");
+        return Ok(None);
+    }
+
+    let dir = ctx.config_dir.join(STATE_DIR).join("state-snapshots");
+    fs::create_dir_all(&dir)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("{}-{}.tfstate", now, source));
+    fs::write(&path, &output.stdout)
+        .map_err(|e| format!("Failed to write state snapshot '{}': {}", path.display(), e))?;
+    status!("State snapshot: {}", path.display());
+
+    let addresses = state_addresses(ctx.tf_tool, ctx.hcl_dir)?;
+    Ok(Some((path, addresses)))
+}
+
+fn state_addresses(tf_tool: &str, hcl_dir: &Path) -> Result<BTreeSet<String>, Box<dyn std::error::Error>> {
+    let state = crate::state_report::read_state(tf_tool, hcl_dir, None)?;
+    Ok(crate::state_report::state_resources(&state).into_iter().map(|r| r.address).collect())
+}
+
+fn regenerate(ctx: &MigrateContext<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Command::new(std::env::current_exe()?)
+        .args(&ctx.global_args)
+        .arg("transpile")
+        .arg(ctx.input)
+        .status()?;
+    if !status.success() {
+        return Err("Failed to regenerate HCL".into());
+    }
+    Ok(())
+}
+
+/// Restores the YAML and the HCL generated from it; with `reinit`, also points the working
+/// directory back at the previous backend (its state was only copied, never removed).
+fn rollback(ctx: &MigrateContext<'_>, original: &str, reinit: bool) {
+    eprintln!("Rolling back {}...", ctx.input_path.display());
+    if let Err(e) = fs::write(ctx.input_path, original) {
+        eprintln!("Error: Could not restore '{}': {}", ctx.input_path.display(), e);
+        return;
+    }
+    if let Err(e) = regenerate(ctx) {
+        eprintln!("Error: Could not regenerate the previous HCL: {}", e);
+        return;
+    }
+    if reinit {
+        let restored = Command::new(ctx.tf_tool)
+            .current_dir(ctx.hcl_dir)
+            .arg("init")
+            .arg("-reconfigure")
+            .args(crate::output::backend_config_args(ctx.hcl_dir))
+            .status()
+            .is_ok_and(|s| s.success());
+        if !restored {
+            eprintln!("Error: {} init -reconfigure failed; run it in {} before the next plan.", ctx.tf_tool, ctx.hcl_dir.display());
+        }
+    }
+}
+
+/// Checks that the GCS bucket of the backend exists; creates it (as `bootstrap` does) with
+/// `create`, otherwise fails before anything was changed.
+async fn ensure_state_bucket(updated: &str, create: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let yaml: serde_yaml::Value = serde_yaml::from_str(updated)?;
+    let Some(bucket) = yaml.get("terraform")
+        .and_then(|t| t.get("backend"))
+        .and_then(|b| b.get("gcs"))
+        .and_then(|g| g.get("bucket"))
+        .and_then(|b| b.as_str()) else {
+        eprintln!("Warning: No terraform.backend.gcs.bucket found in the input; skipping the bucket check.");
+        return Ok(());
+    };
+
+    let credentials = Builder::default()
+        .with_scopes(["https://www.googleapis.com/auth/cloud-platform"])
+        .build_access_token_credentials()?;
+    let token = credentials.access_token().await?;
+    let client = reqwest::Client::new();
+
+    let res = client.get(format!("{}/storage/v1/b/{}", crate::endpoints::base("storage"), bucket))
+        .bearer_auth(&token.token)
+        .send()
+        .await?;
+    match res.status().as_u16() {
+        200 => {
+            status!("State bucket gs://{} exists.", bucket);
+            Ok(())
+        }
+        404 if create => {
+            let vars = yaml.get("variables");
+            let var = |name: &str| vars.and_then(|v| v.get(name)).and_then(|v| v.as_str());
+            let project_id = var("infra-project-name")
+                .ok_or("Cannot create the state bucket: 'infra-project-name' is not set")?;
+            let location = var("default-region").unwrap_or("europe-west3");
+            status!("Creating GCS State Bucket: {}...", bucket);
+            crate::bootstrap::create_state_bucket(&client, &token.token, project_id, bucket, location).await
+        }
+        404 => Err(format!("State bucket gs://{} does not exist. Create it or rerun with --create-bucket.", bucket).into()),
+        status => Err(format!("Cannot verify state bucket gs://{} (HTTP {}): {}", bucket, status, res.text().await.unwrap_or_default()).into()),
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use crate::transpiler::GeneratedProject;
+
+/// The generated project as data: every block of the generated files with its address and the
+/// YAML entry it came from. Written by `transpile --emit-model` for tools that post-process the
+/// result (docs generators, inventories) without parsing HCL.
+#[derive(Debug, Serialize)]
+pub struct Model {
+    pub blocks: Vec<ModelBlock>,
+    /// Addresses of the resources applied via `scripts/apply-extras.sh` instead of Terraform
+    pub unsupported: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelBlock {
+    /// Generated file the block is written to, e.g. `main.tf` or `modules/infra/main.tf`
+    pub file: String,
+    /// `resource`, `data`, `module`, `locals`, `variable`, `output`, `import`, `provider`, ...
+    pub kind: String,
+    pub labels: Vec<String>,
+    /// Address of resources, data sources and modules, including the module path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Dotted YAML path of the entry the block was generated from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yaml_path: Option<String>,
+    /// From the narrowest enclosing `owner:`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// Arguments and nested blocks in the JSON form of HCL: expressions are `"${...}"` strings,
+    /// repeated nested blocks are arrays
+    pub body: hcl::Value,
+}
+
+impl Model {
+    pub fn new(project: &GeneratedProject) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut blocks = Vec::new();
+        for (file, content) in project.files() {
+            if file.ends_with(".tfvars") || file.ends_with(".tfvars.json") {
+                continue;
+            }
+            let body: hcl::Body = hcl::from_str(content)
+                .map_err(|e| format!("Failed to read generated '{}': {}", file, e))?;
+            let module_prefix = crate::state_report::module_prefix(file);
+            for block in body.into_blocks() {
+                let labels: Vec<String> = block.labels.iter().map(|l| l.as_str().to_string()).collect();
+                let local_address = match (block.identifier(), labels.as_slice()) {
+                    ("resource", [tf_type, name]) => Some(format!("{}.{}", tf_type, name)),
+                    ("data", [tf_type, name]) => Some(format!("data.{}.{}", tf_type, name)),
+                    ("module", [name]) => Some(format!("module.{}", name)),
+                    _ => None,
+                };
+                let yaml_path = local_address.as_ref().and_then(|address| project.sources.get(address)).cloned();
+                let owners = yaml_path.as_deref().map(|p| crate::owners::owners_of(&project.owners, p).to_vec()).unwrap_or_default();
+                blocks.push(ModelBlock {
+                    file: file.to_string(),
+                    kind: block.identifier().to_string(),
+                    labels,
+                    address: local_address.map(|address| format!("{}{}", module_prefix, address)),
+                    yaml_path,
+                    owners,
+                    body: hcl::from_body(block.body)?,
+                });
+            }
+        }
+        let unsupported = project.unsupported.iter().map(|r| r.address.clone()).collect();
+        Ok(Self { blocks, unsupported })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .map_err(|e| format!("Failed to write model '{}': {}", path.display(), e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::i18n::t;
+
+/// `[naming]` table of config.toml.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct NamingConfig {
+    /// Fill in missing names from the pattern instead of only validating explicit ones
+    #[serde(default)]
+    pub generate: bool,
+    /// Resource type -> pattern, e.g. `google_project = "{customer}-{env}-{name}"`
+    #[serde(default)]
+    pub patterns: HashMap<String, String>,
+    /// Resource type -> attribute holding the name (defaults: see `name_attribute`)
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+impl NamingConfig {
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    fn pattern_for(&self, resource_type: &str) -> Option<(&str, &String)> {
+        if let Some((k, p)) = self.patterns.get_key_value(resource_type) {
+            return Some((k.as_str(), p));
+        }
+        let prefixed = format!("google_{}", resource_type);
+        self.patterns.get_key_value(&prefixed).map(|(k, p)| (k.as_str(), p))
+    }
+
+    fn name_attribute<'a>(&'a self, tf_type: &str) -> &'a str {
+        if let Some(attr) = self.attributes.get(tf_type) {
+            return attr;
+        }
+        match tf_type {
+            "google_project" => "project_id",
+            "google_folder" => "display_name",
+            "google_service_account" => "account_id",
+            _ => "name",
+        }
+    }
+}
+
+/// A name that does not follow (or could not be generated from) the configured pattern.
+#[derive(Debug, Clone)]
+pub struct NamingFinding {
+    pub yaml_path: String,
+    pub message: String,
+}
+
+/// Placeholder values known at a point in the hierarchy.
+#[derive(Clone, Default)]
+struct Scope {
+    env: Option<String>,
+    folder: Option<String>,
+    project: Option<String>,
+}
+
+/// Generates and validates resource names according to `[naming]` patterns. Runs on the
+/// resolved YAML before it is deserialized, so generated values (e.g. `project_id`) satisfy
+/// required fields.
+///
+/// Placeholders: `{name}` (the YAML key), `{customer}` (`customer-id`), `{env}` (the inherited
+/// `default_labels.env`, or a root `env` variable), `{folder}` (enclosing folder key),
+/// `{project}` (enclosing project ID) and any other root variable, e.g. `{customer-shortname}`.
+pub fn apply_naming(value: &mut serde_yaml::Value, naming: &NamingConfig) -> Vec<NamingFinding> {
+    let mut findings = Vec::new();
+    if naming.is_empty() {
+        return findings;
+    }
+    let serde_yaml::Value::Mapping(root) = value else { return findings };
+
+    let globals: HashMap<String, String> = root
+        .iter()
+        .filter_map(|(k, v)| Some((k.as_str()?.to_string(), scalar_string(v)?)))
+        .collect();
+    let scope = Scope {
+        env: labels_env(root).or_else(|| globals.get("env").cloned()),
+        ..Default::default()
+    };
+
+    let ctx = Naming { config: naming, globals: &globals };
+    ctx.walk_scope("", root, &scope, &mut findings);
+    findings
+}
+
+struct Naming<'a> {
+    config: &'a NamingConfig,
+    globals: &'a HashMap<String, String>,
+}
+
+impl Naming<'_> {
+    fn walk_scope(&self, path: &str, map: &mut serde_yaml::Mapping, scope: &Scope, findings: &mut Vec<NamingFinding>) {
+        for (k, v) in map.iter_mut() {
+            let Some(key) = k.as_str() else { continue };
+            let child_path = if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+            let serde_yaml::Value::Mapping(children) = v else { continue };
+
+            match key {
+                "folder" => {
+                    for (name, folder) in children.iter_mut() {
+                        let (Some(name), serde_yaml::Value::Mapping(folder)) = (name.as_str(), folder) else { continue };
+                        let folder_path = format!("{}.{}", child_path, name);
+                        let mut folder_scope = scope.clone();
+                        folder_scope.folder = Some(name.to_string());
+                        if let Some(env) = labels_env(folder) {
+                            folder_scope.env = Some(env);
+                        }
+                        // An empty display_name folds the folder away, there is nothing to name
+                        if folder.get("display_name").and_then(|v| v.as_str()) != Some("") {
+                            self.apply("google_folder", &folder_path, name, folder, &folder_scope, findings);
+                        }
+                        self.walk_scope(&folder_path, folder, &folder_scope, findings);
+                    }
+                }
+                "project" => {
+                    for (name, project) in children.iter_mut() {
+                        let (Some(name), serde_yaml::Value::Mapping(project)) = (name.as_str(), project) else { continue };
+                        let project_path = format!("{}.{}", child_path, name);
+                        let mut project_scope = scope.clone();
+                        if let Some(env) = labels_env(project) {
+                            project_scope.env = Some(env);
+                        }
+                        self.apply("google_project", &project_path, name, project, &project_scope, findings);
+                        project_scope.project = project.get("project_id").and_then(scalar_string);
+                        self.walk_scope(&project_path, project, &project_scope, findings);
+                    }
+                }
+                _ => {
+                    let Some((tf_type, _)) = self.config.pattern_for(key) else { continue };
+                    if tf_type == "google_folder" || tf_type == "google_project" {
+                        continue;
+                    }
+                    for (name, attrs) in children.iter_mut() {
+                        let (Some(name), serde_yaml::Value::Mapping(attrs)) = (name.as_str(), attrs) else { continue };
+                        self.apply(tf_type, &format!("{}.{}", child_path, name), name, attrs, scope, findings);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Validates the name attribute of one resource, or generates it if missing and enabled.
+    fn apply(&self, tf_type: &str, yaml_path: &str, key: &str, attrs: &mut serde_yaml::Mapping, scope: &Scope, findings: &mut Vec<NamingFinding>) {
+        let Some((_, pattern)) = self.config.pattern_for(tf_type) else { return };
+        let attribute = self.config.name_attribute(tf_type);
+
+        match attrs.get(attribute) {
+            Some(value) => {
+                let Some(actual) = scalar_string(value) else { return };
+                // Interpolated values are only known to Terraform
+                if actual.contains("${") {
+                    return;
+                }
+                if !self.matches(pattern, key, scope, &actual) {
+                    findings.push(NamingFinding {
+                        yaml_path: yaml_path.to_string(),
+                        message: t("naming-mismatch", &[("attribute", &attribute), ("value", &actual), ("type", &tf_type), ("pattern", &pattern)]),
+                    });
+                }
+            }
+            None if self.config.generate => match self.render(pattern, key, scope) {
+                Ok(name) => {
+                    attrs.insert(attribute.into(), name.into());
+                }
+                Err(placeholder) => findings.push(NamingFinding {
+                    yaml_path: yaml_path.to_string(),
+                    message: t("naming-missing-placeholder", &[("attribute", &attribute), ("type", &tf_type), ("placeholder", &placeholder)]),
+                }),
+            },
+            None => {}
+        }
+    }
+
+    fn placeholder(&self, placeholder: &str, key: &str, scope: &Scope) -> Option<String> {
+        match placeholder {
+            "name" => Some(key.to_string()),
+            "customer" => self.globals.get("customer-id").cloned(),
+            "env" => scope.env.clone(),
+            "folder" => scope.folder.clone(),
+            "project" => scope.project.clone(),
+            other => self.globals.get(other).cloned(),
+        }
+    }
+
+    /// Renders a pattern; returns the first placeholder without a value as error.
+    fn render(&self, pattern: &str, key: &str, scope: &Scope) -> Result<String, String> {
+        let mut out = String::new();
+        for segment in split_pattern(pattern) {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Placeholder(name) => out.push_str(&self.placeholder(name, key, scope).ok_or_else(|| name.to_string())?),
+            }
+        }
+        Ok(out)
+    }
+
+    /// `{name}` and placeholders without a value match any non-empty text; all others must match exactly.
+    fn matches(&self, pattern: &str, key: &str, scope: &Scope, actual: &str) -> bool {
+        let mut regex = String::from("^");
+        for segment in split_pattern(pattern) {
+            match segment {
+                Segment::Literal(text) => regex.push_str(&regex::escape(text)),
+                Segment::Placeholder("name") => regex.push_str(".+"),
+                Segment::Placeholder(name) => match self.placeholder(name, key, scope) {
+                    Some(value) => regex.push_str(&regex::escape(&value)),
+                    None => regex.push_str(".+"),
+                },
+            }
+        }
+        regex.push('$');
+        regex::Regex::new(&regex).is_ok_and(|re| re.is_match(actual))
+    }
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn split_pattern(pattern: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else { break };
+        if start > 0 {
+            segments.push(Segment::Literal(&rest[..start]));
+        }
+        segments.push(Segment::Placeholder(&rest[start + 1..start + len]));
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+    segments
+}
+
+fn labels_env(map: &serde_yaml::Mapping) -> Option<String> {
+    let labels = map.get("default_labels")?;
+    labels.get("labels").unwrap_or(labels).get("env").and_then(scalar_string)
+}
+
+fn scalar_string(v: &serde_yaml::Value) -> Option<String> {
+    match v {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+/// Events that commands can post to the notification webhook.
+pub const EVENTS: &[&str] = &["plan-summary", "drift", "bootstrap-complete", "update-available"];
+
+/// `[notifications]` table of config.toml.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct NotificationConfig {
+    /// Slack, Teams or generic HTTP webhook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Events to post (see `EVENTS`); all events if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+}
+
+impl NotificationConfig {
+    pub fn is_empty(&self) -> bool {
+        self.webhook_url.is_none() && self.events.is_empty()
+    }
+
+    /// The webhook to post `event` to, if notifications are configured for it.
+    pub fn webhook_for(&self, event: &str) -> Option<&str> {
+        let enabled = self.events.is_empty() || self.events.iter().any(|e| e == event);
+        self.webhook_url.as_deref().filter(|_| enabled)
+    }
+
+    pub fn warn_unknown_events(&self) {
+        for event in &self.events {
+            if !EVENTS.contains(&event.as_str()) {
+                eprintln!("Warning: Unknown notification event '{}' (known: {})", event, EVENTS.join(", "));
+            }
+        }
+    }
+}
+
+/// Posts `event` if it is enabled. A failing webhook never fails the command.
+pub async fn notify(config: &NotificationConfig, event: &str, text: &str, details: serde_json::Value) {
+    if let Some(url) = config.webhook_for(event) {
+        if let Err(e) = post_webhook(url, text, details).await {
+            eprintln!("Warning: Notification '{}' not sent: {}", event, e);
+        }
+    }
+}
+
+/// Posts a JSON message to a webhook. `text` is what Slack and Teams incoming webhooks
+/// display; the remaining fields are for generic HTTP receivers.
+pub async fn post_webhook(url: &str, text: &str, details: serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+    let mut payload = serde_json::json!({ "text": text });
+    if let (Some(payload), serde_json::Value::Object(details)) = (payload.as_object_mut(), details) {
+        payload.extend(details);
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("cfg2hcl/", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?;
+    let response = client.post(url).json(&payload).send().await
+        .map_err(|e| format!("Failed to post to webhook: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Webhook returned {}: {}", status, body.trim()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use serde_yaml::{Mapping, Value};
+
+/// Resource types of the legacy (v1) organization policies.
+pub const LEGACY_TYPES: &[&str] = &["google_organization_policy", "google_folder_organization_policy", "google_project_organization_policy"];
+
+/// Exactly one of them makes a legacy policy.
+const POLICY_BLOCKS: &[&str] = &["boolean_policy", "list_policy", "restore_policy"];
+
+/// Type of the converted entries.
+const POLICY_TYPE: &str = "google_org_policy_policy";
+
+/// Keys of a legacy policy that have no counterpart in `google_org_policy_policy`.
+const DROPPED_KEYS: &[&str] = &["version", "etag", "update_time"];
+
+/// Rewrites the v1 Cloud Asset data of a legacy policy (`listPolicy.allowedValues`,
+/// `restoreDefault: {}`, ...) to the block shapes of the Terraform schema, so that
+/// `extract_attributes` finds them.
+pub fn from_asset_data(data: &mut serde_json::Map<String, serde_json::Value>) {
+    use serde_json::{json, Value as Json};
+    if let Some(Json::Object(boolean)) = data.remove("booleanPolicy") {
+        // `enforced: false` is left out by the API
+        data.insert("boolean_policy".to_string(), json!({ "enforced": boolean.get("enforced").and_then(Json::as_bool).unwrap_or(false) }));
+    }
+    if let Some(Json::Object(list)) = data.remove("listPolicy") {
+        let mut policy = serde_json::Map::new();
+        match list.get("allValues").and_then(Json::as_str) {
+            Some("ALLOW") => { policy.insert("allow".to_string(), json!({ "all": true })); }
+            Some("DENY") => { policy.insert("deny".to_string(), json!({ "all": true })); }
+            _ => {}
+        }
+        for (from, to) in [("allowedValues", "allow"), ("deniedValues", "deny")] {
+            if let Some(values) = list.get(from).filter(|v| v.as_array().is_some_and(|a| !a.is_empty())) {
+                policy.insert(to.to_string(), json!({ "values": values }));
+            }
+        }
+        if let Some(suggested) = list.get("suggestedValue") {
+            policy.insert("suggested_value".to_string(), suggested.clone());
+        }
+        if let Some(inherit) = list.get("inheritFromParent") {
+            policy.insert("inherit_from_parent".to_string(), inherit.clone());
+        }
+        data.insert("list_policy".to_string(), Json::Object(policy));
+    }
+    if data.remove("restoreDefault").is_some() {
+        data.insert("restore_policy".to_string(), json!({ "default": true }));
+    }
+}
+
+/// A legacy policy entry with its shorthands expanded: `boolean_policy: true`,
+/// `restore_policy: true`, `allow:`/`deny:` as a list of values or `all`, a bare folder ID, and
+/// blocks as one-element lists (as written by discovery). Fails unless exactly one of
+/// `boolean_policy`, `list_policy` and `restore_policy` is given.
+pub fn normalize(tf_type: &str, attrs: &Mapping) -> Result<Mapping, String> {
+    let mut attrs = attrs.clone();
+    let given: Vec<&str> = POLICY_BLOCKS.iter().copied().filter(|b| attrs.contains_key(*b)).collect();
+    if given.len() != 1 {
+        return Err(format!("{} needs exactly one of boolean_policy, list_policy and restore_policy (given: {})", tf_type, if given.is_empty() { "none".to_string() } else { given.join(", ") }));
+    }
+
+    match attrs.get("boolean_policy").map(single_block) {
+        Some(Value::Bool(enforced)) => { attrs.insert("boolean_policy".into(), mapping([("enforced", Value::Bool(enforced))])); }
+        Some(block) => { attrs.insert("boolean_policy".into(), block); }
+        None => {}
+    }
+    match attrs.get("restore_policy").map(single_block) {
+        Some(Value::Bool(default)) => { attrs.insert("restore_policy".into(), mapping([("default", Value::Bool(default))])); }
+        Some(block) => { attrs.insert("restore_policy".into(), block); }
+        None => {}
+    }
+    if let Some(Value::Mapping(mut list)) = attrs.get("list_policy").map(single_block) {
+        for side in ["allow", "deny"] {
+            let expanded = match list.get(side).map(single_block) {
+                Some(Value::String(all)) if all == "all" => mapping([("all", Value::Bool(true))]),
+                Some(values @ Value::Sequence(_)) => mapping([("values", values)]),
+                Some(Value::Mapping(block)) => Value::Mapping(block),
+                Some(other) => return Err(format!("list_policy.{} must be a list of values, 'all' or a mapping, not {:?}", side, other)),
+                None => continue,
+            };
+            list.insert(side.into(), expanded);
+        }
+        attrs.insert("list_policy".into(), Value::Mapping(list));
+    }
+
+    if tf_type == "google_folder_organization_policy" {
+        if let Some(Value::String(folder)) = attrs.get("folder") {
+            if !folder.is_empty() && folder.chars().all(|c| c.is_ascii_digit()) {
+                let folder = format!("folders/{}", folder);
+                attrs.insert("folder".into(), folder.into());
+            }
+        }
+    }
+    Ok(attrs)
+}
+
+/// The `google_org_policy_policy` entry for a legacy policy (`convert-org-policy`). The parent
+/// comes from `org_id`, `folder` or `project`, or, without them, from the scope the entry is in.
+/// Notes on what could not be carried over are added to `notes`.
+pub fn to_policy(tf_type: &str, key: &str, attrs: &Mapping, notes: &mut Vec<String>) -> Result<Mapping, String> {
+    let attrs = normalize(tf_type, attrs)?;
+    let Some(constraint) = attrs.get("constraint").and_then(Value::as_str) else {
+        return Err(format!("{} '{}' has no constraint", tf_type, key));
+    };
+    let constraint = constraint.strip_prefix("constraints/").unwrap_or(constraint);
+
+    let parent = match (attrs.get("org_id"), attrs.get("folder"), attrs.get("project")) {
+        (Some(Value::String(org)), _, _) => Some(Value::String(format!("organizations/{}", org.trim_start_matches("organizations/")))),
+        (Some(Value::Number(org)), _, _) => Some(Value::String(format!("organizations/{}", org))),
+        (_, Some(folder), _) => Some(folder.clone()),
+        (_, _, Some(Value::String(project))) => Some(Value::String(format!("projects/{}", project))),
+        (None, None, None) => None,
+        _ => {
+            notes.push(format!("'{}': the parent is taken from the enclosing folder or project instead of its expression", key));
+            None
+        }
+    };
+
+    let mut spec = Mapping::new();
+    let mut rules: Vec<Value> = Vec::new();
+    if let Some(boolean) = attrs.get("boolean_policy") {
+        let enforced = boolean.get("enforced").and_then(Value::as_bool).unwrap_or(false);
+        rules.push(mapping([("enforce", Value::String(if enforced { "TRUE" } else { "FALSE" }.to_string()))]));
+    }
+    if let Some(list) = attrs.get("list_policy") {
+        let mut values = Mapping::new();
+        for (side, all_key, values_key) in [("allow", "allow_all", "allowed_values"), ("deny", "deny_all", "denied_values")] {
+            let Some(block) = list.get(side) else { continue };
+            if block.get("all").and_then(Value::as_bool).unwrap_or(false) {
+                rules.push(mapping([(all_key, Value::String("TRUE".to_string()))]));
+            } else if let Some(side_values) = block.get("values") {
+                values.insert(values_key.into(), side_values.clone());
+            }
+        }
+        if !values.is_empty() {
+            rules.push(mapping([("values", Value::Mapping(values))]));
+        }
+        if let Some(inherit) = list.get("inherit_from_parent") {
+            spec.insert("inherit_from_parent".into(), inherit.clone());
+        }
+        if list.get("suggested_value").is_some() {
+            notes.push(format!("'{}': suggested_value has no counterpart in google_org_policy_policy and was dropped", key));
+        }
+    }
+    if attrs.get("restore_policy").and_then(|r| r.get("default")).and_then(Value::as_bool).unwrap_or(false) {
+        spec.insert("reset".into(), Value::Bool(true));
+    }
+    if !rules.is_empty() {
+        spec.insert("rules".into(), Value::Sequence(rules));
+    }
+
+    let mut policy = Mapping::new();
+    policy.insert("name".into(), constraint.into());
+    if let Some(parent) = &parent {
+        policy.insert("parent".into(), parent.clone());
+    }
+    for import_key in ["import-id", "import-id-comment"] {
+        if attrs.contains_key(import_key) {
+            match parent.as_ref().and_then(Value::as_str) {
+                Some(parent) => { policy.insert(import_key.into(), format!("{}/policies/{}", parent, constraint).into()); }
+                None => notes.push(format!("'{}': {} dropped, the policy name depends on the enclosing scope", key, import_key)),
+            }
+        }
+    }
+    policy.insert("spec".into(), Value::Mapping(spec));
+    for (k, v) in &attrs {
+        let k_str = k.as_str().unwrap_or_default();
+        let consumed = ["constraint", "org_id", "folder", "project", "import-id", "import-id-comment"].contains(&k_str) || POLICY_BLOCKS.contains(&k_str);
+        if !consumed && !DROPPED_KEYS.contains(&k_str) {
+            policy.insert(k.clone(), v.clone());
+        }
+    }
+    Ok(policy)
+}
+
+/// Rewrites the legacy policy sections of a YAML text to `google_org_policy_policy` entries
+/// (`convert-org-policy`), see [`crate::yaml_edit::rewrite_sections`]. Returns `None` if the text
+/// has no legacy policies.
+pub fn convert(content: &str, notes: &mut Vec<String>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    crate::yaml_edit::rewrite_sections(content, LEGACY_TYPES, |tf_type, _, entries| {
+        let mut policies = Mapping::new();
+        for (key, attrs) in entries {
+            let key_str = key.as_str().unwrap_or_default();
+            let Value::Mapping(attrs) = attrs else {
+                return Err(format!("'{}' is not a mapping", key_str));
+            };
+            policies.insert(key.clone(), Value::Mapping(to_policy(tf_type, key_str, attrs, notes)?));
+        }
+        Ok((POLICY_TYPE.to_string(), policies))
+    }, |key, _, _| Err(format!("'{}' exists in {} already; rename one of them", key, POLICY_TYPE)))
+}
+
+/// A block written as a one-element list (as discovery does) as the block itself.
+fn single_block(value: &Value) -> Value {
+    match value {
+        Value::Sequence(seq) if seq.len() == 1 && seq[0].is_mapping() => seq[0].clone(),
+        other => other.clone(),
+    }
+}
+
+fn mapping<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Mapping(entries.into_iter().map(|(k, v)| (Value::String(k.to_string()), v)).collect())
+}
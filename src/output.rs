@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::transpiler::GeneratedProject;
+
+/// Staging and backup directories inside the output directory (same filesystem, so moves are
+/// renames; Terraform does not read subdirectories).
+const STAGING_DIR: &str = ".cfg2hcl-staging";
+const BACKUP_DIR: &str = ".cfg2hcl-backup";
+
+/// Backend settings written with `backend_config_file`, see [`backend_config_args`].
+pub const BACKEND_CONFIG_FILE: &str = "backend.hcl";
+
+/// `-backend-config=backend.hcl` for `init` in an HCL directory with a backend settings file.
+pub fn backend_config_args(hcl_dir: &Path) -> Vec<String> {
+    if hcl_dir.join(BACKEND_CONFIG_FILE).is_file() {
+        vec![format!("-backend-config={}", BACKEND_CONFIG_FILE)]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Writes a set of generated files into `dir` as a unit. Every file is first written to a
+/// staging directory and only moved into place once all of them were written; if a move
+/// fails, the previous files are restored. `None` removes the file. Names can contain
+/// subdirectories (`modules/x/main.tf`), which are created as needed and removed once empty.
+/// Returns the written paths.
+pub fn write_atomically(dir: &Path, files: &[(&str, Option<&str>)]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let _span = crate::timings::span("file io");
+    let staging = dir.join(STAGING_DIR);
+    let backup = dir.join(BACKUP_DIR);
+    if backup.exists() {
+        return Err(format!("'{}' is left over from an interrupted run; restore its files or remove it", backup.display()).into());
+    }
+    if staging.exists() {
+        fs::remove_dir_all(&staging)
+            .map_err(|e| format!("Failed to remove '{}': {}", staging.display(), e))?;
+    }
+
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create staging directory '{}': {}", staging.display(), e))?;
+    for (name, content) in files {
+        if let Some(content) = content {
+            let path = staging.join(name);
+            if let Err(e) = create_parent(&path).and_then(|_| fs::write(&path, content)) {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(format!("Failed to write file '{}': {}", dir.join(name).display(), e).into());
+            }
+        }
+    }
+
+    fs::create_dir_all(&backup)
+        .map_err(|e| format!("Failed to create backup directory '{}': {}", backup.display(), e))?;
+    let mut backed_up = Vec::new();
+    let mut placed = Vec::new();
+    for (name, content) in files {
+        let target = dir.join(name);
+        let result = (|| -> std::io::Result<()> {
+            if target.exists() {
+                create_parent(&backup.join(name))?;
+                fs::rename(&target, backup.join(name))?;
+                backed_up.push(*name);
+            }
+            if content.is_some() {
+                create_parent(&target)?;
+                fs::rename(staging.join(name), &target)?;
+                placed.push(target.clone());
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            rollback(dir, &backup, &backed_up, &placed);
+            let _ = fs::remove_dir_all(&staging);
+            return Err(format!("Failed to replace '{}': {} (previous files restored)", target.display(), e).into());
+        }
+    }
+
+    let _ = fs::remove_dir_all(&staging);
+    let _ = fs::remove_dir_all(&backup);
+    for (name, _) in files.iter().filter(|(_, content)| content.is_none()) {
+        // Stops at the first directory that is not empty
+        for parent in Path::new(name).ancestors().skip(1).filter(|p| !p.as_os_str().is_empty()) {
+            if fs::remove_dir(dir.join(parent)).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(placed)
+}
+
+fn create_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+fn rollback(dir: &Path, backup: &Path, backed_up: &[&str], placed: &[PathBuf]) {
+    for path in placed {
+        let _ = fs::remove_file(path);
+    }
+    let mut restored = true;
+    for name in backed_up {
+        if let Err(e) = create_parent(&dir.join(name)).and_then(|_| fs::rename(backup.join(name), dir.join(name))) {
+            eprintln!("Error: Could not restore '{}' from '{}': {}", dir.join(name).display(), backup.display(), e);
+            restored = false;
+        }
+    }
+    // Keep the backup around if anything could not be restored
+    if restored {
+        let _ = fs::remove_dir_all(backup);
+    }
+}
+
+/// All generated files as one HCL text, each preceded by a `# <file name>` comment.
+pub fn concatenated(project: &GeneratedProject) -> String {
+    project.files().iter()
+        .map(|(name, content)| format!("# {}\n{}", name, content.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n\n") + "\n"
+}
+
+/// `{"files": {"main.tf": ..}, "unsupported": [..]}`; empty files are left out.
+pub fn bundle(project: &GeneratedProject) -> serde_json::Value {
+    let files: BTreeMap<&str, &str> = project.files().into_iter().collect();
+    let unsupported: Vec<&str> = project.unsupported.iter().map(|r| r.address.as_str()).collect();
+    serde_json::json!({ "files": files, "unsupported": unsupported })
+}
+
+/// Whether a bundle path asks for a compressed archive; fails for unknown extensions.
+pub fn bundle_is_gzip(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Ok(true)
+    } else if name.ends_with(".tar") {
+        Ok(false)
+    } else {
+        Err(format!("Unsupported bundle format '{}' (expected .tar.gz, .tgz or .tar)", path.display()).into())
+    }
+}
+
+/// Packs the generated files and the run report into a `.tar.gz`/`.tgz` (or plain `.tar`)
+/// archive for handing the result from a generate job to an apply job.
+pub fn write_bundle(path: &Path, files: &[(&str, &str)], report: &crate::run_report::RunReport) -> Result<(), Box<dyn std::error::Error>> {
+    let report_json = report.to_json()?;
+    let entries: Vec<(&str, &str)> = files.iter().copied().chain([("run-report.json", report_json.as_str())]).collect();
+    write_archive(path, &entries, report.generated_at)
+}
+
+/// Writes files (name, content) into a `.tar.gz`/`.tgz` or `.tar` archive, all with the given mtime.
+pub fn write_archive(path: &Path, entries: &[(&str, &str)], mtime: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = crate::timings::span("file io");
+    let gzip = bundle_is_gzip(path)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Failed to create bundle '{}': {}", path.display(), e))?;
+    let write = |writer: &mut dyn std::io::Write| -> std::io::Result<()> {
+        let mut archive = tar::Builder::new(writer);
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            archive.append_data(&mut header, name, content.as_bytes())?;
+        }
+        archive.finish()
+    };
+    let result = if gzip {
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        write(&mut encoder).and_then(|_| encoder.finish().map(|_| ()))
+    } else {
+        let mut file = file;
+        write(&mut file)
+    };
+    result.map_err(|e| format!("Failed to write bundle '{}': {}", path.display(), e))?;
+    Ok(())
+}
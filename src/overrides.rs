@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::PathBuf;
+use serde_yaml::Value;
+
+/// Applies the override files (`*.yaml`/`*.yml`, in file name order) of the given directories to
+/// the include-expanded input. Each file maps a YAML path to a patch:
+///
+/// ```yaml
+/// folder.team-a.project.x.google_storage_bucket.logs:
+///   force_destroy: true
+/// ```
+///
+/// Mappings are merged recursively, `null` removes a key and anything else replaces the value.
+/// A path that matches nothing is an error, so that overrides do not silently go stale.
+pub fn apply_overrides(value: &mut Value, dirs: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    for file in override_files(dirs)? {
+        let content = fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read override file '{}': {}", file.display(), e))?;
+        let patches: Value = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Failed to parse override file '{}': {}", file.display(), e))?;
+        let Value::Mapping(patches) = patches else {
+            if patches.is_null() {
+                continue;
+            }
+            return Err(format!("Override file '{}' must map YAML paths to patches", file.display()).into());
+        };
+        for (path, patch) in patches {
+            let Some(path) = path.as_str() else {
+                return Err(format!("Override file '{}' contains a non-string YAML path", file.display()).into());
+            };
+            let target = lookup_mut(value, path)
+                .ok_or_else(|| format!("Override '{}' in '{}' does not match any entry", path, file.display()))?;
+            merge(target, patch);
+        }
+    }
+    Ok(())
+}
+
+fn override_files(dirs: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut all = Vec::new();
+    for dir in dirs {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read overrides directory '{}': {}", dir.display(), e))?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "yaml" || ext == "yml"))
+            .collect();
+        files.sort();
+        all.extend(files);
+    }
+    Ok(all)
+}
+
+/// Follows a dotted path through mappings (by key) and sequences (by index).
+fn lookup_mut<'a>(value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.').try_fold(value, |current, segment| match current {
+        Value::Mapping(map) => map.get_mut(segment),
+        Value::Sequence(seq) => segment.parse::<usize>().ok().and_then(|idx| seq.get_mut(idx)),
+        _ => None,
+    })
+}
+
+/// Merges `patch` into `target`: mappings recursively, `null` removes a key and anything else
+/// replaces the value. Also used by the `!merge` tag.
+pub fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Mapping(target), Value::Mapping(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else if let Some(existing) = target.get_mut(&key) {
+                    merge(existing, value);
+                } else {
+                    target.insert(key, value);
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::{Account, Config, Folder, OrganizationalUnit, Owners, Project};
+use crate::include_processor::SourceMap;
+
+/// Start and end of the part of a CODEOWNERS file written by `cfg2hcl codeowners`.
+const BEGIN_MARKER: &str = "# BEGIN cfg2hcl";
+const END_MARKER: &str = "# END cfg2hcl";
+
+/// The `owner:` of the root (empty path), each folder, project, organizational unit and account,
+/// keyed by YAML path (`folder.infra.project.app`). Scopes without `owner:` are left out; they inherit, see [`owners_of`].
+pub fn scope_owners(config: &Config) -> BTreeMap<String, Vec<String>> {
+    let mut owners = BTreeMap::new();
+    if let Some(root) = config.extra.get("owner").and_then(|v| serde_yaml::from_value::<Owners>(v.clone()).ok()) {
+        owners.insert(String::new(), root.to_vec());
+    }
+    if let Some(folders) = &config.folder {
+        collect_folders(folders, "", &mut owners);
+    }
+    if let Some(projects) = &config.project {
+        collect_projects(projects, "", &mut owners);
+    }
+    if let Some(units) = &config.organizational_unit {
+        collect_units(units, "", &mut owners);
+    }
+    if let Some(accounts) = &config.account {
+        collect_accounts(accounts, "", &mut owners);
+    }
+    owners
+}
+
+fn collect_folders(folders: &HashMap<String, Folder>, parent: &str, owners: &mut BTreeMap<String, Vec<String>>) {
+    for (key, folder) in folders {
+        let path = join(parent, &format!("folder.{}", key));
+        if let Some(owner) = &folder.owner {
+            owners.insert(path.clone(), owner.to_vec());
+        }
+        if let Some(sub_folders) = &folder.folder {
+            collect_folders(sub_folders, &path, owners);
+        }
+        if let Some(projects) = &folder.project {
+            collect_projects(projects, &path, owners);
+        }
+    }
+}
+
+fn collect_projects(projects: &HashMap<String, Project>, parent: &str, owners: &mut BTreeMap<String, Vec<String>>) {
+    for (key, project) in projects {
+        if let Some(owner) = &project.owner {
+            owners.insert(join(parent, &format!("project.{}", key)), owner.to_vec());
+        }
+    }
+}
+
+fn collect_units(units: &HashMap<String, OrganizationalUnit>, parent: &str, owners: &mut BTreeMap<String, Vec<String>>) {
+    for (key, unit) in units {
+        let path = join(parent, &format!("organizational_unit.{}", key));
+        if let Some(owner) = &unit.owner {
+            owners.insert(path.clone(), owner.to_vec());
+        }
+        if let Some(sub_units) = &unit.organizational_unit {
+            collect_units(sub_units, &path, owners);
+        }
+        if let Some(accounts) = &unit.account {
+            collect_accounts(accounts, &path, owners);
+        }
+    }
+}
+
+fn collect_accounts(accounts: &HashMap<String, Account>, parent: &str, owners: &mut BTreeMap<String, Vec<String>>) {
+    for (key, account) in accounts {
+        if let Some(owner) = &account.owner {
+            owners.insert(join(parent, &format!("account.{}", key)), owner.to_vec());
+        }
+    }
+}
+
+fn join(parent: &str, child: &str) -> String {
+    if parent.is_empty() { child.to_string() } else { format!("{}.{}", parent, child) }
+}
+
+/// Owners of a YAML path: those of the narrowest enclosing scope with an `owner:`.
+pub fn owners_of<'a>(scope_owners: &'a BTreeMap<String, Vec<String>>, yaml_path: &str) -> &'a [String] {
+    scope_owners.iter()
+        .filter(|(scope, _)| scope.is_empty() || *scope == yaml_path || yaml_path.starts_with(&format!("{}.", scope)))
+        .max_by_key(|(scope, _)| scope.len())
+        .map_or(&[], |(_, owners)| owners.as_slice())
+}
+
+/// CODEOWNERS rules for a transpile input: the input file and the generated stack (`hcl_dir`)
+/// belong to the root owners, each included file to the owners of the scope it is included in.
+/// Paths are relative to `repo_root`, files without owners are left out.
+pub fn codeowners_rules(input_path: &Path, processed_content: &str, source_map: &SourceMap, config: &Config, hcl_dir: &Path, repo_root: &Path) -> Vec<(String, Vec<String>)> {
+    let scopes = scope_owners(config);
+    let mut files: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+    files.entry(input_path.to_path_buf()).or_default().extend(owners_of(&scopes, "").iter().cloned());
+
+    let lines: Vec<&str> = processed_content.lines().collect();
+    for (start, file) in source_map.includes() {
+        let scope = yaml_path_at(&lines, start);
+        files.entry(file.to_path_buf()).or_default().extend(owners_of(&scopes, &scope).iter().cloned());
+    }
+
+    let relative = |path: &Path| -> String {
+        let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let root = repo_root.canonicalize().unwrap_or_else(|_| repo_root.to_path_buf());
+        let relative = absolute.strip_prefix(&root).unwrap_or(&absolute);
+        format!("/{}", relative.display().to_string().replace('\\', "/").trim_start_matches('/'))
+    };
+    let mut rules: Vec<(String, Vec<String>)> = files.into_iter()
+        .filter(|(_, owners)| !owners.is_empty())
+        .map(|(file, owners)| (relative(&file), owners.into_iter().collect()))
+        .collect();
+    let stack_owners = owners_of(&scopes, "");
+    if !stack_owners.is_empty() {
+        rules.push((format!("{}/", relative(hcl_dir)), stack_owners.to_vec()));
+    }
+    rules
+}
+
+/// YAML path of the mapping the content from line `idx` on (an included file) belongs to.
+fn yaml_path_at(lines: &[&str], idx: usize) -> String {
+    let content_line = lines.iter().skip(idx)
+        .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'));
+    let Some(content_line) = content_line else { return String::new() };
+    let mut indent = content_line.len() - content_line.trim_start().len();
+
+    let mut keys = Vec::new();
+    for line in lines[..idx].iter().rev() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let line_indent = line.len() - trimmed.len();
+        if line_indent < indent {
+            if let Some((key, _)) = trimmed.split_once(':') {
+                keys.push(key.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+            indent = line_indent;
+            if indent == 0 {
+                break;
+            }
+        }
+    }
+    keys.reverse();
+    keys.join(".")
+}
+
+/// Writes the rules between the cfg2hcl markers of a CODEOWNERS file, keeping everything else.
+/// Without markers the section is appended, so that it takes precedence over earlier rules.
+pub fn write_codeowners(path: &Path, input: &str, rules: &[(String, Vec<String>)]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut section = vec![
+        BEGIN_MARKER.to_string(),
+        format!("# Generated by `cfg2hcl codeowners {}` from the owner: entries; do not edit", input),
+    ];
+    let width = rules.iter().map(|(path, _)| path.len()).max().unwrap_or(0);
+    section.extend(rules.iter().map(|(path, owners)| format!("{:<width$} {}", path, owners.join(" "), width = width)));
+    section.push(END_MARKER.to_string());
+
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    let mut in_section = false;
+    for line in existing.lines() {
+        if line.trim() == BEGIN_MARKER {
+            in_section = true;
+            lines.append(&mut section.clone());
+            replaced = true;
+        } else if line.trim() == END_MARKER {
+            in_section = false;
+        } else if !in_section {
+            lines.push(line.to_string());
+        }
+    }
+    if !replaced {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.extend(section);
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    Ok(())
+}
@@ -0,0 +1,589 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use google_cloud_auth::credentials::Builder;
+use crate::config::{Config, Folder, Project};
+
+/// Permissions that have to be granted on a single resource (organization, folder, project or billing account).
+#[derive(Debug, Default)]
+struct ScopeCheck {
+    /// Human readable description of why the scope is checked
+    reason: BTreeSet<String>,
+    permissions: BTreeSet<String>,
+}
+
+/// An org policy constraint that would block creating a planned resource if enforced.
+#[derive(Debug)]
+struct ConstraintCheck {
+    /// Planned resource, e.g. `google_service_account_key.ci (project 'infra-p')`
+    resource: String,
+    /// Nearest existing ancestor the effective policy is evaluated on
+    scope: String,
+    constraint: &'static str,
+    /// Value that must be allowed by a list constraint; `None` for boolean constraints
+    value: Option<String>,
+}
+
+/// Collects the permissions needed to apply a configuration, keyed by the Google resource name
+/// they have to be tested on (`organizations/1`, `folders/2`, `projects/x`, `billingAccounts/y`).
+#[derive(Debug, Default)]
+struct PreflightPlan {
+    scopes: BTreeMap<String, ScopeCheck>,
+    constraints: Vec<ConstraintCheck>,
+    /// New projects to be linked per billing account (for the project-count quota)
+    new_billing_links: BTreeMap<String, usize>,
+    /// Existing projects, project ID -> planned parent (`None` below a folder that is created);
+    /// liens are checked if the project moves
+    existing_projects: BTreeMap<String, Option<String>>,
+    /// Projects of the previously generated HCL that are no longer configured; liens block their deletion
+    deleted_projects: BTreeSet<String>,
+    /// IDs of all configured projects
+    project_ids: BTreeSet<String>,
+    /// Named billing accounts (`billing_accounts:`), name -> ID
+    billing_accounts: HashMap<String, String>,
+}
+
+impl PreflightPlan {
+    fn require(&mut self, scope: &str, reason: &str, permission: &str) {
+        let entry = self.scopes.entry(scope.to_string()).or_default();
+        entry.reason.insert(reason.to_string());
+        entry.permissions.insert(permission.to_string());
+    }
+
+    /// `billingAccounts/<id>` for a billing account ID or a name declared in `billing_accounts:`.
+    fn billing_scope(&self, name_or_id: &str) -> String {
+        let id = self.billing_accounts.get(name_or_id).map(|s| s.as_str()).unwrap_or(name_or_id);
+        format!("billingAccounts/{}", id)
+    }
+
+    /// Billing IAM declared inside a folder or project targets the scope's billing account.
+    fn require_billing_iam(&mut self, extra: &HashMap<String, serde_yaml::Value>, default_billing: Option<&str>) {
+        for key in ["billing_account_iam_member", "google_billing_account_iam_member"] {
+            let Some(billing) = extra.get(key) else { continue };
+            let id = billing.get("billing_account_id").and_then(|v| v.as_str()).or(default_billing);
+            if let Some(id) = id {
+                let scope = self.billing_scope(id);
+                self.require(&scope, "billing IAM", "billing.accounts.setIamPolicy");
+            }
+        }
+    }
+}
+
+/// Permission needed to create a resource of the given type inside a project, if known.
+fn create_permission(resource_type: &str) -> Option<&'static str> {
+    let t = resource_type.strip_prefix("google_").unwrap_or(resource_type);
+    match t {
+        "storage_bucket" => Some("storage.buckets.create"),
+        "service_account" => Some("iam.serviceAccounts.create"),
+        "project_service" => Some("serviceusage.services.enable"),
+        "compute_network" => Some("compute.networks.create"),
+        "compute_subnetwork" => Some("compute.subnetworks.create"),
+        "kms_key_ring" => Some("cloudkms.keyRings.create"),
+        "pubsub_topic" => Some("pubsub.topics.create"),
+        "bigquery_dataset" => Some("bigquery.datasets.create"),
+        "logging_project_sink" => Some("logging.sinks.create"),
+        _ => None,
+    }
+}
+
+/// Org policy constraints that would block creating a resource type. The third element is the
+/// attribute whose value must be allowed for list constraints (`None` for boolean constraints).
+const BLOCKING_CONSTRAINTS: &[(&str, &str, Option<&str>)] = &[
+    ("service_account_key", "iam.disableServiceAccountKeyCreation", None),
+    ("service_account", "iam.disableServiceAccountCreation", None),
+    ("storage_bucket", "gcp.resourceLocations", Some("location")),
+    ("compute_subnetwork", "gcp.resourceLocations", Some("region")),
+    ("bigquery_dataset", "gcp.resourceLocations", Some("location")),
+];
+
+/// Normalizes a folder import-id (`folders/123` or `123`) to a resource name.
+fn folder_resource_name(import_id: &str) -> String {
+    if import_id.starts_with("folders/") {
+        import_id.to_string()
+    } else {
+        format!("folders/{}", import_id)
+    }
+}
+
+/// The parent a project is placed in: an explicit literal `folder_id`/`org_id`, otherwise its
+/// direct parent if that exists. `None` if the parent is a folder that is created.
+fn planned_parent(project: &Project, direct_parent: Option<&str>) -> Option<String> {
+    let literal = |key: &str| project.extra.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    if let Some(folder) = literal("folder_id") {
+        let id = folder.trim_start_matches("folders/");
+        return (!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then(|| format!("folders/{}", id));
+    }
+    if let Some(org) = literal("org_id") {
+        return Some(format!("organizations/{}", org.trim_start_matches("organizations/")));
+    }
+    direct_parent.map(|p| p.to_string())
+}
+
+fn build_plan(config: &Config, generated_projects: &BTreeSet<String>) -> Result<PreflightPlan, Box<dyn std::error::Error>> {
+    let mut plan = PreflightPlan {
+        billing_accounts: config.billing_accounts.clone().unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let org_id = config.extra.get("customer-organization-id")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'customer-organization-id' in configuration (required for preflight)")?;
+    let org = format!("organizations/{}", org_id);
+
+    if config.org_policy_policy.as_ref().is_some_and(|p| !p.is_empty()) {
+        plan.require(&org, "org policies", "orgpolicy.policies.create");
+    }
+    if config.organization_iam_member.as_ref().is_some_and(|m| !m.is_empty()) {
+        plan.require(&org, "organization IAM", "resourcemanager.organizations.setIamPolicy");
+    }
+
+    let default_billing = config.defaults.as_ref().and_then(|d| d.billing_account.clone())
+        .or_else(|| config.extra.get("billing-account-infra").and_then(|v| v.as_str()).map(|s| s.to_string()));
+    if let Some(serde_yaml::Value::Mapping(billing)) = &config.billing_account_iam_member {
+        if let Some(id) = billing.get("billing_account_id").and_then(|v| v.as_str()).map(|s| s.to_string()).or(default_billing.clone()) {
+            let scope = plan.billing_scope(&id);
+            plan.require(&scope, "billing IAM", "billing.accounts.setIamPolicy");
+        }
+    }
+
+    add_extra_permissions(&mut plan, &org, "organization", &config.extra);
+
+    if let Some(folders) = &config.folder {
+        for (key, folder) in folders {
+            add_folder(&mut plan, key, folder, &org, default_billing.as_deref());
+        }
+    }
+    if let Some(projects) = &config.project {
+        for project in projects.values() {
+            add_project(&mut plan, project, &org, Some(&org), default_billing.as_deref());
+        }
+    }
+    plan.deleted_projects = generated_projects.difference(&plan.project_ids).cloned().collect();
+
+    Ok(plan)
+}
+
+/// `existing_parent` is the nearest ancestor that already exists; permissions for resources
+/// that will be created are tested there, as IAM grants are inherited down the hierarchy.
+fn add_folder(plan: &mut PreflightPlan, key: &str, folder: &Folder, existing_parent: &str, default_billing: Option<&str>) {
+    let scope = match &folder.import_id {
+        Some(id) => folder_resource_name(id),
+        None => {
+            plan.require(existing_parent, &format!("create folder '{}'", key), "resourcemanager.folders.create");
+            existing_parent.to_string()
+        }
+    };
+
+    if folder.extra.keys().any(|k| (k.contains("iam_member") || k.contains("iam_binding")) && !k.contains("billing_account")) {
+        plan.require(&scope, &format!("IAM on folder '{}'", key), "resourcemanager.folders.setIamPolicy");
+    }
+    add_extra_permissions(plan, &scope, &format!("folder '{}'", key), &folder.extra);
+
+    let default_billing = folder.defaults.as_ref().and_then(|d| d.billing_account.as_deref()).or(default_billing);
+    plan.require_billing_iam(&folder.extra, default_billing);
+    if let Some(folders) = &folder.folder {
+        for (sub_key, sub) in folders {
+            add_folder(plan, sub_key, sub, &scope, default_billing);
+        }
+    }
+    if let Some(projects) = &folder.project {
+        let direct_parent = folder.import_id.is_some().then_some(scope.as_str());
+        for project in projects.values() {
+            add_project(plan, project, &scope, direct_parent, default_billing);
+        }
+    }
+}
+
+/// `direct_parent` is the folder or organization the project is placed in if that already exists.
+fn add_project(plan: &mut PreflightPlan, project: &Project, existing_parent: &str, direct_parent: Option<&str>, default_billing: Option<&str>) {
+    plan.project_ids.insert(project.project_id.clone());
+    let scope = if project.import_id.is_some() {
+        plan.existing_projects.insert(project.project_id.clone(), planned_parent(project, direct_parent));
+        format!("projects/{}", project.project_id)
+    } else {
+        plan.require(existing_parent, &format!("create project '{}'", project.project_id), "resourcemanager.projects.create");
+        existing_parent.to_string()
+    };
+    let label = format!("project '{}'", project.project_id);
+
+    if let Some(billing) = project.billing_account.as_deref().or(default_billing) {
+        let billing_scope = plan.billing_scope(billing);
+        plan.require(&billing_scope, &format!("link billing for '{}'", project.project_id), "billing.resourceAssociations.create");
+        if project.import_id.is_none() {
+            plan.require(&scope, &label, "resourcemanager.projects.createBillingAssignment");
+            let id = billing_scope.trim_start_matches("billingAccounts/").to_string();
+            *plan.new_billing_links.entry(id).or_default() += 1;
+        }
+    }
+    plan.require_billing_iam(&project.extra, default_billing);
+
+    if let Some(services) = &project.project_service {
+        if !services.is_empty() {
+            plan.require(&scope, &label, "serviceusage.services.enable");
+        }
+        for service in services.iter().flat_map(service_names) {
+            plan.constraints.push(ConstraintCheck {
+                resource: format!("google_project_service.{} ({})", service, label),
+                scope: scope.clone(),
+                constraint: "gcp.restrictServiceUsage",
+                value: Some(service),
+            });
+        }
+    }
+    if project.extra.keys().any(|k| (k.contains("iam_member") || k.contains("iam_binding")) && !k.contains("billing_account")) {
+        plan.require(&scope, &label, "resourcemanager.projects.setIamPolicy");
+    }
+    add_extra_permissions(plan, &scope, &label, &project.extra);
+}
+
+fn add_extra_permissions(plan: &mut PreflightPlan, scope: &str, label: &str, extra: &HashMap<String, serde_yaml::Value>) {
+    for (resource_type, value) in extra {
+        if let Some(permission) = create_permission(resource_type) {
+            plan.require(scope, label, permission);
+        }
+
+        let short_type = resource_type.strip_prefix("google_").unwrap_or(resource_type);
+        let Some(instances) = value.as_mapping() else { continue };
+        for (t, constraint, attribute) in BLOCKING_CONSTRAINTS {
+            if *t != short_type {
+                continue;
+            }
+            for (name, attrs) in instances {
+                let value = match attribute {
+                    Some(attr) => match attrs.get(*attr).and_then(|v| v.as_str()) {
+                        Some(v) => Some(v.to_string()),
+                        None => continue,
+                    },
+                    None => None,
+                };
+                plan.constraints.push(ConstraintCheck {
+                    resource: format!("google_{}.{} ({})", short_type, name.as_str().unwrap_or(""), label),
+                    scope: scope.to_string(),
+                    constraint,
+                    value,
+                });
+            }
+        }
+    }
+}
+
+/// Service names from a `project_service` entry (plain string, flat or nested mapping).
+fn service_names(service_val: &serde_yaml::Value) -> Vec<String> {
+    match service_val {
+        serde_yaml::Value::String(s) => vec![s.clone()],
+        serde_yaml::Value::Mapping(m) => match m.get("service").and_then(|v| v.as_str()) {
+            Some(s) => vec![s.to_string()],
+            None => m.keys().filter_map(|k| k.as_str()).map(|s| s.to_string()).collect(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Calls testIamPermissions for one scope and returns the granted subset.
+async fn test_permissions(client: &reqwest::Client, token: &str, scope: &str, permissions: &BTreeSet<String>) -> Result<BTreeSet<String>, Box<dyn std::error::Error>> {
+    let url = if scope.starts_with("billingAccounts/") {
+        format!("{}/v1/{}:testIamPermissions", crate::endpoints::base("cloudbilling"), scope)
+    } else {
+        format!("{}/v3/{}:testIamPermissions", crate::endpoints::base("cloudresourcemanager"), scope)
+    };
+
+    let res = client.post(&url)
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "permissions": permissions }))
+        .send()
+        .await?;
+
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(format!("testIamPermissions on {} failed ({}): {}", scope, status, body.trim()).into());
+    }
+
+    let body: serde_json::Value = res.json().await?;
+    Ok(body.get("permissions")
+        .and_then(|p| p.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()).collect())
+        .unwrap_or_default())
+}
+
+/// The effective org policy for a constraint on a resource, reduced to what preflight needs.
+#[derive(Debug, Default)]
+struct EffectivePolicy {
+    enforced: bool,
+    deny_all: bool,
+    allow_all: bool,
+    allowed_values: Vec<String>,
+    denied_values: Vec<String>,
+}
+
+impl EffectivePolicy {
+    /// Returns a reason if the policy blocks the given value (or the resource for boolean constraints).
+    fn blocks(&self, value: Option<&str>) -> Option<String> {
+        let Some(value) = value else {
+            return self.enforced.then(|| "constraint is enforced".to_string());
+        };
+        let matches = |candidates: &[String]| candidates.iter().any(|c| {
+            let c = c.strip_prefix("is:").unwrap_or(c);
+            c.eq_ignore_ascii_case(value)
+        });
+        if self.deny_all {
+            return Some("all values are denied".to_string());
+        }
+        if matches(&self.denied_values) {
+            return Some(format!("'{}' is denied", value));
+        }
+        // Value groups (in:eu-locations) cannot be resolved locally, so only plain allow lists are checked
+        if !self.allow_all
+            && !self.allowed_values.is_empty()
+            && self.allowed_values.iter().all(|v| !v.starts_with("in:"))
+            && !matches(&self.allowed_values)
+        {
+            return Some(format!("'{}' is not in the allowed values [{}]", value, self.allowed_values.join(", ")));
+        }
+        None
+    }
+}
+
+async fn get_effective_policy(client: &reqwest::Client, token: &str, scope: &str, constraint: &str) -> Result<EffectivePolicy, Box<dyn std::error::Error>> {
+    let url = format!("{}/v2/{}/policies/{}:getEffectivePolicy", crate::endpoints::base("orgpolicy"), scope, constraint);
+    let res = client.get(&url).bearer_auth(token).send().await?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(format!("getEffectivePolicy {} on {} failed ({}): {}", constraint, scope, status, body.trim()).into());
+    }
+
+    let body: serde_json::Value = res.json().await?;
+    let mut policy = EffectivePolicy::default();
+    let rules = body.pointer("/spec/rules").and_then(|r| r.as_array()).cloned().unwrap_or_default();
+    for rule in rules {
+        // Conditional rules only apply to some resources; treat them as not blocking
+        if rule.get("condition").is_some() {
+            continue;
+        }
+        policy.enforced |= rule.get("enforce").and_then(|v| v.as_bool()).unwrap_or(false);
+        policy.deny_all |= rule.get("denyAll").and_then(|v| v.as_bool()).unwrap_or(false);
+        policy.allow_all |= rule.get("allowAll").and_then(|v| v.as_bool()).unwrap_or(false);
+        for (key, target) in [("allowedValues", &mut policy.allowed_values), ("deniedValues", &mut policy.denied_values)] {
+            if let Some(values) = rule.pointer(&format!("/values/{}", key)).and_then(|v| v.as_array()) {
+                target.extend(values.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()));
+            }
+        }
+    }
+    Ok(policy)
+}
+
+/// Counts the projects currently linked to a billing account.
+async fn count_billing_projects(client: &reqwest::Client, token: &str, billing_account: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut count = 0;
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut url = format!("{}/v1/billingAccounts/{}/projects?pageSize=200", crate::endpoints::base("cloudbilling"), billing_account);
+        if let Some(t) = &page_token {
+            url.push_str(&format!("&pageToken={}", t));
+        }
+        let res = client.get(&url).bearer_auth(token).send().await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(format!("listing projects of billing account {} failed ({}): {}", billing_account, status, body.trim()).into());
+        }
+        let body: serde_json::Value = res.json().await?;
+        count += body.get("projectBillingInfo")
+            .and_then(|p| p.as_array())
+            .map(|arr| arr.iter().filter(|p| p.get("billingEnabled").and_then(|b| b.as_bool()).unwrap_or(false)).count())
+            .unwrap_or(0);
+        page_token = body.get("nextPageToken").and_then(|t| t.as_str()).filter(|t| !t.is_empty()).map(|t| t.to_string());
+        if page_token.is_none() {
+            return Ok(count);
+        }
+    }
+}
+
+/// The current parent of a project (`folders/1` or `organizations/2`).
+async fn get_project_parent(client: &reqwest::Client, token: &str, project_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let url = format!("{}/v3/projects/{}", crate::endpoints::base("cloudresourcemanager"), project_id);
+    let res = client.get(&url).bearer_auth(token).send().await?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(format!("reading project {} failed ({}): {}", project_id, status, body.trim()).into());
+    }
+    let body: serde_json::Value = res.json().await?;
+    body.get("parent").and_then(|p| p.as_str()).map(|p| p.to_string())
+        .ok_or_else(|| format!("project {} has no parent", project_id).into())
+}
+
+/// Returns the reasons of all liens placed on a project.
+async fn list_liens(client: &reqwest::Client, token: &str, project_id: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let url = format!("{}/v3/liens?parent=projects/{}", crate::endpoints::base("cloudresourcemanager"), project_id);
+    let res = client.get(&url).bearer_auth(token).send().await?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(format!("listing liens of project {} failed ({}): {}", project_id, status, body.trim()).into());
+    }
+    let body: serde_json::Value = res.json().await?;
+    Ok(body.get("liens")
+        .and_then(|l| l.as_array())
+        .map(|arr| arr.iter().map(|l| {
+            let reason = l.get("reason").and_then(|r| r.as_str()).unwrap_or("no reason given");
+            let origin = l.get("origin").and_then(|o| o.as_str()).unwrap_or("unknown origin");
+            format!("{} ({})", reason, origin)
+        }).collect())
+        .unwrap_or_default())
+}
+
+/// Checks that the caller holds the permissions needed to bootstrap/apply the configuration
+/// and that no quota, org policy constraint or lien blocks the planned resources, then prints
+/// a readiness report. Fails if any permission is missing or any blocker is found.
+/// `generated_projects` are the project IDs of the previously generated HCL; those no longer
+/// configured are deleted by the next apply.
+pub async fn preflight(config: &Config, generated_projects: &BTreeSet<String>, dry_run: bool, billing_project_quota: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let plan = build_plan(config, generated_projects)?;
+
+    if dry_run {
+        println!("--- Preflight Plan ---");
+        for (scope, check) in &plan.scopes {
+            println!("{} ({})", scope, check.reason.iter().cloned().collect::<Vec<_>>().join(", "));
+            for permission in &check.permissions {
+                println!("  - {}", permission);
+            }
+        }
+        if !plan.constraints.is_empty() {
+            println!("Org policy constraints:");
+            for check in &plan.constraints {
+                println!("  - {} on {} for {}", check.constraint, check.scope, check.resource);
+            }
+        }
+        for (billing, new_projects) in &plan.new_billing_links {
+            println!("Project quota: {} new project(s) on billingAccounts/{}", new_projects, billing);
+        }
+        for (project, parent) in &plan.existing_projects {
+            match parent {
+                Some(parent) => println!("Liens: projects/{} (if not already in {})", project, parent),
+                None => println!("Liens: projects/{} (moves to a new folder)", project),
+            }
+        }
+        for project in &plan.deleted_projects {
+            println!("Liens: projects/{} (deleted)", project);
+        }
+        println!("----------------------");
+        println!("Dry run enabled. Nothing was checked.");
+        return Ok(());
+    }
+
+    status!("Authenticating using Application Default Credentials...");
+    let scopes = ["https://www.googleapis.com/auth/cloud-platform"];
+    let credentials = Builder::default()
+        .with_scopes(scopes)
+        .build_access_token_credentials()?;
+    let token = credentials.access_token().await?;
+    let client = reqwest::Client::new();
+
+    let mut total = 0;
+    let mut missing = 0;
+
+    println!("--- Preflight Report ---");
+    for (scope, check) in &plan.scopes {
+        println!("{} ({})", scope, check.reason.iter().cloned().collect::<Vec<_>>().join(", "));
+        total += check.permissions.len();
+        match test_permissions(&client, &token.token, scope, &check.permissions).await {
+            Ok(granted) => {
+                for permission in &check.permissions {
+                    if granted.contains(permission) {
+                        println!("  {} {}", crate::term::icon("✅", "[ok]"), permission);
+                    } else {
+                        println!("  {} {}", crate::term::icon("❌", "[missing]"), permission);
+                        missing += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                println!("  {} {}", crate::term::icon("⚠️ ", "[error]"), e);
+                for permission in &check.permissions {
+                    println!("  {} {}", crate::term::icon("❓", "[?]"), permission);
+                }
+                missing += check.permissions.len();
+            }
+        }
+    }
+
+    // Blockers are grouped by the planned resource they affect
+    let mut blockers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    let mut policy_cache: HashMap<(String, &str), Result<EffectivePolicy, String>> = HashMap::new();
+    for check in &plan.constraints {
+        let key = (check.scope.clone(), check.constraint);
+        if !policy_cache.contains_key(&key) {
+            let policy = get_effective_policy(&client, &token.token, &check.scope, check.constraint).await.map_err(|e| e.to_string());
+            policy_cache.insert(key.clone(), policy);
+        }
+        match &policy_cache[&key] {
+            Ok(policy) => {
+                if let Some(reason) = policy.blocks(check.value.as_deref()) {
+                    blockers.entry(check.resource.clone()).or_default().push(format!("org policy {}: {}", check.constraint, reason));
+                }
+            }
+            Err(e) => blockers.entry(check.resource.clone()).or_default().push(format!("could not check {}: {}", check.constraint, e)),
+        }
+    }
+
+    for (billing, new_projects) in &plan.new_billing_links {
+        let resource = format!("billingAccounts/{}", billing);
+        match count_billing_projects(&client, &token.token, billing).await {
+            Ok(linked) => {
+                println!("{}: {} linked project(s), {} new", resource, linked, new_projects);
+                if let Some(quota) = billing_project_quota {
+                    if linked + new_projects > quota {
+                        blockers.entry(resource).or_default().push(format!(
+                            "project quota exceeded: {} linked + {} new > {} allowed",
+                            linked, new_projects, quota
+                        ));
+                    }
+                }
+            }
+            Err(e) => blockers.entry(resource).or_default().push(format!("could not check project quota: {}", e)),
+        }
+    }
+
+    let mut lien_checks = Vec::new();
+    for (project, planned) in &plan.existing_projects {
+        let resource = format!("google_project ({})", project);
+        match get_project_parent(&client, &token.token, project).await {
+            Ok(current) if planned.as_deref() == Some(current.as_str()) => {}
+            Ok(current) => lien_checks.push((project, resource, format!("move from {} to {}", current, planned.as_deref().unwrap_or("a new folder")))),
+            Err(e) => blockers.entry(resource).or_default().push(format!("could not check for a move: {}", e)),
+        }
+    }
+    for project in &plan.deleted_projects {
+        lien_checks.push((project, format!("google_project ({})", project), "deletion".to_string()));
+    }
+    for (project, resource, change) in lien_checks {
+        match list_liens(&client, &token.token, project).await {
+            Ok(liens) => {
+                for lien in liens {
+                    blockers.entry(resource.clone()).or_default().push(format!("lien prevents {}: {}", change, lien));
+                }
+            }
+            Err(e) => blockers.entry(resource).or_default().push(format!("could not check liens: {}", e)),
+        }
+    }
+
+    println!("------------------------");
+    if !blockers.is_empty() {
+        println!("Blockers:");
+        for (resource, reasons) in &blockers {
+            println!("  {}", resource);
+            for reason in reasons {
+                println!("    {} {}", crate::term::icon("❌", "[blocker]"), reason);
+            }
+        }
+        println!("------------------------");
+    }
+    println!("Ready: {}/{} permissions granted, {} blocked resource(s)", total - missing, total, blockers.len());
+
+    if missing > 0 || !blockers.is_empty() {
+        return Err(format!("Preflight failed: {} permission(s) missing or not checkable, {} blocked resource(s)", missing, blockers.len()).into());
+    }
+    println!("{}All required permissions are granted and no blockers were found.", crate::term::icon("✅ ", ""));
+    Ok(())
+}
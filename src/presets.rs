@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Presets shipped with the binary, the last place a preset is looked for. The discovery config is
+/// left out for its size; it has `discovery_config` and `--discovery-config` instead.
+const BUILTIN: &[(&str, &str)] = &[
+    ("CIS-GCP-Foundation-4.0.yaml", include_str!("../presets/CIS-GCP-Foundation-4.0.yaml")),
+    ("essential-contacts-organization-1.0.yaml", include_str!("../presets/essential-contacts-organization-1.0.yaml")),
+    ("organization-budget.yaml", include_str!("../presets/organization-budget.yaml")),
+    ("security-group-models/s1-group-definitions-1.0.yaml", include_str!("../presets/security-group-models/s1-group-definitions-1.0.yaml")),
+    ("security-group-models/s1-group-permissions-1.0.yaml", include_str!("../presets/security-group-models/s1-group-permissions-1.0.yaml")),
+];
+
+static SEARCH_PATH: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// Sets the directories presets are looked for in: `preset_dirs` of config.toml (project-local
+/// overrides) in their order, then `yaml_dir/presets` (written by `get-presets`).
+pub fn init(preset_dirs: &[PathBuf], yaml_dir: &Path) {
+    let dirs = preset_dirs.iter().cloned().chain([yaml_dir.join("presets")]).collect();
+    let _ = SEARCH_PATH.set(dirs);
+}
+
+/// The first file `name` (relative to a presets directory, e.g. `organization-budget.yaml`) in
+/// the preset directories.
+pub fn find(name: &str) -> Option<PathBuf> {
+    SEARCH_PATH.get()?.iter().map(|dir| dir.join(name)).find(|path| path.is_file())
+}
+
+/// The built-in preset `name`, extracted to the cache directory of this version so that it can be
+/// included like any other file.
+pub fn builtin(name: &str) -> Option<PathBuf> {
+    let (_, content) = BUILTIN.iter().find(|(builtin, _)| *builtin == name)?;
+    let cache = std::env::var_os("XDG_CACHE_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    let path = cache.join("cfg2hcl").join(format!("presets-{}", env!("CARGO_PKG_VERSION"))).join(name);
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(*content) {
+        std::fs::create_dir_all(path.parent()?).ok()?;
+        std::fs::write(&path, content).ok()?;
+    }
+    Some(path)
+}
+
+/// Manifest of a preset download in the presets directory: the release the presets came from and
+/// the hash of every file as downloaded, the base to tell local from upstream changes.
+pub const MANIFEST_FILE: &str = ".cfg2hcl-presets.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Release tag (`v0.6.2`), or `main` for a repository without releases
+    version: String,
+    /// Path relative to the presets directory -> SHA-256 of the downloaded content
+    files: BTreeMap<String, String>,
+}
+
+/// What `get-presets` does with the upstream presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Write every upstream file, replacing local ones
+    Download,
+    /// Only report the differences
+    Check,
+    /// Write upstream changes with a diff; keep local modifications
+    Update,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Unchanged,
+    /// Not there locally
+    New,
+    /// Changed upstream, not locally
+    ChangedUpstream,
+    /// Changed locally, not upstream
+    ModifiedLocally,
+    /// Changed locally and upstream, or different without a recorded download to compare with
+    Conflict,
+}
+
+impl Status {
+    fn describe(self) -> &'static str {
+        match self {
+            Status::Unchanged => "unchanged",
+            Status::New => "new upstream",
+            Status::ChangedUpstream => "changed upstream",
+            Status::ModifiedLocally => "modified locally",
+            Status::Conflict => "conflict",
+        }
+    }
+}
+
+/// Compares the upstream presets of `version` (path relative to the presets directory -> content)
+/// with the ones in `dir` and, unless checking, writes them according to `mode` and records the
+/// download in [`MANIFEST_FILE`]. With [`Mode::Update`], the upstream version of a locally modified
+/// file that also changed upstream is written next to it as `<file>.upstream`.
+pub fn sync(dir: &Path, version: &str, upstream: &BTreeMap<String, Vec<u8>>, mode: Mode) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let previous: Manifest = match std::fs::read_to_string(&manifest_path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse '{}': {}", manifest_path.display(), e))?,
+        Err(_) => Manifest::default(),
+    };
+    if !previous.version.is_empty() {
+        println!("Presets: local {}, upstream {}", previous.version, version);
+    }
+
+    let mut differences = 0u32;
+    let mut upstream_changes = 0u32;
+    let mut written = 0u32;
+    let mut conflicts = 0u32;
+    for (path, content) in upstream {
+        let local_path = dir.join(path);
+        let local = std::fs::read(&local_path).ok();
+        let upstream_hash = sha256(content);
+        let status = match &local {
+            None => Status::New,
+            Some(local) if sha256(local) == upstream_hash => Status::Unchanged,
+            Some(local) => match previous.files.get(path) {
+                Some(base) if *base == sha256(local) => Status::ChangedUpstream,
+                Some(base) if *base == upstream_hash => Status::ModifiedLocally,
+                _ => Status::Conflict,
+            },
+        };
+        if status == Status::Unchanged {
+            continue;
+        }
+        differences += 1;
+        if status != Status::ModifiedLocally {
+            upstream_changes += 1;
+        }
+        if mode != Mode::Download {
+            println!("  {:<17} {}", status.describe(), path);
+        } else if matches!(status, Status::ModifiedLocally | Status::Conflict) {
+            println!("  replacing local changes of {}", path);
+        }
+        if mode == Mode::Check || (mode == Mode::Update && status == Status::ModifiedLocally) {
+            continue;
+        }
+        if mode == Mode::Update {
+            let old = local.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+            print!("{}", crate::yaml_edit::diff(&format!("presets/{}", path), &old, &String::from_utf8_lossy(content)));
+        }
+        let target = if mode == Mode::Update && status == Status::Conflict {
+            conflicts += 1;
+            local_path.with_file_name(format!("{}.upstream", local_path.file_name().unwrap_or_default().to_string_lossy()))
+        } else {
+            local_path
+        };
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, content).map_err(|e| format!("Failed to write '{}': {}", target.display(), e))?;
+        written += 1;
+    }
+    let removed: Vec<&String> = previous.files.keys().filter(|path| !upstream.contains_key(*path)).collect();
+    for path in &removed {
+        println!("  {:<17} {} (kept)", "removed upstream", path);
+    }
+
+    match mode {
+        Mode::Check => {
+            if differences == 0 && removed.is_empty() {
+                println!("Presets in {} are up to date with {}", dir.display(), version);
+            } else if upstream_changes == 0 {
+                println!("{} preset file(s) differ from {} by local changes only", differences, version);
+            } else {
+                println!("{} preset file(s) differ from {}; run `cfg2hcl get-presets --update` to apply the upstream changes", differences, version);
+            }
+            return Ok(());
+        }
+        Mode::Download => println!("Downloaded {} preset file(s) of {} to {}", upstream.len(), version, dir.display()),
+        Mode::Update => {
+            println!("Updated {} preset file(s) to {} in {}", written - conflicts, version, dir.display());
+            if conflicts > 0 {
+                println!("{} file(s) changed locally and upstream keep their local content; merge the .upstream files next to them by hand.", conflicts);
+            }
+        }
+    }
+    let manifest = Manifest {
+        version: version.to_string(),
+        files: upstream.iter().map(|(path, content)| (path.clone(), sha256(content))).collect(),
+    };
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .map_err(|e| format!("Failed to write '{}': {}", manifest_path.display(), e))?;
+    Ok(())
+}
+
+fn sha256(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
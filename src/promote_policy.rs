@@ -0,0 +1,59 @@
+use crate::yaml_edit::{child, entries, indentation, is_content, mapping_key, Entry};
+
+/// Keys under which `google_org_policy_policy` entries are written.
+pub const POLICY_TYPES: &[&str] = &["google_org_policy_policy", "org_policy_policy"];
+
+/// Moves the rules of a policy from `dry_run_spec` to `spec` (`promote-policy`): the previous
+/// `spec` (with the comments directly above it) is removed and `dry_run_spec:` becomes `spec:`,
+/// so that comments, anchors and the formatting of the rules stay as they are. The policy is
+/// found by its YAML key or by its `name` (`gcp.resourceLocations` or
+/// `.../policies/gcp.resourceLocations`). Returns `None` if the text has no such policy.
+pub fn promote(content: &str, policy: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let matches: Vec<Entry> = entries(&lines, POLICY_TYPES).into_iter().filter(|e| e.key == policy || name_matches(&lines[e.line + 1..e.end], policy)).collect();
+    let entry = match matches.as_slice() {
+        [] => return Ok(None),
+        [entry] => entry,
+        _ => return Err(format!("'{}' matches several policies (lines {}); use the YAML key", policy, matches.iter().map(|e| (e.line + 1).to_string()).collect::<Vec<_>>().join(", ")).into()),
+    };
+
+    let Some(dry_run) = child(&lines, entry, "dry_run_spec") else {
+        return Err(format!("Policy '{}' (line {}) has no dry_run_spec to promote", entry.key, entry.line + 1).into());
+    };
+    // With the comment lines directly above it, which describe the rules being replaced
+    let spec = child(&lines, entry, "spec").map(|(start, end)| {
+        let comments = lines[entry.line + 1..start].iter().rev()
+            .take_while(|l| l.trim_start().starts_with('#') && indentation(l) == indentation(lines[start]))
+            .count();
+        (start - comments, end)
+    });
+
+    let mut promoted: Vec<String> = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if spec.as_ref().is_some_and(|(start, end)| (*start..*end).contains(&idx)) {
+            continue;
+        }
+        if idx == dry_run.0 {
+            promoted.push(line.replacen("dry_run_spec:", "spec:", 1));
+        } else {
+            promoted.push(line.to_string());
+        }
+    }
+    let mut result = promoted.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(Some(result))
+}
+
+/// Whether the `name` in the body of an entry is the policy.
+fn name_matches(body: &[&str], policy: &str) -> bool {
+    let Some(indent) = body.iter().find(|l| is_content(l)).map(|l| indentation(l)) else { return false };
+    body.iter()
+        .filter(|l| is_content(l) && indentation(l) == indent)
+        .filter_map(|l| mapping_key(l))
+        .any(|(key, value)| {
+            let value = value.trim_matches(|c| c == '"' || c == '\'');
+            key == "name" && (value == policy || value.ends_with(&format!("/policies/{}", policy)))
+        })
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::manifest::STATE_DIR;
+use crate::transpiler::GeneratedProject;
+
+/// Where each generated resource came from. Written by `transpile` as
+/// `.cfg2hcl/provenance.json`, used to point findings on the HCL back to the YAML.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Provenance {
+    /// Input file as given on the command line
+    pub input: String,
+    /// Resource address -> source
+    pub resources: BTreeMap<String, Source>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Source {
+    /// Dotted YAML path, e.g. `folder.infra.project.app.google_storage_bucket.logs`
+    pub yaml_path: String,
+    /// File and 1-based line of the entry, if it could be located (includes are followed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// From the narrowest enclosing `owner:`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+impl Source {
+    /// `file:line (yaml.path)`, or only the YAML path if the entry was not located.
+    pub fn describe(&self) -> String {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!("{}:{} ({})", file.display(), line, self.yaml_path),
+            _ => self.yaml_path.clone(),
+        }
+    }
+}
+
+impl Provenance {
+    /// Locates the YAML paths recorded by the transpiler in the include-expanded `content`.
+    pub fn new(input: &str, project: &GeneratedProject, content: &str, source_map: &crate::include_processor::SourceMap) -> Self {
+        let resources = project.sources.iter().map(|(address, yaml_path)| {
+            let location = locate(content, yaml_path).map(|idx| source_map.location(idx));
+            let source = Source {
+                yaml_path: yaml_path.clone(),
+                file: location.map(|(file, _)| file.to_path_buf()),
+                line: location.map(|(_, line)| line),
+                owners: crate::owners::owners_of(&project.owners, yaml_path).to_vec(),
+            };
+            (address.clone(), source)
+        }).collect();
+        Self { input: input.to_string(), resources }
+    }
+
+    /// Number of resources per owner.
+    pub fn owner_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for owner in self.resources.values().flat_map(|s| &s.owners) {
+            *counts.entry(owner.clone()).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(STATE_DIR).join("provenance.json")
+    }
+
+    /// Loads the provenance of the last transpile, if there was one.
+    pub fn load(config_dir: &Path) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let path = Self::path(config_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read provenance '{}': {}", path.display(), e))?;
+        Ok(Some(serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse provenance '{}': {}", path.display(), e))?))
+    }
+
+    pub fn save(&self, config_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path(config_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .map_err(|e| format!("Failed to write provenance '{}': {}", path.display(), e))?;
+        Ok(())
+    }
+}
+
+/// The top-level blocks of a generated HCL file: 1-based start line and, for `resource` blocks,
+/// the address.
+pub fn top_level_blocks(content: &str) -> Vec<(usize, Option<String>)> {
+    content.lines().enumerate()
+        // Top-level blocks start in the first column
+        .filter(|(_, text)| text.starts_with(|c: char| c.is_alphabetic()))
+        .map(|(idx, text)| {
+            let address = text.strip_prefix("resource ").and_then(|rest| {
+                let mut labels = rest.split('"').filter(|s| !s.trim().is_empty() && s.trim() != "{");
+                Some(format!("{}.{}", labels.next()?, labels.next()?))
+            });
+            (idx + 1, address)
+        })
+        .collect()
+}
+
+/// Line index of the entry at a dotted YAML path, following keys by indentation. Keys may
+/// contain dots themselves (`compute.googleapis.com`), and resource types may be written without
+/// `google_` or with `CEX_` in the YAML.
+pub fn locate(content: &str, yaml_path: &str) -> Option<usize> {
+    let lines: Vec<&str> = content.lines().collect();
+    let segments: Vec<&str> = yaml_path.split('.').collect();
+    let (mut consumed, mut start, mut parent_indent) = (0, 0, None);
+    let mut found = None;
+
+    while consumed < segments.len() {
+        let mut child_indent = None;
+        let mut matched = None;
+        for (idx, line) in lines.iter().enumerate().skip(start) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            if parent_indent.is_some_and(|p| indent <= p) {
+                break;
+            }
+            if *child_indent.get_or_insert(indent) != indent {
+                continue;
+            }
+            let Some((key, _)) = trimmed.trim_start_matches("- ").split_once(':') else { continue };
+            let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+            // Alias keys (`*group-name:`) stand for the value of their anchor
+            let key = key.strip_prefix('*').and_then(|name| anchor_value(content, name)).unwrap_or(key);
+            // Longest run of segments that forms this key
+            let len = (1..=segments.len() - consumed).rev()
+                .find(|&n| key_matches(key, &segments[consumed..consumed + n].join(".")));
+            if let Some(len) = len {
+                matched = Some((idx, indent, len));
+                break;
+            }
+        }
+        let (idx, indent, len) = matched?;
+        found = Some(idx);
+        consumed += len;
+        start = idx + 1;
+        parent_indent = Some(indent);
+    }
+    found
+}
+
+/// Scalar value of `&name value`, e.g. `svc-iac-users` for `&svc-iac-users-group svc-iac-users`.
+fn anchor_value<'a>(content: &'a str, name: &str) -> Option<&'a str> {
+    let marker = format!("&{} ", name);
+    let start = content.find(&marker)? + marker.len();
+    let value = content[start..].lines().next()?.split(" #").next()?.trim();
+    Some(value.trim_matches(|c| c == '"' || c == '\''))
+}
+
+fn key_matches(key: &str, segment: &str) -> bool {
+    key == segment
+        || crate::transpiler::resource_label(key) == segment
+        || segment.strip_prefix("google_").is_some_and(|s| key == s || key.strip_prefix("CEX_").is_some_and(|k| k == s || k == segment))
+}
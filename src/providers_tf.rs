@@ -0,0 +1,55 @@
+/// providers.tf in fixed sections: the `terraform` block, the providers of the YAML and the
+/// providers generated per project and AWS account. Providers are sorted by name and alias,
+/// identical blocks are written once, so that the file does not change with the order of the YAML.
+pub fn render(terraform: Vec<hcl::Block>, mut root: Vec<hcl::Block>, mut project: Vec<hcl::Block>) -> Result<String, Box<dyn std::error::Error>> {
+    root.sort_by_key(sort_key);
+    root.dedup();
+    project.sort_by_key(sort_key);
+    project.dedup();
+
+    let sections = [
+        ("Terraform settings", terraform.into_iter().map(ordered_settings).collect::<Vec<_>>()),
+        ("Providers", root),
+        ("Project and account providers", project),
+    ];
+    let mut rendered = Vec::new();
+    for (header, blocks) in sections {
+        if blocks.is_empty() {
+            continue;
+        }
+        let mut body = hcl::Body::builder();
+        for block in blocks {
+            body = body.add_block(block);
+        }
+        rendered.push(format!("# {}\n\n{}", header, hcl::to_string(&body.build())?));
+    }
+    Ok(rendered.join("\n"))
+}
+
+/// (`google`, `project_infra`)
+fn sort_key(block: &hcl::Block) -> (String, String) {
+    let name = block.labels.first().map(|l| l.as_str().to_string()).unwrap_or_default();
+    let alias = block.body.attributes().find(|a| a.key() == "alias").map(|a| a.expr().to_string()).unwrap_or_default();
+    (name, alias)
+}
+
+/// Settings attributes first, then the backend and `required_providers` sorted by provider name.
+fn ordered_settings(mut block: hcl::Block) -> hcl::Block {
+    let rank = |structure: &hcl::Structure| match structure {
+        hcl::Structure::Attribute(_) => 0,
+        hcl::Structure::Block(b) if b.identifier() == "required_providers" => 2,
+        hcl::Structure::Block(_) => 1,
+    };
+    block.body.0.sort_by_key(rank);
+    for structure in block.body.0.iter_mut() {
+        if let hcl::Structure::Block(b) = structure {
+            if b.identifier() == "required_providers" {
+                b.body.0.sort_by_key(|s| match s {
+                    hcl::Structure::Attribute(a) => a.key().to_string(),
+                    hcl::Structure::Block(b) => b.identifier().to_string(),
+                });
+            }
+        }
+    }
+    block
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use regex::Regex;
+use serde_yaml::Value;
+
+/// Domains of Google-managed identities; emails in them keep their domain (but not the project in it).
+const GOOGLE_DOMAINS: &[&str] = &["gserviceaccount.com", "google.com", "googleapis.com", "cloudbuild.gserviceaccount.com"];
+
+/// Kinds of identifying values, in the order they are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Organization,
+    Folder,
+    Customer,
+    BillingAccount,
+    Project,
+    Domain,
+    Email,
+    ServiceAccount,
+}
+
+impl Kind {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Kind::Organization => "organization ID(s)",
+            Kind::Folder => "folder ID(s)",
+            Kind::Customer => "customer ID(s)",
+            Kind::BillingAccount => "billing account(s)",
+            Kind::Project => "project ID(s)",
+            Kind::Domain => "domain(s)",
+            Kind::Email => "email address(es)",
+            Kind::ServiceAccount => "service account(s)",
+        }
+    }
+
+    fn fake(self, n: usize) -> String {
+        match self {
+            Kind::Organization => format!("{}", 100_000_000_000 + n),
+            Kind::Folder => format!("{}", 200_000_000_000 + n),
+            Kind::Customer => format!("C{:08}", n),
+            Kind::BillingAccount => format!("000000-000000-{:06}", n),
+            Kind::Project => format!("project-{}", n),
+            Kind::Domain => format!("org{}.example", n),
+            Kind::Email => format!("user{}", n),
+            Kind::ServiceAccount => format!("sa-account-{}", n),
+        }
+    }
+}
+
+/// Real values and the fake ones replacing them, numbered per kind in order of discovery.
+#[derive(Default)]
+struct Replacements {
+    values: BTreeMap<String, (Kind, String)>,
+    counts: BTreeMap<Kind, usize>,
+}
+
+impl Replacements {
+    fn add(&mut self, kind: Kind, real: &str) -> String {
+        if let Some((_, fake)) = self.values.get(real) {
+            return fake.clone();
+        }
+        let n = self.counts.entry(kind).or_insert(0);
+        *n += 1;
+        let fake = kind.fake(*n);
+        self.values.insert(real.to_string(), (kind, fake.clone()));
+        fake
+    }
+
+    fn get(&self, real: &str) -> Option<&str> {
+        self.values.get(real).map(|(_, fake)| fake.as_str())
+    }
+}
+
+/// A redacted config and the number of values replaced per kind.
+pub struct Redacted {
+    pub content: String,
+    pub counts: BTreeMap<Kind, usize>,
+}
+
+/// Replaces organization, folder and customer IDs, billing accounts, project IDs, domains and
+/// email addresses in include-expanded YAML with fake values. Each real value always gets the same
+/// fake one, so anchors, references and names built from these values keep matching.
+/// `raw_value` is the parsed YAML, used to find the values by their keys.
+pub fn redact(processed_content: &str, raw_value: &Value) -> Redacted {
+    let (mut contents, counts) = redact_documents(&[processed_content], raw_value);
+    Redacted { content: contents.remove(0), counts }
+}
+
+/// Like [`redact`] for several documents that share one set of fake values, so that a value
+/// replaced in one still matches the same value in the others. Documents that parse as YAML or
+/// JSON (or JSON lines) contribute the values of their keys like `raw_value` does.
+pub fn redact_documents(documents: &[&str], raw_value: &Value) -> (Vec<String>, BTreeMap<Kind, usize>) {
+    let mut replacements = Replacements::default();
+    collect(raw_value, None, &mut replacements);
+    for document in documents {
+        match serde_yaml::from_str::<Value>(document) {
+            Ok(value) => collect(&value, None, &mut replacements),
+            Err(_) => document.lines()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .for_each(|value| collect(&value, None, &mut replacements)),
+        }
+    }
+
+    let documents: Vec<String> = documents.iter()
+        .map(|document| document.lines().map(|line| format!("{}\n", line)).collect())
+        .collect();
+
+    let patterns: [(Kind, &str); 4] = [
+        (Kind::Organization, r"organizations/(\d+)"),
+        (Kind::Folder, r"folders/(\d+)"),
+        (Kind::Customer, r"customers/([A-Za-z0-9]+)"),
+        (Kind::BillingAccount, r"\b([0-9A-F]{6}-[0-9A-F]{6}-[0-9A-F]{6})\b"),
+    ];
+    for (kind, pattern) in patterns {
+        let re = Regex::new(pattern).expect("valid pattern");
+        for content in &documents {
+            for caps in re.captures_iter(content) {
+                replacements.add(kind, &caps[1]);
+            }
+        }
+    }
+
+    // Emails first: their project and domain parts are replaced by the mappings above
+    let email = Regex::new(r"([A-Za-z0-9._%+-]+)@([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,})").expect("valid pattern");
+    let documents: Vec<String> = documents.iter().map(|content| email.replace_all(content, |caps: &regex::Captures| {
+        let (local, domain) = (&caps[1], &caps[2]);
+        if let Some(project) = domain.strip_suffix(".iam.gserviceaccount.com") {
+            let project = replacements.get(project).map(str::to_string).unwrap_or_else(|| replacements.add(Kind::Project, project));
+            let account = replacements.add(Kind::ServiceAccount, local);
+            return format!("{}@{}.iam.gserviceaccount.com", account, project);
+        }
+        if GOOGLE_DOMAINS.iter().any(|d| domain == *d || domain.ends_with(&format!(".{}", d))) {
+            return caps[0].to_string();
+        }
+        let domain = replacements.get(domain).map(str::to_string).unwrap_or_else(|| replacements.add(Kind::Domain, domain));
+        format!("{}@{}", replacements.add(Kind::Email, &format!("{}@", local)), domain)
+    }).into_owned()).collect();
+
+    // Then every other occurrence, longest values first so that no value is cut by a shorter one
+    let mut values: Vec<(&String, &(Kind, String))> = replacements.values.iter()
+        .filter(|(real, (kind, _))| *kind != Kind::Email && !real.is_empty())
+        .collect();
+    values.sort_by_key(|(real, _)| std::cmp::Reverse(real.len()));
+    let documents = documents.into_iter().map(|mut content| {
+        for (real, (kind, fake)) in &values {
+            content = replace_token(&content, real, fake);
+            // Project keys and resource labels are often the project ID with underscores
+            if *kind == Kind::Project && real.contains('-') {
+                content = replace_token(&content, &real.replace('-', "_"), &fake.replace('-', "_"));
+            }
+        }
+        content
+    }).collect();
+
+    (documents, replacements.counts)
+}
+
+/// Identifying values found by their key, e.g. `project_id:` or `customer-domain:`.
+fn collect(value: &Value, key: Option<&str>, replacements: &mut Replacements) {
+    match value {
+        Value::Mapping(map) => {
+            for (k, v) in map {
+                let k = k.as_str();
+                // Named billing accounts: `billing_accounts: { name: ID }`
+                if k == Some("billing_accounts") {
+                    for id in v.as_mapping().into_iter().flat_map(|m| m.values()) {
+                        collect(id, Some("billing_account"), replacements);
+                    }
+                    continue;
+                }
+                collect(v, k, replacements);
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                collect(item, key, replacements);
+            }
+        }
+        Value::Tagged(tagged) => collect(&tagged.value, key, replacements),
+        Value::String(_) | Value::Number(_) => {
+            let Some(kind) = key.and_then(kind_of_key) else { return };
+            let text = match value {
+                Value::Number(n) => n.to_string(),
+                _ => value.as_str().unwrap_or_default().to_string(),
+            };
+            // Skip references to other resources and variables
+            if text.contains(['.', '$', '{', ' ']) && kind != Kind::Domain {
+                return;
+            }
+            let text = text.trim_start_matches("organizations/").trim_start_matches("customers/");
+            if !text.is_empty() {
+                replacements.add(kind, text);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn kind_of_key(key: &str) -> Option<Kind> {
+    let key = key.replace('-', "_");
+    match key.as_str() {
+        "customer_organization_id" | "org_id" | "organization_id" => Some(Kind::Organization),
+        "customer_id" => Some(Kind::Customer),
+        "project" | "project_id" | "billing_project" | "infra_project_name" => Some(Kind::Project),
+        "svc_iac_account" | "account_id" => Some(Kind::ServiceAccount),
+        k if k.starts_with("billing_account") => Some(Kind::BillingAccount),
+        k if k.ends_with("domain") => Some(Kind::Domain),
+        _ => None,
+    }
+}
+
+/// Replaces `real` where it is not part of a longer word (letters and digits).
+fn replace_token(content: &str, real: &str, fake: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(pos) = rest.find(real) {
+        let before = rest[..pos].chars().next_back();
+        let after = rest[pos + real.len()..].chars().next();
+        let standalone = !before.is_some_and(|c| c.is_ascii_alphanumeric()) && !after.is_some_and(|c| c.is_ascii_alphanumeric());
+        result.push_str(&rest[..pos]);
+        result.push_str(if standalone { fake } else { real });
+        rest = &rest[pos + real.len()..];
+    }
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,55 @@
+use std::collections::BTreeSet;
+use crate::state_report::module_prefix;
+
+/// `removed` blocks (with `destroy = false`) for the resources of the previous resource files
+/// that are no longer generated, so that dropping an entry from the YAML forgets the resource
+/// instead of destroying it. Files are given by their path relative to the output directory;
+/// resources in `modules/<name>/` are addressed as `module.<name>.<type>.<name>`. Entries of the
+/// previous removed.tf are kept until their resource comes back, as they are only needed until
+/// the next apply. Returns an empty string if there is nothing.
+pub fn removed_blocks(previous_files: &[(String, String)], previous_removed_tf: Option<&str>, files: &[(&str, &str)]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut generated: BTreeSet<String> = BTreeSet::new();
+    for (file, content) in files {
+        let current: hcl::Body = hcl::from_str(content)?;
+        let prefix = module_prefix(file);
+        generated.extend(resource_addresses(&current, &prefix));
+        // Renamed resources live on under their new address
+        generated.extend(current.blocks()
+            .filter(|b| b.identifier() == "moved")
+            .filter_map(|b| b.body.attributes().find(|a| a.key() == "from"))
+            .map(|a| format!("{}{}", prefix, crate::state_report::strip_index(&a.expr().to_string()))));
+    }
+
+    let mut removed: BTreeSet<String> = BTreeSet::new();
+    for (file, previous) in previous_files {
+        // A file that no longer parses (e.g. edited by hand) has nothing to compare against
+        if let Ok(previous) = hcl::from_str::<hcl::Body>(previous) {
+            removed.extend(resource_addresses(&previous, &module_prefix(file)));
+        }
+    }
+    if let Some(previous) = previous_removed_tf.and_then(|p| hcl::from_str::<hcl::Body>(p).ok()) {
+        removed.extend(previous.blocks()
+            .filter(|b| b.identifier() == "removed")
+            .filter_map(|b| b.body.attributes().find(|a| a.key() == "from"))
+            .map(|a| a.expr().to_string()));
+    }
+    removed.retain(|address| !generated.contains(address));
+
+    let mut body = hcl::Body::builder();
+    for address in &removed {
+        let from = address.parse::<hcl::Expression>()
+            .map_err(|e| format!("Invalid resource address '{}': {}", address, e))?;
+        body = body.add_block(hcl::Block::builder("removed")
+            .add_attribute(("from", from))
+            .add_block(hcl::Block::builder("lifecycle").add_attribute(("destroy", false)).build())
+            .build());
+    }
+    Ok(hcl::to_string(&body.build())?)
+}
+
+fn resource_addresses(body: &hcl::Body, prefix: &str) -> BTreeSet<String> {
+    body.blocks()
+        .filter(|b| b.identifier() == "resource" && b.labels().len() == 2)
+        .map(|b| format!("{}{}.{}", prefix, b.labels()[0].as_str(), b.labels()[1].as_str()))
+        .collect()
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::manifest::STATE_DIR;
+
+/// Summary of a transpile run. The last one is kept as `.cfg2hcl/run-report.json` and
+/// added to `--bundle` archives.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RunReport {
+    pub cfg2hcl_version: String,
+    /// Input file as given on the command line (`-` for stdin)
+    pub input: String,
+    /// Unix timestamp (seconds)
+    pub generated_at: u64,
+    pub resources: usize,
+    pub imports: usize,
+    /// Addresses of resources handled by `scripts/apply-extras.sh`
+    pub unsupported: Vec<String>,
+    /// Generated file name -> SHA-256 of its content
+    pub files: BTreeMap<String, String>,
+    /// Owner (`owner:` in the YAML) -> number of resources
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, usize>,
+}
+
+impl RunReport {
+    pub fn new(input: &str, files: &[(&str, &str)], unsupported: &[crate::extras::UnsupportedResource], owners: BTreeMap<String, usize>) -> Self {
+        // Resources may be split over several files
+        let count_blocks = |identifier: &str| {
+            files.iter()
+                .filter_map(|(_, content)| hcl::from_str::<hcl::Body>(content).ok())
+                .map(|body| body.blocks().filter(|b| b.identifier() == identifier).count())
+                .sum()
+        };
+        Self {
+            cfg2hcl_version: env!("CARGO_PKG_VERSION").to_string(),
+            input: input.to_string(),
+            generated_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            resources: count_blocks("resource"),
+            imports: count_blocks("import"),
+            unsupported: unsupported.iter().map(|r| r.address.clone()).collect(),
+            files: files.iter()
+                .map(|(name, content)| (name.to_string(), hex::encode(Sha256::digest(content.as_bytes()))))
+                .collect(),
+            owners,
+        }
+    }
+
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(STATE_DIR).join("run-report.json")
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    pub fn save(&self, config_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path(config_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.to_json()?)
+            .map_err(|e| format!("Failed to write run report '{}': {}", path.display(), e))?;
+        Ok(())
+    }
+}
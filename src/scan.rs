@@ -0,0 +1,292 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::provenance::{Provenance, Source};
+
+/// `[scan]` table of config.toml.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ScanConfig {
+    /// Scanners run by `cfg2hcl scan`, in this order; missing ones are skipped
+    #[serde(default = "default_scanners")]
+    pub scanners: Vec<String>,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self { scanners: default_scanners() }
+    }
+}
+
+impl ScanConfig {
+    pub fn is_default(&self) -> bool {
+        self.scanners == default_scanners()
+    }
+}
+
+fn default_scanners() -> Vec<String> {
+    SCANNERS.iter().map(|s| s.to_string()).collect()
+}
+
+const SCANNERS: &[&str] = &["tflint", "checkov", "trivy"];
+
+/// A scanner finding on the generated HCL, traced back to the YAML entry that produced it.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub scanner: String,
+    pub rule: String,
+    /// Lowercase severity as reported by the scanner (`error`, `high`, ...), `unknown` if none
+    pub severity: String,
+    pub message: String,
+    /// File relative to hcl_dir
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Resource address, e.g. `google_storage_bucket.logs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+}
+
+impl Finding {
+    fn new(scanner: &str, rule: String, severity: Option<String>, message: String) -> Self {
+        Self {
+            scanner: scanner.to_string(),
+            rule,
+            severity: severity.filter(|s| !s.is_empty()).map_or("unknown".to_string(), |s| s.to_lowercase()),
+            message,
+            file: None,
+            line: None,
+            address: None,
+            source: None,
+        }
+    }
+
+    fn at(mut self, file: Option<&str>, line: Option<usize>, address: Option<String>) -> Self {
+        self.file = file.map(|f| f.trim_start_matches('/').to_string()).filter(|f| !f.is_empty());
+        self.line = line.filter(|l| *l > 0);
+        self.address = address.filter(|a| !a.is_empty());
+        self
+    }
+}
+
+/// Runs the configured scanners against `hcl_dir` and maps their findings to resource addresses
+/// and, through the provenance of the last transpile, to the YAML.
+pub fn run(config: &ScanConfig, hcl_dir: &Path, provenance: Option<&Provenance>) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    let mut findings = Vec::new();
+    let mut ran = 0;
+    for scanner in &config.scanners {
+        if !SCANNERS.contains(&scanner.as_str()) {
+            return Err(format!("Unknown scanner '{}' in [scan] (supported: {})", scanner, SCANNERS.join(", ")).into());
+        }
+        if !is_installed(scanner) {
+            eprintln!("Warning: {} not found in PATH, skipped", scanner);
+            continue;
+        }
+        // On stderr, stdout may carry the JSON report
+        if !crate::term::quiet() {
+            eprintln!("Running {}...", scanner);
+        }
+        findings.extend(run_scanner(scanner, hcl_dir)?);
+        ran += 1;
+    }
+    if ran == 0 {
+        return Err(format!("None of the configured scanners is installed ({})", config.scanners.join(", ")).into());
+    }
+
+    for finding in &mut findings {
+        if finding.address.is_none() {
+            if let (Some(file), Some(line)) = (&finding.file, finding.line) {
+                finding.address = enclosing_resource(&hcl_dir.join(file), line);
+            }
+        }
+        finding.source = finding.address.as_ref()
+            .and_then(|address| provenance?.resources.get(address).cloned());
+    }
+    Ok(findings)
+}
+
+fn is_installed(scanner: &str) -> bool {
+    Command::new(scanner).arg("--version").output().is_ok()
+}
+
+fn run_scanner(scanner: &str, hcl_dir: &Path) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    let dir = hcl_dir.display().to_string();
+    let args: Vec<&str> = match scanner {
+        "tflint" => vec!["--chdir", &dir, "--format", "json"],
+        "checkov" => vec!["-d", &dir, "--framework", "terraform", "-o", "json", "--quiet", "--compact"],
+        _ => vec!["config", "--format", "json", "--quiet", &dir],
+    };
+    let output = Command::new(scanner).args(&args).output()
+        .map_err(|e| format!("Failed to run {}: {}", scanner, e))?;
+    // The exit code only says whether there were findings; a missing or broken report is the error
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let invalid = |e: serde_json::Error| format!("{} did not produce a JSON report ({}): {}", scanner, e, String::from_utf8_lossy(&output.stderr).trim());
+    match scanner {
+        "tflint" => {
+            let report: TflintReport = serde_json::from_str(&stdout).map_err(invalid)?;
+            if let Some(error) = report.errors.first() {
+                return Err(format!("tflint failed: {}", error.message).into());
+            }
+            Ok(report.issues.into_iter().map(|i| {
+                Finding::new(scanner, i.rule.name, Some(i.rule.severity), i.message)
+                    .at(Some(&i.range.filename), Some(i.range.start.line), None)
+            }).collect())
+        }
+        "checkov" => {
+            // One report per framework; a list when several ran
+            let reports: Vec<CheckovReport> = match serde_json::from_str(&stdout).map_err(invalid)? {
+                serde_json::Value::Array(reports) => reports.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?,
+                report => vec![serde_json::from_value(report)?],
+            };
+            Ok(reports.into_iter().flat_map(|r| r.results.failed_checks).map(|c| {
+                Finding::new(scanner, c.check_id, c.severity, c.check_name)
+                    .at(Some(&c.file_path), c.file_line_range.first().copied(), Some(c.resource))
+            }).collect())
+        }
+        _ => {
+            let report: TrivyReport = serde_json::from_str(&stdout).map_err(invalid)?;
+            Ok(report.results.into_iter().flat_map(|r| {
+                let target = r.target;
+                r.misconfigurations.into_iter().map(move |m| {
+                    Finding::new(scanner, m.id, Some(m.severity), if m.message.is_empty() { m.title } else { m.message })
+                        .at(Some(&target), Some(m.cause_metadata.start_line), Some(m.cause_metadata.resource))
+                })
+            }).collect())
+        }
+    }
+}
+
+/// Address of the `resource` block that contains the (1-based) line of a generated file.
+fn enclosing_resource(file: &Path, line: usize) -> Option<String> {
+    let content = fs::read_to_string(file).ok()?;
+    crate::provenance::top_level_blocks(&content).into_iter()
+        .take_while(|(start, _)| *start <= line)
+        .last()
+        .and_then(|(_, address)| address)
+}
+
+/// Prints the findings, each with its HCL location and YAML origin.
+pub fn print_findings(findings: &[Finding]) {
+    for finding in findings {
+        println!("[{}] {} {}: {}", finding.scanner, finding.severity, finding.rule, finding.message);
+        match (&finding.file, finding.line, &finding.address) {
+            (Some(file), Some(line), Some(address)) => println!("    at {}:{} ({})", file, line, address),
+            (Some(file), Some(line), None) => println!("    at {}:{}", file, line),
+            (_, _, Some(address)) => println!("    at {}", address),
+            _ => {}
+        }
+        if let Some(source) = &finding.source {
+            println!("    from {}", source.describe());
+            if !source.owners.is_empty() {
+                println!("    owned by {}", source.owners.join(" "));
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TflintReport {
+    #[serde(default)]
+    issues: Vec<TflintIssue>,
+    #[serde(default)]
+    errors: Vec<TflintError>,
+}
+
+#[derive(Deserialize)]
+struct TflintIssue {
+    rule: TflintRule,
+    message: String,
+    range: TflintRange,
+}
+
+#[derive(Deserialize)]
+struct TflintRule {
+    name: String,
+    #[serde(default)]
+    severity: String,
+}
+
+#[derive(Deserialize)]
+struct TflintRange {
+    filename: String,
+    start: TflintPos,
+}
+
+#[derive(Deserialize)]
+struct TflintPos {
+    line: usize,
+}
+
+#[derive(Deserialize)]
+struct TflintError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct CheckovReport {
+    /// Missing when nothing was scanned
+    #[serde(default)]
+    results: CheckovResults,
+}
+
+#[derive(Deserialize, Default)]
+struct CheckovResults {
+    #[serde(default)]
+    failed_checks: Vec<CheckovCheck>,
+}
+
+#[derive(Deserialize)]
+struct CheckovCheck {
+    check_id: String,
+    check_name: String,
+    #[serde(default)]
+    resource: String,
+    #[serde(default)]
+    file_path: String,
+    #[serde(default)]
+    file_line_range: Vec<usize>,
+    #[serde(default)]
+    severity: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyReport {
+    #[serde(default)]
+    results: Vec<TrivyResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyResult {
+    target: String,
+    #[serde(default)]
+    misconfigurations: Vec<TrivyMisconfiguration>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyMisconfiguration {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    cause_metadata: TrivyCause,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+struct TrivyCause {
+    #[serde(default)]
+    resource: String,
+    #[serde(default)]
+    start_line: usize,
+}
@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[derive(Debug, Deserialize)]
@@ -25,6 +26,24 @@ pub struct AttributeSchema {
     pub computed: bool,
     #[serde(default)]
     pub default: Option<serde_json::Value>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Terraform type: `"string"`, or `["map", "string"]` etc. for collections
+    #[serde(default, rename = "type")]
+    pub attr_type: Option<serde_json::Value>,
+}
+
+impl AttributeSchema {
+    /// Whether the attribute is a plain string; a structured YAML value for it is a JSON document.
+    pub fn is_string(&self) -> bool {
+        self.attr_type.as_ref().is_some_and(|t| t == "string")
+    }
+
+    /// Whether an organization-valued attribute takes `organizations/{id}` instead of the bare
+    /// numeric ID, as stated in the provider's description. `None` if there is no description.
+    pub fn expects_org_resource_name(&self) -> Option<bool> {
+        self.description.as_ref().map(|d| d.contains("organizations/"))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -155,7 +174,8 @@ pub struct ResourceRegistry {
 }
 
 impl ResourceRegistry {
-    pub fn load_all(directory: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load_all(directory: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let _span = crate::timings::span("schema load");
         let mut resources = HashMap::new();
         if let Ok(entries) = fs::read_dir(directory) {
             for entry in entries {
@@ -172,8 +192,8 @@ impl ResourceRegistry {
                             file_resource_count += 1;
                         }
                     }
-                    if let Some(file_name) = entry.path().file_name().and_then(|f| f.to_str()) {
-                         println!("Loaded {} resource types from schema file '{}'", file_resource_count, file_name);
+                    if let Some(file_name) = entry.path().file_name().and_then(|f| f.to_str()).filter(|_| !crate::term::quiet()) {
+                         eprintln!("Loaded {} resource types from schema file '{}'", file_resource_count, file_name);
                     }
                 }
             }
@@ -181,6 +201,11 @@ impl ResourceRegistry {
         Ok(ResourceRegistry { resources })
     }
 
+    /// True if no provider schema was found, e.g. before the first `update-schema`.
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
     pub fn find_resource(&self, key: &str) -> Option<(&str, &ResourceSchema)> {
         // 1. Try exact match
         if let Some((prov, schema)) = self.resources.get(key) {
@@ -194,10 +219,10 @@ impl ResourceRegistry {
         None
     }
 
-    pub fn generate_schema(tool: &str, provider: &str, version: &str, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let work_dir = format!(".temp_schema_gen_{}", provider);
+    pub fn generate_schema(tool: &str, provider: &str, version: &str, output_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let work_dir = PathBuf::from(format!(".temp_schema_gen_{}", provider));
         fs::create_dir_all(&work_dir)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create work directory '{}': {}", work_dir, e)))?;
+            .map_err(|e| std::io::Error::other(format!("Failed to create work directory '{}': {}", work_dir.display(), e)))?;
 
         // Determine base provider name and source
         let parts: Vec<&str> = provider.split('/').collect();
@@ -232,7 +257,7 @@ impl ResourceRegistry {
             name, full_source, version
         );
 
-        fs::write(format!("{}/main.tf", work_dir), main_tf)?;
+        fs::write(work_dir.join("main.tf"), main_tf)?;
 
         let status = Command::new(tool)
             .arg("init")
@@ -253,9 +278,9 @@ impl ResourceRegistry {
         }
 
         fs::write(output_path, output.stdout)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to write schema to '{}': {}", output_path, e)))?;
+            .map_err(|e| std::io::Error::other(format!("Failed to write schema to '{}': {}", output_path.display(), e)))?;
         fs::remove_dir_all(&work_dir)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to remove work directory '{}': {}", work_dir, e)))?;
+            .map_err(|e| std::io::Error::other(format!("Failed to remove work directory '{}': {}", work_dir.display(), e)))?;
 
         Ok(())
     }
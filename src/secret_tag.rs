@@ -0,0 +1,72 @@
+/// Data source type `!secret` values are read through.
+pub const DATA_SOURCE: &str = "google_secret_manager_secret_version";
+
+/// A `!secret projects/<project>/secrets/<secret>[/versions/<version>]` value; without a version
+/// the latest one is read.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SecretRef {
+    pub project: String,
+    pub secret: String,
+    pub version: Option<String>,
+}
+
+impl SecretRef {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let segments: Vec<&str> = name.split('/').collect();
+        match segments.as_slice() {
+            ["projects", project, "secrets", secret] if !project.is_empty() && !secret.is_empty() => {
+                Ok(Self { project: project.to_string(), secret: secret.to_string(), version: None })
+            }
+            ["projects", project, "secrets", secret, "versions", version] if !project.is_empty() && !secret.is_empty() && !version.is_empty() => {
+                Ok(Self { project: project.to_string(), secret: secret.to_string(), version: Some(version.to_string()) })
+            }
+            _ => Err("not a secret like projects/<project>/secrets/<secret>[/versions/<version>]".to_string()),
+        }
+    }
+
+    /// Label of the data source, shared by every `!secret` of the same secret version.
+    pub fn label(&self) -> String {
+        let label = match &self.version {
+            Some(version) => format!("{}_{}_{}", self.project, self.secret, version),
+            None => format!("{}_{}", self.project, self.secret),
+        };
+        crate::transpiler::resource_label(&label)
+    }
+
+    /// `data.google_secret_manager_secret_version.<label>.secret_data`
+    pub fn expression(&self) -> hcl::Expression {
+        hcl::Expression::from(
+            hcl::expr::Traversal::builder(hcl::expr::Variable::unchecked("data"))
+                .attr(DATA_SOURCE)
+                .attr(self.label())
+                .attr("secret_data")
+                .build(),
+        )
+    }
+
+    pub fn data_block(&self, provider: Option<hcl::Expression>) -> hcl::Block {
+        let mut block = hcl::Block::builder("data")
+            .add_label(DATA_SOURCE)
+            .add_label(self.label());
+        if let Some(provider) = provider {
+            block = block.add_attribute(("provider", provider));
+        }
+        block = block
+            .add_attribute(("project", self.project.as_str()))
+            .add_attribute(("secret", self.secret.as_str()));
+        if let Some(version) = &self.version {
+            block = block.add_attribute(("version", version.as_str()));
+        }
+        block.build()
+    }
+}
+
+/// Whether `value` contains a `!secret` value.
+pub fn contains_secret(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Tagged(tagged) => tagged.tag == "!secret" || contains_secret(&tagged.value),
+        serde_yaml::Value::Mapping(map) => map.values().any(contains_secret),
+        serde_yaml::Value::Sequence(seq) => seq.iter().any(contains_secret),
+        _ => false,
+    }
+}
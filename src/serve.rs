@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use crate::schema::ResourceRegistry;
+use crate::transpiler::{GeneratedProject, Transpiler};
+use crate::{LoadedInput, ToolConfig};
+
+/// Limits for a single request; inputs are one (include-expanded) YAML document.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+/// Time a client has to send its complete request
+const READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Everything a request needs; loaded once when the server starts.
+pub struct ServeContext {
+    /// Tool config with paths resolved against the config directory
+    pub runtime_config: ToolConfig,
+    pub validation_level: String,
+    pub registry: Arc<ResourceRegistry>,
+}
+
+/// Serves `POST /transpile` and `POST /validate` (YAML request body, JSON response) until the
+/// process is stopped. Relative includes in a request are resolved against yaml_dir and have to
+/// stay inside yaml_dir and include_dirs; `!env`, `!file` and tag plugins are rejected.
+pub async fn serve(listen: &str, context: ServeContext) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(listen).await
+        .map_err(|e| format!("Failed to listen on '{}': {}", listen, e))?;
+    println!("Listening on http://{} (POST /transpile, POST /validate)", listener.local_addr()?);
+
+    let context = Arc::new(context);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let context = context.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, context).await {
+                eprintln!("Warning: Request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, context: Arc<ServeContext>) -> std::io::Result<()> {
+    let (status, body) = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Err(_) => (408, serde_json::json!({ "error": format!("Request not received within {} seconds", READ_TIMEOUT.as_secs()) })),
+        Ok(request) => match request? {
+            Err(message) => (400, serde_json::json!({ "error": message })),
+            Ok((method, path, body)) => route(&method, &path, body, context).await,
+        },
+    };
+    let body = serde_json::to_string_pretty(&body)? + "\n";
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason(status), body.len(), body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads one HTTP/1.1 request. The inner error is a malformed request (answered with 400).
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Result<(String, String, String), String>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Ok(Err("Request header too large".to_string()));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(Err("Incomplete request".to_string()));
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let header = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = header.lines();
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Ok(Err("Malformed request line".to_string()));
+    };
+    let content_length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>());
+    let content_length = match content_length {
+        None => 0,
+        Some(Ok(len)) if len <= MAX_BODY_BYTES => len,
+        Some(Ok(_)) => return Ok(Err(format!("Request body larger than {} bytes", MAX_BODY_BYTES))),
+        Some(Err(_)) => return Ok(Err("Invalid Content-Length".to_string())),
+    };
+
+    let mut body = buffer.split_off(header_end + 4);
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(Err("Incomplete request body".to_string()));
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(content_length);
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(Err("Request body is not valid UTF-8".to_string()));
+    };
+
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    Ok(Ok((method.to_string(), path, body)))
+}
+
+async fn route(method: &str, path: &str, body: String, context: Arc<ServeContext>) -> (u16, serde_json::Value) {
+    match (method, path) {
+        ("GET", "/health") => (200, serde_json::json!({ "status": "ok" })),
+        ("POST", "/transpile") | ("POST", "/validate") => {
+            let transpile = path == "/transpile";
+            // Transpiling is CPU bound and not Send; keep it off the async workers
+            let result = tokio::task::spawn_blocking(move || {
+                if transpile {
+                    transpile_request(&context, &body).map(|project| crate::output::bundle(&project))
+                } else {
+                    load_request(&context, &body).map(|_| serde_json::json!({ "valid": true }))
+                }.map_err(|e| e.to_string())
+            }).await;
+            match result {
+                Ok(Ok(response)) => (200, response),
+                Ok(Err(message)) if transpile => (422, serde_json::json!({ "error": message })),
+                Ok(Err(message)) => (422, serde_json::json!({ "valid": false, "error": message })),
+                Err(e) => (500, serde_json::json!({ "error": e.to_string() })),
+            }
+        }
+        (_, "/health") | (_, "/transpile") | (_, "/validate") => (405, serde_json::json!({ "error": format!("{} not allowed on {}", method, path) })),
+        _ => (404, serde_json::json!({ "error": format!("Unknown endpoint '{}'", path) })),
+    }
+}
+
+/// Parses the request YAML and runs the configuration, naming and IAM member checks.
+fn load_request(context: &ServeContext, body: &str) -> Result<LoadedInput, Box<dyn std::error::Error>> {
+    let config = &context.runtime_config;
+    let root = config.yaml_dir.join("request.yaml");
+    let expanded = crate::include_processor::process_includes_content(body, &root, &config.yaml_dir, &config.include_dirs, true)?;
+    let loaded = crate::parse_input(expanded, config, true)?;
+    crate::check_input(&loaded, config, &context.validation_level)?;
+    Ok(loaded)
+}
+
+fn transpile_request(context: &ServeContext, body: &str) -> Result<GeneratedProject, Box<dyn std::error::Error>> {
+    let loaded = load_request(context, body)?;
+    let config = &context.runtime_config;
+    let (provider_sources, provider_versions) = crate::provider_settings(config);
+    let transpiler = Transpiler::new(
+        &loaded.config,
+        Some(context.registry.clone()),
+        config.auto_explode.clone(),
+        context.validation_level.clone(),
+        crate::extract_variables(&loaded.raw_value)?,
+        provider_sources,
+        provider_versions,
+    ).with_required_labels(config.required_labels.clone())
+        .with_implicit_dependencies(config.implicit_dependencies)
+        .with_implicit_references(config.implicit_references()?)
+        .with_service_label_template(config.service_label_template.clone(), Vec::new())
+        .with_variable_descriptions(crate::yaml_comments::variable_descriptions(&loaded.processed_content))
+        .with_variable_declarations(crate::variable_declarations(&loaded.raw_value)?)
+        .with_resource_aliases(config.resource_aliases())
+        .with_tfvars(crate::tfvars::TfvarsFormat::parse(&config.tfvars_format)?, crate::environment_variables(&loaded.processed_content, config)?)
+        .with_split_by(crate::split::SplitBy::parse(&config.split_output)?);
+    let mut project = transpiler.transpile()?;
+    project.annotate(&loaded.processed_content);
+    project.format(config.sort_attributes);
+
+    // Guardrails cannot be overridden through the API
+    let violations = crate::guardrails::check_generated(&project.resources_tf(), &config.guardrails)?;
+    crate::guardrails::enforce(&violations, false)?;
+    Ok(project)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
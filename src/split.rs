@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+/// How the generated blocks are spread over files, `split_output` in config.toml or `--split-by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitBy {
+    /// Everything in `main.tf`
+    #[default]
+    None,
+    /// `folders.tf`, `projects.tf`, `iam.tf`, `org_policies.tf`, ... and one file per other type
+    Type,
+    /// One `folder_<key>.tf` per top-level folder, root resources in `main.tf`
+    Folder,
+    /// One child module `modules/<key>` per top-level folder, called from `main.tf`, see
+    /// [`crate::extract_module::module_layout`]
+    Module,
+}
+
+impl SplitBy {
+    pub fn parse(split_by: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match split_by {
+            "none" => Ok(SplitBy::None),
+            "type" => Ok(SplitBy::Type),
+            "folder" => Ok(SplitBy::Folder),
+            "module" => Ok(SplitBy::Module),
+            other => Err(format!("Unknown split_output '{}' (expected none, type, folder or module)", other).into()),
+        }
+    }
+
+    /// File of a block; `locals`, `module` and `moved` blocks stay in `main.tf`.
+    pub fn file_name(self, block: &hcl::Block, sources: &BTreeMap<String, String>) -> String {
+        let [tf_type, label] = block.labels.as_slice() else { return "main.tf".to_string() };
+        if block.identifier() != "resource" {
+            return "main.tf".to_string();
+        }
+        match self {
+            SplitBy::None | SplitBy::Module => "main.tf".to_string(),
+            SplitBy::Type => type_file(tf_type.as_str()),
+            SplitBy::Folder => sources.get(&format!("{}.{}", tf_type.as_str(), label.as_str()))
+                .and_then(|yaml_path| yaml_path.strip_prefix("folder."))
+                .and_then(|rest| rest.split('.').next())
+                .map_or_else(|| "main.tf".to_string(), |key| format!("folder_{}.tf", crate::transpiler::resource_label(key))),
+        }
+    }
+}
+
+/// `google_project_iam_member` -> `iam.tf`, `google_storage_bucket` -> `storage_bucket.tf`.
+fn type_file(tf_type: &str) -> String {
+    const IAM_SUFFIXES: &[&str] = &["_iam_member", "_iam_binding", "_iam_policy", "_iam_audit_config"];
+    if IAM_SUFFIXES.iter().any(|s| tf_type.ends_with(s)) {
+        return "iam.tf".to_string();
+    }
+    match tf_type {
+        "google_folder" => "folders.tf".to_string(),
+        "google_project" => "projects.tf".to_string(),
+        "google_project_service" => "services.tf".to_string(),
+        t if t == "google_org_policy_policy" || t.ends_with("organization_policy") => "org_policies.tf".to_string(),
+        t if t.starts_with("google_cloud_identity_group") => "groups.tf".to_string(),
+        // Without the provider prefix
+        t => format!("{}.tf", t.split_once('_').map_or(t, |(_, rest)| rest)),
+    }
+}
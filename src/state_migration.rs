@@ -92,6 +92,45 @@ pub fn scan_plan(plan_path: &Path) -> Result<HashMap<String, String>, Box<dyn st
     Ok(mapping)
 }
 
+/// Number of resources per planned action.
+#[derive(Debug, Default)]
+pub struct PlanSummary {
+    pub create: usize,
+    pub update: usize,
+    pub replace: usize,
+    pub delete: usize,
+}
+
+pub fn plan_summary(plan_path: &Path) -> Result<PlanSummary, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(plan_path)?;
+    let plan: PlanJson = serde_json::from_str(&content)?;
+
+    let mut summary = PlanSummary::default();
+    for rc in plan.resource_changes.unwrap_or_default() {
+        let actions: Vec<&str> = rc.change.actions.iter().map(|a| a.as_str()).collect();
+        match actions.as_slice() {
+            ["create"] => summary.create += 1,
+            ["update"] => summary.update += 1,
+            ["delete"] => summary.delete += 1,
+            ["delete", "create"] | ["create", "delete"] => summary.replace += 1,
+            _ => {}
+        }
+    }
+    Ok(summary)
+}
+
+/// Addresses of all resources the plan destroys, including replacements.
+pub fn planned_destroys(plan_path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(plan_path)?;
+    let plan: PlanJson = serde_json::from_str(&content)?;
+
+    Ok(plan.resource_changes.unwrap_or_default()
+        .into_iter()
+        .filter(|rc| rc.change.actions.iter().any(|a| a == "delete"))
+        .map(|rc| rc.address)
+        .collect())
+}
+
 fn is_match(res_type: &str, before: &Value, after: &Value) -> bool {
     let keys_to_check = match res_type {
         "google_project" => vec!["project_id", "folder_id", "org_id", "name"],
@@ -256,3 +295,26 @@ pub fn generate_migration(mapping_path: &Path, output_path: &Path, tf_tool: &str
     fs::write(output_path, script)?;
     Ok(())
 }
+
+/// Writes the renames of a mapping file as `moved { from = .. to = .. }` blocks, to be placed
+/// next to the generated main.tf, so that the migration is part of the reviewed plan.
+pub fn generate_moved_blocks(mapping_path: &Path, output_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(mapping_path)?;
+    let mapping: HashMap<String, String> = serde_yaml::from_str(&content)?;
+
+    let mut items: Vec<_> = mapping.into_iter().collect();
+    items.sort();
+
+    let mut body = hcl::Body::builder();
+    for (old, new) in items {
+        let address = |a: &str| a.parse::<hcl::Expression>()
+            .map_err(|e| format!("Invalid resource address '{}' in '{}': {}", a, mapping_path.display(), e));
+        body = body.add_block(hcl::Block::builder("moved")
+            .add_attribute(("from", address(&old)?))
+            .add_attribute(("to", address(&new)?))
+            .build());
+    }
+
+    fs::write(output_path, hcl::to_string(&body.build())?)?;
+    Ok(())
+}
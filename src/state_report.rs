@@ -0,0 +1,414 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+
+/// Group used for resources that are not bound to a project (organization, folders, billing).
+const NO_PROJECT: &str = "(organization / folders)";
+
+/// A managed resource, either found in state or generated from the YAML model.
+#[derive(Debug, Clone)]
+pub struct InspectedResource {
+    /// Address without a `count`/`for_each` index, e.g. `google_storage_bucket.state`
+    pub address: String,
+    /// Address of the instance in state, with its index (`google_x.y["a"]`); the address for
+    /// generated resources
+    pub instance: String,
+    pub project: Option<String>,
+}
+
+/// Reads state as JSON, either from a `show -json` export or by running `<tf_tool> show -json` in `dir`.
+pub fn read_state(tf_tool: &str, dir: &Path, state_json: Option<&Path>) -> Result<Value, Box<dyn std::error::Error>> {
+    if let Some(path) = state_json {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read state file '{}': {}", path.display(), e))?;
+        return Ok(serde_json::from_str(&content)?);
+    }
+
+    let output = std::process::Command::new(tf_tool)
+        .arg("show")
+        .arg("-json")
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run {} show -json: {}", tf_tool, e))?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to run {} show -json: {}", tf_tool, err).into());
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Managed resources in a `show -json` state, including those in child modules.
+pub fn state_resources(state: &Value) -> Vec<InspectedResource> {
+    let mut resources = Vec::new();
+    if let Some(root) = state.get("values").and_then(|v| v.get("root_module")) {
+        collect_module_resources(root, &mut resources);
+    }
+    resources
+}
+
+fn collect_module_resources(module: &Value, out: &mut Vec<InspectedResource>) {
+    if let Some(resources) = module.get("resources").and_then(|r| r.as_array()) {
+        for res in resources {
+            if res.get("mode").and_then(|m| m.as_str()) != Some("managed") {
+                continue;
+            }
+            let Some(address) = res.get("address").and_then(|a| a.as_str()) else { continue };
+            let values = res.get("values");
+            let project = values
+                .and_then(|v| v.get("project").or_else(|| v.get("project_id")))
+                .and_then(|p| p.as_str())
+                .map(|p| p.trim_start_matches("projects/").to_string());
+            out.push(InspectedResource { address: strip_index(address), instance: address.to_string(), project });
+        }
+    }
+    if let Some(children) = module.get("child_modules").and_then(|c| c.as_array()) {
+        for child in children {
+            collect_module_resources(child, out);
+        }
+    }
+}
+
+/// Resources declared in the generated `.tf` files of `hcl_dir` and of its folder modules
+/// (`modules/<name>`, addressed as `module.<name>.<type>.<name>`). References to a generated
+/// `google_project` (e.g. `google_project.infra.project_id`) are resolved to its project ID.
+pub fn generated_resources(hcl_dir: &Path) -> Result<Vec<InspectedResource>, Box<dyn std::error::Error>> {
+    let mut blocks = Vec::new();
+    for (file, content) in read_tf_files(hcl_dir)? {
+        let body: hcl::Body = hcl::from_str(&content)
+            .map_err(|e| format!("Failed to parse '{}': {}", hcl_dir.join(&file).display(), e))?;
+        let prefix = module_prefix(&file);
+        blocks.extend(body.into_blocks()
+            .filter(|b| b.identifier() == "resource" && b.labels().len() == 2)
+            .map(|b| (prefix.clone(), b)));
+    }
+
+    // (module prefix, google_project resource name) -> project ID
+    let project_ids: BTreeMap<(String, String), String> = blocks.iter()
+        .filter(|(_, b)| b.labels()[0].as_str() == "google_project")
+        .filter_map(|(prefix, b)| {
+            let project_id = literal_attribute(b, "project_id")?;
+            Some(((prefix.clone(), b.labels()[1].as_str().to_string()), project_id))
+        })
+        .collect();
+
+    Ok(blocks.iter().map(|(prefix, b)| {
+        let (tf_type, name) = (b.labels()[0].as_str(), b.labels()[1].as_str());
+        let project = if tf_type == "google_project" {
+            literal_attribute(b, "project_id")
+        } else {
+            ["project", "project_id"].iter()
+                .find_map(|key| b.body.attributes().find(|a| a.key() == *key))
+                .and_then(|a| match a.expr() {
+                    hcl::Expression::String(s) => Some(s.trim_start_matches("projects/").to_string()),
+                    expr => {
+                        let reference = expr.to_string();
+                        let resource = reference.strip_prefix("google_project.")?.split('.').next()?;
+                        project_ids.get(&(prefix.clone(), resource.to_string())).cloned()
+                    }
+                })
+        };
+        let address = format!("{}{}.{}", prefix, tf_type, name);
+        InspectedResource { instance: address.clone(), address, project }
+    }).collect())
+}
+
+/// The `.tf` files of `hcl_dir` and of its `modules/<name>` directories, by path relative to
+/// `hcl_dir` (`main.tf`, `modules/shared/main.tf`).
+pub fn read_tf_files(hcl_dir: &Path) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    let tf_files = |dir: &Path| -> Result<Vec<std::path::PathBuf>, Box<dyn std::error::Error>> {
+        Ok(fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read HCL directory '{}': {}", dir.display(), e))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "tf"))
+            .collect())
+    };
+    let mut paths = tf_files(hcl_dir)?;
+    let modules = hcl_dir.join("modules");
+    if modules.is_dir() {
+        for entry in fs::read_dir(&modules)?.filter_map(|e| e.ok()) {
+            if entry.path().is_dir() {
+                paths.extend(tf_files(&entry.path())?);
+            }
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    for path in paths {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let name = path.strip_prefix(hcl_dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        files.insert(name, content);
+    }
+    Ok(files)
+}
+
+/// Address prefix of the resources in a generated file: `modules/<name>/main.tf` ->
+/// `module.<name>.`, empty for the files of the root module.
+pub fn module_prefix(file: &str) -> String {
+    file.strip_prefix("modules/")
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(name, _)| format!("module.{}.", name))
+        .unwrap_or_default()
+}
+
+fn literal_attribute(block: &hcl::Block, key: &str) -> Option<String> {
+    block.body.attributes()
+        .find(|a| a.key() == key)
+        .and_then(|a| match a.expr() {
+            hcl::Expression::String(s) => Some(s.clone()),
+            _ => None,
+        })
+}
+
+/// Instances that were added, removed or whose values changed between two `show -json` states,
+/// e.g. the state before and after an apply.
+pub fn changed_instances(before: &Value, after: &Value) -> Vec<String> {
+    let (mut old, mut new) = (BTreeMap::new(), BTreeMap::new());
+    for (state, out) in [(before, &mut old), (after, &mut new)] {
+        if let Some(root) = state.get("values").and_then(|v| v.get("root_module")) {
+            collect_instance_values(root, out);
+        }
+    }
+    let addresses: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    addresses.into_iter().filter(|a| old.get(*a) != new.get(*a)).cloned().collect()
+}
+
+fn collect_instance_values(module: &Value, out: &mut BTreeMap<String, Value>) {
+    if let Some(resources) = module.get("resources").and_then(|r| r.as_array()) {
+        for res in resources {
+            if res.get("mode").and_then(|m| m.as_str()) != Some("managed") {
+                continue;
+            }
+            let Some(address) = res.get("address").and_then(|a| a.as_str()) else { continue };
+            out.insert(address.to_string(), res.get("values").cloned().unwrap_or(Value::Null));
+        }
+    }
+    if let Some(children) = module.get("child_modules").and_then(|c| c.as_array()) {
+        for child in children {
+            collect_instance_values(child, out);
+        }
+    }
+}
+
+/// `google_project.x["a"]` / `google_project.x[0]` -> `google_project.x`
+pub fn strip_index(address: &str) -> String {
+    match address.rfind('[') {
+        Some(idx) if address.ends_with(']') => address[..idx].to_string(),
+        _ => address.to_string(),
+    }
+}
+
+/// Differences between state and the generated HCL, grouped by project.
+#[derive(Debug, Default)]
+pub struct DriftReport {
+    pub state_count: usize,
+    pub generated_count: usize,
+    /// project -> (only in state, only in YAML)
+    pub groups: BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)>,
+}
+
+impl DriftReport {
+    pub fn differences(&self) -> usize {
+        self.groups.values().map(|(state_only, yaml_only)| state_only.len() + yaml_only.len()).sum()
+    }
+
+    /// Plain text summary for notifications.
+    pub fn text(&self, scope: &Path) -> String {
+        let mut text = format!("Drift in {}: {} difference(s)", scope.display(), self.differences());
+        for (project, (state_only, yaml_only)) in &self.groups {
+            text.push_str(&format!("\n{}:", project));
+            for address in state_only {
+                text.push_str(&format!("\n  - {} (in state, not in YAML)", address));
+            }
+            for address in yaml_only {
+                text.push_str(&format!("\n  + {} (in YAML, not in state)", address));
+            }
+        }
+        text
+    }
+
+    /// Structured fields for notifications.
+    pub fn details(&self, scope: &Path) -> serde_json::Value {
+        let projects: serde_json::Map<String, Value> = self.groups.iter()
+            .map(|(project, (state_only, yaml_only))| {
+                (project.clone(), serde_json::json!({ "only_in_state": state_only, "only_in_yaml": yaml_only }))
+            })
+            .collect();
+        serde_json::json!({
+            "event": "drift",
+            "scope": scope.display().to_string(),
+            "differences": self.differences(),
+            "projects": projects,
+        })
+    }
+}
+
+/// Compares state against the generated HCL in `hcl_dir`.
+pub fn drift(state: &Value, hcl_dir: &Path) -> Result<DriftReport, Box<dyn std::error::Error>> {
+    let in_state = state_resources(state);
+    let generated = generated_resources(hcl_dir)?;
+
+    let state_addresses: BTreeSet<&str> = in_state.iter().map(|r| r.address.as_str()).collect();
+    let generated_addresses: BTreeSet<&str> = generated.iter().map(|r| r.address.as_str()).collect();
+
+    let mut report = DriftReport {
+        state_count: state_addresses.len(),
+        generated_count: generated_addresses.len(),
+        ..Default::default()
+    };
+    for res in &in_state {
+        if !generated_addresses.contains(res.address.as_str()) {
+            let group = res.project.clone().unwrap_or_else(|| NO_PROJECT.to_string());
+            report.groups.entry(group).or_default().0.insert(res.address.clone());
+        }
+    }
+    for res in &generated {
+        if !state_addresses.contains(res.address.as_str()) {
+            let group = res.project.clone().unwrap_or_else(|| NO_PROJECT.to_string());
+            report.groups.entry(group).or_default().1.insert(res.address.clone());
+        }
+    }
+    Ok(report)
+}
+
+/// Compares state against the generated HCL and prints the differences per project.
+pub fn state_report(state: &Value, hcl_dir: &Path) -> Result<DriftReport, Box<dyn std::error::Error>> {
+    let report = drift(state, hcl_dir)?;
+
+    println!("State: {} resources, YAML model: {} resources", report.state_count, report.generated_count);
+    if report.groups.is_empty() {
+        println!("{}State and YAML model are in sync.", crate::term::icon("✅ ", ""));
+        return Ok(report);
+    }
+
+    for (project, (state_only, yaml_only)) in &report.groups {
+        println!("\n{}:", project);
+        for address in state_only {
+            println!("  - {}  (in state, not in YAML)", address);
+        }
+        for address in yaml_only {
+            println!("  + {}  (in YAML, not in state)", address);
+        }
+    }
+    println!("\n{} difference(s) found.", report.differences());
+    Ok(report)
+}
+
+/// An import block that should no longer be in `imports.tf`.
+#[derive(Debug, Clone)]
+pub struct StaleImport {
+    pub address: String,
+    pub reason: &'static str,
+}
+
+/// Finds import blocks whose target is already in state or is no longer generated (the
+/// latter only if `generated` is given). `in_state` holds the instance addresses with their
+/// index, so that one instance of a `for_each` resource in state leaves the imports of the
+/// others; `generated` holds addresses without index.
+/// Returns the remaining `imports.tf` content and the stale imports.
+pub fn find_stale_imports(
+    imports_tf: &str,
+    generated: Option<&BTreeSet<String>>,
+    in_state: &BTreeSet<String>,
+) -> Result<(String, Vec<StaleImport>), Box<dyn std::error::Error>> {
+    let body: hcl::Body = hcl::from_str(imports_tf)?;
+    let mut kept = hcl::Body::builder();
+    let mut stale = Vec::new();
+
+    for structure in body.into_inner() {
+        if let hcl::Structure::Block(block) = &structure {
+            if block.identifier() == "import" {
+                if let Some(to) = block.body.attributes().find(|a| a.key() == "to") {
+                    let address = to.expr().to_string();
+                    let bare = strip_index(&address);
+                    let reason = if in_state.contains(&address) {
+                        Some("already in state")
+                    } else if generated.is_some_and(|g| !g.contains(&bare)) {
+                        Some("target is no longer generated")
+                    } else {
+                        None
+                    };
+                    if let Some(reason) = reason {
+                        stale.push(StaleImport { address, reason });
+                        continue;
+                    }
+                }
+            }
+        }
+        kept = kept.add_structure(structure);
+    }
+
+    Ok((hcl::to_string(&kept.build())?, stale))
+}
+
+pub fn print_stale_imports(stale: &[StaleImport], pruned: bool) {
+    for import in stale {
+        let action = if pruned { "removed" } else { "should be removed" };
+        eprintln!("Warning: Import block for '{}' {} ({})", import.address, action, import.reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_imports_compare_for_each_instances() {
+        let imports = r#"
+import {
+  to = google_project_iam_member.iam["user:a@example.com roles/viewer"]
+  id = "p roles/viewer user:a@example.com"
+}
+
+import {
+  to = google_project_iam_member.iam["user:b@example.com roles/viewer"]
+  id = "p roles/viewer user:b@example.com"
+}
+
+import {
+  to = google_storage_bucket.old
+  id = "old"
+}
+"#;
+        let state = serde_json::json!({ "values": { "root_module": { "resources": [
+            { "address": "google_project_iam_member.iam[\"user:a@example.com roles/viewer\"]", "mode": "managed", "values": { "project": "p" } },
+        ] } } });
+        let in_state: BTreeSet<String> = state_resources(&state).into_iter().map(|r| r.instance).collect();
+        let generated: BTreeSet<String> = ["google_project_iam_member.iam".to_string()].into();
+
+        let (kept, stale) = find_stale_imports(imports, Some(&generated), &in_state).unwrap();
+        let stale: Vec<(&str, &str)> = stale.iter().map(|s| (s.address.as_str(), s.reason)).collect();
+        assert_eq!(stale, [
+            ("google_project_iam_member.iam[\"user:a@example.com roles/viewer\"]", "already in state"),
+            ("google_storage_bucket.old", "target is no longer generated"),
+        ]);
+        assert!(kept.contains("user:b@example.com roles/viewer"));
+    }
+
+    #[test]
+    fn changed_instances_lists_created_updated_and_destroyed() {
+        let before = serde_json::json!({ "values": { "root_module": { "resources": [
+            { "address": "google_storage_bucket.kept", "mode": "managed", "values": { "name": "kept" } },
+            { "address": "google_storage_bucket.updated", "mode": "managed", "values": { "name": "u", "labels": { "a": "1" } } },
+            { "address": "google_storage_bucket.destroyed", "mode": "managed", "values": { "name": "d" } },
+        ] } } });
+        let after = serde_json::json!({ "values": { "root_module": {
+            "resources": [
+                { "address": "google_storage_bucket.kept", "mode": "managed", "values": { "name": "kept" } },
+                { "address": "google_storage_bucket.updated", "mode": "managed", "values": { "name": "u", "labels": { "a": "2" } } },
+                { "address": "data.google_project.p", "mode": "data", "values": { "number": "1" } },
+            ],
+            "child_modules": [{ "resources": [
+                { "address": "module.f.google_folder.f[\"x\"]", "mode": "managed", "values": { "display_name": "x" } },
+            ] }],
+        } } });
+
+        assert_eq!(changed_instances(&before, &after), [
+            "google_storage_bucket.destroyed",
+            "google_storage_bucket.updated",
+            "module.f.google_folder.f[\"x\"]",
+        ]);
+        assert!(changed_instances(&serde_json::json!({ "format_version": "1.0" }), &before).len() == 3);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde_json::json;
+use crate::ToolConfig;
+
+/// Keys of config.toml copied as they are; all other string values are replaced.
+const SAFE_CONFIG_KEYS: &[&str] = &[
+    "yaml_dir", "hcl_dir", "include_dirs", "override_dirs", "preset_dirs", "schema_dir", "tf_tool",
+    "google_providers", "aws_providers", "azure_providers", "alibaba_providers", "provider_version",
+    "auto_explode", "validation_level", "language", "discovery_config", "enforce_member_domain",
+    "billing_project_quota", "required_labels", "implicit_dependencies", "implicit_references", "service_label_template", "tfvars_format", "split_output", "sort_attributes", "backend_config_file", "environments", "scan",
+];
+
+/// Number of audit log records included.
+const AUDIT_LOG_LINES: usize = 100;
+
+/// Files of a support bundle (name in the archive, content): environment, redacted config.toml,
+/// schema versions, lock file, last run report, recent audit log and optionally the input. The
+/// run report, the audit log and the input are redacted together, so that the fake values match
+/// across them.
+pub fn collect(config_file: &Path, config_dir: &Path, runtime_config: &ToolConfig, input: Option<(&str, &serde_yaml::Value)>) -> Vec<(String, String)> {
+    let mut entries = vec![("environment.json".to_string(), environment(runtime_config))];
+
+    match fs::read_to_string(config_file) {
+        Ok(content) => entries.push(("config.toml".to_string(), redact_config(&content))),
+        Err(e) => entries.push(("config.toml.missing".to_string(), format!("{}: {}\n", config_file.display(), e))),
+    }
+    entries.push(("schemas.json".to_string(), schemas(runtime_config)));
+    if let Ok(lock) = fs::read_to_string(runtime_config.hcl_dir.join(".terraform.lock.hcl")) {
+        entries.push(("terraform.lock.hcl".to_string(), lock));
+    }
+
+    let mut redacted: Vec<(&str, String)> = Vec::new();
+    if let Ok(report) = fs::read_to_string(crate::run_report::RunReport::path(config_dir)) {
+        redacted.push(("run-report.json", report));
+    }
+    if let Ok(log) = fs::read_to_string(crate::audit::log_path(config_dir)) {
+        redacted.push(("audit.log", recent_audit_records(&log)));
+    }
+    let (content, raw_value) = input.unwrap_or(("", &serde_yaml::Value::Null));
+    if input.is_some() {
+        redacted.push(("redacted.yaml", content.to_string()));
+    }
+    let documents: Vec<&str> = redacted.iter().map(|(_, content)| content.as_str()).collect();
+    let (documents, _) = crate::redact::redact_documents(&documents, raw_value);
+    entries.extend(redacted.iter().zip(documents).map(|((name, _), content)| (name.to_string(), content)));
+    entries
+}
+
+fn environment(runtime_config: &ToolConfig) -> String {
+    let tf_tool_version = std::process::Command::new(&runtime_config.tf_tool).arg("version").output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().next().unwrap_or_default().to_string())
+        .unwrap_or_else(|e| format!("not available: {}", e));
+    let environment = json!({
+        "cfg2hcl_version": env!("CARGO_PKG_VERSION"),
+        "build_date": env!("BUILD_DATE"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "tf_tool": runtime_config.tf_tool,
+        "tf_tool_version": tf_tool_version,
+    });
+    serde_json::to_string_pretty(&environment).unwrap_or_default() + "\n"
+}
+
+/// config.toml with the values of all keys but [`SAFE_CONFIG_KEYS`] replaced: webhooks, buckets,
+/// hook commands and domains can be secret or identifying.
+fn redact_config(content: &str) -> String {
+    let Ok(mut value) = toml::from_str::<toml::Table>(content) else {
+        return "# config.toml could not be parsed and is left out\n".to_string();
+    };
+    for (key, value) in value.iter_mut() {
+        if !SAFE_CONFIG_KEYS.contains(&key.as_str()) {
+            redact_strings(value);
+        }
+    }
+    toml::to_string_pretty(&value).unwrap_or_default()
+}
+
+fn redact_strings(value: &mut toml::Value) {
+    match value {
+        toml::Value::String(s) => *s = "<redacted>".to_string(),
+        toml::Value::Array(items) => items.iter_mut().for_each(redact_strings),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, v)| redact_strings(v)),
+        _ => {}
+    }
+}
+
+/// The configured provider versions and, per schema file, its date and provider resource counts.
+fn schemas(runtime_config: &ToolConfig) -> String {
+    let mut files = BTreeMap::new();
+    if let Ok(dir) = fs::read_dir(&runtime_config.schema_dir) {
+        for path in dir.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "json")) {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| crate::history::date(d.as_secs() as i64));
+            let providers: BTreeMap<String, usize> = fs::read_to_string(&path).ok()
+                .and_then(|content| serde_json::from_str::<crate::schema::Schema>(&content).ok())
+                .map(|schema| schema.provider_schemas.into_iter().map(|(name, p)| (name, p.resource_schemas.len())).collect())
+                .unwrap_or_default();
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            files.insert(name, json!({ "modified": modified, "resource_types": providers }));
+        }
+    }
+    let configured: BTreeMap<String, String> = runtime_config.parsed_providers().into_iter().collect();
+    serde_json::to_string_pretty(&json!({ "configured": configured, "files": files })).unwrap_or_default() + "\n"
+}
+
+/// The last audit records without user, host and working directory, also where they appear in
+/// the command or note; [`collect`] redacts the other identifying values in them.
+fn recent_audit_records(log: &str) -> String {
+    let lines: Vec<&str> = log.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(AUDIT_LOG_LINES)..].iter()
+        .filter_map(|line| {
+            let mut record: serde_json::Value = serde_json::from_str(line).ok()?;
+            // Longest first, as the working directory usually contains the user name
+            let mut identifying: Vec<(String, &str)> = [("working_dir", "<working_dir>"), ("user", "<user>"), ("host", "<host>")].iter()
+                .filter_map(|(key, placeholder)| Some((record.get(*key)?.as_str()?.to_string(), *placeholder)))
+                .filter(|(value, _)| value.len() >= 3)
+                .collect();
+            identifying.sort_by_key(|(value, _)| std::cmp::Reverse(value.len()));
+            for key in ["command", "note"] {
+                if let Some(text) = record.get(key).and_then(|v| v.as_str()) {
+                    let text = identifying.iter().fold(text.to_string(), |text, (value, placeholder)| text.replace(value.as_str(), placeholder));
+                    record[key] = json!(text);
+                }
+            }
+            for key in ["user", "host", "working_dir"] {
+                if record.get(key).is_some() {
+                    record[key] = json!("<redacted>");
+                }
+            }
+            serde_json::to_string(&record).ok()
+        })
+        .map(|line| line + "\n")
+        .collect()
+}
@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+/// Tags resolved by cfg2hcl itself; plugins cannot replace them.
+const BUILTIN_TAGS: &[&str] = &["expr", "ref", "join", "format", "dynamic", "env", "file", "secret", "merge"];
+
+/// A `[tags.<name>]` table of config.toml: an external command that resolves `!<name>` values.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TagPlugin {
+    /// Program and arguments, e.g. `["scripts/vault-tag", "--mount", "kv"]`. A relative program
+    /// path containing `/` is resolved against the config file directory, others via `PATH`.
+    pub command: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    tag: &'a str,
+    values: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    values: Vec<serde_json::Value>,
+}
+
+/// Replaces every value tagged with a plugin tag by what the plugin returns for it. Each plugin
+/// runs once per input, reading `{"tag": "vault", "values": [...]}` on stdin and answering with
+/// `{"values": [...]}` (same order) on stdout. Built-in tags inside a tagged value are resolved
+/// before it is passed on.
+pub fn apply(value: &mut Value, plugins: &BTreeMap<String, TagPlugin>) -> Result<(), Box<dyn std::error::Error>> {
+    if plugins.is_empty() {
+        return Ok(());
+    }
+    for (name, plugin) in plugins {
+        if BUILTIN_TAGS.contains(&name.as_str()) {
+            return Err(format!("Tag plugin '!{}' would replace a built-in tag", name).into());
+        }
+        if plugin.command.is_empty() {
+            return Err(format!("Tag plugin '!{}' has an empty command", name).into());
+        }
+    }
+
+    let mut pending: BTreeMap<&str, Vec<serde_json::Value>> = BTreeMap::new();
+    collect(value, plugins, &mut pending)?;
+
+    let mut resolved: BTreeMap<&str, VecDeque<Value>> = BTreeMap::new();
+    for (name, values) in pending {
+        let count = values.len();
+        let results = run(name, &plugins[name], values)?;
+        if results.len() != count {
+            return Err(format!("Tag plugin '!{}' returned {} value(s) for {} input(s)", name, results.len(), count).into());
+        }
+        let results = results.into_iter().map(serde_yaml::to_value).collect::<Result<_, _>>()?;
+        resolved.insert(name, results);
+    }
+    replace(value, &mut resolved);
+    Ok(())
+}
+
+/// Gathers the (built-in resolved) inputs of every plugin tag in document order.
+fn collect<'a>(value: &Value, plugins: &'a BTreeMap<String, TagPlugin>, pending: &mut BTreeMap<&'a str, Vec<serde_json::Value>>) -> Result<(), Box<dyn std::error::Error>> {
+    match value {
+        Value::Mapping(map) => map.values().try_for_each(|v| collect(v, plugins, pending)),
+        Value::Sequence(seq) => seq.iter().try_for_each(|v| collect(v, plugins, pending)),
+        Value::Tagged(tagged) => match plugins.get_key_value(tagged.tag.to_string().trim_start_matches('!')) {
+            Some((name, _)) => {
+                let input = crate::resolve_yaml_custom_tags(tagged.value.clone())?;
+                pending.entry(name.as_str()).or_default().push(serde_json::to_value(&input)?);
+                Ok(())
+            }
+            None => collect(&tagged.value, plugins, pending),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Substitutes the plugin results, visiting the tags in the same order as [`collect`].
+fn replace(value: &mut Value, resolved: &mut BTreeMap<&str, VecDeque<Value>>) {
+    match value {
+        Value::Mapping(map) => map.values_mut().for_each(|v| replace(v, resolved)),
+        Value::Sequence(seq) => seq.iter_mut().for_each(|v| replace(v, resolved)),
+        Value::Tagged(tagged) => {
+            let tag = tagged.tag.to_string();
+            match resolved.get_mut(tag.trim_start_matches('!')) {
+                Some(results) => {
+                    if let Some(result) = results.pop_front() {
+                        *value = result;
+                    }
+                }
+                None => replace(&mut tagged.value, resolved),
+            }
+        }
+        _ => {}
+    }
+}
+
+fn run(name: &str, plugin: &TagPlugin, values: Vec<serde_json::Value>) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+    status!("Resolving {} !{} value(s) with {}", values.len(), name, plugin.command[0]);
+    let request = serde_json::to_vec(&Request { tag: name, values })?;
+    let mut child = Command::new(&plugin.command[0])
+        .args(&plugin.command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run tag plugin '!{}' ({}): {}", name, plugin.command[0], e))?;
+    // Written from this thread: plugins are expected to read all of stdin before answering
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&request)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!("Tag plugin '!{}' failed ({}): {}", name, output.status, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    let response: Response = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Tag plugin '!{}' returned invalid JSON: {}", name, e))?;
+    Ok(response.values)
+}
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Applies `--quiet`/`--no-color`. Plain output is also chosen by a non-empty `NO_COLOR`
+/// (https://no-color.org) or `TERM=dumb`.
+pub fn init(quiet: bool, no_color: bool) {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let dumb = std::env::var("TERM").is_ok_and(|t| t == "dumb");
+    QUIET.store(quiet, Ordering::Relaxed);
+    PLAIN.store(no_color || no_color_env || dumb, Ordering::Relaxed);
+}
+
+/// With `--quiet`, only errors and what a command produced (files, reports) are printed.
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// The emoji, or its plain-text stand-in when output must stay plain.
+pub fn icon(emoji: &'static str, plain: &'static str) -> &'static str {
+    if PLAIN.load(Ordering::Relaxed) { plain } else { emoji }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use serde::Deserialize;
+
+/// Keys of a variable in the declaration form, see [`Declaration`].
+const DECLARATION_KEYS: &[&str] = &["value", "description", "sensitive", "default", "validation"];
+
+/// Format of the variable value files, `tfvars_format` in config.toml.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TfvarsFormat {
+    #[default]
+    Hcl,
+    Json,
+}
+
+impl TfvarsFormat {
+    pub fn parse(format: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match format {
+            "hcl" => Ok(TfvarsFormat::Hcl),
+            "json" => Ok(TfvarsFormat::Json),
+            other => Err(format!("Unknown tfvars_format '{}' (expected hcl or json)", other).into()),
+        }
+    }
+
+    /// `terraform.tfvars` or `terraform.tfvars.json` for the stem `terraform`.
+    pub fn file_name(self, stem: &str) -> String {
+        match self {
+            TfvarsFormat::Hcl => format!("{}.tfvars", stem),
+            TfvarsFormat::Json => format!("{}.tfvars.json", stem),
+        }
+    }
+}
+
+/// A variable written in the declaration form instead of as a plain value:
+///
+/// ```yaml
+/// variables:
+///   billing-account-infra:
+///     value: &billing-account-infra 000000-111111-222222
+///     description: Billing account of the infrastructure project
+///     sensitive: true
+///     validation:
+///       - condition: can(regex("^[0-9A-F]{6}-[0-9A-F]{6}-[0-9A-F]{6}$", var.billing-account-infra))
+///         error_message: Not a billing account ID
+/// ```
+///
+/// A mapping is taken as a declaration if it has only these keys and a `value` or `default`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Declaration {
+    /// Written to the tfvars file; without it Terraform uses the `default`
+    pub value: Option<serde_yaml::Value>,
+    /// Takes precedence over the YAML comment of the variable
+    pub description: Option<String>,
+    #[serde(default)]
+    pub sensitive: bool,
+    pub default: Option<serde_yaml::Value>,
+    #[serde(default)]
+    pub validation: Vec<Validation>,
+}
+
+/// A `validation` block of a variable declaration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Validation {
+    /// HCL expression, e.g. `length(var.region) > 0`
+    pub condition: String,
+    pub error_message: String,
+}
+
+/// The declaration of a variable given in the declaration form, `None` for plain values.
+pub fn declaration(value: &serde_yaml::Value) -> Result<Option<Declaration>, serde_yaml::Error> {
+    let Some(mapping) = value.as_mapping() else { return Ok(None) };
+    let Some(keys) = mapping.keys().map(|k| k.as_str()).collect::<Option<Vec<&str>>>() else { return Ok(None) };
+    let declaration_form = keys.iter().all(|k| DECLARATION_KEYS.contains(k)) && keys.iter().any(|k| *k == "value" || *k == "default");
+    if !declaration_form {
+        return Ok(None);
+    }
+    serde_yaml::from_value(value.clone()).map(Some)
+}
+
+/// The value of a variable: its `value` (or `default`) if it is written in the declaration form.
+pub fn variable_value(value: &serde_yaml::Value) -> serde_yaml::Value {
+    match declaration(value) {
+        Ok(Some(declaration)) => declaration.value.or(declaration.default).unwrap_or(serde_yaml::Value::Null),
+        _ => value.clone(),
+    }
+}
+
+/// Type of the `variable` declaration of a value: `bool`, `number` and `string` for scalars,
+/// `list(string)` and `map(string)` for lists and maps of scalars, `any` for everything else.
+/// Lists and maps of mixed scalars are declared with `string` elements, as Terraform converts
+/// numbers and booleans to strings but not back.
+pub fn declared_type(value: &hcl::Expression) -> &'static str {
+    match value {
+        hcl::Expression::Bool(_) => "bool",
+        hcl::Expression::Number(_) => "number",
+        hcl::Expression::Null | hcl::Expression::String(_) => "string",
+        hcl::Expression::Array(items) if items.iter().all(is_scalar) => "list(string)",
+        hcl::Expression::Object(object) if object.values().all(is_scalar) => "map(string)",
+        _ => "any",
+    }
+}
+
+fn is_scalar(value: &hcl::Expression) -> bool {
+    matches!(value, hcl::Expression::Bool(_) | hcl::Expression::Number(_) | hcl::Expression::String(_))
+}
+
+/// The `type` expression of a declared type: `string` or `list(string)`.
+pub fn type_expression(declared_type: &str) -> hcl::Expression {
+    match declared_type.strip_suffix("(string)") {
+        Some(collection) => hcl::FuncCall::builder(hcl::Identifier::unchecked(collection))
+            .arg(hcl::Variable::unchecked("string"))
+            .build()
+            .into(),
+        None => hcl::Variable::unchecked(declared_type).into(),
+    }
+}
+
+/// A value as its declaration expects it: numbers and booleans of `string` variables and in
+/// `list(string)`/`map(string)` variables are quoted.
+pub fn typed(value: hcl::Expression, declared_type: &str) -> hcl::Expression {
+    match value {
+        hcl::Expression::Bool(b) if declared_type == "string" => hcl::Expression::String(b.to_string()),
+        hcl::Expression::Number(n) if declared_type == "string" => hcl::Expression::String(n.to_string()),
+        hcl::Expression::Array(items) if declared_type == "list(string)" => {
+            hcl::Expression::Array(items.into_iter().map(|v| typed(v, "string")).collect())
+        }
+        hcl::Expression::Object(object) if declared_type == "map(string)" => {
+            hcl::Expression::Object(object.into_iter().map(|(k, v)| (k, typed(v, "string"))).collect())
+        }
+        value => value,
+    }
+}
+
+/// Variable values as a tfvars file. HCL strings are escaped by hcl-rs (quotes, newlines and
+/// `${`/`%{`, which a tfvars file must not interpolate); JSON values are taken literally by
+/// Terraform and need no template escaping.
+pub fn render(values: &BTreeMap<String, hcl::Expression>, format: TfvarsFormat) -> Result<String, Box<dyn std::error::Error>> {
+    if values.is_empty() {
+        return Ok(String::new());
+    }
+    match format {
+        TfvarsFormat::Hcl => {
+            let mut body = hcl::Body::builder();
+            for (key, value) in values {
+                body = body.add_attribute((key.as_str(), value.clone()));
+            }
+            Ok(hcl::to_string(&body.build())?)
+        }
+        TfvarsFormat::Json => {
+            let object: serde_json::Map<String, serde_json::Value> = values.iter()
+                .map(|(key, value)| (key.clone(), to_json(key, value)))
+                .collect();
+            Ok(serde_json::to_string_pretty(&object)? + "\n")
+        }
+    }
+}
+
+fn to_json(key: &str, value: &hcl::Expression) -> serde_json::Value {
+    match value {
+        hcl::Expression::Null => serde_json::Value::Null,
+        hcl::Expression::Bool(b) => serde_json::Value::Bool(*b),
+        hcl::Expression::Number(n) => serde_json::to_value(n).unwrap_or(serde_json::Value::Null),
+        hcl::Expression::String(s) => serde_json::Value::String(s.clone()),
+        hcl::Expression::Array(items) => items.iter().map(|item| to_json(key, item)).collect(),
+        hcl::Expression::Object(object) => serde_json::Value::Object(object.iter()
+            .map(|(k, v)| (object_key(k), to_json(key, v)))
+            .collect()),
+        other => {
+            // terraform.tfvars.json holds values, not expressions
+            let text = hcl::format::to_string(other).unwrap_or_default();
+            eprintln!("Warning: Variable '{}' contains the expression '{}', which terraform.tfvars.json can only hold as a string.", key, text);
+            serde_json::Value::String(text)
+        }
+    }
+}
+
+fn object_key(key: &hcl::ObjectKey) -> String {
+    match key {
+        hcl::ObjectKey::Identifier(ident) => ident.to_string(),
+        hcl::ObjectKey::Expression(hcl::Expression::String(s)) => s.clone(),
+        hcl::ObjectKey::Expression(expr) => hcl::format::to_string(expr).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
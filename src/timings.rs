@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Time per phase, in the order the phases first ran: (name, time including nested phases, runs).
+static TOTALS: Mutex<Vec<(String, Duration, usize)>> = Mutex::new(Vec::new());
+
+/// Time per stack of phases (`transpile;hcl generation;validation`) without nested phases.
+static FOLDED: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+struct Frame {
+    name: String,
+    start: Instant,
+    /// Time spent in nested phases
+    nested: Duration,
+}
+
+thread_local! {
+    /// The phases running on this thread, outermost first.
+    static STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A running phase; its time is recorded when dropped. Does nothing unless timings are recorded.
+pub struct Span {
+    active: bool,
+}
+
+/// Starts a phase of the current command (`include processing`, `schema load`, ...). Phases can
+/// be nested; a phase that runs several times is summed up.
+pub fn span(name: &str) -> Span {
+    let active = ENABLED.load(Ordering::Relaxed);
+    if active {
+        STACK.with(|s| s.borrow_mut().push(Frame { name: name.to_string(), start: Instant::now(), nested: Duration::ZERO }));
+    }
+    Span { active }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+        STACK.with(|s| {
+            let mut stack = s.borrow_mut();
+            let Some(frame) = stack.pop() else { return };
+            let elapsed = frame.start.elapsed();
+            let stack_key = stack.iter().map(|f| f.name.as_str()).chain([frame.name.as_str()]).collect::<Vec<_>>().join(";");
+            // A phase nested in itself is counted once in the totals
+            let recursive = stack.iter().any(|f| f.name == frame.name);
+            if let Some(parent) = stack.last_mut() {
+                parent.nested += elapsed;
+            }
+            drop(stack);
+
+            let mut folded = FOLDED.lock().unwrap_or_else(|e| e.into_inner());
+            match folded.iter_mut().find(|(key, _)| *key == stack_key) {
+                Some((_, time)) => *time += elapsed.saturating_sub(frame.nested),
+                None => folded.push((stack_key, elapsed.saturating_sub(frame.nested))),
+            }
+            if recursive {
+                return;
+            }
+            let mut totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+            match totals.iter_mut().find(|(name, _, _)| *name == frame.name) {
+                Some((_, time, runs)) => {
+                    *time += elapsed;
+                    *runs += 1;
+                }
+                None => totals.push((frame.name, elapsed, 1)),
+            }
+        });
+    }
+}
+
+/// Prints the timings (`--timings`) and writes the folded stacks (`--profile-run`) when dropped,
+/// also when the command fails.
+pub struct Recording {
+    print: bool,
+    profile: Option<PathBuf>,
+}
+
+/// Records the phases of this run if `--timings` or `--profile-run <FILE>` was given.
+pub fn record(print: bool, profile: Option<PathBuf>) -> Option<Recording> {
+    if !print && profile.is_none() {
+        return None;
+    }
+    ENABLED.store(true, Ordering::Relaxed);
+    Some(Recording { print, profile })
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        ENABLED.store(false, Ordering::Relaxed);
+        if self.print {
+            // stderr, so that `--emit json` and the like stay parseable
+            let totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+            let width = totals.iter().map(|(name, _, _)| name.len()).max().unwrap_or_default();
+            eprintln!("Timings:");
+            for (name, time, runs) in totals.iter() {
+                let runs = if *runs > 1 { format!(" ({} runs)", runs) } else { String::new() };
+                eprintln!("  {:<width$}  {:>10.1} ms{}", name, time.as_secs_f64() * 1000.0, runs, width = width);
+            }
+        }
+        if let Some(path) = &self.profile {
+            // One `stack microseconds` line per stack, as read by flamegraph.pl and inferno
+            let folded = FOLDED.lock().unwrap_or_else(|e| e.into_inner());
+            let content: String = folded.iter().map(|(stack, time)| format!("{} {}\n", stack, time.as_micros())).collect();
+            match std::fs::write(path, content) {
+                Ok(()) => eprintln!("Profile written to {} (render with flamegraph.pl or inferno-flamegraph)", path.display()),
+                Err(e) => eprintln!("Failed to write profile '{}': {}", path.display(), e),
+            }
+        }
+    }
+}
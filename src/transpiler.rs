@@ -1,23 +1,149 @@
-use std::collections::HashMap;
-use crate::config::{Config, Folder, Project};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use crate::config::{Account, Config, DefaultLabels, Folder, LabelMode, OrganizationalUnit, Project};
 use crate::schema::ResourceRegistry;
+use crate::i18n::t;
 
 pub struct GeneratedProject {
-    pub main_tf: String,
-    pub providers_tf: String,
-    pub variables_tf: String,
-    pub tfvars: String,
-    pub imports_tf: String,
+    /// Every generated file by name: the resource files (`main.tf`, the files of
+    /// [`crate::split::SplitBy`], `modules/<key>/*.tf`), `providers.tf`, `backend.hcl`,
+    /// `variables.tf`, `outputs.tf`, the tfvars files and `imports.tf`. Files without content are
+    /// kept, so that writing the project removes their previous version.
+    pub files: BTreeMap<String, String>,
+    /// Resources without Terraform support, applied via `scripts/apply-extras.sh`
+    pub unsupported: Vec<crate::extras::UnsupportedResource>,
+    /// Resource address -> YAML path of the entry that produced it
+    pub sources: BTreeMap<String, String>,
+    /// YAML path of each scope with an `owner:` -> its owners
+    pub owners: BTreeMap<String, Vec<String>>,
+}
+
+/// `.tf` files of a generated project that hold no resources.
+const NON_RESOURCE_FILES: &[&str] = &["providers.tf", "variables.tf", "outputs.tf", "imports.tf"];
+
+impl GeneratedProject {
+    /// Whether `name` is a file with resources, locals and modules (`main.tf`, a split file or a
+    /// file of a folder module).
+    pub fn is_resource_file(name: &str) -> bool {
+        name.ends_with(".tf") && !NON_RESOURCE_FILES.contains(&name)
+    }
+
+    /// Position of a file in the written order: `main.tf` (it has the locals), the other root
+    /// resource files, the module files, then the files around them.
+    fn file_order(name: &str) -> u8 {
+        match name {
+            "main.tf" => 0,
+            "providers.tf" => 3,
+            crate::output::BACKEND_CONFIG_FILE => 4,
+            "variables.tf" => 5,
+            "outputs.tf" => 6,
+            "imports.tf" => 8,
+            _ if name.starts_with("modules/") => 2,
+            _ if Self::is_resource_file(name) => 1,
+            _ => 7,
+        }
+    }
+
+    /// The generated files by name, in the order they are written; empty ones are left out.
+    pub fn files(&self) -> Vec<(&str, &str)> {
+        let mut files: Vec<(&str, &str)> = self.files.iter()
+            .filter(|(_, content)| !content.trim().is_empty())
+            .map(|(name, content)| (name.as_str(), content.as_str()))
+            .collect();
+        files.sort_by_key(|(name, _)| Self::file_order(name));
+        files
+    }
+
+    /// Content of a generated file; empty if there is none.
+    pub fn file(&self, name: &str) -> &str {
+        self.files.get(name).map_or("", String::as_str)
+    }
+
+    /// Adds the YAML comments of each resource's entry in `content` (the include-expanded YAML) to
+    /// the resource files, see [`crate::yaml_comments::annotate`].
+    pub fn annotate(&mut self, content: &str) {
+        for (name, file) in self.files.iter_mut() {
+            if Self::is_resource_file(name) {
+                *file = crate::yaml_comments::annotate(file, &self.sources, content);
+            }
+        }
+    }
+
+    /// All resource files as one HCL text, for checks over every generated resource.
+    pub fn resources_tf(&self) -> String {
+        self.files().into_iter()
+            .filter(|(name, _)| Self::is_resource_file(name))
+            .map(|(_, content)| content)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The generated blocks with their addresses and YAML paths, see [`crate::model::Model`].
+    pub fn model(&self) -> Result<crate::model::Model, Box<dyn std::error::Error>> {
+        crate::model::Model::new(self)
+    }
+
+    /// Formats the `.tf` and `.tfvars` files and `backend.hcl`, see [`crate::formatter::format`].
+    pub fn format(&mut self, sort_attributes: bool) {
+        let _span = crate::timings::span("format");
+        for (name, content) in self.files.iter_mut() {
+            if name.ends_with(".tf") || name.ends_with(".tfvars") || name == crate::output::BACKEND_CONFIG_FILE {
+                *content = crate::formatter::format(content, sort_attributes);
+            }
+        }
+    }
+}
+
+/// Label of exploded `google_project_service` resources unless `service_label_template` is set.
+pub const DEFAULT_SERVICE_LABEL_TEMPLATE: &str = "{project}_{service}";
+
+/// A problem in the YAML that prevents generating a resource.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// YAML path of the offending entry (e.g. `folder.infra.google_org_policy_policy.restrict`)
+    pub yaml_path: String,
+    pub message: String,
 }
 
 pub struct Transpiler<'a> {
     config: &'a Config,
-    registry: Option<ResourceRegistry>,
+    /// Shared so that `serve` loads the schemas once for all requests
+    registry: Option<Arc<ResourceRegistry>>,
     auto_explode: Vec<String>,
     validation_level: String,
     variables: HashMap<String, serde_yaml::Value>,
     provider_sources: HashMap<String, String>,
     provider_versions: HashMap<String, String>,
+    required_labels: Vec<String>,
+    /// Add `depends_on` from the dependency knowledge base, see `dependencies.rs`
+    implicit_dependencies: bool,
+    /// Compatibility mode: plain strings that look like references are still read as references
+    /// in reference contexts (with a warning) instead of needing `!ref`/`!expr`
+    implicit_references: bool,
+    service_label_template: String,
+    /// Earlier service label templates, oldest first; resources get `moved` blocks through them
+    previous_service_label_templates: Vec<String>,
+    /// Variable name -> `description` in variables.tf (from the YAML comments)
+    variable_descriptions: HashMap<String, String>,
+    /// Variables in the declaration form, with description, default, sensitive and validation
+    variable_declarations: HashMap<String, crate::tfvars::Declaration>,
+    tfvars_format: crate::tfvars::TfvarsFormat,
+    /// Environment name -> its variables, written to `<name>.tfvars`
+    environment_variables: BTreeMap<String, HashMap<String, serde_yaml::Value>>,
+    split_by: crate::split::SplitBy,
+    /// Write the backend settings to backend.hcl instead of providers.tf
+    backend_config_file: bool,
+    unsupported: RefCell<Vec<crate::extras::UnsupportedResource>>,
+    /// Errors collected while transpiling; reported together at the end
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    sources: RefCell<BTreeMap<String, String>>,
+    /// `!ref` values that may be YAML keys (`infra.project_id`), see [`Self::resolve_key_references`]
+    key_references: RefCell<BTreeSet<String>>,
+    /// Secrets read by `!secret` values, emitted as data sources after the locals
+    secrets: RefCell<BTreeSet<crate::secret_tag::SecretRef>>,
+    /// Short YAML key -> resource type, see `aliases.rs`
+    resource_aliases: BTreeMap<String, String>,
 }
 
 #[derive(Clone, Default)]
@@ -28,46 +154,143 @@ struct ResourceContext {
     org_ref: Option<String>,
     folder_ref: Option<String>,
     project_ref: Option<String>,
+    /// ID of the enclosing AWS organizational unit, or of the organization root
+    aws_parent_ref: Option<String>,
     provider_alias: Option<String>,
+    /// Inherited `provider-alias:` routes: resource type (or `default`) -> provider
+    provider_routes: BTreeMap<String, String>,
+    /// Inherited `default_labels:` with `mode: provider`, the `default_labels` of the generated project providers
+    provider_labels: BTreeMap<String, String>,
+    /// Inherited `protect:` switch (prevent_destroy / deletion protection)
+    protect: bool,
+    /// Inherited `default_labels:` to merge into resources (narrowest scope wins per key)
+    labels: BTreeMap<String, String>,
+    /// Inherited `defaults:` block (narrowest scope wins per attribute)
+    defaults: crate::config::Defaults,
+    /// `iam-mode:` of the current scope (not inherited)
+    iam_mode: crate::config::IamMode,
+    /// YAML path of the current scope (e.g. `folder.infra.project.infra`), empty at the root
+    yaml_path: String,
 }
 
 impl<'a> Transpiler<'a> {
     pub fn new(
         config: &'a Config,
-        registry: Option<ResourceRegistry>,
+        registry: Option<Arc<ResourceRegistry>>,
         auto_explode: Vec<String>,
         validation_level: String,
         variables: HashMap<String, serde_yaml::Value>,
         provider_sources: HashMap<String, String>,
         provider_versions: HashMap<String, String>,
     ) -> Self {
-        Self { config, registry, auto_explode, validation_level, variables, provider_sources, provider_versions }
+        Self { config, registry, auto_explode, validation_level, variables, provider_sources, provider_versions, required_labels: Vec::new(), implicit_dependencies: true, implicit_references: false, service_label_template: DEFAULT_SERVICE_LABEL_TEMPLATE.to_string(), previous_service_label_templates: Vec::new(), variable_descriptions: HashMap::new(), variable_declarations: HashMap::new(), tfvars_format: Default::default(), environment_variables: BTreeMap::new(), split_by: Default::default(), backend_config_file: false, unsupported: RefCell::new(Vec::new()), diagnostics: RefCell::new(Vec::new()), sources: RefCell::new(BTreeMap::new()), key_references: RefCell::new(BTreeSet::new()), secrets: RefCell::new(BTreeSet::new()), resource_aliases: crate::aliases::resource_aliases(&BTreeMap::new()) }
+    }
+
+    /// Label keys every labelled resource must carry (after `default_labels` are merged).
+    pub fn with_required_labels(mut self, required_labels: Vec<String>) -> Self {
+        self.required_labels = required_labels;
+        self
+    }
+
+    /// Whether required services and known orderings are added as `depends_on` (default: on).
+    pub fn with_implicit_dependencies(mut self, enabled: bool) -> Self {
+        self.implicit_dependencies = enabled;
+        self
+    }
+
+    /// Whether reference-like plain strings still become references, with a warning (default: off).
+    pub fn with_implicit_references(mut self, enabled: bool) -> Self {
+        self.implicit_references = enabled;
+        self
     }
 
+    /// Label template of `google_project_service` resources, and the templates used before it
+    /// (oldest first) to emit `moved` blocks from.
+    pub fn with_service_label_template(mut self, template: String, previous: Vec<String>) -> Self {
+        self.service_label_template = template;
+        self.previous_service_label_templates = previous;
+        self
+    }
+
+    /// Descriptions of the variables, see [`crate::yaml_comments::variable_descriptions`].
+    pub fn with_variable_descriptions(mut self, descriptions: HashMap<String, String>) -> Self {
+        self.variable_descriptions = descriptions;
+        self
+    }
+
+    /// Short YAML keys of resource types (`bucket` -> `google_storage_bucket`), resolved before
+    /// the schema lookup; defaults to the built-in aliases.
+    pub fn with_resource_aliases(mut self, aliases: BTreeMap<String, String>) -> Self {
+        self.resource_aliases = aliases;
+        self
+    }
+
+    /// Variables in the declaration form, see [`crate::tfvars::Declaration`].
+    pub fn with_variable_declarations(mut self, declarations: HashMap<String, crate::tfvars::Declaration>) -> Self {
+        self.variable_declarations = declarations;
+        self
+    }
+
+    /// Format of the tfvars files and the variables of each environment, which get a
+    /// `<environment>.tfvars` next to `terraform.tfvars`.
+    pub fn with_tfvars(mut self, format: crate::tfvars::TfvarsFormat, environment_variables: BTreeMap<String, HashMap<String, serde_yaml::Value>>) -> Self {
+        self.tfvars_format = format;
+        self.environment_variables = environment_variables;
+        self
+    }
+
+    /// How the resources are spread over files (default: all in `main.tf`).
+    pub fn with_split_by(mut self, split_by: crate::split::SplitBy) -> Self {
+        self.split_by = split_by;
+        self
+    }
+
+    /// Backend settings in backend.hcl instead of providers.tf (default: inline).
+    pub fn with_backend_config_file(mut self, enabled: bool) -> Self {
+        self.backend_config_file = enabled;
+        self
+    }
+
+    /// A reference (`google_folder.infra.id`, `var.regions[0]`, `google_x.y["k"].id`) as a
+    /// traversal; anything the HCL parser does not read as one (`folders/123`, e-mail addresses,
+    /// plain names) stays a string.
     fn parse_hcl_expr(&self, s: &str) -> hcl::Expression {
-        if s.contains('.') && !s.contains('/') && !s.contains(':') {
-            let parts: Vec<&str> = s.split('.').collect();
-            if let Ok(var) = hcl::Variable::new(parts[0]) {
-                let mut operators = Vec::new();
-                for part in &parts[1..] {
-                    if let Ok(ident) = hcl::Identifier::new(*part) {
-                        operators.push(hcl::TraversalOperator::GetAttr(ident));
-                    } else {
-                        return hcl::Expression::from(s.to_string());
-                    }
-                }
-                return hcl::Expression::Traversal(Box::new(hcl::Traversal::new(var, operators)));
+        match s.parse::<hcl::Expression>() {
+            Ok(expr @ hcl::Expression::Traversal(_)) => expr,
+            _ => hcl::Expression::from(s.to_string()),
+        }
+    }
+
+    /// A value given in the YAML where a reference is expected (output values, exploded project
+    /// keys, parents): `!ref`/`!expr` are expressions, plain strings stay strings unless
+    /// `implicit_references` is on and they read as a reference.
+    fn reference_value(&self, v: &serde_yaml::Value, yaml_path: &str) -> Option<hcl::Expression> {
+        match v {
+            serde_yaml::Value::String(s) => Some(self.reference(s, yaml_path)),
+            v => self.yaml_to_hcl_value(v),
+        }
+    }
+
+    /// A plain string of a reference context, see [`Self::reference_value`].
+    fn reference(&self, s: &str, yaml_path: &str) -> hcl::Expression {
+        match self.parse_hcl_expr(s) {
+            expr @ hcl::Expression::Traversal(_) if self.implicit_references => {
+                crate::warnings::warn(t("implicit-reference-group", &[]), yaml_path,
+                    t("implicit-reference", &[("value", &s), ("path", &yaml_path)]));
+                expr
             }
+            _ => hcl::Expression::from(s.to_string()),
         }
-        hcl::Expression::from(s.to_string())
     }
 
     pub fn transpile(&self) -> Result<GeneratedProject, Box<dyn std::error::Error>> {
+        let _span = crate::timings::span("hcl generation");
         let mut main_blocks: Vec<hcl::Block> = Vec::new();
         let mut provider_blocks: Vec<hcl::Block> = Vec::new();
         let mut variable_blocks: Vec<hcl::Block> = Vec::new();
+        let mut output_blocks: Vec<hcl::Block> = Vec::new();
         let mut import_blocks: Vec<hcl::Block> = Vec::new();
-        let mut tfvars_lines: Vec<String> = Vec::new();
+        let mut backend_hcl = String::new();
 
         // Terraform Block (Backend)
         // Terraform Block (Backend & Settings)
@@ -86,16 +309,22 @@ impl<'a> Transpiler<'a> {
                                          // Only include the backend block that matches the current mode
                                          if (mode == "local" && be_type_str == "local") || (mode == "cloud" && be_type_str == "gcs") {
                                              let mut be_builder = hcl::Block::builder("backend").add_label(be_type_str);
+                                             let mut settings = hcl::Body::builder();
                                              if let serde_yaml::Value::Mapping(c_map) = be_config {
                                                  for (ck, cv) in c_map {
                                                      if let serde_yaml::Value::String(cks) = ck {
                                                          if let Some(cval) = self.yaml_to_hcl_value(cv) {
-                                                             be_builder = be_builder.add_attribute((cks.as_str(), cval));
+                                                             if self.backend_config_file {
+                                                                 settings = settings.add_attribute((cks.as_str(), cval));
+                                                             } else {
+                                                                 be_builder = be_builder.add_attribute((cks.as_str(), cval));
+                                                             }
                                                          }
                                                      }
                                                  }
                                              }
                                              tf_block = tf_block.add_block(be_builder.build());
+                                             backend_hcl = hcl::to_string(&settings.build())?;
                                          }
                                      }
                                  }
@@ -118,7 +347,9 @@ impl<'a> Transpiler<'a> {
             if !has_required_providers {
                 if let Some(providers) = &self.config.providers {
                     let mut rp_builder = hcl::Block::builder("required_providers");
-                    for p_name in providers.keys() {
+                    let mut names: Vec<&String> = providers.keys().collect();
+                    names.sort();
+                    for p_name in names {
                         if let Some(source) = self.provider_sources.get(p_name) {
                             let mut p_map = hcl::Map::new();
                             p_map.insert("source".to_string(), hcl::Value::from(source.clone()));
@@ -137,12 +368,14 @@ impl<'a> Transpiler<'a> {
         }
 
         // Providers
+        let root_provider_labels = inherit_labels(&BTreeMap::new(), labels_in_mode(self.config.default_labels.as_ref(), LabelMode::Provider));
         if let Some(providers) = &self.config.providers {
             let mut sorted_providers: Vec<_> = providers.keys().collect();
             sorted_providers.sort();
 
             for p_name in sorted_providers {
                 let p_val = providers.get(p_name).unwrap();
+                let overridden: Vec<String> = crate::endpoints::provider_attributes(p_name).into_iter().map(|(a, _)| a).collect();
                 match p_val {
                     serde_yaml::Value::Sequence(seq) => {
                         for item in seq {
@@ -152,14 +385,18 @@ impl<'a> Transpiler<'a> {
                                 let mut project_id = None;
                                 let mut has_billing_project = false;
                                 let mut has_user_project_override = false;
+                                let mut has_default_labels = false;
 
                                 for (k, v) in map {
                                     if let serde_yaml::Value::String(k_str) = k {
                                         if k_str == "alias" { has_alias = true; }
+                                        if k_str == "default_labels" { has_default_labels = true; }
                                         if k_str == "project" { project_id = v.as_str().map(|s| s.to_string()); }
                                         if k_str == "billing_project" { has_billing_project = true; }
                                         if k_str == "user_project_override" { has_user_project_override = true; }
 
+                                        // Endpoint overrides replace the YAML value
+                                        if overridden.contains(k_str) { continue; }
                                         if let Some(val) = self.yaml_to_hcl_value(v) {
                                             builder = builder.add_attribute((k_str.as_str(), val));
                                         }
@@ -170,7 +407,7 @@ impl<'a> Transpiler<'a> {
                                  }
 
                                  if p_name == "google" || p_name == "google-beta" {
-                                     builder = self.configure_google_provider(builder, project_id, has_billing_project, has_user_project_override);
+                                     builder = self.configure_google_provider(builder, p_name, project_id, has_billing_project, has_user_project_override, (!has_default_labels).then_some(&root_provider_labels));
                                  }
 
                                  provider_blocks.push(builder.build());
@@ -183,14 +420,18 @@ impl<'a> Transpiler<'a> {
                         let mut project_id = None;
                         let mut has_billing_project = false;
                         let mut has_user_project_override = false;
+                        let mut has_default_labels = false;
 
                         for (k, v) in map {
                             if let serde_yaml::Value::String(k_str) = k {
                                 if k_str == "alias" { has_alias = true; }
+                                if k_str == "default_labels" { has_default_labels = true; }
                                 if k_str == "project" { project_id = v.as_str().map(|s| s.to_string()); }
                                 if k_str == "billing_project" { has_billing_project = true; }
                                 if k_str == "user_project_override" { has_user_project_override = true; }
 
+                                // Endpoint overrides replace the YAML value
+                                if overridden.contains(k_str) { continue; }
                                 if let Some(val) = self.yaml_to_hcl_value(v) {
                                      builder = builder.add_attribute((k_str.as_str(), val));
                                 }
@@ -201,7 +442,7 @@ impl<'a> Transpiler<'a> {
                         }
 
                         if p_name == "google" || p_name == "google-beta" {
-                            builder = self.configure_google_provider(builder, project_id, has_billing_project, has_user_project_override);
+                            builder = self.configure_google_provider(builder, p_name, project_id, has_billing_project, has_user_project_override, (!has_default_labels).then_some(&root_provider_labels));
                         }
 
                         provider_blocks.push(builder.build());
@@ -210,19 +451,41 @@ impl<'a> Transpiler<'a> {
                 }
             }
         }
+        // Everything after the providers of the YAML is generated per project
+        let root_providers_end = provider_blocks.len();
 
         // Root Context
         let cust_org_id = self.config.extra.get("customer-organization-id")
             .and_then(|v| v.as_str())
-            .unwrap_or_else(|| panic!("Missing 'customer-organization-id' in configuration"));
+            .ok_or("Missing 'customer-organization-id' in configuration")?;
 
         let root_ctx = ResourceContext {
             org_id: Some(cust_org_id.to_string()),
             org_ref: Some(format!("organizations/{}", cust_org_id)),
             provider_alias: Some("google.google".to_string()),
+            provider_labels: root_provider_labels.clone(),
+            protect: self.config.extra.get("protect").and_then(|v| v.as_bool()).unwrap_or(false),
+            labels: inherit_labels(
+                &inherit_labels(&BTreeMap::new(), self.config.defaults.as_ref().and_then(|d| d.labels.as_ref())),
+                labels_in_mode(self.config.default_labels.as_ref(), LabelMode::Merge),
+            ),
+            defaults: crate::config::Defaults::default().overlay(self.config.defaults.as_ref()),
+            iam_mode: match self.config.extra.get("iam-mode") {
+                Some(mode) => serde_yaml::from_value(mode.clone()).unwrap_or_else(|_| {
+                    self.error_at("iam-mode", t("iam-mode-invalid", &[]));
+                    Default::default()
+                }),
+                None => Default::default(),
+            },
             ..Default::default()
         };
 
+        // Locals (first in main.tf)
+        if let Some(locals) = &self.config.locals {
+            self.transpile_locals(&mut main_blocks, locals);
+        }
+        let data_sources_at = main_blocks.len();
+
         // Organization Policies (google_org_policy_policy)
         if let Some(policies) = &self.config.org_policy_policy {
             let schema = if let Some(reg) = &self.registry {
@@ -252,6 +515,22 @@ impl<'a> Transpiler<'a> {
             );
         }
 
+        // Legacy organization policies (google_organization_policy)
+        if let Some(policies) = &self.config.google_organization_policy {
+            let schema = self.registry.as_ref().and_then(|reg| reg.find_resource("google_organization_policy")).map(|(_, s)| s);
+            let map: serde_yaml::Mapping = policies.iter().map(|(k, v)| (serde_yaml::Value::String(k.clone()), v.clone())).collect();
+            self.transpile_mapping_resources(
+                &mut main_blocks,
+                &mut provider_blocks,
+                &mut import_blocks,
+                "google_organization_policy",
+                &map,
+                schema,
+                &root_ctx,
+                root_ctx.provider_alias.as_deref(),
+            );
+        }
+
         // Organization IAM
         if let Some(iam_members) = &self.config.organization_iam_member {
             self.transpile_iam_members(&mut main_blocks, &mut import_blocks, iam_members, "google_organization_iam_member", "org_id", &root_ctx, root_ctx.provider_alias.as_deref(), None);
@@ -259,30 +538,7 @@ impl<'a> Transpiler<'a> {
 
         // Billing Account IAM
         if let Some(val) = &self.config.billing_account_iam_member {
-            let mut members_map = HashMap::new();
-            let mut explicit_id = None;
-
-            if let serde_yaml::Value::Mapping(map) = val {
-                for (k, v) in map {
-                    if let serde_yaml::Value::String(k_str) = k {
-                        if k_str == "billing_account_id" {
-                             if let serde_yaml::Value::String(s) = v {
-                                 explicit_id = Some(s.clone());
-                             }
-                        } else if let serde_yaml::Value::Sequence(seq) = v {
-                            members_map.insert(k_str.clone(), seq.clone());
-                        }
-                    }
-                }
-            }
-            // If explicit ID is not in YAML, checking variable override or falling back to assumption
-            if explicit_id.is_none() {
-                 if let Some(ba) = self.variables.get("billing-account-infra").and_then(|v| v.as_str()) {
-                     explicit_id = Some(ba.to_string());
-                 }
-            }
-
-            self.transpile_iam_members(&mut main_blocks, &mut import_blocks, &members_map, "google_billing_account_iam_member", "billing_account_id", &root_ctx, root_ctx.provider_alias.as_deref(), explicit_id);
+            self.transpile_billing_iam(&mut main_blocks, &mut import_blocks, val, &root_ctx, root_ctx.provider_alias.as_deref());
         }
 
         // Folders and Projects
@@ -297,51 +553,173 @@ impl<'a> Transpiler<'a> {
             self.transpile_google_project(&mut main_blocks, &mut provider_blocks, &mut import_blocks, projects, &root_ctx);
         }
 
+        // AWS Organizations: organizational units and accounts
+        if self.config.organizational_unit.is_some() || self.config.account.is_some() {
+            let root_id = match self.config.extra.get("aws-organization-root-id").and_then(|v| v.as_str()) {
+                Some(id) => id.to_string(),
+                None => {
+                    main_blocks.insert(data_sources_at, hcl::Block::builder("data")
+                        .add_label("aws_organizations_organization")
+                        .add_label("this")
+                        .build());
+                    "data.aws_organizations_organization.this.roots[0].id".to_string()
+                }
+            };
+            let aws_ctx = ResourceContext {
+                aws_parent_ref: Some(root_id),
+                provider_alias: self.root_provider("aws"),
+                protect: root_ctx.protect,
+                labels: root_ctx.labels.clone(),
+                defaults: root_ctx.defaults.clone(),
+                ..Default::default()
+            };
+            if let Some(units) = &self.config.organizational_unit {
+                self.transpile_aws_organizational_unit(&mut main_blocks, &mut provider_blocks, &mut import_blocks, units, &aws_ctx);
+            }
+            if let Some(accounts) = &self.config.account {
+                self.transpile_aws_account(&mut main_blocks, &mut provider_blocks, &mut import_blocks, accounts, &aws_ctx);
+            }
+        }
+
         // Root Generic Resources
         // Use google.google as default root provider to match ci.py and state
         self.transpile_generic_resources(&mut main_blocks, &mut provider_blocks, &mut import_blocks, &self.config.extra, &root_ctx, Some("google.google"));
 
+        // Modules
+        if let Some(modules) = &self.config.module {
+            self.transpile_modules(&mut main_blocks, modules);
+        }
+
+        // Outputs
+        if let Some(outputs) = &self.config.outputs {
+            self.transpile_outputs(&mut output_blocks, outputs);
+        }
+
+        // Secret Manager data sources of the `!secret` values
+        let provider = root_ctx.provider_alias.as_ref().and_then(|alias| alias.parse::<hcl::Expression>().ok());
+        let data_blocks: Vec<hcl::Block> = self.secrets.borrow().iter().map(|secret| secret.data_block(provider.clone())).collect();
+        main_blocks.splice(data_sources_at..data_sources_at, data_blocks);
+
+        // `depends_on` entries and `!ref` values written as YAML keys, now that all addresses are known
+        self.resolve_depends_on(&mut main_blocks);
+        self.resolve_depends_on(&mut output_blocks);
+        self.resolve_key_references(&mut main_blocks);
+        self.resolve_key_references(&mut output_blocks);
+
+        // Orderings Terraform cannot infer from references
+        if self.implicit_dependencies {
+            crate::dependencies::apply(&mut main_blocks);
+        }
+
         // Variables
-        let mut sorted_vars: Vec<_> = self.variables.keys().collect();
-        sorted_vars.sort();
-        for key in sorted_vars {
-            let val = self.variables.get(key).unwrap();
-
-            // vars.tf: variable "key" { type = string }
-            // For now, assume everything is a string or let terraform infer 'any'
-            // But usually string is safe for what we see in the yaml
-            variable_blocks.push(hcl::Block::builder("variable")
+        // The value of a `!secret` would end up in the tfvars
+        let mut secret_variables: Vec<&String> = self.variables.keys().filter(|key| crate::secret_tag::contains_secret(&self.variables[*key])).collect();
+        secret_variables.sort();
+        for key in secret_variables {
+            self.error_at(&format!("variables.{}", key), t("secret-in-variables", &[]));
+        }
+        // vars.tf: variable "key" { type = number }, typed after the value
+        let declared_types: BTreeMap<String, &'static str> = self.variables.iter()
+            .map(|(key, value)| (key.clone(), self.yaml_to_hcl_value(value).map_or("string", |v| crate::tfvars::declared_type(&v))))
+            .collect();
+        for (key, declared_type) in &declared_types {
+            let declaration = self.variable_declarations.get(key);
+            let mut variable = hcl::Block::builder("variable")
                 .add_label(key)
-                .add_attribute(("type", hcl::Expression::Variable(hcl::Variable::new("string").unwrap())))
-                .build());
-
-            // .tfvars: key = "value"
-            if let Some(hcl_val) = self.yaml_to_hcl_value(val) {
-                 tfvars_lines.push(format!("{} = {}", key, hcl_val.to_string()));
+                .add_attribute(("type", crate::tfvars::type_expression(declared_type)));
+            if let Some(description) = declaration.and_then(|d| d.description.as_ref()).or_else(|| self.variable_descriptions.get(key)) {
+                variable = variable.add_attribute(("description", description.as_str()));
+            }
+            if let Some(declaration) = declaration {
+                if let Some(default) = declaration.default.as_ref().and_then(|d| self.yaml_to_hcl_value(d)) {
+                    variable = variable.add_attribute(("default", crate::tfvars::typed(default, declared_type)));
+                }
+                if declaration.sensitive {
+                    variable = variable.add_attribute(("sensitive", true));
+                }
+                for validation in &declaration.validation {
+                    let condition: hcl::Expression = validation.condition.parse()
+                        .map_err(|e| format!("Invalid validation condition of variable '{}': {}", key, e))?;
+                    variable = variable.add_block(hcl::Block::builder("validation")
+                        .add_attribute(("condition", condition))
+                        .add_attribute(("error_message", validation.error_message.as_str()))
+                        .build());
+                }
             }
+            variable_blocks.push(variable.build());
         }
 
-        let mut main_body = hcl::Body::builder();
-        for block in main_blocks { main_body = main_body.add_block(block); }
+        let mut tfvars = vec![(self.tfvars_format.file_name("terraform"), self.render_tfvars(&self.variables, &declared_types)?)];
+        for (environment, variables) in &self.environment_variables {
+            tfvars.push((self.tfvars_format.file_name(environment), self.render_tfvars(variables, &declared_types)?));
+        }
+
+        let sources = self.sources.take();
+        let mut files = BTreeMap::new();
+        if self.split_by == crate::split::SplitBy::Module {
+            files.extend(crate::extract_module::module_layout(&main_blocks, &mut output_blocks, &mut import_blocks, &sources, &self.provider_sources)?);
+        } else {
+            let mut file_bodies: BTreeMap<String, hcl::BodyBuilder> = BTreeMap::new();
+            file_bodies.insert("main.tf".to_string(), hcl::Body::builder());
+            for block in main_blocks {
+                let file = self.split_by.file_name(&block, &sources);
+                let body = file_bodies.remove(&file).unwrap_or_else(hcl::Body::builder);
+                file_bodies.insert(file, body.add_block(block));
+            }
+            for (name, body) in file_bodies {
+                files.insert(name, hcl::to_string(&body.build())?);
+            }
+        }
 
-        let mut prov_body = hcl::Body::builder();
-        for block in provider_blocks { prov_body = prov_body.add_block(block); }
+        let project_providers = provider_blocks.split_off(root_providers_end);
+        let root_providers = provider_blocks.split_off(1);
+        let providers_tf = crate::providers_tf::render(provider_blocks, root_providers, project_providers)?;
 
         let mut var_body = hcl::Body::builder();
         for block in variable_blocks { var_body = var_body.add_block(block); }
 
+        let mut output_body = hcl::Body::builder();
+        for block in output_blocks { output_body = output_body.add_block(block); }
+
         let mut import_body = hcl::Body::builder();
         for block in import_blocks { import_body = import_body.add_block(block); }
 
+        let diagnostics = self.diagnostics.take();
+        if !diagnostics.is_empty() {
+            for d in &diagnostics {
+                eprintln!("{}", t("error", &[("message", &t("at-path", &[("message", &d.message), ("path", &d.yaml_path)]))]));
+            }
+            return Err(t("transpile-failed", &[("count", &diagnostics.len())]).into());
+        }
+
+        files.insert("providers.tf".to_string(), providers_tf);
+        files.insert(crate::output::BACKEND_CONFIG_FILE.to_string(), backend_hcl);
+        files.insert("variables.tf".to_string(), hcl::to_string(&var_body.build())?);
+        files.insert("outputs.tf".to_string(), hcl::to_string(&output_body.build())?);
+        files.extend(tfvars);
+        files.insert("imports.tf".to_string(), hcl::to_string(&import_body.build())?);
         Ok(GeneratedProject {
-            main_tf: hcl::to_string(&main_body.build())?,
-            providers_tf: hcl::to_string(&prov_body.build())?,
-            variables_tf: hcl::to_string(&var_body.build())?,
-            tfvars: tfvars_lines.join("\n"),
-            imports_tf: hcl::to_string(&import_body.build())?,
+            files,
+            unsupported: self.unsupported.take(),
+            sources,
+            owners: crate::owners::scope_owners(self.config),
         })
     }
 
+    /// Variable values typed after their declaration in variables.tf. Variables declared without
+    /// a `value` are left to their `default` unless the environment sets another value.
+    fn render_tfvars(&self, variables: &HashMap<String, serde_yaml::Value>, declared_types: &BTreeMap<String, &'static str>) -> Result<String, Box<dyn std::error::Error>> {
+        let values: BTreeMap<String, hcl::Expression> = variables.iter()
+            .filter(|(key, value)| !self.variable_declarations.get(*key).is_some_and(|d| d.value.is_none() && d.default.as_ref() == Some(value)))
+            .filter_map(|(key, value)| {
+                let value = self.yaml_to_hcl_value(value)?;
+                let declared_type = declared_types.get(key).copied().unwrap_or_else(|| crate::tfvars::declared_type(&value));
+                Some((key.clone(), crate::tfvars::typed(value, declared_type)))
+            })
+            .collect();
+        crate::tfvars::render(&values, self.tfvars_format)
+    }
+
     fn transpile_google_folder(
         &self,
         blocks: &mut Vec<hcl::Block>,
@@ -355,24 +733,56 @@ impl<'a> Transpiler<'a> {
 
         for key in sorted_keys {
             let folder = folders.get(key).unwrap();
-            let resource_name = key.as_str().replace("-", "_");
+            let resource_name = resource_label(key.as_str());
+            let protect = folder.protect.unwrap_or(ctx.protect);
+            let labels = inherit_labels(
+                &inherit_labels(&ctx.labels, folder.defaults.as_ref().and_then(|d| d.labels.as_ref())),
+                labels_in_mode(folder.default_labels.as_ref(), LabelMode::Merge),
+            );
+            let defaults = ctx.defaults.overlay(folder.defaults.as_ref());
+            let yaml_path = yaml_path_join(&ctx.yaml_path, &format!("folder.{}", key));
+            let provider_routes = self.inherit_provider_routes(&ctx.provider_routes, folder.provider_alias.as_ref(), &yaml_path);
+            let provider_alias = provider_routes.get("default").cloned().or_else(|| ctx.provider_alias.clone());
 
             // Conditional Folders: If display_name is empty, skip folder creation and promote children to current context.
             if folder.display_name.trim().is_empty() {
+                let mut promoted_ctx = ctx.clone();
+                promoted_ctx.provider_alias = provider_alias;
+                promoted_ctx.provider_routes = provider_routes;
+                promoted_ctx.provider_labels = inherit_labels(&ctx.provider_labels, labels_in_mode(folder.default_labels.as_ref(), LabelMode::Provider));
+                promoted_ctx.protect = protect;
+                promoted_ctx.labels = labels;
+                promoted_ctx.defaults = defaults;
+                promoted_ctx.yaml_path = yaml_path;
                 if let Some(sub_folders) = &folder.folder {
-                    self.transpile_google_folder(blocks, provider_blocks, import_blocks, sub_folders, ctx);
+                    self.transpile_google_folder(blocks, provider_blocks, import_blocks, sub_folders, &promoted_ctx);
                 }
                 if let Some(projects) = &folder.project {
-                    self.transpile_google_project(blocks, provider_blocks, import_blocks, projects, ctx);
+                    self.transpile_google_project(blocks, provider_blocks, import_blocks, projects, &promoted_ctx);
                 }
-                self.transpile_generic_resources(blocks, provider_blocks, import_blocks, &folder.extra, ctx, None);
+                self.transpile_generic_resources(blocks, provider_blocks, import_blocks, &folder.extra, &promoted_ctx, None);
                 continue;
             }
 
             let parent_val_expr = if let Some(pref) = &ctx.folder_ref {
                 self.parse_hcl_expr(pref)
+            } else if let Some(parent) = &folder.parent {
+                match self.reference_value(parent, &yaml_path) {
+                    Some(hcl::Expression::String(parent)) if !parent.starts_with("folders/") && !parent.starts_with("organizations/") => {
+                        self.error_at(&yaml_path, t("folder-parent-invalid", &[("parent", &parent)]));
+                        continue;
+                    }
+                    Some(expr) => expr,
+                    None => {
+                        self.error_at(&yaml_path, t("folder-parent-type", &[]));
+                        continue;
+                    }
+                }
+            } else if let Some(org_ref) = &ctx.org_ref {
+                hcl::Expression::from(org_ref.clone())
             } else {
-                hcl::Expression::from(ctx.org_ref.as_ref().unwrap().clone())
+                self.error_at(&yaml_path, t("folder-parent-missing", &[]));
+                continue;
             };
 
             let mut folder_builder = hcl::Block::builder("resource")
@@ -381,28 +791,47 @@ impl<'a> Transpiler<'a> {
                 .add_attribute(("display_name", folder.display_name.clone()))
                 .add_attribute(hcl::Attribute::new("parent", parent_val_expr));
 
-            if let Some(alias) = &ctx.provider_alias {
+            if let Some(alias) = provider_routes.get("google_folder").or(provider_alias.as_ref()) {
                 if let Ok(expr) = alias.parse::<hcl::Expression>() {
                     folder_builder = folder_builder.add_attribute(("provider", expr));
                 }
             }
 
-            // Emit labels for the folder resource itself
-            if let Some(serde_yaml::Value::Mapping(labels_map)) = folder.extra.get("labels") {
-                let mut sorted: Vec<_> = labels_map.iter()
-                    .filter_map(|(k, v)| k.as_str().zip(v.as_str()))
-                    .collect();
-                sorted.sort_by_key(|(k, _)| *k);
-                if !sorted.is_empty() {
-                    let map: hcl::Map<String, hcl::Value> = sorted
-                        .into_iter()
-                        .map(|(k, v)| (k.to_string(), hcl::Value::from(v.to_string())))
-                        .collect();
-                    folder_builder = folder_builder.add_attribute(("labels", hcl::Value::from(map)));
+            // Emit labels for the folder resource itself (default labels only where the schema supports them)
+            let folder_schema = self.registry.as_ref().and_then(|reg| reg.find_resource("google_folder")).map(|(_, s)| s);
+            let supports_labels = folder_schema.is_some_and(|s| s.block.attributes.contains_key("labels"));
+            let no_labels = BTreeMap::new();
+            match merge_labels(if supports_labels { &labels } else { &no_labels }, folder.extra.get("labels")) {
+                Ok(merged) => {
+                    if let (true, Some(keys)) = (supports_labels, label_keys(&merged)) {
+                        self.check_required_labels("google_folder", &resource_name, &keys, &yaml_path);
+                    }
+                    if merged.as_mapping().is_none_or(|m| !m.is_empty()) {
+                        if let Some(value) = self.yaml_to_hcl_value(&merged) {
+                            folder_builder = folder_builder.add_attribute(("labels", value));
+                        }
+                    }
                 }
+                Err(msg) => self.error_at(&yaml_path_join(&yaml_path, "labels"), msg),
+            }
+
+            // Deletion protection: explicit YAML value first, then the inherited protect switch
+            if let Some(dp) = folder.extra.get("deletion_protection").and_then(|v| v.as_bool()) {
+                folder_builder = folder_builder.add_attribute(("deletion_protection", dp));
+            } else if protect && folder_schema.is_none_or(|s| s.block.attributes.contains_key("deletion_protection")) {
+                folder_builder = folder_builder.add_attribute(("deletion_protection", true));
+            }
+            if let Some(lifecycle) = self.lifecycle_block(folder.extra.get("lifecycle"), protect) {
+                folder_builder = folder_builder.add_block(lifecycle);
+            }
+            if let Some(timeouts) = self.timeouts_block(folder.extra.get("timeouts"), "google_folder", key, folder_schema) {
+                folder_builder = folder_builder.add_block(timeouts);
+            }
+            if let Some(depends_on) = folder.extra.get("depends_on").and_then(|v| self.depends_on_value(v, &yaml_path)) {
+                folder_builder = folder_builder.add_attribute(("depends_on", depends_on));
             }
 
-            blocks.push(folder_builder.build());
+            self.push_resource(blocks, folder_builder.build(), &yaml_path);
 
             // Generate Import Block if requested
             if let Some(id) = &folder.import_id {
@@ -414,8 +843,16 @@ impl<'a> Transpiler<'a> {
 
             let current_hcl_ref = format!("google_folder.{}.name", resource_name);
             let mut folder_ctx = ctx.clone();
+            folder_ctx.provider_alias = provider_alias;
+            folder_ctx.provider_routes = provider_routes;
+            folder_ctx.provider_labels = inherit_labels(&ctx.provider_labels, labels_in_mode(folder.default_labels.as_ref(), LabelMode::Provider));
+            folder_ctx.protect = protect;
+            folder_ctx.labels = labels;
+            folder_ctx.defaults = defaults;
+            folder_ctx.yaml_path = yaml_path;
             folder_ctx.folder_id = Some(current_hcl_ref.clone()); // Simplification: we use HCL ref as identifier in YAML usually
             folder_ctx.folder_ref = Some(current_hcl_ref);
+            folder_ctx.iam_mode = folder.iam_mode.unwrap_or_default();
 
             // Generic Resources (includes CEX_ and others in extra)
             self.transpile_generic_resources(blocks, provider_blocks, import_blocks, &folder.extra, &folder_ctx, folder_ctx.provider_alias.as_deref());
@@ -442,7 +879,11 @@ impl<'a> Transpiler<'a> {
 
         for key in sorted_keys {
             let project = projects.get(key).unwrap();
-            let resource_name = key.as_str().replace("-", "_");
+            let resource_name = resource_label(key.as_str());
+            let protect = project.protect.unwrap_or(ctx.protect);
+            let labels = inherit_labels(&ctx.labels, labels_in_mode(project.default_labels.as_ref(), LabelMode::Merge));
+            let provider_routes = self.inherit_provider_routes(&ctx.provider_routes, project.provider_alias.as_ref(), &yaml_path_join(&ctx.yaml_path, &format!("project.{}", key)));
+            let provider_alias = provider_routes.get("default").cloned().or_else(|| ctx.provider_alias.clone());
 
             let mut block_builder = hcl::Block::builder("resource")
                 .add_label("google_project")
@@ -450,25 +891,33 @@ impl<'a> Transpiler<'a> {
                 .add_attribute(hcl::Attribute::new("project_id", project.project_id.clone()))
                 .add_attribute(hcl::Attribute::new("name", project.name.clone().unwrap_or_else(|| project.project_id.clone())));
 
-            if let Some(alias) = &ctx.provider_alias {
+            if let Some(alias) = provider_routes.get("google_project").or(provider_alias.as_ref()) {
                 if let Ok(expr) = alias.parse::<hcl::Expression>() {
                     block_builder = block_builder.add_attribute(("provider", expr));
                 }
             }
 
-            // Emit billing_account: explicit YAML value takes priority, then variable fallback
+            // Emit billing_account: explicit YAML value takes priority, then inherited defaults, then variable fallback
             if let Some(ba) = &project.billing_account {
-                block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", ba.clone()));
+                block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", self.billing_account_id(ba)));
             } else if !project.extra.contains_key("billing_account") {
-                if let Some(ba) = self.variables.get("billing-account-infra") {
+                if let Some(ba) = &ctx.defaults.billing_account {
+                    block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", self.billing_account_id(ba)));
+                } else if let Some(ba) = self.variables.get("billing-account-infra") {
                     if let Some(val) = self.yaml_to_hcl_value(ba) {
                         block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", val));
                     }
                 }
             }
 
-            let has_org = project.extra.contains_key("org_id") || project.extra.contains_key("org") || project.extra.contains_key("folder_id");
-            if !has_org {
+            let project_path = yaml_path_join(&ctx.yaml_path, &format!("project.{}", key));
+            let Some(explicit_parent) = self.project_parent(project, ctx, &project_path) else {
+                continue;
+            };
+            let has_org = project.extra.contains_key("org_id") || project.extra.contains_key("folder_id");
+            if let Some((attr, parent)) = &explicit_parent {
+                block_builder = block_builder.add_attribute(hcl::Attribute::new(*attr, parent.clone()));
+            } else if !has_org {
                 if let Some(f_ref) = &ctx.folder_ref {
                     block_builder = block_builder.add_attribute(hcl::Attribute::new("folder_id", self.parse_hcl_expr(f_ref)));
                 } else if let Some(oid) = &ctx.org_id {
@@ -477,19 +926,27 @@ impl<'a> Transpiler<'a> {
             }
 
             // Emit explicit Project fields that serde captures outside of `extra`
-            if let Some(labels) = &project.labels {
-                if !labels.is_empty() {
-                    let mut sorted: Vec<_> = labels.iter().collect();
-                    sorted.sort_by_key(|(k, _)| k.as_str());
-                    let map: hcl::Map<String, hcl::Value> = sorted
-                        .into_iter()
-                        .map(|(k, v)| (k.clone(), hcl::Value::from(v.clone())))
-                        .collect();
-                    block_builder = block_builder.add_attribute(("labels", hcl::Value::from(map)));
-                }
+            let mut merged_labels = labels.clone();
+            if let Some(own) = &project.labels {
+                merged_labels.extend(own.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            self.check_required_labels("google_project", &resource_name, &merged_labels.keys().cloned().collect(), &project_path);
+            if !merged_labels.is_empty() {
+                let map: hcl::Map<String, hcl::Value> = merged_labels
+                    .into_iter()
+                    .map(|(k, v)| (k, hcl::Value::from(v)))
+                    .collect();
+                block_builder = block_builder.add_attribute(("labels", hcl::Value::from(map)));
             }
             if let Some(dp) = &project.deletion_policy {
                 block_builder = block_builder.add_attribute(("deletion_policy", dp.clone()));
+            } else if protect {
+                block_builder = block_builder.add_attribute(("deletion_policy", protected_deletion_policy("google_project")));
+            } else if let Some(dp) = &ctx.defaults.deletion_policy {
+                block_builder = block_builder.add_attribute(("deletion_policy", dp.clone()));
+            }
+            if let (Some(acn), false) = (ctx.defaults.auto_create_network, project.extra.contains_key("auto_create_network")) {
+                block_builder = block_builder.add_attribute(("auto_create_network", acn));
             }
             if let Some(tags) = &project.tags {
                 if !tags.is_empty() {
@@ -508,12 +965,18 @@ impl<'a> Transpiler<'a> {
             for (k, v) in &project.extra {
                 // Filter out keys that are actually resources handled later
                 let is_resource = if let Some(reg) = &self.registry {
-                    reg.find_resource(k).is_some()
+                    reg.find_resource(crate::aliases::resolve(&self.resource_aliases, k)).is_some()
                 } else {
                     false // Without registry, we can't verify, so be conservative
                 };
 
-                if is_resource { continue; }
+                if is_resource || PROJECT_PARENT_KEYS.contains(&k.as_str()) || k == "lifecycle" || k == "timeouts" || k == crate::discovery::DELETED_PRINCIPALS_KEY || crate::extras::is_unsupported_group(v) { continue; }
+                if k == "depends_on" {
+                    if let Some(depends_on) = self.depends_on_value(v, &project_path) {
+                        block_builder = block_builder.add_attribute(("depends_on", depends_on));
+                    }
+                    continue;
+                }
 
                 let is_block = if let Some(schema) = resource_schema {
                     schema.block.block_types.contains_key(k)
@@ -532,7 +995,14 @@ impl<'a> Transpiler<'a> {
                 }
             }
 
-            blocks.push(block_builder.build());
+            if let Some(lifecycle) = self.lifecycle_block(project.extra.get("lifecycle"), protect) {
+                block_builder = block_builder.add_block(lifecycle);
+            }
+            if let Some(timeouts) = self.timeouts_block(project.extra.get("timeouts"), "google_project", key, resource_schema) {
+                block_builder = block_builder.add_block(timeouts);
+            }
+
+            self.push_resource(blocks, block_builder.build(), &project_path);
 
             // Generate Import Block if requested
             if let Some(id) = &project.import_id {
@@ -545,48 +1015,73 @@ impl<'a> Transpiler<'a> {
             if let Some(reg) = &self.registry {
                 if let Some((_, schema)) = reg.find_resource("google_project") {
                     let mut validation_attrs = project.extra.clone();
+                    validation_attrs.retain(|k, v| k != crate::discovery::DELETED_PRINCIPALS_KEY && !PROJECT_PARENT_KEYS.contains(&k.as_str()) && !crate::extras::is_unsupported_group(v));
                     validation_attrs.insert("project_id".to_string(), serde_yaml::Value::String(project.project_id.clone()));
                     if let Some(name) = &project.name {
                         validation_attrs.insert("name".to_string(), serde_yaml::Value::String(name.clone()));
                     } else {
                         validation_attrs.insert("name".to_string(), serde_yaml::Value::String(project.project_id.clone()));
                     }
-                    if let Some(fid) = &ctx.folder_id {
+                    if let Some((attr, _)) = &explicit_parent {
+                        validation_attrs.insert(attr.to_string(), serde_yaml::Value::String(String::new()));
+                    } else if let Some(fid) = &ctx.folder_id {
                         validation_attrs.insert("folder_id".to_string(), serde_yaml::Value::String(fid.clone()));
                     } else if let Some(oid) = &ctx.org_id {
                         validation_attrs.insert("org_id".to_string(), serde_yaml::Value::String(oid.clone()));
                     }
 
-                    self.validate_resource("google_project", &resource_name, &validation_attrs, schema);
+                    self.validate_resource("google_project", &resource_name, &validation_attrs, schema, &project_path);
                 }
             }
 
             let project_id_ref = format!("google_project.{}.project_id", resource_name);
             let mut project_ctx = ctx.clone();
+            project_ctx.provider_alias = provider_alias.clone();
+            project_ctx.provider_routes = provider_routes.clone();
+            project_ctx.provider_labels = inherit_labels(&ctx.provider_labels, labels_in_mode(project.default_labels.as_ref(), LabelMode::Provider));
+            project_ctx.protect = protect;
+            project_ctx.labels = labels;
             project_ctx.project_id = Some(project.project_id.clone());
             project_ctx.project_ref = Some(project_id_ref);
+            project_ctx.iam_mode = project.iam_mode.unwrap_or_default();
+            project_ctx.yaml_path = project_path;
+            // Billing IAM inside the project targets the account the project is linked to
+            if let Some(ba) = &project.billing_account {
+                project_ctx.defaults.billing_account = Some(ba.clone());
+            }
 
-            // Project specific provider for project resources
-            let p_alias = format!("project_{}", key.replace("-", "_"));
-            let mut p_builder = hcl::Block::builder("provider")
-                .add_label("google")
-                .add_attribute(("alias", p_alias.clone()))
-                .add_attribute(("project", project.project_id.clone()));
-
-            p_builder = self.configure_google_provider(p_builder, Some(project.project_id.clone()), false, false);
+            // Project specific provider for project resources, unless a `provider-alias:` routes them
+            let p_ref = match provider_routes.get("default") {
+                Some(alias) => {
+                    if project_ctx.provider_labels != inherit_labels(&BTreeMap::new(), labels_in_mode(self.config.default_labels.as_ref(), LabelMode::Provider)) {
+                        crate::warnings::warn(t("provider-labels-routed-group", &[]), &project_ctx.yaml_path, t("provider-labels-routed", &[("path", &project_ctx.yaml_path), ("provider", alias)]));
+                    }
+                    alias.clone()
+                }
+                None => {
+                    let p_alias = format!("project_{}", resource_label(key));
+                    let mut p_builder = hcl::Block::builder("provider")
+                        .add_label("google")
+                        .add_attribute(("alias", p_alias.clone()))
+                        .add_attribute(("project", project.project_id.clone()));
 
-            // Default region if not specified (could be improved to come from project config)
-            p_builder = p_builder.add_attribute(("region", "europe-west3"));
+                    p_builder = self.configure_google_provider(p_builder, "google", Some(project.project_id.clone()), false, false, Some(&project_ctx.provider_labels));
 
-            provider_blocks.push(p_builder.build());
+                    // Region from the inherited defaults, europe-west3 if none is set
+                    let region = ctx.defaults.region.clone().unwrap_or_else(|| "europe-west3".to_string());
+                    p_builder = p_builder.add_attribute(("region", region));
 
-            let p_ref = format!("google.{}", p_alias);
+                    provider_blocks.push(p_builder.build());
+                    format!("google.{}", p_alias)
+                }
+            };
 
             // Project Services
             if let Some(services) = &project.project_service {
+                let service_provider = provider_routes.get("google_project_service").or(provider_alias.as_ref());
                 for service_val in services {
                     let project_id_ref = format!("google_project.{}.project_id", resource_name);
-                    self.transpile_google_project_service(blocks, import_blocks, &project_id_ref, service_val, ctx.provider_alias.as_deref(), &resource_name);
+                    self.transpile_google_project_service(blocks, import_blocks, (&project_id_ref, self.parse_hcl_expr(&project_id_ref)), service_val, service_provider.map(String::as_str), &resource_name, &yaml_path_join(&project_ctx.yaml_path, "project_service"));
                 }
             }
 
@@ -595,6 +1090,298 @@ impl<'a> Transpiler<'a> {
         }
     }
 
+    /// `organizational_unit:` entries, like [`Self::transpile_google_folder`]: nested units and
+    /// accounts are created in the unit they are nested in and inherit `protect:`,
+    /// `default_labels:` (as tags) and `owner:`.
+    fn transpile_aws_organizational_unit(
+        &self,
+        blocks: &mut Vec<hcl::Block>,
+        provider_blocks: &mut Vec<hcl::Block>,
+        import_blocks: &mut Vec<hcl::Block>,
+        units: &HashMap<String, OrganizationalUnit>,
+        ctx: &ResourceContext,
+    ) {
+        let mut sorted_keys: Vec<_> = units.keys().collect();
+        sorted_keys.sort();
+
+        for key in sorted_keys {
+            let unit = units.get(key).unwrap();
+            let resource_name = resource_label(key.as_str());
+            let protect = unit.protect.unwrap_or(ctx.protect);
+            let labels = inherit_labels(&ctx.labels, unit.default_labels.as_ref());
+            let yaml_path = yaml_path_join(&ctx.yaml_path, &format!("organizational_unit.{}", key));
+
+            // Nested units always go into the enclosing unit; `parent:` only moves top-level ones
+            let parent_val_expr = match (&unit.parent, ctx.yaml_path.is_empty()) {
+                (Some(parent), true) => match self.reference_value(parent, &yaml_path) {
+                    Some(expr) => expr,
+                    None => {
+                        self.error_at(&yaml_path, t("ou-parent-type", &[]));
+                        continue;
+                    }
+                },
+                _ => match &ctx.aws_parent_ref {
+                    Some(parent) => self.parse_hcl_expr(parent),
+                    None => {
+                        self.error_at(&yaml_path, t("ou-parent-missing", &[]));
+                        continue;
+                    }
+                },
+            };
+
+            let mut unit_builder = hcl::Block::builder("resource")
+                .add_label("aws_organizations_organizational_unit")
+                .add_label(&resource_name)
+                .add_attribute(("name", unit.name.clone()))
+                .add_attribute(hcl::Attribute::new("parent_id", parent_val_expr));
+            if let Some(expr) = ctx.provider_alias.as_ref().and_then(|alias| alias.parse::<hcl::Expression>().ok()) {
+                unit_builder = unit_builder.add_attribute(("provider", expr));
+            }
+            unit_builder = self.aws_organization_attributes(unit_builder, &unit.extra, &labels, protect, ("aws_organizations_organizational_unit", key), &yaml_path);
+            self.push_resource(blocks, unit_builder.build(), &yaml_path);
+
+            if let Some(id) = &unit.import_id {
+                import_blocks.push(hcl::Block::builder("import")
+                    .add_attribute(("to", self.parse_hcl_expr(&format!("aws_organizations_organizational_unit.{}", resource_name))))
+                    .add_attribute(("id", id.clone()))
+                    .build());
+            }
+
+            let current_hcl_ref = format!("aws_organizations_organizational_unit.{}.id", resource_name);
+            self.push_policy_attachments(blocks, unit.policies.as_deref(), (&resource_name, &current_hcl_ref), ctx.provider_alias.as_deref(), &yaml_path);
+
+            let mut unit_ctx = ctx.clone();
+            unit_ctx.protect = protect;
+            unit_ctx.labels = labels;
+            unit_ctx.aws_parent_ref = Some(current_hcl_ref);
+            unit_ctx.yaml_path = yaml_path;
+
+            if let Some(sub_units) = &unit.organizational_unit {
+                self.transpile_aws_organizational_unit(blocks, provider_blocks, import_blocks, sub_units, &unit_ctx);
+            }
+            if let Some(accounts) = &unit.account {
+                self.transpile_aws_account(blocks, provider_blocks, import_blocks, accounts, &unit_ctx);
+            }
+        }
+    }
+
+    /// `account:` entries, like [`Self::transpile_google_project`]: the account is created in the
+    /// enclosing unit, gets a provider that assumes its access role (`aws.account_<key>`, with the
+    /// inherited tags as `default_tags`), and the resources in it are generated with that provider.
+    fn transpile_aws_account(
+        &self,
+        blocks: &mut Vec<hcl::Block>,
+        provider_blocks: &mut Vec<hcl::Block>,
+        import_blocks: &mut Vec<hcl::Block>,
+        accounts: &HashMap<String, Account>,
+        ctx: &ResourceContext,
+    ) {
+        let mut sorted_keys: Vec<_> = accounts.keys().collect();
+        sorted_keys.sort();
+
+        for key in sorted_keys {
+            let account = accounts.get(key).unwrap();
+            let resource_name = resource_label(key.as_str());
+            let protect = account.protect.unwrap_or(ctx.protect);
+            let labels = inherit_labels(&ctx.labels, account.default_labels.as_ref());
+            let yaml_path = yaml_path_join(&ctx.yaml_path, &format!("account.{}", key));
+
+            // Account attributes (close_on_deletion, role_name, ...) vs. resources in the account
+            let is_resource = |k: &str, v: &serde_yaml::Value| {
+                let resource_type = crate::aliases::resolve(&self.resource_aliases, k);
+                self.registry.as_ref().is_some_and(|reg| reg.find_resource(resource_type).is_some())
+                    || (resource_type.starts_with("aws_") && matches!(v, serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_)))
+            };
+            let (resources, attributes): (HashMap<String, serde_yaml::Value>, HashMap<String, serde_yaml::Value>) = account.extra.clone()
+                .into_iter()
+                .partition(|(k, v)| is_resource(k, v));
+
+            let mut account_builder = hcl::Block::builder("resource")
+                .add_label("aws_organizations_account")
+                .add_label(&resource_name)
+                .add_attribute(("name", account.name.clone().unwrap_or_else(|| key.clone())))
+                .add_attribute(("email", account.email.clone()));
+            if !attributes.contains_key("parent_id") {
+                if let Some(parent) = &ctx.aws_parent_ref {
+                    account_builder = account_builder.add_attribute(hcl::Attribute::new("parent_id", self.parse_hcl_expr(parent)));
+                }
+            }
+            if let Some(expr) = ctx.provider_alias.as_ref().and_then(|alias| alias.parse::<hcl::Expression>().ok()) {
+                account_builder = account_builder.add_attribute(("provider", expr));
+            }
+            account_builder = self.aws_organization_attributes(account_builder, &attributes, &labels, protect, ("aws_organizations_account", key), &yaml_path);
+            self.push_resource(blocks, account_builder.build(), &yaml_path);
+
+            if let Some(id) = &account.import_id {
+                import_blocks.push(hcl::Block::builder("import")
+                    .add_attribute(("to", self.parse_hcl_expr(&format!("aws_organizations_account.{}", resource_name))))
+                    .add_attribute(("id", id.clone()))
+                    .build());
+            }
+
+            let account_id_ref = format!("aws_organizations_account.{}.id", resource_name);
+            self.push_policy_attachments(blocks, account.policies.as_deref(), (&resource_name, &account_id_ref), ctx.provider_alias.as_deref(), &yaml_path);
+
+            // Account specific provider for the resources in the account
+            let p_alias = format!("account_{}", resource_name);
+            let role_name = attributes.get("role_name").and_then(|v| v.as_str()).unwrap_or("OrganizationAccountAccessRole");
+            let role_arn = format!("arn:aws:iam::${{{}}}:role/{}", account_id_ref, role_name);
+            let mut p_builder = hcl::Block::builder("provider")
+                .add_label("aws")
+                .add_attribute(("alias", p_alias.clone()));
+            let region = account.region.clone().or_else(|| {
+                self.config.providers.as_ref()?.get("aws")?.get("region")?.as_str().map(|r| r.to_string())
+            });
+            if let Some(region) = region {
+                p_builder = p_builder.add_attribute(("region", region));
+            }
+            p_builder = p_builder.add_block(hcl::Block::builder("assume_role")
+                .add_attribute(("role_arn", hcl::Expression::TemplateExpr(Box::new(hcl::TemplateExpr::QuotedString(role_arn)))))
+                .build());
+            if !labels.is_empty() {
+                let map: hcl::Map<String, hcl::Value> = labels.iter().map(|(k, v)| (k.clone(), hcl::Value::from(v.clone()))).collect();
+                p_builder = p_builder.add_block(hcl::Block::builder("default_tags")
+                    .add_attribute(("tags", hcl::Value::from(map)))
+                    .build());
+            }
+            provider_blocks.push(p_builder.build());
+
+            let mut account_ctx = ctx.clone();
+            account_ctx.protect = protect;
+            account_ctx.labels = labels;
+            account_ctx.provider_alias = Some(format!("aws.{}", p_alias));
+            account_ctx.yaml_path = yaml_path;
+
+            self.transpile_generic_resources(blocks, provider_blocks, import_blocks, &resources, &account_ctx, account_ctx.provider_alias.as_deref());
+        }
+    }
+
+    /// The attributes of an organizational unit or account besides name and parent: `tags` over
+    /// the inherited labels, `lifecycle`/`timeouts`/`depends_on`, and the rest as they are.
+    fn aws_organization_attributes(
+        &self,
+        mut builder: hcl::BlockBuilder,
+        attributes: &HashMap<String, serde_yaml::Value>,
+        labels: &BTreeMap<String, String>,
+        protect: bool,
+        (tf_type, key): (&str, &str),
+        yaml_path: &str,
+    ) -> hcl::BlockBuilder {
+        let schema = self.registry.as_ref().and_then(|reg| reg.find_resource(tf_type)).map(|(_, s)| s);
+        match merge_labels(labels, attributes.get("tags")) {
+            Ok(tags) if tags.as_mapping().is_some_and(|m| m.is_empty()) => {}
+            Ok(tags) => {
+                if let Some(tags) = self.yaml_to_hcl_value(&tags) {
+                    builder = builder.add_attribute(("tags", tags));
+                }
+            }
+            Err(msg) => self.error_at(&yaml_path_join(yaml_path, "tags"), msg),
+        }
+
+        let mut sorted_keys: Vec<_> = attributes.keys().collect();
+        sorted_keys.sort();
+        for k in sorted_keys {
+            let v = &attributes[k];
+            match k.as_str() {
+                "tags" | "lifecycle" | "timeouts" => {}
+                "depends_on" => {
+                    if let Some(depends_on) = self.depends_on_value(v, yaml_path) {
+                        builder = builder.add_attribute(("depends_on", depends_on));
+                    }
+                }
+                _ => {
+                    if let Some(val) = self.yaml_to_hcl_value(v) {
+                        builder = builder.add_attribute(hcl::Attribute::new(k.as_str(), val));
+                    }
+                }
+            }
+        }
+        if let Some(lifecycle) = self.lifecycle_block(attributes.get("lifecycle"), protect) {
+            builder = builder.add_block(lifecycle);
+        }
+        if let Some(timeouts) = self.timeouts_block(attributes.get("timeouts"), tf_type, key, schema) {
+            builder = builder.add_block(timeouts);
+        }
+        builder
+    }
+
+    /// `aws_organizations_policy_attachment` of each `policies:` entry of a unit or account,
+    /// labelled `<key>_<policy>`.
+    fn push_policy_attachments(&self, blocks: &mut Vec<hcl::Block>, policies: Option<&[serde_yaml::Value]>, (target_name, target_ref): (&str, &str), provider_alias: Option<&str>, yaml_path: &str) {
+        for (i, policy) in policies.unwrap_or_default().iter().enumerate() {
+            let policy_path = yaml_path_join(yaml_path, &format!("policies.{}", i));
+            let Some(policy_id) = self.reference_value(policy, &policy_path) else {
+                self.error_at(&policy_path, t("policy-id-invalid", &[]));
+                continue;
+            };
+            // `aws_organizations_policy.deny_all.id` -> `deny_all`
+            let policy_name = match &policy_id {
+                hcl::Expression::String(id) => id.clone(),
+                hcl::Expression::Traversal(traversal) => traversal.operators.first()
+                    .and_then(|op| match op {
+                        hcl::expr::TraversalOperator::GetAttr(name) => Some(name.to_string()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| i.to_string()),
+                _ => i.to_string(),
+            };
+            let mut attachment = hcl::Block::builder("resource")
+                .add_label("aws_organizations_policy_attachment")
+                .add_label(resource_label(&format!("{}_{}", target_name, policy_name)))
+                .add_attribute(("policy_id", policy_id))
+                .add_attribute(("target_id", self.parse_hcl_expr(target_ref)));
+            if let Some(expr) = provider_alias.and_then(|alias| alias.parse::<hcl::Expression>().ok()) {
+                attachment = attachment.add_attribute(("provider", expr));
+            }
+            self.push_resource(blocks, attachment.build(), &policy_path);
+        }
+    }
+
+    /// Layers a scope's own `provider-alias:` over the inherited routes (narrowest scope wins per
+    /// resource type); a single provider becomes the `default` route. Providers not declared under
+    /// `providers:` are reported.
+    fn inherit_provider_routes(&self, inherited: &BTreeMap<String, String>, own: Option<&crate::config::ProviderAlias>, yaml_path: &str) -> BTreeMap<String, String> {
+        let mut routes = inherited.clone();
+        let own: Vec<(&str, &str)> = match own {
+            None => return routes,
+            Some(crate::config::ProviderAlias::All(provider)) => vec![("default", provider.as_str())],
+            Some(crate::config::ProviderAlias::ByType(by_type)) => by_type.iter().map(|(t, p)| (t.as_str(), p.as_str())).collect(),
+        };
+        let path = yaml_path_join(yaml_path, "provider-alias");
+        for (tf_type, provider) in own {
+            if !self.is_declared_provider(provider) {
+                self.error_at(&path, t("provider-alias-unknown", &[("provider", &provider)]));
+                continue;
+            }
+            let tf_type = if tf_type == "default" { tf_type } else { crate::aliases::resolve(&self.resource_aliases, tf_type) };
+            routes.insert(tf_type.to_string(), provider.to_string());
+        }
+        routes
+    }
+
+    /// Whether `<name>.<alias>` is a provider of the YAML (an entry without `alias` is `<name>.<name>`).
+    fn is_declared_provider(&self, reference: &str) -> bool {
+        let Some((name, alias)) = reference.split_once('.') else { return false };
+        let Some(provider) = self.config.providers.as_ref().and_then(|p| p.get(name)) else { return false };
+        let entries: Vec<&serde_yaml::Value> = match provider {
+            serde_yaml::Value::Sequence(seq) => seq.iter().collect(),
+            other => vec![other],
+        };
+        entries.iter().any(|entry| entry.get("alias").and_then(|a| a.as_str()).unwrap_or(name) == alias)
+    }
+
+    /// The provider of the YAML named `name` (`aws.aws`, or its alias), for resources of that
+    /// provider outside a scope that sets one.
+    fn root_provider(&self, name: &str) -> Option<String> {
+        let provider = self.config.providers.as_ref()?.get(name)?;
+        let provider = match provider {
+            serde_yaml::Value::Sequence(seq) => seq.first()?,
+            other => other,
+        };
+        let alias = provider.get("alias").and_then(|a| a.as_str()).unwrap_or(name);
+        Some(format!("{}.{}", name, alias))
+    }
+
     fn transpile_generic_resources(
         &self,
         blocks: &mut Vec<hcl::Block>,
@@ -607,20 +1394,26 @@ impl<'a> Transpiler<'a> {
         let mut sorted_types: Vec<_> = extra.keys().collect();
         sorted_types.sort();
 
-        for resource_type in sorted_types {
-            let value = extra.get(resource_type).unwrap();
+        for yaml_key in sorted_types {
+            let value = extra.get(yaml_key).unwrap();
+            let resource_type = crate::aliases::resolve(&self.resource_aliases, yaml_key);
 
             // Skip known non-resource keys
-            if resource_type == "variables" {
+            if resource_type == "variables" || resource_type == crate::discovery::DELETED_PRINCIPALS_KEY {
+                continue;
+            }
+            // A misspelled `default_labels:` would otherwise be skipped below without notice
+            if yaml_key == "default-labels" {
+                self.error_at(&yaml_path_join(&ctx.yaml_path, yaml_key), t("default-labels-spelling", &[]));
                 continue;
             }
 
             // Skip keys that are known resource parameters (never Terraform resource types)
             const KNOWN_ATTRIBUTE_KEYS: &[&str] = &[
                 "labels", "deletion_protection", "deletion_policy", "metadata", "annotations",
-                "name", "project_id", "billing_account", "tags", "display_name", "parent",
+                "name", "project_id", "billing_account", "tags", "display_name", "parent", "depends_on",
             ];
-            if KNOWN_ATTRIBUTE_KEYS.contains(&resource_type.as_str()) {
+            if KNOWN_ATTRIBUTE_KEYS.contains(&resource_type) {
                 continue;
             }
 
@@ -672,7 +1465,15 @@ impl<'a> Transpiler<'a> {
                                     }
                                 }
                             } else {
-                                // TODO: Generic explosion for non-IAM resources
+                                match compact_resources(&tf_type, key, items) {
+                                    Ok(resources) => {
+                                        let resource_schema = self.registry.as_ref().and_then(|reg| reg.find_resource(&tf_type)).map(|(_, s)| s);
+                                        for (res_name, attrs) in resources {
+                                            self.transpile_single_resource(blocks, import_blocks, &tf_type, &res_name, &attrs, resource_schema, ctx, provider_alias);
+                                        }
+                                    }
+                                    Err(message) => self.error_at(&yaml_path_join(&ctx.yaml_path, &format!("{}.{}", resource_type, key)), message),
+                                }
                             }
                         }
                     }
@@ -680,10 +1481,16 @@ impl<'a> Transpiler<'a> {
                 continue;
             }
 
+            // Billing IAM inside a folder/project uses the billing account selected for that scope
+            if resource_type == "billing_account_iam_member" || resource_type == "google_billing_account_iam_member" {
+                self.transpile_billing_iam(blocks, import_blocks, value, ctx, provider_alias);
+                continue;
+            }
+
             // Compact Cloud Identity Group Expansion
             if resource_type == "cloud_identity_group" {
                 if let serde_yaml::Value::Mapping(groups) = value {
-                    self.transpile_cloud_identity_groups(blocks, import_blocks, groups, provider_alias);
+                    self.transpile_cloud_identity_groups(blocks, import_blocks, groups, provider_alias, &ctx.yaml_path);
                 }
                 continue;
             }
@@ -693,7 +1500,7 @@ impl<'a> Transpiler<'a> {
                 if let Some((_, schema)) = reg.find_resource(resource_type) {
                     let resolved_name = if reg.resources.contains_key(resource_type) {
                         resource_type.to_string()
-                    } else if resource_type.starts_with("google_") {
+                    } else if resource_type.starts_with("google_") || resource_type.starts_with("aws_") {
                         resource_type.to_string()
                     } else {
                         format!("google_{}", resource_type)
@@ -702,17 +1509,18 @@ impl<'a> Transpiler<'a> {
                 } else {
                     // Resource type not found in registry - only generate error if value is a Mapping/Sequence
                     // (which would indicate it's meant to be a resource, not just an attribute)
-                    let resolved_name = if resource_type.starts_with("google_") {
+                    let resolved_name = if resource_type.starts_with("google_") || resource_type.starts_with("aws_") {
                         resource_type.to_string()
                     } else {
                         format!("google_{}", resource_type)
                     };
-                    if matches!(value, serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_)) {
+                    // Entries applied via the extras script don't need a schema
+                    if !crate::extras::is_unsupported_group(value) && matches!(value, serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_)) {
                         eprintln!("Error: Unknown resource type '{}' (resolved as '{}'). This resource type does not exist in the Terraform provider schema. Please check the resource name or use a valid Terraform resource type.", resource_type, resolved_name);
                     }
                     (resolved_name, None)
                 }
-            } else if resource_type.starts_with("google_") {
+            } else if resource_type.starts_with("google_") || resource_type.starts_with("aws_") {
                 (resource_type.to_string(), None)
             } else {
                 (format!("google_{}", resource_type), None)
@@ -750,7 +1558,7 @@ impl<'a> Transpiler<'a> {
         // Check if this tf_type is in the auto_explode list
         let mut should_explode = false;
         for pattern in &self.auto_explode {
-            if self.matches_pattern(pattern, &tf_type) {
+            if self.matches_pattern(pattern.strip_suffix(FOR_EACH_SUFFIX).unwrap_or(pattern), tf_type) {
                 should_explode = true;
                 break;
             }
@@ -775,10 +1583,17 @@ impl<'a> Transpiler<'a> {
                         return;
                     } else if tf_type == "google_project_service" {
                         for (project_ref_val, s_val) in map {
-                            if let (serde_yaml::Value::String(project_ref), serde_yaml::Value::Sequence(services)) = (project_ref_val, s_val) {
+                            // Keyed by a project ID or `!ref google_project.<name>.project_id`
+                            let project_ref = match project_ref_val {
+                                serde_yaml::Value::Tagged(tagged) => tagged.value.as_str(),
+                                other => other.as_str(),
+                            };
+                            if let (Some(project_ref), serde_yaml::Value::Sequence(services)) = (project_ref, s_val) {
+                                let yaml_path = yaml_path_join(&ctx.yaml_path, &format!("{}.{}", tf_type, project_ref));
+                                let Some(project_expr) = self.reference_value(project_ref_val, &yaml_path) else { continue };
+                                let safe_project = project_ref.replace(&['.', ':'][..], "_");
                                 for service_val in services {
-                                    let safe_project = project_ref.replace(&['.', ':'][..], "_");
-                                    self.transpile_google_project_service(blocks, import_blocks, project_ref, service_val, provider_alias, &safe_project);
+                                    self.transpile_google_project_service(blocks, import_blocks, (project_ref, project_expr.clone()), service_val, provider_alias, &safe_project, &yaml_path);
                                 }
                             }
                         }
@@ -788,13 +1603,35 @@ impl<'a> Transpiler<'a> {
             }
         }
 
+        if tf_type.ends_with("_iam_binding") || tf_type.ends_with("_iam_policy") {
+            self.warn_authoritative(tf_type, &yaml_path_join(&ctx.yaml_path, tf_type));
+        }
         for (res_name_val, res_attrs_val) in map {
-            if let (serde_yaml::Value::String(res_name), serde_yaml::Value::Mapping(attrs)) = (res_name_val, res_attrs_val) {
-                self.transpile_single_resource(blocks, import_blocks, tf_type, res_name, attrs, resource_schema, ctx, provider_alias);
+            match (res_name_val, res_attrs_val) {
+                (serde_yaml::Value::String(res_name), serde_yaml::Value::Mapping(attrs)) => {
+                    self.transpile_single_resource(blocks, import_blocks, tf_type, res_name, attrs, resource_schema, ctx, provider_alias);
+                }
+                // `roles/viewer: [members]`: a binding keyed `<scope>_<role>`, as `convert-iam` writes them
+                (serde_yaml::Value::String(role), serde_yaml::Value::Sequence(members)) if tf_type.ends_with("_iam_binding") => {
+                    let scope = ctx.yaml_path.rsplit('.').next().filter(|s| !s.is_empty()).unwrap_or("organization");
+                    let res_name = format!("{}_{}", scope, role.rsplit('/').next().unwrap_or(role));
+                    let mut attrs = serde_yaml::Mapping::new();
+                    attrs.insert("role".into(), role.as_str().into());
+                    attrs.insert("members".into(), serde_yaml::Value::Sequence(members.clone()));
+                    self.transpile_single_resource(blocks, import_blocks, tf_type, &res_name, &attrs, resource_schema, ctx, provider_alias);
+                }
+                _ => {}
             }
         }
     }
 
+    /// Warns that `tf_type` (`google_*_iam_binding` or `_iam_policy`) at `yaml_path` removes grants
+    /// made outside the YAML.
+    fn warn_authoritative(&self, tf_type: &str, yaml_path: &str) {
+        let key = if tf_type.ends_with("_iam_policy") { "authoritative-iam-policy" } else { "authoritative-iam-binding" };
+        crate::warnings::warn(t("authoritative-iam-group", &[]), yaml_path, t(key, &[("type", &tf_type), ("path", &yaml_path)]));
+    }
+
     fn transpile_single_resource(
         &self,
         blocks: &mut Vec<hcl::Block>,
@@ -806,9 +1643,53 @@ impl<'a> Transpiler<'a> {
         ctx: &ResourceContext,
         provider_alias: Option<&str>,
     ) {
-        let label = res_name.replace("-", "_");
+        let label = resource_label(res_name);
+        let resource_path = yaml_path_join(&ctx.yaml_path, &format!("{}.{}", tf_type, res_name));
+
+        // `enabled: false` leaves the resource out, `when:` creates it only if the condition holds
+        let Some(condition) = self.resource_condition(attrs, resource_schema, &resource_path) else {
+            return;
+        };
+
+        // Legacy organization policies with their shorthands expanded
+        let normalized;
+        let attrs = if crate::org_policy::LEGACY_TYPES.contains(&tf_type) {
+            match crate::org_policy::normalize(tf_type, attrs) {
+                Ok(policy) => {
+                    normalized = policy;
+                    &normalized
+                }
+                Err(e) => {
+                    self.error_at(&resource_path, e);
+                    return;
+                }
+            }
+        } else {
+            attrs
+        };
+
+        if crate::extras::is_unsupported(attrs, resource_schema.is_some()) {
+            self.record_unsupported(tf_type, &label, attrs, ctx);
+            return;
+        }
+
         let mut block_builder = hcl::Block::builder("resource").add_label(tf_type).add_label(&label);
+        if let Some(condition) = &condition {
+            let count = hcl::expr::Conditional::new(condition.clone(), 1, 0);
+            block_builder = block_builder.add_attribute(("count", hcl::Expression::from(count)));
+        }
 
+        // Resources of another provider (aws_ at the root) get the YAML's provider of their own
+        // family instead of the google alias of the scope
+        let family_provider;
+        let provider_alias = match provider_alias {
+            Some(alias) if provider_family(alias) != provider_family(tf_type) => {
+                family_provider = self.root_provider(provider_family(tf_type));
+                family_provider.as_deref()
+            }
+            other => other,
+        };
+        let provider_alias = ctx.provider_routes.get(tf_type).map(String::as_str).or(provider_alias);
         if let Some(alias) = provider_alias {
             if !attrs.contains_key(&serde_yaml::Value::String("provider".to_string())) {
                 if let Ok(expr) = (alias).parse::<hcl::Expression>() {
@@ -825,6 +1706,59 @@ impl<'a> Transpiler<'a> {
         // Removal of import-existing logic (as requested by user)
         final_attrs.remove(&serde_yaml::Value::String("import-existing".to_string()));
 
+        // Deletion protection: per-resource `protect:` overrides the inherited switch
+        let protect = final_attrs.remove("protect")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(ctx.protect);
+        let explicit_lifecycle = final_attrs.remove("lifecycle");
+        let explicit_timeouts = final_attrs.remove("timeouts");
+        for meta in CONDITION_KEYS {
+            if !resource_schema.is_some_and(|s| s.block.attributes.contains_key(*meta)) {
+                final_attrs.remove(*meta);
+            }
+        }
+
+        // Inherited `defaults:` for attributes not set explicitly
+        if let Some(schema) = resource_schema {
+            let has = |attr: &str| schema.block.attributes.contains_key(attr) && !attrs.contains_key(attr);
+            if let (Some(region), true) = (&ctx.defaults.region, has("region")) {
+                final_attrs.insert("region".into(), region.as_str().into());
+            }
+            if tf_type == "google_project" {
+                if let (Some(dp), true) = (&ctx.defaults.deletion_policy, has("deletion_policy") && !final_attrs.contains_key("deletion_policy")) {
+                    final_attrs.insert("deletion_policy".into(), dp.as_str().into());
+                }
+                if let (Some(acn), true) = (ctx.defaults.auto_create_network, has("auto_create_network")) {
+                    final_attrs.insert("auto_create_network".into(), acn.into());
+                }
+            }
+        }
+
+        // Default labels: merged under explicit labels for every resource whose schema has `labels`
+        if resource_schema.is_some_and(|s| s.block.attributes.contains_key("labels")) {
+            match merge_labels(&ctx.labels, attrs.get("labels")) {
+                Ok(merged) => {
+                    if let Some(keys) = label_keys(&merged) {
+                        self.check_required_labels(tf_type, res_name, &keys, &resource_path);
+                    }
+                    if merged.as_mapping().is_none_or(|m| !m.is_empty()) {
+                        final_attrs.insert("labels".into(), merged);
+                    }
+                }
+                Err(msg) => self.error_at(&yaml_path_join(&resource_path, "labels"), msg),
+            }
+        }
+        if protect {
+            if let Some(schema) = resource_schema {
+                if schema.block.attributes.contains_key("deletion_protection") && !attrs.contains_key("deletion_protection") {
+                    final_attrs.insert("deletion_protection".into(), true.into());
+                }
+                if schema.block.attributes.contains_key("deletion_policy") && !attrs.contains_key("deletion_policy") {
+                    final_attrs.insert("deletion_policy".into(), protected_deletion_policy(tf_type).into());
+                }
+            }
+        }
+
         if tf_type == "google_project" {
             let has_org = attrs.contains_key(&serde_yaml::Value::String("org_id".to_string())) ||
                           attrs.contains_key(&serde_yaml::Value::String("org".to_string()));
@@ -840,18 +1774,21 @@ impl<'a> Transpiler<'a> {
                 }
             }
 
-            // Inject billing_account if missing and variable exists
-            if !attrs.contains_key(&serde_yaml::Value::String("billing_account".to_string())) {
-                if let Some(ba) = self.variables.get("billing-account-infra") {
-                    if let Some(val) = self.yaml_to_hcl_value(ba) {
-                        block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", val));
-                    }
+            // Resolve a named billing account, or inject one: inherited defaults first, then the variable
+            if let Some(ba) = attrs.get("billing_account").and_then(|v| v.as_str()) {
+                final_attrs.insert("billing_account".into(), self.billing_account_id(ba).into());
+            } else if let Some(ba) = &ctx.defaults.billing_account {
+                block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", self.billing_account_id(ba)));
+            } else if let Some(ba) = self.variables.get("billing-account-infra") {
+                if let Some(val) = self.yaml_to_hcl_value(ba) {
+                    block_builder = block_builder.add_attribute(hcl::Attribute::new("billing_account", val));
                 }
             }
         } else if tf_type == "google_org_policy_policy" {
-            let name_val = attrs.get(&serde_yaml::Value::String("name".to_string()))
-                .and_then(|v| v.as_str())
-                .expect("Mandatory 'name' attribute missing for google_org_policy_policy");
+            let Some(name_val) = attrs.get("name").and_then(|v| v.as_str()) else {
+                self.error_at(&yaml_path_join(&ctx.yaml_path, &format!("{}.{}", tf_type, res_name)), t("org-policy-name-missing", &[]));
+                return;
+            };
 
             let has_parent = attrs.contains_key(&serde_yaml::Value::String("parent".to_string()));
             let (resolved_parent_expr, resolved_parent_str) = if has_parent {
@@ -859,9 +1796,11 @@ impl<'a> Transpiler<'a> {
                 (self.yaml_to_hcl_value(v), v.as_str().map(|s| s.to_string()))
             } else if let Some(p_ref) = ctx.project_ref.as_ref().or(ctx.folder_ref.as_ref()).or(ctx.org_ref.as_ref()) {
                 (Some(self.parse_hcl_expr(p_ref)), Some(p_ref.clone()))
-            } else {
-                let org_id = ctx.org_id.as_ref().unwrap();
+            } else if let Some(org_id) = &ctx.org_id {
                 (Some(hcl::Expression::from(format!("organizations/{}", org_id))), Some(format!("organizations/{}", org_id)))
+            } else {
+                self.error_at(&yaml_path_join(&ctx.yaml_path, &format!("{}.{}", tf_type, res_name)), t("org-policy-parent-missing", &[]));
+                return;
             };
 
             // Calculate final name
@@ -891,7 +1830,7 @@ impl<'a> Transpiler<'a> {
             // Narrowest Context Inheritance
             let project_params = ["project", "project_id"];
             let folder_params = ["folder", "folder_id"];
-            let org_params = ["org_id", "organization"];
+            let org_params = ["org_id", "organization", "parent"];
 
             let mut context_set = false;
 
@@ -939,16 +1878,42 @@ impl<'a> Transpiler<'a> {
                 }
             }
 
-            // 3. Try Org Context
+            // 3. Try Org Context (`parent` only outside of folders/projects, where it is the organization)
+            let in_org_scope = ctx.project_ref.is_none() && ctx.project_id.is_none() && ctx.folder_ref.is_none() && ctx.folder_id.is_none();
             if !context_set {
                 if let Some(o_id) = &ctx.org_id {
                     for o in org_params {
-                        if schema.block.attributes.contains_key(o) && !attrs.contains_key(&serde_yaml::Value::String(o.to_string())) {
-                            block_builder = block_builder.add_attribute(hcl::Attribute::new(o, o_id.clone()));
-                            final_attrs.insert(serde_yaml::Value::String(o.to_string()), serde_yaml::Value::String(o_id.clone()));
-                            context_set = true;
-                            break;
+                        if o == "parent" && !in_org_scope {
+                            continue;
                         }
+                        let Some(attr_schema) = schema.block.attributes.get(o) else { continue };
+                        if attrs.contains_key(o) {
+                            continue;
+                        }
+                        let Some(value) = org_attribute_value(tf_type, o, attr_schema, o_id) else { continue };
+                        block_builder = block_builder.add_attribute(hcl::Attribute::new(o, value.clone()));
+                        final_attrs.insert(o.into(), value.into());
+                        context_set = true;
+                        break;
+                    }
+                }
+            }
+
+            // Explicit organization IDs are normalized to the format the attribute expects
+            for o in org_params {
+                let Some(attr_schema) = schema.block.attributes.get(o) else { continue };
+                let explicit = match attrs.get(o) {
+                    Some(serde_yaml::Value::String(s)) => s.clone(),
+                    Some(serde_yaml::Value::Number(n)) => n.to_string(),
+                    _ => continue,
+                };
+                let numeric = explicit.strip_prefix("organizations/").unwrap_or(&explicit);
+                if numeric.is_empty() || !numeric.chars().all(|c| c.is_ascii_digit()) {
+                    continue;
+                }
+                if let Some(value) = org_attribute_value(tf_type, o, attr_schema, numeric) {
+                    if value != explicit {
+                        final_attrs.insert(o.into(), value.into());
                     }
                 }
             }
@@ -959,13 +1924,30 @@ impl<'a> Transpiler<'a> {
                 let needs_folder = folder_params.iter().any(|f| schema.block.attributes.contains_key(*f) && !attrs.contains_key(&serde_yaml::Value::String(f.to_string())));
 
                 if needs_project {
-                    eprintln!("Warning: Resource '{}' ({}) requires a 'project' parameter but is defined outside a project context and no explicit project is provided.", res_name, tf_type);
+                    crate::warnings::warn(t("no-project-context-group", &[("type", &tf_type)]), res_name,
+                        t("no-project-context", &[("resource", &res_name), ("type", &tf_type)]));
                 } else if needs_folder {
-                    eprintln!("Warning: Resource '{}' ({}) requires a 'folder' parameter but is defined outside a folder context and no explicit folder is provided.", res_name, tf_type);
+                    crate::warnings::warn(t("no-folder-context-group", &[("type", &tf_type)]), res_name,
+                        t("no-folder-context", &[("resource", &res_name), ("type", &tf_type)]));
                 }
             }
         }
 
+        // `each.*` and `count.index` only exist in resources with `for_each` / `count`
+        let iterated = final_attrs.contains_key("for_each") || final_attrs.contains_key("count");
+        if !iterated {
+            if let Some(reference) = final_attrs.values().find_map(iteration_reference) {
+                self.error_at(&resource_path, t("instance-reference-outside-loop", &[("reference", &reference)]));
+            }
+        }
+        if let Some(serde_yaml::Value::Sequence(items)) = final_attrs.get("for_each") {
+            // A YAML list becomes a set, as for_each does not take lists
+            let items: Vec<hcl::Expression> = items.iter().filter_map(|v| self.yaml_to_hcl_value(v)).collect();
+            let set = hcl::expr::FuncCall::builder("toset").arg(hcl::Expression::Array(items)).build();
+            block_builder = block_builder.add_attribute(("for_each", hcl::Expression::from(set)));
+            final_attrs.remove("for_each");
+        }
+
         for (k, v) in &final_attrs {
             if let serde_yaml::Value::String(k_str) = k {
                 // Skip fields that were handled specially, but only if they were auto-injected
@@ -973,7 +1955,7 @@ impl<'a> Transpiler<'a> {
                 let was_explicitly_provided = attrs.contains_key(k);
                 let should_skip = if tf_type == "google_org_policy_policy" && (k_str == "name" || k_str == "constraint" || k_str == "parent") {
                     true
-                } else if ["project", "project_id", "folder", "folder_id", "org_id", "organization", "import-id", "import-existing"].contains(&k_str.as_str()) {
+                } else if ["project", "project_id", "folder", "folder_id", "org_id", "organization", "parent", "import-id", "import-existing"].contains(&k_str.as_str()) {
                     // Only skip if it was auto-injected, not if explicitly provided
                     !was_explicitly_provided
                 } else {
@@ -1008,26 +1990,15 @@ impl<'a> Transpiler<'a> {
                                     for (rk, rv) in rule_map {
                                         if let serde_yaml::Value::String(rks) = rk {
                                             if rks == "parameters" {
-                                                // Parameters must be a JSON string. If the user provided
-                                                // a structured YAML value, JSON-encode it. If it's already
-                                                // a string, pass it through unchanged.
-                                                match rv {
-                                                    serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_) => {
-                                                        if let Ok(json_str) = serde_json::to_string(rv) {
-                                                            rule_builder = rule_builder.add_attribute((
-                                                                "parameters",
-                                                                hcl::Value::from(json_str),
-                                                            ));
-                                                        }
-                                                    }
-                                                    _ => {
-                                                        if let Some(val) = self.yaml_to_hcl_value(rv) {
-                                                            rule_builder = rule_builder.add_attribute((
-                                                                "parameters",
-                                                                val,
-                                                            ));
-                                                        }
-                                                    }
+                                                // Parameters must be a JSON string. A structured YAML value
+                                                // becomes `jsonencode({...})` (keys in YAML order); a string
+                                                // is passed through unchanged.
+                                                let val = match rv {
+                                                    serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_) => self.jsonencode(rv),
+                                                    _ => self.yaml_to_hcl_value(rv),
+                                                };
+                                                if let Some(val) = val {
+                                                    rule_builder = rule_builder.add_attribute(("parameters", val));
                                                 }
                                             } else if rks == "values" {
                                                 // `values` is a nested block whose fields (like
@@ -1069,6 +2040,23 @@ impl<'a> Transpiler<'a> {
                     }
                 }
 
+                if k_str == "depends_on" {
+                    if let Some(depends_on) = self.depends_on_value(v, &resource_path) {
+                        block_builder = block_builder.add_attribute(("depends_on", depends_on));
+                    }
+                    continue;
+                }
+
+                if let serde_yaml::Value::Tagged(tagged) = v {
+                    if tagged.tag == "!dynamic" {
+                        let nested_schema = resource_schema.and_then(|s| s.block.block_types.get(k_str).map(|bts| &bts.block));
+                        if let Some(block) = self.dynamic_block(k_str, &tagged.value, nested_schema) {
+                            block_builder = block_builder.add_block(block);
+                        }
+                        continue;
+                    }
+                }
+
                 let is_block = if let Some(schema) = resource_schema {
                     schema.block.block_types.contains_key(k_str)
                 } else {
@@ -1087,13 +2075,21 @@ impl<'a> Transpiler<'a> {
                         block_builder = block_builder.add_block(block);
                     }
                 } else {
-                    if let Some(val) = self.yaml_to_hcl_value(v) {
+                    let attr_schema = resource_schema.and_then(|s| s.block.attributes.get(k_str));
+                    if let Some(val) = self.attribute_value(v, attr_schema) {
                         block_builder = block_builder.add_attribute(hcl::Attribute::new(k_str.as_str(), val));
                     }
                 }
             }
         }
 
+        if let Some(lifecycle) = self.lifecycle_block(explicit_lifecycle.as_ref(), protect) {
+            block_builder = block_builder.add_block(lifecycle);
+        }
+        if let Some(timeouts) = self.timeouts_block(explicit_timeouts.as_ref(), tf_type, res_name, resource_schema) {
+            block_builder = block_builder.add_block(timeouts);
+        }
+
         if let Some(schema) = resource_schema {
             let mut val_attrs = HashMap::new();
             for (k, v) in final_attrs {
@@ -1101,20 +2097,108 @@ impl<'a> Transpiler<'a> {
                     val_attrs.insert(ks, v);
                 }
             }
-            self.validate_resource(tf_type, res_name, &val_attrs, schema);
+            self.validate_resource(tf_type, res_name, &val_attrs, schema, &resource_path);
         }
 
-        blocks.push(block_builder.build());
+        self.push_resource(blocks, block_builder.build(), &resource_path);
 
         // Generate Import Block if requested
         if let Some(id) = import_id {
-            import_blocks.push(hcl::Block::builder("import")
-                .add_attribute(("to", self.parse_hcl_expr(&format!("{}.{}", tf_type, label))))
-                .add_attribute(("id", id))
-                .build());
+            let import = match condition {
+                // Imported only while the resource exists
+                Some(condition) => {
+                    let instances = hcl::Expression::Object(hcl::Object::from([(hcl::ObjectKey::from("0"), hcl::Expression::from(0))]));
+                    let for_each = hcl::expr::Conditional::new(condition, instances, hcl::Expression::Object(hcl::Object::new()));
+                    hcl::Block::builder("import")
+                        .add_attribute(("for_each", hcl::Expression::from(for_each)))
+                        .add_attribute(("to", self.parse_hcl_expr(&format!("{}.{}[each.value]", tf_type, label))))
+                }
+                None => hcl::Block::builder("import")
+                    .add_attribute(("to", self.parse_hcl_expr(&format!("{}.{}", tf_type, label)))),
+            };
+            import_blocks.push(import.add_attribute(("id", id)).build());
         }
     }
 
+    /// An explicit parent of a project instead of the folder it is nested in: `folder:` (a folder
+    /// key as `!ref <key>` or `!ref folder.<key>`, or `folders/<id>`) becomes `folder_id`,
+    /// `org: true` (or an organization ID) `org_id`. `Some(None)` keeps the nesting (or an
+    /// explicit `folder_id`/`org_id`); `None` if more than one parent is given or it is invalid.
+    fn project_parent(&self, project: &Project, ctx: &ResourceContext, yaml_path: &str) -> Option<Option<(&'static str, hcl::Expression)>> {
+        let given: Vec<&str> = ["folder", "org", "folder_id", "org_id"].into_iter()
+            .filter(|k| project.extra.get(*k).is_some_and(|v| !matches!(v, serde_yaml::Value::Bool(false) | serde_yaml::Value::Null)))
+            .collect();
+        if given.len() > 1 {
+            self.error_at(yaml_path, t("project-several-parents", &[("parents", &given.join(", "))]));
+            return None;
+        }
+        let parent = match given.first().map(|k| (*k, &project.extra[*k])) {
+            Some(("folder", serde_yaml::Value::Tagged(tagged))) if tagged.tag == "!ref" => {
+                let Some(reference) = tagged.value.as_str() else {
+                    self.error_at(yaml_path, t("ref-needs-string", &[]));
+                    return None;
+                };
+                // `folder.shared` is the folder with the key `shared`
+                let reference = reference.strip_prefix("folder.").map_or_else(|| reference.to_string(), |key| format!("{}.name", key));
+                let reference = serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue { tag: tagged.tag.clone(), value: reference.into() }));
+                self.yaml_to_hcl_value(&reference).map(|expr| ("folder_id", expr))
+            }
+            Some(("folder", value @ serde_yaml::Value::Tagged(_))) => self.yaml_to_hcl_value(value).map(|expr| ("folder_id", expr)),
+            Some(("folder", serde_yaml::Value::String(id))) if id.starts_with("folders/") => Some(("folder_id", hcl::Expression::from(id.clone()))),
+            Some(("folder", _)) => {
+                self.error_at(yaml_path, t("project-folder-invalid", &[]));
+                return None;
+            }
+            Some(("org", serde_yaml::Value::Bool(true))) => match &ctx.org_id {
+                Some(org_id) => Some(("org_id", hcl::Expression::from(org_id.clone()))),
+                None => {
+                    self.error_at(yaml_path, t("project-org-without-id", &[]));
+                    return None;
+                }
+            },
+            Some(("org", value @ (serde_yaml::Value::String(_) | serde_yaml::Value::Number(_)))) => {
+                let org_id = value.as_str().map_or_else(|| value.as_u64().unwrap_or_default().to_string(), |s| s.trim_start_matches("organizations/").to_string());
+                Some(("org_id", hcl::Expression::from(org_id)))
+            }
+            Some(("org", _)) => {
+                self.error_at(yaml_path, t("project-org-invalid", &[]));
+                return None;
+            }
+            _ => None,
+        };
+        Some(parent)
+    }
+
+    /// The condition of a resource with `when:`, `Some(None)` for an unconditional one and `None`
+    /// if it is left out (`enabled: false`, `when: false` or an invalid entry). Resources whose
+    /// schema has an `enabled` or `when` attribute keep it as an attribute.
+    fn resource_condition(&self, attrs: &serde_yaml::Mapping, resource_schema: Option<&crate::schema::ResourceSchema>, resource_path: &str) -> Option<Option<hcl::Expression>> {
+        let meta = |key: &str| attrs.get(key).filter(|_| !resource_schema.is_some_and(|s| s.block.attributes.contains_key(key)));
+        match meta("enabled") {
+            None | Some(serde_yaml::Value::Bool(true)) => {}
+            Some(serde_yaml::Value::Bool(false)) => return None,
+            Some(_) => {
+                self.error_at(resource_path, t("enabled-invalid", &[]));
+                return None;
+            }
+        }
+        let when = match meta("when") {
+            None | Some(serde_yaml::Value::Bool(true)) => return Some(None),
+            Some(serde_yaml::Value::Bool(false)) => return None,
+            Some(when @ serde_yaml::Value::Tagged(_)) => self.yaml_to_hcl_value(when),
+            Some(_) => None,
+        };
+        let Some(when) = when else {
+            self.error_at(resource_path, t("when-invalid", &[]));
+            return None;
+        };
+        if attrs.contains_key("count") || attrs.contains_key("for_each") {
+            self.error_at(resource_path, t("when-with-count", &[]));
+            return None;
+        }
+        Some(Some(when))
+    }
+
     fn transpile_iam_members(
         &self,
         blocks: &mut Vec<hcl::Block>,
@@ -1126,18 +2210,27 @@ impl<'a> Transpiler<'a> {
         provider_alias: Option<&str>,
         explicit_parent_id: Option<String>,
     ) {
-        let parent_expr_str_option = match id_attribute {
-            "project" | "project_id" => ctx.project_ref.as_deref().or(ctx.project_id.as_deref()),
-            "folder" | "folder_id" => ctx.folder_ref.as_deref().or(ctx.folder_id.as_deref()),
-            "org_id" => ctx.org_id.as_deref().or(ctx.org_ref.as_deref()),
-            _ => None,
+        let provider_alias = ctx.provider_routes.get(resource_type).map(String::as_str).or(provider_alias);
+        // References to generated parents, or IDs from the YAML
+        let (generated_parent, given_parent) = match id_attribute {
+            "project" | "project_id" => (ctx.project_ref.as_deref(), ctx.project_id.as_deref()),
+            "folder" | "folder_id" => (ctx.folder_ref.as_deref(), ctx.folder_id.as_deref()),
+            "org_id" => (None, ctx.org_id.as_deref().or(ctx.org_ref.as_deref())),
+            _ => (None, None),
         };
+        let parent_expr_str_option = generated_parent.or(given_parent);
+        let parent_path = yaml_path_join(&ctx.yaml_path, resource_type);
 
-        let parent_val_expr = if let Some(explicit) = explicit_parent_id {
-            self.parse_hcl_expr(&explicit)
-        } else {
-            self.parse_hcl_expr(parent_expr_str_option.unwrap_or(""))
+        let parent_val_expr = match (&explicit_parent_id, generated_parent) {
+            (Some(explicit), _) => self.reference(explicit, &parent_path),
+            (None, Some(generated)) => self.parse_hcl_expr(generated),
+            (None, None) => self.reference(given_parent.unwrap_or(""), &parent_path),
         };
+        let for_each = self.iam_for_each(resource_type);
+        let mut grants: Vec<(String, String, Option<String>)> = Vec::new();
+        // `iam-mode: binding|policy` applies to the IAM of the scope itself
+        let iam_mode = if AUTHORITATIVE_IAM_TYPES.contains(&resource_type) { ctx.iam_mode } else { crate::config::IamMode::Member };
+        let mut authoritative_grants: Vec<IamGrant> = Vec::new();
 
         for (member, roles) in iam_members {
             for role_val in roles {
@@ -1169,6 +2262,17 @@ impl<'a> Transpiler<'a> {
                     }
                 };
 
+                if iam_mode != crate::config::IamMode::Member {
+                    authoritative_grants.push(IamGrant { member: member.clone(), role, condition: condition_val.cloned(), import_id });
+                    continue;
+                }
+
+                // A condition makes the grants differ in shape, so conditional ones keep their own resource
+                if for_each && condition_val.is_none() {
+                    grants.push((member.clone(), role, import_id));
+                    continue;
+                }
+
                 use std::collections::hash_map::DefaultHasher;
                 use std::hash::{Hash, Hasher};
                 let mut hasher = DefaultHasher::new();
@@ -1178,7 +2282,11 @@ impl<'a> Transpiler<'a> {
                     // Simple hash of the condition value
                     format!("{:?}", cv).hash(&mut hasher);
                 }
-                let label = format!("iam_{}_{:x}", member.replace(&['@', '.', ':', '-'][..], "_"), hasher.finish());
+                // Billing IAM of a non-default account must not collide with the same grant on the default one
+                if resource_type == "google_billing_account_iam_member" && explicit_parent_id != self.root_billing_account() {
+                    explicit_parent_id.hash(&mut hasher);
+                }
+                let label = safe_label(format!("iam_{}_{:x}", member.replace(&['@', '.', ':', '-'][..], "_"), hasher.finish()));
 
                 let mut rb = hcl::Block::builder("resource")
                     .add_label(resource_type)
@@ -1199,7 +2307,7 @@ impl<'a> Transpiler<'a> {
                     }
                 }
 
-                blocks.push(rb.build());
+                self.push_resource(blocks, rb.build(), &yaml_path_join(&ctx.yaml_path, resource_type));
 
                 // Generate Import Block if requested
                 if let Some(id) = import_id {
@@ -1210,10 +2318,230 @@ impl<'a> Transpiler<'a> {
                 }
             }
         }
+
+        let parent = explicit_parent_id.as_deref().or(parent_expr_str_option).unwrap_or_default();
+        if !authoritative_grants.is_empty() {
+            let imports = self.push_iam_authoritative(blocks, (resource_type, iam_mode), (id_attribute, parent_val_expr.clone(), parent), authoritative_grants, provider_alias, &yaml_path_join(&ctx.yaml_path, resource_type));
+            import_blocks.extend(imports);
+        }
+        if !grants.is_empty() {
+            let imports = self.push_iam_for_each(blocks, resource_type, (id_attribute, parent_val_expr, parent), grants, provider_alias, &yaml_path_join(&ctx.yaml_path, resource_type));
+            import_blocks.extend(imports);
+        }
+    }
+
+    /// The grants of a scope with `iam-mode: binding` or `policy`: a `google_*_iam_binding` per
+    /// role (and condition), labelled `iam_<parent>_<role>`, or one `google_*_iam_policy` per
+    /// parent, labelled `iam_<parent>`, with its bindings in a `google_iam_policy` data source.
+    /// Grants on the same parent from several places of the YAML are merged. A grant with an
+    /// `import-id` (`<parent> <role> <member>`) imports the binding or policy it ends up in, with
+    /// the ID that resource type expects (`<parent> <role>` or `<parent>`).
+    fn push_iam_authoritative(
+        &self,
+        blocks: &mut Vec<hcl::Block>,
+        (resource_type, mode): (&str, crate::config::IamMode),
+        (id_attribute, parent_val_expr, parent): (&str, hcl::Expression, &str),
+        grants: Vec<IamGrant>,
+        provider_alias: Option<&str>,
+        yaml_path: &str,
+    ) -> Vec<hcl::Block> {
+        let parent_label = iam_parent_label(parent);
+        let scope_type = resource_type.strip_suffix("_iam_member").unwrap_or(resource_type);
+        let provider = provider_alias.and_then(|alias| alias.parse::<hcl::Expression>().ok());
+        let generated_type = match mode {
+            crate::config::IamMode::Policy => format!("{}_iam_policy", scope_type),
+            _ => format!("{}_iam_binding", scope_type),
+        };
+        self.warn_authoritative(&generated_type, yaml_path);
+
+        // Bindings: (role, condition) -> members, sorted as the member maps have no order
+        let mut bindings: Vec<(String, Option<serde_yaml::Value>, Vec<String>)> = Vec::new();
+        // (role, condition) of the binding (`None` for the policy) -> parent ID
+        type BindingKey = (String, Option<serde_yaml::Value>);
+        let mut import_ids: Vec<(Option<BindingKey>, String)> = Vec::new();
+        for grant in grants {
+            if let Some(parent_id) = grant.import_id.as_deref().and_then(|id| id.split_whitespace().next()) {
+                let binding = (mode == crate::config::IamMode::Binding).then(|| (grant.role.clone(), grant.condition.clone()));
+                if !import_ids.iter().any(|(b, _)| *b == binding) {
+                    import_ids.push((binding, parent_id.to_string()));
+                }
+            }
+            match bindings.iter_mut().find(|(role, condition, _)| *role == grant.role && *condition == grant.condition) {
+                Some((_, _, members)) => {
+                    if !members.contains(&grant.member) {
+                        members.push(grant.member);
+                    }
+                }
+                None => bindings.push((grant.role, grant.condition, vec![grant.member])),
+            }
+        }
+        bindings.sort_by(|a, b| (&a.0, format!("{:?}", a.1)).cmp(&(&b.0, format!("{:?}", b.1))));
+        for (_, _, members) in &mut bindings {
+            members.sort();
+        }
+
+        let condition_block = |condition: &Option<serde_yaml::Value>| condition.as_ref().and_then(|c| self.yaml_to_hcl_block("condition", c, None));
+        let condition_title = |condition: &Option<serde_yaml::Value>| condition.as_ref()
+            .and_then(|c| c.get("title")).and_then(|t| t.as_str()).map(str::to_string);
+        let mut import_blocks = Vec::new();
+        let mut import = |to: String, id: String| import_blocks.push(hcl::Block::builder("import")
+            .add_attribute(("to", self.parse_hcl_expr(&to)))
+            .add_attribute(("id", id))
+            .build());
+
+        if mode == crate::config::IamMode::Binding {
+            let binding_type = format!("{}_iam_binding", scope_type);
+            for (role, condition, members) in bindings {
+                let mut label = format!("{}_{}", parent_label, role.rsplit('/').next().unwrap_or(&role));
+                if let Some(title) = condition_title(&condition) {
+                    label = format!("{}_{}", label, title);
+                }
+                let label = resource_label(&label);
+
+                let existing = blocks.iter_mut()
+                    .find(|b| b.identifier() == "resource" && b.labels().iter().map(|l| l.as_str()).eq([binding_type.as_str(), label.as_str()]))
+                    .and_then(|b| b.body.attributes_mut().find(|a| a.key() == "members"));
+                if let Some(hcl::Attribute { expr: hcl::Expression::Array(existing), .. }) = existing {
+                    existing.extend(members.into_iter().map(hcl::Expression::from).filter(|m| !existing.contains(m)).collect::<Vec<_>>());
+                    existing.sort_by_key(|m| m.to_string());
+                } else {
+                    let mut rb = hcl::Block::builder("resource")
+                        .add_label(&binding_type)
+                        .add_label(&label)
+                        .add_attribute(("role", role.clone()))
+                        .add_attribute(("members", hcl::Expression::Array(members.into_iter().map(hcl::Expression::from).collect())))
+                        .add_attribute((id_attribute, parent_val_expr.clone()));
+                    if let Some(cond_block) = condition_block(&condition) {
+                        rb = rb.add_block(cond_block);
+                    }
+                    if let Some(provider) = &provider {
+                        rb = rb.add_attribute(("provider", provider.clone()));
+                    }
+                    self.push_resource(blocks, rb.build(), yaml_path);
+                }
+
+                let binding = Some((role.clone(), condition.clone()));
+                if let Some((_, parent_id)) = import_ids.iter().find(|(b, _)| *b == binding) {
+                    let id = std::iter::once(parent_id.clone()).chain([role]).chain(condition_title(&condition)).collect::<Vec<_>>().join(" ");
+                    import(format!("{}.{}", binding_type, label), id);
+                }
+            }
+            return import_blocks;
+        }
+
+        // Policy: the bindings go into the data source, merged with those from other places
+        let data_label = resource_label(&format!("{}_{}", scope_type.trim_start_matches("google_"), parent_label));
+        let binding_blocks: Vec<hcl::Block> = bindings.into_iter().map(|(role, condition, members)| {
+            let mut binding = hcl::Block::builder("binding")
+                .add_attribute(("role", role))
+                .add_attribute(("members", hcl::Expression::Array(members.into_iter().map(hcl::Expression::from).collect())));
+            if let Some(cond_block) = condition_block(&condition) {
+                binding = binding.add_block(cond_block);
+            }
+            binding.build()
+        }).collect();
+        let existing = blocks.iter_mut()
+            .find(|b| b.identifier() == "data" && b.labels().iter().map(|l| l.as_str()).eq(["google_iam_policy", data_label.as_str()]));
+        if let Some(existing) = existing {
+            existing.body.0.extend(binding_blocks.into_iter().map(hcl::Structure::Block));
+        } else {
+            let mut data = hcl::Block::builder("data").add_label("google_iam_policy").add_label(&data_label);
+            for binding in binding_blocks {
+                data = data.add_block(binding);
+            }
+            if let Some(provider) = &provider {
+                data = data.add_attribute(("provider", provider.clone()));
+            }
+            blocks.push(data.build());
+
+            let policy_type = format!("{}_iam_policy", scope_type);
+            let mut rb = hcl::Block::builder("resource")
+                .add_label(&policy_type)
+                .add_label(&parent_label)
+                .add_attribute((id_attribute, parent_val_expr))
+                .add_attribute(("policy_data", self.parse_hcl_expr(&format!("data.google_iam_policy.{}.policy_data", data_label))));
+            if let Some(provider) = &provider {
+                rb = rb.add_attribute(("provider", provider.clone()));
+            }
+            self.push_resource(blocks, rb.build(), yaml_path);
+            if let Some((_, parent_id)) = import_ids.first() {
+                import(format!("{}.{}", policy_type, parent_label), parent_id.clone());
+            }
+        }
+        import_blocks
+    }
+
+    /// One `for_each` resource with the unconditional grants on a parent, labelled after the
+    /// parent (`iam_infra` for `google_project.infra.project_id`). Grants on the same parent
+    /// from several places of the YAML are merged into it; the keys are `<member> <role>`.
+    /// Returns the import blocks of the grants with an `import-id`.
+    fn push_iam_for_each(
+        &self,
+        blocks: &mut Vec<hcl::Block>,
+        resource_type: &str,
+        (id_attribute, parent_val_expr, parent): (&str, hcl::Expression, &str),
+        grants: Vec<(String, String, Option<String>)>,
+        provider_alias: Option<&str>,
+        yaml_path: &str,
+    ) -> Vec<hcl::Block> {
+        let label = iam_parent_label(parent);
+
+        let mut entries: hcl::Object<hcl::ObjectKey, hcl::Expression> = hcl::Object::new();
+        let mut import_blocks = Vec::new();
+        for (member, role, import_id) in grants {
+            let key = format!("{} {}", member, role);
+            if let Some(id) = import_id {
+                import_blocks.push(hcl::Block::builder("import")
+                    .add_attribute(("to", self.parse_hcl_expr(&format!("{}.{}[\"{}\"]", resource_type, label, key))))
+                    .add_attribute(("id", id))
+                    .build());
+            }
+            let value = hcl::Expression::Object(hcl::Object::from([
+                (hcl::ObjectKey::Identifier(hcl::Identifier::unchecked("member")), hcl::Expression::from(member)),
+                (hcl::ObjectKey::Identifier(hcl::Identifier::unchecked("role")), hcl::Expression::from(role)),
+            ]));
+            entries.insert(hcl::ObjectKey::Expression(hcl::Expression::from(key)), value);
+        }
+
+        let existing = blocks.iter_mut()
+            .find(|b| b.identifier() == "resource" && b.labels().iter().map(|l| l.as_str()).eq([resource_type, label.as_str()]))
+            .and_then(|b| b.body.attributes_mut().find(|a| a.key() == "for_each"));
+        if let Some(hcl::Attribute { expr: hcl::Expression::Object(object), .. }) = existing {
+            object.extend(entries);
+            object.sort_by(|(a, _), (b, _)| a.to_string().cmp(&b.to_string()));
+            return import_blocks;
+        }
+
+        entries.sort_by(|(a, _), (b, _)| a.to_string().cmp(&b.to_string()));
+        let mut rb = hcl::Block::builder("resource")
+            .add_label(resource_type)
+            .add_label(&label)
+            .add_attribute(("for_each", hcl::Expression::Object(entries)))
+            .add_attribute(("role", self.parse_hcl_expr("each.value.role")))
+            .add_attribute(("member", self.parse_hcl_expr("each.value.member")))
+            .add_attribute((id_attribute, parent_val_expr));
+        if let Some(alias) = provider_alias {
+            if let Ok(expr) = alias.parse::<hcl::Expression>() {
+                rb = rb.add_attribute(("provider", expr));
+            }
+        }
+        self.push_resource(blocks, rb.build(), yaml_path);
+        import_blocks
     }
 
-    fn validate_resource(&self, tf_type: &str, name: &str, attrs: &HashMap<String, serde_yaml::Value>, schema: &crate::schema::ResourceSchema) {
+    /// Whether IAM members of a type are generated as one `for_each` resource per parent, i.e. an
+    /// `auto_explode` pattern matching it ends in `:for_each`.
+    fn iam_for_each(&self, resource_type: &str) -> bool {
+        self.auto_explode.iter()
+            .filter_map(|pattern| pattern.strip_suffix(FOR_EACH_SUFFIX))
+            .any(|pattern| self.matches_pattern(pattern, resource_type))
+    }
+
+    /// Checks a resource against its schema. With validation level `error`, problems are collected
+    /// as diagnostics at `yaml_path` and fail the run; otherwise they are warnings.
+    fn validate_resource(&self, tf_type: &str, name: &str, attrs: &HashMap<String, serde_yaml::Value>, schema: &crate::schema::ResourceSchema, yaml_path: &str) {
         if self.validation_level == "none" { return; }
+        let _span = crate::timings::span("validation");
 
         for (attr_name, attr_schema) in &schema.block.attributes {
             if attr_schema.required && !attrs.contains_key(attr_name) {
@@ -1222,12 +2550,11 @@ impl<'a> Transpiler<'a> {
                     continue;
                 }
 
-                let msg = format!("Missing mandatory parameter '{}' for resource '{}' ({})", attr_name, name, tf_type);
+                let msg = t("missing-parameter", &[("attribute", attr_name), ("resource", &name), ("type", &tf_type)]);
                 if self.validation_level == "error" {
-                    eprintln!("Error: {}", msg);
-                    std::process::exit(1);
+                    self.error_at(yaml_path, msg);
                 } else {
-                    eprintln!("Warning: {}", msg);
+                    crate::warnings::warn(t("missing-parameter-group", &[("attribute", attr_name), ("type", &tf_type)]), name, msg);
                 }
             }
         }
@@ -1235,12 +2562,11 @@ impl<'a> Transpiler<'a> {
         for (block_name, block_schema) in &schema.block.block_types {
             if let Some(min) = block_schema.min_items {
                 if min > 0 && !attrs.contains_key(block_name) {
-                    let msg = format!("Missing mandatory block '{}' for resource '{}' ({})", block_name, name, tf_type);
+                    let msg = t("missing-block", &[("block", block_name), ("resource", &name), ("type", &tf_type)]);
                     if self.validation_level == "error" {
-                        eprintln!("Error: {}", msg);
-                        std::process::exit(1);
+                        self.error_at(yaml_path, msg);
                     } else {
-                        eprintln!("Warning: {}", msg);
+                        crate::warnings::warn(t("missing-block-group", &[("block", block_name), ("type", &tf_type)]), name, msg);
                     }
                 }
             }
@@ -1261,7 +2587,7 @@ impl<'a> Transpiler<'a> {
 
             // Automatically ignore if it's a resource type (nested resource)
             if let Some(reg) = &self.registry {
-                if reg.find_resource(attr_name).is_some() {
+                if reg.find_resource(crate::aliases::resolve(&self.resource_aliases, attr_name)).is_some() {
                     continue;
                 }
             }
@@ -1275,17 +2601,360 @@ impl<'a> Transpiler<'a> {
                     continue;
                 }
 
-                let msg = format!("Unknown field '{}' for resource '{}' ({})", attr_name, name, tf_type);
+                let msg = t("unknown-field", &[("field", attr_name), ("resource", &name), ("type", &tf_type)]);
                 if self.validation_level == "error" {
-                    eprintln!("Error: {}", msg);
-                    std::process::exit(1);
+                    self.error_at(yaml_path, msg);
                 } else {
-                    eprintln!("Warning: {}", msg);
+                    crate::warnings::warn(t("unknown-field-group", &[("field", attr_name), ("type", &tf_type)]), name, msg);
+                }
+            }
+        }
+    }
+
+    /// Collects an entry without Terraform support for the extras script instead of emitting HCL.
+    fn record_unsupported(&self, tf_type: &str, label: &str, attrs: &serde_yaml::Mapping, ctx: &ResourceContext) {
+        let mut placeholders: BTreeMap<String, String> = attrs.iter()
+            .filter_map(|(k, v)| {
+                let value = match v {
+                    serde_yaml::Value::String(s) => s.clone(),
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    _ => return None,
+                };
+                Some((k.as_str()?.to_string(), value))
+            })
+            .collect();
+        placeholders.insert("name".to_string(), label.to_string());
+        if let Some(project) = &ctx.project_id {
+            placeholders.insert("project".to_string(), project.clone());
+        }
+        if let Some(org) = &ctx.org_id {
+            placeholders.insert("org".to_string(), org.clone());
+        }
+
+        let mut apply = crate::extras::commands(attrs, "apply", &placeholders);
+        let mut destroy = crate::extras::commands(attrs, "destroy", &placeholders);
+        if apply.is_empty() {
+            if let Some((derived_apply, derived_destroy)) = crate::extras::derived_commands(tf_type, attrs, ctx.project_id.as_deref(), ctx.org_id.as_deref()) {
+                apply = derived_apply;
+                if !attrs.contains_key("destroy") {
+                    destroy = derived_destroy;
+                }
+            }
+        }
+        if apply.is_empty() {
+            eprintln!("Warning: Unsupported resource '{}.{}' has no 'apply' commands", tf_type, label);
+        }
+        self.unsupported.borrow_mut().push(crate::extras::UnsupportedResource {
+            address: format!("{}.{}", tf_type, label),
+            apply,
+            destroy,
+        });
+    }
+
+    /// `module` blocks. `source`, `version`, `providers`, `depends_on`, `count` and `for_each` are
+    /// meta-arguments, everything else is passed as input. Without `providers`, the aliased
+    /// providers of the YAML are passed under their plain name (`google = google.google`), like the
+    /// root resources use them.
+    fn transpile_modules(&self, blocks: &mut Vec<hcl::Block>, modules: &HashMap<String, serde_yaml::Value>) {
+        let mut names: Vec<&String> = modules.keys().collect();
+        names.sort();
+        for name in names {
+            let yaml_path = format!("module.{}", name);
+            let Some(attrs) = modules[name].as_mapping() else {
+                self.error_at(&yaml_path, t("module-not-mapping", &[]));
+                continue;
+            };
+            let Some(source) = attrs.get("source").and_then(|v| self.yaml_to_hcl_value(v)) else {
+                self.error_at(&yaml_path, t("module-source-missing", &[]));
+                continue;
+            };
+
+            let mut builder = hcl::Block::builder("module").add_label(resource_label(name)).add_attribute(("source", source));
+            if let Some(version) = attrs.get("version").and_then(|v| self.yaml_to_hcl_value(v)) {
+                builder = builder.add_attribute(("version", version));
+            }
+            for meta in ["count", "for_each"] {
+                if let Some(value) = attrs.get(meta).and_then(|v| self.yaml_to_hcl_value(v)) {
+                    builder = builder.add_attribute((meta, value));
+                }
+            }
+
+            let providers: Vec<(String, String)> = match attrs.get("providers").and_then(|v| v.as_mapping()) {
+                Some(map) => map.iter()
+                    .filter_map(|(k, v)| Some((k.as_str()?.to_string(), v.as_str()?.to_string())))
+                    .collect(),
+                None => self.aliased_providers(),
+            };
+            if !providers.is_empty() {
+                let mut object = hcl::Object::new();
+                for (local, configured) in providers {
+                    // Provider names are bare keys (`google`, `google.alias`), not strings
+                    let key = hcl::Identifier::new(local.as_str()).map(hcl::ObjectKey::Identifier)
+                        .unwrap_or_else(|_| hcl::ObjectKey::Expression(self.parse_hcl_expr(&local)));
+                    object.insert(key, self.parse_hcl_expr(&configured));
+                }
+                builder = builder.add_attribute(("providers", hcl::Expression::Object(object)));
+            }
+            if let Some(depends_on) = attrs.get("depends_on").and_then(|v| self.depends_on_value(v, &yaml_path)) {
+                builder = builder.add_attribute(("depends_on", depends_on));
+            }
+
+            const META_ARGUMENTS: &[&str] = &["source", "version", "count", "for_each", "providers", "depends_on"];
+            for (key, value) in attrs {
+                let Some(key) = key.as_str().filter(|k| !META_ARGUMENTS.contains(k)) else { continue };
+                if let Some(value) = self.yaml_to_hcl_value(value) {
+                    builder = builder.add_attribute((key, value));
+                }
+            }
+
+            self.sources.borrow_mut().insert(format!("module.{}", resource_label(name)), yaml_path);
+            blocks.push(builder.build());
+        }
+    }
+
+    /// One `locals` block with all entries, sorted by name.
+    fn transpile_locals(&self, blocks: &mut Vec<hcl::Block>, locals: &HashMap<String, serde_yaml::Value>) {
+        let mut names: Vec<&String> = locals.keys().collect();
+        names.sort();
+        let mut builder = hcl::Block::builder("locals");
+        for name in names {
+            if hcl::Identifier::new(name.as_str()).is_err() {
+                self.error_at(&format!("locals.{}", name), t("local-name-invalid", &[("name", &name)]));
+                continue;
+            }
+            if let Some(value) = self.yaml_to_hcl_value(&locals[name]) {
+                builder = builder.add_attribute((name.as_str(), value));
+            }
+        }
+        blocks.push(builder.build());
+    }
+
+    /// Whether a string is a reference to a declared local (`local.name` or `local.name.attr`).
+    fn is_local_reference(&self, s: &str) -> bool {
+        let Some(path) = s.strip_prefix("local.") else { return false };
+        let name = path.split('.').next().unwrap_or_default();
+        self.config.locals.as_ref().is_some_and(|locals| locals.contains_key(name))
+            && path.split('.').all(|part| hcl::Identifier::new(part).is_ok())
+    }
+
+    /// `output` blocks; an entry is the value itself or a mapping with `value`, `description` and
+    /// `sensitive`. References are written `!ref google_storage_bucket.state.name`, `!expr` takes
+    /// any expression.
+    fn transpile_outputs(&self, blocks: &mut Vec<hcl::Block>, outputs: &HashMap<String, serde_yaml::Value>) {
+        let mut names: Vec<&String> = outputs.keys().collect();
+        names.sort();
+        for name in names {
+            let yaml_path = format!("outputs.{}", name);
+            if hcl::Identifier::new(name.as_str()).is_err() {
+                self.error_at(&yaml_path, t("output-name-invalid", &[("name", &name)]));
+                continue;
+            }
+            let entry = &outputs[name];
+            let (value, attrs) = match entry.as_mapping() {
+                Some(attrs) => (attrs.get("value"), Some(attrs)),
+                None => (Some(entry), None),
+            };
+            let Some(value) = value.and_then(|v| self.reference_value(v, &yaml_path)) else {
+                self.error_at(&yaml_path, t("output-value-missing", &[]));
+                continue;
+            };
+
+            let mut builder = hcl::Block::builder("output").add_label(name.as_str()).add_attribute(("value", value));
+            for (key, attr) in attrs.into_iter().flatten() {
+                match key.as_str() {
+                    Some("value") => {}
+                    Some("description") => match attr.as_str() {
+                        Some(description) => builder = builder.add_attribute(("description", description)),
+                        None => self.error_at(&yaml_path, t("output-description-invalid", &[])),
+                    },
+                    Some("sensitive") => match attr.as_bool() {
+                        Some(sensitive) => builder = builder.add_attribute(("sensitive", sensitive)),
+                        None => self.error_at(&yaml_path, t("output-sensitive-invalid", &[])),
+                    },
+                    Some("depends_on") => {
+                        if let Some(depends_on) = self.depends_on_value(attr, &yaml_path) {
+                            builder = builder.add_attribute(("depends_on", depends_on));
+                        }
+                    }
+                    other => self.error_at(&yaml_path, t("output-attribute-unknown", &[("attribute", &other.unwrap_or_default())])),
+                }
+            }
+            blocks.push(builder.build());
+        }
+    }
+
+    /// `(name, name.alias)` of the providers in the YAML that have a single aliased configuration.
+    fn aliased_providers(&self) -> Vec<(String, String)> {
+        let mut providers: Vec<(String, String)> = self.config.providers.iter().flatten()
+            .filter_map(|(name, value)| {
+                let alias = value.as_mapping()?.get("alias")?.as_str()?;
+                Some((name.clone(), format!("{}.{}", name, alias)))
+            })
+            .collect();
+        providers.sort();
+        providers
+    }
+
+    /// Adds a resource block, remembering the YAML entry it was generated from.
+    /// Two entries generating the same address, e.g. `my-project` and `my_project`, are an error.
+    fn push_resource(&self, blocks: &mut Vec<hcl::Block>, block: hcl::Block, yaml_path: &str) {
+        if let [tf_type, label] = block.labels.as_slice() {
+            let address = format!("{}.{}", tf_type.as_str(), label.as_str());
+            if hcl::Identifier::new(label.as_str()).is_err() {
+                self.error_at(yaml_path, t("resource-label-invalid", &[("label", &label.as_str())]));
+                return;
+            }
+            let previous = self.sources.borrow().get(&address).cloned();
+            if let Some(previous) = previous {
+                self.error_at(yaml_path, t("address-duplicate", &[("address", &address), ("previous", &previous)]));
+                return;
+            }
+            self.sources.borrow_mut().insert(address, yaml_path.to_string());
+        }
+        blocks.push(block);
+    }
+
+    /// Records an error for the YAML entry at `yaml_path`; the transpilation fails at the end.
+    /// A `depends_on` list. Plain strings stay strings until [`Self::resolve_depends_on`] turns them
+    /// into addresses; `!ref` and `!expr` entries are taken as they are.
+    fn depends_on_value(&self, v: &serde_yaml::Value, yaml_path: &str) -> Option<hcl::Expression> {
+        let Some(items) = v.as_sequence() else {
+            self.error_at(yaml_path, t("depends-on-invalid", &[]));
+            return None;
+        };
+        Some(hcl::Expression::Array(items.iter()
+            .filter_map(|item| match item {
+                serde_yaml::Value::String(s) => Some(hcl::Expression::String(s.clone())),
+                other => self.yaml_to_hcl_value(other),
+            })
+            .collect()))
+    }
+
+    /// Resolves the string entries of `depends_on` lists: an address of a generated block is kept,
+    /// otherwise the entry is a chain of YAML keys (`my-folder`, `my-project.state`) matched
+    /// against the end of the YAML paths of the generated resources and modules. Other addresses
+    /// (`data.x.y`, resources defined outside cfg2hcl) are kept as well.
+    fn resolve_depends_on(&self, blocks: &mut [hcl::Block]) {
+        let sources = self.sources.borrow();
+        for block in blocks.iter_mut() {
+            let yaml_path = block_yaml_path(&sources, block);
+            let Some(attr) = block.body.attributes_mut().find(|a| a.key.as_str() == "depends_on") else { continue };
+            let hcl::Expression::Array(items) = &mut attr.expr else { continue };
+            for item in items.iter_mut() {
+                let hcl::Expression::String(entry) = item else { continue };
+                match resolve_dependency(&sources, entry) {
+                    Ok(address) => *item = address,
+                    Err(message) => self.error_at(&yaml_path, message),
                 }
             }
         }
     }
 
+    /// Resolves the `!ref` values written as YAML keys: `infra.project_id` is the attribute
+    /// `project_id` of the resource with the key `infra`, `network.vpc.self_link` the one of the
+    /// resource `vpc` of the folder or project `network`. Generated addresses and references to
+    /// resources outside cfg2hcl (`google_x.y.attr`) are kept; a key that matches nothing fails
+    /// the transpilation, and so does a reference to a resource with `count`/`for_each` (`when:`
+    /// included) that does not name the instance (`logs[0].name`).
+    fn resolve_key_references(&self, blocks: &mut [hcl::Block]) {
+        let key_references = self.key_references.borrow();
+        if key_references.is_empty() {
+            return;
+        }
+        let sources = self.sources.borrow();
+        let multi_instance = multi_instance_addresses(blocks);
+        for block in blocks.iter_mut() {
+            let yaml_path = block_yaml_path(&sources, block);
+            let mut errors = Vec::new();
+            for_each_expression_mut(&mut block.body, &mut |expr| resolve_key_reference(expr, &sources, &key_references, &multi_instance, &mut errors));
+            for message in errors {
+                self.error_at(&yaml_path, message);
+            }
+        }
+    }
+
+    fn error_at(&self, yaml_path: &str, message: String) {
+        self.diagnostics.borrow_mut().push(Diagnostic { yaml_path: yaml_path.to_string(), message });
+    }
+
+    /// Resolves a billing account name declared in `billing_accounts:` to its ID; IDs pass through.
+    fn billing_account_id(&self, name_or_id: &str) -> String {
+        self.config.billing_accounts.as_ref()
+            .and_then(|accounts| accounts.get(name_or_id))
+            .cloned()
+            .unwrap_or_else(|| name_or_id.to_string())
+    }
+
+    /// Billing account used by the top-level `billing_account_iam_member` block.
+    fn root_billing_account(&self) -> Option<String> {
+        let explicit = self.config.billing_account_iam_member.as_ref()
+            .and_then(|v| v.get("billing_account_id"))
+            .and_then(|v| v.as_str());
+        explicit
+            .or_else(|| self.config.defaults.as_ref().and_then(|d| d.billing_account.as_deref()))
+            .map(|ba| self.billing_account_id(ba))
+            .or_else(|| self.variables.get("billing-account-infra").and_then(|v| v.as_str()).map(|s| s.to_string()))
+    }
+
+    /// Compact billing IAM (`member: [roles]`). The account is taken from `billing_account_id`
+    /// (ID or name), then the inherited `defaults.billing_account`, then `billing-account-infra`.
+    fn transpile_billing_iam(
+        &self,
+        blocks: &mut Vec<hcl::Block>,
+        import_blocks: &mut Vec<hcl::Block>,
+        value: &serde_yaml::Value,
+        ctx: &ResourceContext,
+        provider_alias: Option<&str>,
+    ) {
+        let mut members_map = HashMap::new();
+        let mut explicit_id = None;
+
+        if let serde_yaml::Value::Mapping(map) = value {
+            for (k, v) in map {
+                if let serde_yaml::Value::String(k_str) = k {
+                    if k_str == "billing_account_id" {
+                        if let serde_yaml::Value::String(s) = v {
+                            explicit_id = Some(self.billing_account_id(s));
+                        }
+                    } else if let serde_yaml::Value::Sequence(seq) = v {
+                        members_map.insert(k_str.clone(), seq.clone());
+                    }
+                }
+            }
+        }
+        if explicit_id.is_none() {
+            explicit_id = ctx.defaults.billing_account.as_deref().map(|ba| self.billing_account_id(ba));
+        }
+        if explicit_id.is_none() {
+            if let Some(ba) = self.variables.get("billing-account-infra").and_then(|v| v.as_str()) {
+                explicit_id = Some(ba.to_string());
+            }
+        }
+
+        self.transpile_iam_members(blocks, import_blocks, &members_map, "google_billing_account_iam_member", "billing_account_id", ctx, provider_alias, explicit_id);
+    }
+
+    /// Lints a resource's final labels against the `required_labels` list from config.toml.
+    /// With validation level `error`, missing labels fail the run as a diagnostic at `yaml_path`.
+    fn check_required_labels(&self, tf_type: &str, name: &str, labels: &BTreeSet<String>, yaml_path: &str) {
+        if self.validation_level == "none" { return; }
+
+        let missing: Vec<&str> = self.required_labels.iter()
+            .filter(|key| !labels.contains(key.as_str()))
+            .map(|key| key.as_str())
+            .collect();
+        if missing.is_empty() { return; }
+
+        let missing = missing.join(", ");
+        let msg = t("missing-labels", &[("labels", &missing), ("resource", &name), ("type", &tf_type)]);
+        if self.validation_level == "error" {
+            self.error_at(yaml_path, msg);
+        } else {
+            crate::warnings::warn(t("missing-labels-group", &[("labels", &missing), ("type", &tf_type)]), name, msg);
+        }
+    }
+
     fn resolve_anchor_reference(&self, v: &serde_yaml::Value) -> Option<serde_yaml::Value> {
         // Check if the value is a string that looks like an anchor reference (starts with *)
         if let serde_yaml::Value::String(s) = v {
@@ -1314,13 +2983,29 @@ impl<'a> Transpiler<'a> {
         };
         
         match v {
-            serde_yaml::Value::Tagged(tagged) if tagged.tag == "!expr" => {
+            serde_yaml::Value::Tagged(tagged) if tagged.tag == "!expr" || tagged.tag == "!ref" => {
                 if let serde_yaml::Value::String(s) = &tagged.value {
-                    s.parse::<hcl::Expression>().ok()
+                    let expr = s.parse::<hcl::Expression>().ok()?;
+                    if tagged.tag == "!ref" && reference_root(&expr).is_some_and(|root| !REFERENCE_ROOTS.contains(&root)) {
+                        self.key_references.borrow_mut().insert(hcl::format::to_string(&expr).unwrap_or_default());
+                    }
+                    Some(expr)
                 } else {
                     None
                 }
             }
+            serde_yaml::Value::Tagged(tagged) if tagged.tag == "!secret" => {
+                let secret = crate::secret_tag::SecretRef::parse(tagged.value.as_str()?).ok()?;
+                let expr = secret.expression();
+                self.secrets.borrow_mut().insert(secret);
+                Some(expr)
+            }
+            serde_yaml::Value::String(s) if self.is_local_reference(s) || is_iteration_reference(s) => Some(self.parse_hcl_expr(s)),
+            serde_yaml::Value::String(s) if ITERATION_VARIABLES.iter().any(|v| s.contains(&format!("${{{}", v))) => {
+                // `bucket-${each.key}` is a template, not a string with an escaped `$${`
+                let quoted = s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+                Some(hcl::Expression::from(hcl::expr::TemplateExpr::QuotedString(quoted)))
+            }
             serde_yaml::Value::String(s) => Some(hcl::Expression::from(s.clone())),
             serde_yaml::Value::Bool(b) => Some(hcl::Expression::from(*b)),
             serde_yaml::Value::Number(n) => {
@@ -1347,11 +3032,36 @@ impl<'a> Transpiler<'a> {
         }
     }
 
+    /// Value of a resource attribute; a mapping or list given for a string attribute of the schema
+    /// (IAM policy documents, JSON configs) becomes `jsonencode(...)`.
+    fn attribute_value(&self, v: &serde_yaml::Value, schema: Option<&crate::schema::AttributeSchema>) -> Option<hcl::Expression> {
+        match v {
+            serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_) if schema.is_some_and(|s| s.is_string()) => self.jsonencode(v),
+            _ => self.yaml_to_hcl_value(v),
+        }
+    }
+
+    /// `jsonencode(...)` of a structured YAML value, keeping the keys in YAML order so that diffs
+    /// stay readable (a pre-serialized JSON string is opaque to Terraform's diff).
+    fn jsonencode(&self, v: &serde_yaml::Value) -> Option<hcl::Expression> {
+        let value = self.yaml_to_hcl_value(v)?;
+        Some(hcl::Expression::from(hcl::expr::FuncCall::builder("jsonencode").arg(value).build()))
+    }
+
     fn yaml_to_hcl_block(&self, name: &str, v: &serde_yaml::Value, schema: Option<&crate::schema::BlockSchema>) -> Option<hcl::Block> {
         if let serde_yaml::Value::Mapping(map) = v {
             let mut builder = hcl::Block::builder(name);
             for (bk, bv) in map {
                 if let serde_yaml::Value::String(bks) = bk {
+                    if let serde_yaml::Value::Tagged(tagged) = bv {
+                        if tagged.tag == "!dynamic" {
+                            let nested_schema = schema.and_then(|s| s.block_types.get(bks).map(|bts| &bts.block));
+                            if let Some(nb) = self.dynamic_block(bks, &tagged.value, nested_schema) {
+                                builder = builder.add_block(nb);
+                            }
+                            continue;
+                        }
+                    }
                     let is_nested_block = if let Some(s) = schema {
                         s.block_types.contains_key(bks)
                     } else {
@@ -1371,7 +3081,8 @@ impl<'a> Transpiler<'a> {
                             builder = builder.add_block(nb);
                         }
                     } else {
-                        if let Some(val) = self.yaml_to_hcl_value(bv) {
+                        let attr_schema = schema.and_then(|s| s.attributes.get(bks));
+                        if let Some(val) = self.attribute_value(bv, attr_schema) {
                             builder = builder.add_attribute((bks.as_str(), val));
                         }
                     }
@@ -1381,13 +3092,163 @@ impl<'a> Transpiler<'a> {
         } else { None }
     }
 
-    fn transpile_cloud_identity_groups(&self, blocks: &mut Vec<hcl::Block>, import_blocks: &mut Vec<hcl::Block>, groups: &serde_yaml::Mapping, provider_alias: Option<&str>) {
+    /// `name: !dynamic [...]` as one `dynamic` block iterating over the entries instead of one
+    /// block per entry. Keys missing in some entries are `null` there, and nested blocks become
+    /// nested `dynamic` blocks. The explicit form `!dynamic { for_each, iterator, content }` is
+    /// written as it is, with `content` converted like any block.
+    fn dynamic_block(&self, name: &str, v: &serde_yaml::Value, schema: Option<&crate::schema::BlockSchema>) -> Option<hcl::Block> {
+        match v {
+            serde_yaml::Value::Mapping(map) if map.contains_key("content") => {
+                let for_each = map.get("for_each").and_then(|f| self.yaml_to_hcl_value(f))?;
+                let mut builder = hcl::Block::builder("dynamic").add_label(name).add_attribute(("for_each", for_each));
+                if let Some(iterator) = map.get("iterator").and_then(|i| i.as_str()) {
+                    builder = builder.add_attribute(("iterator", hcl::Expression::Variable(hcl::Variable::sanitized(iterator))));
+                }
+                Some(builder.add_block(self.yaml_to_hcl_block("content", map.get("content")?, schema)?).build())
+            }
+            serde_yaml::Value::Sequence(items) => {
+                let items: Vec<&serde_yaml::Mapping> = items.iter().filter_map(|i| i.as_mapping()).collect();
+                let (entries, content) = self.dynamic_entries(name, &items, schema);
+                Some(hcl::Block::builder("dynamic")
+                    .add_label(name)
+                    .add_attribute(("for_each", hcl::Expression::Array(entries.into_iter().map(hcl::Expression::Object).collect())))
+                    .add_block(content)
+                    .build())
+            }
+            _ => None,
+        }
+    }
+
+    /// The `for_each` entries of a `dynamic` block (all with the same keys) and its `content`
+    /// block reading them through `iterator`. Nested blocks are lists in the entries, so that one
+    /// block is the same as several.
+    fn dynamic_entries(&self, iterator: &str, items: &[&serde_yaml::Mapping], schema: Option<&crate::schema::BlockSchema>) -> (Vec<hcl::Object<hcl::ObjectKey, hcl::Expression>>, hcl::Block) {
+        let mut keys: Vec<&str> = Vec::new();
+        for key in items.iter().flat_map(|item| item.keys()).filter_map(|k| k.as_str()) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        let mut entries = vec![hcl::Object::new(); items.len()];
+        let mut content = hcl::Block::builder("content");
+        for key in keys {
+            let values: Vec<Option<&serde_yaml::Value>> = items.iter().map(|item| item.get(key)).collect();
+            let object_key = hcl::ObjectKey::Identifier(hcl::Identifier::sanitized(key));
+            let reference = hcl::Expression::Traversal(Box::new(hcl::Traversal::new(
+                hcl::Variable::sanitized(iterator),
+                [hcl::TraversalOperator::GetAttr(hcl::Identifier::unchecked("value")), hcl::TraversalOperator::GetAttr(hcl::Identifier::sanitized(key))],
+            )));
+            let is_nested_block = match schema {
+                Some(s) => s.block_types.contains_key(key),
+                None => values.iter().flatten()
+                    .any(|v| v.is_mapping() || v.as_sequence().is_some_and(|seq| seq.iter().any(|i| i.is_mapping())))
+                    && !matches!(key, "labels" | "metadata" | "annotations"),
+            };
+
+            if is_nested_block {
+                let nested_schema = schema.and_then(|s| s.block_types.get(key).map(|bts| &bts.block));
+                // Blocks of all entries at once, so that they get the same keys, then split up again
+                let mut owners = Vec::new();
+                let mut nested_items = Vec::new();
+                for (idx, value) in values.iter().enumerate() {
+                    let blocks: Vec<&serde_yaml::Mapping> = match value {
+                        Some(serde_yaml::Value::Sequence(seq)) => seq.iter().filter_map(|i| i.as_mapping()).collect(),
+                        Some(serde_yaml::Value::Mapping(m)) => vec![m],
+                        _ => Vec::new(),
+                    };
+                    owners.extend(std::iter::repeat_n(idx, blocks.len()));
+                    nested_items.extend(blocks);
+                }
+                let (nested_entries, nested_content) = self.dynamic_entries(key, &nested_items, nested_schema);
+                let mut lists = vec![Vec::new(); items.len()];
+                for (owner, entry) in owners.into_iter().zip(nested_entries) {
+                    lists[owner].push(hcl::Expression::Object(entry));
+                }
+                // No blocks are `null` rather than `[]`, which hcl-rs formats without the comma
+                // before the next entry
+                for (entry, list) in entries.iter_mut().zip(lists) {
+                    let list = if list.is_empty() { hcl::Expression::Null } else { hcl::Expression::Array(list) };
+                    entry.insert(object_key.clone(), list);
+                }
+                let is_null = hcl::expr::BinaryOp::new(reference.clone(), hcl::expr::BinaryOperator::Eq, hcl::Expression::Null);
+                let for_each = hcl::expr::Conditional::new(hcl::Expression::from(hcl::expr::Operation::Binary(is_null)), hcl::Expression::Array(Vec::new()), reference);
+                content = content.add_block(hcl::Block::builder("dynamic")
+                    .add_label(key)
+                    .add_attribute(("for_each", hcl::Expression::from(for_each)))
+                    .add_block(nested_content)
+                    .build());
+            } else {
+                let attr_schema = schema.and_then(|s| s.attributes.get(key));
+                for (entry, value) in entries.iter_mut().zip(values) {
+                    let value = value.and_then(|v| self.attribute_value(v, attr_schema)).unwrap_or(hcl::Expression::Null);
+                    entry.insert(object_key.clone(), value);
+                }
+                content = content.add_attribute((key, reference));
+            }
+        }
+        (entries, content.build())
+    }
+
+    /// Builds the `lifecycle` meta-argument block from an explicit YAML mapping, adding
+    /// `prevent_destroy = true` for protected resources unless it is set explicitly.
+    fn lifecycle_block(&self, explicit: Option<&serde_yaml::Value>, protect: bool) -> Option<hcl::Block> {
+        let mut map = explicit.and_then(|v| v.as_mapping()).cloned().unwrap_or_default();
+        if protect && !map.contains_key("prevent_destroy") {
+            map.insert("prevent_destroy".into(), true.into());
+        }
+        if map.is_empty() {
+            return None;
+        }
+        // All lifecycle arguments are attributes
+        let plain = crate::schema::BlockSchema { attributes: HashMap::new(), block_types: HashMap::new() };
+        self.yaml_to_hcl_block("lifecycle", &serde_yaml::Value::Mapping(map), Some(&plain))
+    }
+
+    /// Builds the `timeouts` block (`create: 30m`, `delete: 1h`). With a schema, operations the
+    /// resource type has no timeout for are reported, and the block is left out for types without
+    /// timeouts (Terraform would reject it).
+    fn timeouts_block(&self, explicit: Option<&serde_yaml::Value>, tf_type: &str, name: &str, schema: Option<&crate::schema::ResourceSchema>) -> Option<hcl::Block> {
+        let map = explicit?.as_mapping()?;
+        let supported = schema.map(|s| s.block.block_types.get("timeouts").map(|t| &t.block));
+        let check = self.validation_level != "none";
+        if let Some(None) = supported {
+            if check {
+                crate::warnings::warn(t("timeouts-unsupported-group", &[("type", &tf_type)]), name,
+                    t("timeouts-unsupported", &[("type", &tf_type), ("resource", &name)]));
+            }
+            return None;
+        }
+
+        let duration = regex::Regex::new(r"^(\d+(\.\d+)?(ns|us|µs|ms|s|m|h))+$").expect("valid pattern");
+        let mut builder = hcl::Block::builder("timeouts");
+        for (operation, value) in map {
+            let Some(operation) = operation.as_str() else { continue };
+            if let (true, Some(Some(block))) = (check, supported) {
+                if !block.attributes.contains_key(operation) {
+                    let mut expected: Vec<&str> = block.attributes.keys().map(String::as_str).collect();
+                    expected.sort();
+                    crate::warnings::warn(t("timeout-unknown-group", &[("operation", &operation), ("type", &tf_type)]), name,
+                        t("timeout-unknown", &[("operation", &operation), ("resource", &name), ("type", &tf_type), ("expected", &expected.join(", "))]));
+                }
+            }
+            let text = value.as_str().map(str::to_string).unwrap_or_else(|| serde_yaml::to_string(value).unwrap_or_default().trim().to_string());
+            if check && !duration.is_match(&text) {
+                crate::warnings::warn(t("timeout-invalid-group", &[("operation", &operation), ("type", &tf_type)]), name,
+                    t("timeout-invalid", &[("operation", &operation), ("resource", &name), ("type", &tf_type), ("value", &text)]));
+            }
+            builder = builder.add_attribute((operation, text));
+        }
+        Some(builder.build())
+    }
+
+    fn transpile_cloud_identity_groups(&self, blocks: &mut Vec<hcl::Block>, import_blocks: &mut Vec<hcl::Block>, groups: &serde_yaml::Mapping, provider_alias: Option<&str>, scope_path: &str) {
         let customer_id = self.config.extra.get("customer-id").and_then(|v| v.as_str()).unwrap_or("");
         let customer_domain = self.config.extra.get("customer-domain").and_then(|v| v.as_str()).unwrap_or("");
 
         for (g_name_val, g_attrs_val) in groups {
             if let (serde_yaml::Value::String(group_name), serde_yaml::Value::Mapping(attrs)) = (g_name_val, g_attrs_val) {
-                let resource_name = group_name.replace("-", "_");
+                let resource_name = resource_label(group_name);
 
                 let mut builder = hcl::Block::builder("resource")
                     .add_label("google_cloud_identity_group")
@@ -1436,7 +3297,8 @@ impl<'a> Transpiler<'a> {
                     .unwrap_or("EMPTY");
                 builder = builder.add_attribute(("initial_group_config", igc.to_owned()));
 
-                blocks.push(builder.build());
+                let group_path = yaml_path_join(scope_path, &format!("cloud_identity_group.{}", group_name));
+                self.push_resource(blocks, builder.build(), &group_path);
 
                 // Generate Import Block if requested
                 if let Some(id) = attrs.get(&serde_yaml::Value::String("import-id".to_string())).and_then(|v| v.as_str()) {
@@ -1517,7 +3379,7 @@ impl<'a> Transpiler<'a> {
                     }
                 }
 
-                blocks.push(mb.build());
+                self.push_resource(blocks, mb.build(), &group_path);
             }
         }
     }
@@ -1527,10 +3389,11 @@ impl<'a> Transpiler<'a> {
         &self,
         blocks: &mut Vec<hcl::Block>,
         import_blocks: &mut Vec<hcl::Block>,
-        project_ref: &str,
+        (project_ref, project_expr): (&str, hcl::Expression),
         service_val: &serde_yaml::Value,
         provider_alias: Option<&str>,
         safe_project_name: &str,
+        yaml_path: &str,
     ) {
         let service_configs = match service_val {
             serde_yaml::Value::String(s) => vec![(s.clone(), None)],
@@ -1552,14 +3415,30 @@ impl<'a> Transpiler<'a> {
             _ => return,
         };
 
+        // The project's resource label stays the same however the project is referenced
+        let project_key = project_ref.strip_prefix("google_project.")
+            .and_then(|r| r.split('.').next())
+            .unwrap_or(safe_project_name);
+
         for (service, service_attrs) in service_configs {
-            let safe_service = service.replace(".", "_");
-            let label = format!("{}_{}", safe_project_name, safe_service);
-            let project_expr = self.parse_hcl_expr(project_ref);
+            let render = |template: &str| service_label(template, safe_project_name, project_key, &service);
+            let label = safe_label(render(&self.service_label_template));
+
+            // Chain of renames through the earlier templates, ending at the current label
+            let mut from_labels: Vec<String> = self.previous_service_label_templates.iter().map(|t| safe_label(render(t))).collect();
+            from_labels.dedup();
+            let mut chain = from_labels.into_iter().filter(|l| *l != label).peekable();
+            while let Some(from) = chain.next() {
+                let to = chain.peek().cloned().unwrap_or_else(|| label.clone());
+                blocks.push(hcl::Block::builder("moved")
+                    .add_attribute(("from", self.parse_hcl_expr(&format!("google_project_service.{}", from))))
+                    .add_attribute(("to", self.parse_hcl_expr(&format!("google_project_service.{}", to))))
+                    .build());
+            }
             let mut service_builder = hcl::Block::builder("resource")
                 .add_label("google_project_service")
                 .add_label(&label)
-                .add_attribute(hcl::Attribute::new("project", project_expr))
+                .add_attribute(hcl::Attribute::new("project", project_expr.clone()))
                 .add_attribute(("service", service.to_owned()));
 
             if let Some(alias) = provider_alias {
@@ -1579,7 +3458,7 @@ impl<'a> Transpiler<'a> {
                 }
             }
 
-            blocks.push(service_builder.build());
+            self.push_resource(blocks, service_builder.build(), yaml_path);
 
             // Generate Import Block if requested
             if let Some(attrs) = service_attrs {
@@ -1593,7 +3472,12 @@ impl<'a> Transpiler<'a> {
         }
     }
 
-    fn configure_google_provider(&self, mut builder: hcl::BlockBuilder, project_id: Option<String>, has_billing_project: bool, has_user_project_override: bool) -> hcl::BlockBuilder {
+    fn configure_google_provider(&self, mut builder: hcl::BlockBuilder, provider: &str, project_id: Option<String>, has_billing_project: bool, has_user_project_override: bool, default_labels: Option<&BTreeMap<String, String>>) -> hcl::BlockBuilder {
+        // Emulator endpoints from --endpoint-overrides / [endpoint_overrides]
+        for (attribute, url) in crate::endpoints::provider_attributes(provider) {
+            builder = builder.add_attribute((attribute, url));
+        }
+
         // Use central infra project for billing/quota if available
         let infra_project = self.config.extra.get("infra-project-name").and_then(|v| v.as_str());
 
@@ -1625,6 +3509,12 @@ impl<'a> Transpiler<'a> {
                 builder = builder.add_attribute(("impersonate_service_account", sa_email));
             }
         }
+
+        // `default-labels:` of the scope, applied by the provider to every resource with labels
+        if let Some(labels) = default_labels.filter(|l| !l.is_empty()) {
+            let map: hcl::Map<String, hcl::Value> = labels.iter().map(|(k, v)| (k.clone(), hcl::Value::from(v.clone()))).collect();
+            builder = builder.add_attribute(("default_labels", hcl::Value::from(map)));
+        }
         builder
     }
 
@@ -1645,3 +3535,494 @@ impl<'a> Transpiler<'a> {
         }
     }
 }
+
+/// Non-IAM types `CEX_` can explode: (type, field the key goes to, field each list item goes to).
+const CEX_TEMPLATES: &[(&str, &str, &str)] = &[
+    ("google_tags_tag_key", "parent", "short_name"),
+    ("google_tags_tag_value", "parent", "short_name"),
+    ("google_tags_tag_binding", "parent", "tag_value"),
+    ("google_tags_location_tag_binding", "parent", "tag_value"),
+    ("google_compute_project_metadata_item", "project", "key"),
+];
+
+/// A `CEX_` entry of a non-IAM type (`parent: [item, ...]`) as (name, attributes) of one resource
+/// per item, with the key and the item in the fields of the type's [`CEX_TEMPLATES`] entry.
+/// Items can also be mappings with further attributes; they then hold the item field themselves.
+fn compact_resources(tf_type: &str, key: &str, items: &[serde_yaml::Value]) -> Result<Vec<(String, serde_yaml::Mapping)>, String> {
+    let Some((_, key_field, item_field)) = CEX_TEMPLATES.iter().find(|(t, _, _)| *t == tf_type) else {
+        let known: Vec<&str> = CEX_TEMPLATES.iter().map(|(t, _, _)| *t).collect();
+        return Err(t("cex-unsupported", &[("type", &tf_type), ("supported", &known.join(", "))]));
+    };
+    // `//cloudresourcemanager.googleapis.com/projects/42` -> `projects/42`
+    let short = |name: &str| name.rsplit('/').take(2).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("/");
+    items.iter().map(|item| {
+        let mut attrs = match item {
+            serde_yaml::Value::Mapping(attrs) => attrs.clone(),
+            item => serde_yaml::Mapping::from_iter([(serde_yaml::Value::from(*item_field), item.clone())]),
+        };
+        let item_name = attrs.get(*item_field).and_then(|v| match v {
+            serde_yaml::Value::Tagged(tagged) => tagged.value.as_str(),
+            v => v.as_str(),
+        }).map(str::to_string)
+            .ok_or_else(|| format!("Every item of {} needs a string '{}'", tf_type, item_field))?;
+        attrs.insert((*key_field).into(), key.into());
+        Ok((format!("{}_{}", short(key), short(&item_name)), attrs))
+    }).collect()
+}
+
+/// IAM member types whose scope can switch to authoritative bindings or a policy (`iam-mode:`).
+const AUTHORITATIVE_IAM_TYPES: &[&str] = &["google_organization_iam_member", "google_folder_iam_member", "google_project_iam_member"];
+
+/// A grant of a scope with `iam-mode: binding|policy`, collected for [`Transpiler::push_iam_authoritative`].
+struct IamGrant {
+    member: String,
+    role: String,
+    condition: Option<serde_yaml::Value>,
+    import_id: Option<String>,
+}
+
+/// Label of the IAM resources of a parent: `iam_infra` for `google_project.infra.project_id`.
+fn iam_parent_label(parent: &str) -> String {
+    let parent_name = match parent.split('.').collect::<Vec<_>>().as_slice() {
+        [_, name, _] => name.to_string(),
+        _ => parent.to_string(),
+    };
+    format!("iam_{}", parent_name.replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+}
+
+/// Keys of a project entry naming its parent explicitly, see [`Transpiler::project_parent`].
+const PROJECT_PARENT_KEYS: &[&str] = &["folder", "org"];
+
+/// Keys of a resource entry that make it conditional, see [`Transpiler::resource_condition`].
+const CONDITION_KEYS: &[&str] = &["enabled", "when"];
+
+/// Suffix of `auto_explode` patterns whose IAM members become one `for_each` resource per parent.
+const FOR_EACH_SUFFIX: &str = ":for_each";
+
+/// Iteration variables of resources with `for_each` or `count`.
+const ITERATION_VARIABLES: &[&str] = &["each.key", "each.value", "count.index"];
+
+/// Whether a string is a reference to the current iteration (`each.key`, `each.value.name`,
+/// `each.value["name"]`, `count.index`).
+fn is_iteration_reference(s: &str) -> bool {
+    ITERATION_VARIABLES.iter().any(|v| {
+        s.strip_prefix(v).is_some_and(|rest| rest.is_empty() || ((rest.starts_with('.') || rest.starts_with('[')) && !rest.contains(' ')))
+    })
+}
+
+/// The first iteration reference or `${...}` template with one in a YAML value.
+fn iteration_reference(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) if is_iteration_reference(s) => Some(s.clone()),
+        serde_yaml::Value::String(s) => ITERATION_VARIABLES.iter().find(|v| s.contains(&format!("${{{}", v))).map(|_| s.clone()),
+        serde_yaml::Value::Sequence(items) => items.iter().find_map(iteration_reference),
+        serde_yaml::Value::Mapping(map) => map.values().find_map(iteration_reference),
+        serde_yaml::Value::Tagged(tagged) => iteration_reference(&tagged.value),
+        _ => None,
+    }
+}
+
+/// `deletion_policy` value for protected resources. `PREVENT` only exists for projects;
+/// other resources are abandoned (removed from state without being deleted) instead.
+fn protected_deletion_policy(tf_type: &str) -> &'static str {
+    if tf_type == "google_project" { "PREVENT" } else { "ABANDON" }
+}
+
+/// Organization-valued attributes known to take `organizations/{id}`, used when the schema
+/// carries no description. Any other `org_id` / `organization` takes the numeric ID.
+const ORG_RESOURCE_NAME_ATTRIBUTES: &[(&str, &str)] = &[
+    ("google_org_policy_policy", "parent"),
+    ("google_access_context_manager_access_policy", "parent"),
+    ("google_essential_contacts_contact", "parent"),
+    ("google_tags_tag_key", "parent"),
+    ("google_securityposture_posture", "parent"),
+    ("google_securityposture_posture_deployment", "parent"),
+    ("google_org_policy_custom_constraint", "parent"),
+];
+
+/// Formats an organization ID for `attr` of `tf_type`: `organizations/{id}` or the bare ID,
+/// based on the attribute's description, else on `ORG_RESOURCE_NAME_ATTRIBUTES`.
+/// Returns `None` for a `parent` that does not take an organization.
+fn org_attribute_value(tf_type: &str, attr: &str, attr_schema: &crate::schema::AttributeSchema, org_id: &str) -> Option<String> {
+    let resource_name = attr_schema.expects_org_resource_name()
+        .unwrap_or_else(|| ORG_RESOURCE_NAME_ATTRIBUTES.contains(&(tf_type, attr)));
+    let id = org_id.strip_prefix("organizations/").unwrap_or(org_id);
+    match (attr, resource_name) {
+        (_, true) => Some(format!("organizations/{}", id)),
+        ("parent", false) => None,
+        (_, false) => Some(id.to_string()),
+    }
+}
+
+/// Renders a service label template: `{project}` (project reference with `.`/`:` replaced),
+/// `{project_key}` (resource label of the referenced project), `{service}`
+/// (`compute_googleapis_com`) and `{service_name}` (`compute`).
+fn service_label(template: &str, project: &str, project_key: &str, service: &str) -> String {
+    template
+        .replace("{project_key}", project_key)
+        .replace("{project}", project)
+        .replace("{service_name}", service.split('.').next().unwrap_or(service))
+        .replace("{service}", &service.replace('.', "_"))
+}
+
+/// YAML path of a generated block, for its diagnostics.
+fn block_yaml_path(sources: &BTreeMap<String, String>, block: &hcl::Block) -> String {
+    let labels: Vec<&str> = block.labels.iter().map(|l| l.as_str()).collect();
+    match block.identifier.as_str() {
+        "resource" => sources.get(&labels.join(".")).cloned(),
+        "module" => sources.get(&format!("module.{}", labels.join("."))).cloned(),
+        _ => None,
+    }.unwrap_or_else(|| format!("outputs.{}", labels.join(".")))
+}
+
+/// Addresses of the resources and modules whose YAML keys end with `keys`.
+fn key_matches<'a>(sources: &'a BTreeMap<String, String>, keys: &[&str]) -> Vec<&'a String> {
+    let keys: Vec<String> = keys.iter().map(|k| resource_label(k)).collect();
+    sources.iter()
+        .filter(|(address, _)| address.rsplit('.').next() == keys.last().map(String::as_str))
+        .filter(|(_, path)| {
+            // `folder.a.project.b.google_storage_bucket.c` -> [a, b, c]; IAM and services have no key
+            let segments: Vec<&str> = path.split('.').collect();
+            segments.len().is_multiple_of(2) && segments.iter().skip(1).step_by(2).map(|k| resource_label(k)).collect::<Vec<_>>().ends_with(&keys)
+        })
+        .map(|(address, _)| address)
+        .collect()
+}
+
+/// Address a `depends_on` entry refers to, see [`Transpiler::resolve_depends_on`].
+fn resolve_dependency(sources: &BTreeMap<String, String>, entry: &str) -> Result<hcl::Expression, String> {
+    let address = |a: &str| a.parse::<hcl::Expression>().map_err(|e| format!("Invalid 'depends_on' entry '{}': {}", a, e));
+    if sources.contains_key(entry) {
+        return address(entry);
+    }
+    let keys: Vec<&str> = entry.split('.').collect();
+    let matches = key_matches(sources, &keys);
+    match matches.as_slice() {
+        [found] => address(found),
+        [] => match entry.parse::<hcl::Expression>() {
+            Ok(traversal @ hcl::Expression::Traversal(_)) => Ok(traversal),
+            _ => Err(t("depends-on-no-match", &[("entry", &entry)])),
+        },
+        several => Err(t("depends-on-ambiguous", &[("entry", &entry), ("matches", &several.iter().map(|a| a.as_str()).collect::<Vec<_>>().join(", "))])),
+    }
+}
+
+/// Roots of references that are never YAML keys.
+const REFERENCE_ROOTS: &[&str] = &["var", "local", "module", "data", "each", "count", "self", "path", "terraform"];
+
+/// Root variable of a reference: `infra` of `infra.project_id`.
+fn reference_root(expr: &hcl::Expression) -> Option<&str> {
+    match expr {
+        hcl::Expression::Variable(variable) => Some(variable.as_str()),
+        hcl::Expression::Traversal(traversal) => match &traversal.expr {
+            hcl::Expression::Variable(variable) => Some(variable.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Calls `f` for every expression of `body`, its nested blocks and sub-expressions.
+fn for_each_expression_mut(body: &mut hcl::Body, f: &mut dyn FnMut(&mut hcl::Expression)) {
+    for attr in body.attributes_mut() {
+        f(&mut attr.expr);
+    }
+    for block in body.blocks_mut() {
+        for_each_expression_mut(&mut block.body, f);
+    }
+}
+
+/// Addresses of the resources and modules with `count` or `for_each`, whose attributes are only
+/// reachable through an instance index.
+fn multi_instance_addresses(blocks: &[hcl::Block]) -> BTreeSet<String> {
+    blocks.iter()
+        .filter(|block| block.body.attributes().any(|attr| attr.key() == "count" || attr.key() == "for_each"))
+        .filter_map(|block| {
+            let labels: Vec<&str> = block.labels.iter().map(|l| l.as_str()).collect();
+            match block.identifier.as_str() {
+                "resource" => Some(labels.join(".")),
+                "module" => Some(format!("module.{}", labels.join("."))),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Replaces `expr`, or the `!ref` values in it, by the address of the resource its YAML keys
+/// name, see [`Transpiler::resolve_key_references`].
+fn resolve_key_reference(expr: &mut hcl::Expression, sources: &BTreeMap<String, String>, key_references: &BTreeSet<String>, multi_instance: &BTreeSet<String>, errors: &mut Vec<String>) {
+    let is_key_reference = matches!(expr, hcl::Expression::Variable(_) | hcl::Expression::Traversal(_))
+        && hcl::format::to_string(expr).is_ok_and(|s| key_references.contains(&s));
+    if !is_key_reference {
+        if let hcl::Expression::Traversal(traversal) = expr {
+            for op in traversal.operators.iter_mut() {
+                if let hcl::expr::TraversalOperator::Index(index) = op {
+                    resolve_key_reference(index, sources, key_references, multi_instance, errors);
+                }
+            }
+        } else {
+            crate::extract_module::for_each_child_mut(expr, &mut |child| resolve_key_reference(child, sources, key_references, multi_instance, errors));
+        }
+        return;
+    }
+
+    let reference = hcl::format::to_string(expr).unwrap_or_default();
+    let (root, mut operators) = match expr.clone() {
+        hcl::Expression::Traversal(traversal) => (reference_root(&traversal.expr).unwrap_or_default().to_string(), traversal.operators),
+        other => (reference_root(&other).unwrap_or_default().to_string(), Vec::new()),
+    };
+    let keys: Vec<&str> = std::iter::once(root.as_str())
+        .chain(operators.iter().map_while(|op| match op {
+            hcl::expr::TraversalOperator::GetAttr(ident) => Some(ident.as_str()),
+            _ => None,
+        }))
+        .collect();
+    // A resource with count/for_each is referenced through one of its instances
+    let check_instance = |address: &str, rest: &[hcl::expr::TraversalOperator], errors: &mut Vec<String>| {
+        use hcl::expr::TraversalOperator::{AttrSplat, FullSplat, Index, LegacyIndex};
+        if multi_instance.contains(address) && !matches!(rest.first(), Some(Index(_) | LegacyIndex(_) | AttrSplat | FullSplat)) {
+            errors.push(t("ref-needs-instance", &[("reference", &reference), ("address", &address)]));
+        }
+    };
+    if keys.len() >= 2 && sources.contains_key(&keys[..2].join(".")) {
+        check_instance(&keys[..2].join("."), &operators[1..], errors);
+        return;
+    }
+    // The longest chain of keys that names a resource, the rest are its attributes
+    for n in (1..=keys.len()).rev() {
+        match key_matches(sources, &keys[..n]).as_slice() {
+            [] => continue,
+            [address] => {
+                check_instance(address, &operators[n - 1..], errors);
+                let Ok(hcl::Expression::Traversal(resolved)) = address.parse::<hcl::Expression>() else { return };
+                let mut resolved = *resolved;
+                resolved.operators.extend(operators.drain(n - 1..));
+                *expr = hcl::Expression::Traversal(Box::new(resolved));
+                return;
+            }
+            several => {
+                errors.push(t("ref-ambiguous", &[("reference", &reference), ("matches", &several.iter().map(|a| a.as_str()).collect::<Vec<_>>().join(", "))]));
+                return;
+            }
+        }
+    }
+    // `google_x.y.attr` of a resource defined outside cfg2hcl
+    if !(keys.len() >= 2 && root.contains('_')) {
+        errors.push(t("ref-no-match", &[("reference", &reference)]));
+    }
+}
+
+/// Longest generated label; longer ones are cut and get a hash of the full name appended.
+const MAX_LABEL_LENGTH: usize = 128;
+
+/// Resource label of a YAML key: `my-project` -> `my_project`, `123-project` -> `_123_project`.
+pub fn resource_label(name: &str) -> String {
+    safe_label(name.replace('-', "_"))
+}
+
+/// A label as a valid HCL identifier. Valid labels are kept as they are so that existing addresses
+/// do not change; otherwise runs of other characters become a single `_` and a leading digit gets
+/// a `_` prefix.
+fn safe_label(label: String) -> String {
+    let mut safe = if hcl::Identifier::new(label.as_str()).is_ok() {
+        label.clone()
+    } else {
+        let mut safe = String::new();
+        for c in label.chars() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                safe.push(c);
+            } else if !safe.ends_with('_') {
+                safe.push('_');
+            }
+        }
+        if !safe.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            safe.insert(0, '_');
+        }
+        safe
+    };
+    if safe.len() > MAX_LABEL_LENGTH {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        label.hash(&mut hasher);
+        let suffix = format!("_{:x}", hasher.finish());
+        let mut end = MAX_LABEL_LENGTH - suffix.len();
+        while !safe.is_char_boundary(end) {
+            end -= 1;
+        }
+        safe.truncate(end);
+        safe.push_str(&suffix);
+    }
+    safe
+}
+
+/// `google` of `google_project`, `google.project_infra` and `google-beta.beta`, `aws` of `aws_s3_bucket`.
+fn provider_family(name: &str) -> &str {
+    name.split(['.', '_', '-']).next().unwrap_or(name)
+}
+
+/// `folder.infra` + `project.p` -> `folder.infra.project.p`
+fn yaml_path_join(parent: &str, child: &str) -> String {
+    if parent.is_empty() {
+        child.to_string()
+    } else {
+        format!("{}.{}", parent, child)
+    }
+}
+
+/// Layers a scope's own `default_labels:` over the inherited ones.
+fn inherit_labels(inherited: &BTreeMap<String, String>, own: Option<&HashMap<String, String>>) -> BTreeMap<String, String> {
+    let mut labels = inherited.clone();
+    if let Some(own) = own {
+        labels.extend(own.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    labels
+}
+
+/// The labels of a scope's `default_labels:` if they are applied in `mode`.
+fn labels_in_mode(labels: Option<&DefaultLabels>, mode: LabelMode) -> Option<&HashMap<String, String>> {
+    labels.and_then(|l| l.in_mode(mode))
+}
+
+/// Merges default labels under a resource's explicit `labels:` (explicit keys win). Label values
+/// stay as written, so `!ref`/`!expr` labels pass through; a `labels:` expression (`!expr
+/// var.labels`) is merged by Terraform as `merge({defaults}, var.labels)`. `Err` when `labels:` is
+/// neither a mapping nor an expression and there are defaults to merge into it.
+fn merge_labels(defaults: &BTreeMap<String, String>, explicit: Option<&serde_yaml::Value>) -> Result<serde_yaml::Value, String> {
+    let mut labels: serde_yaml::Mapping = defaults.iter().map(|(k, v)| (k.as_str().into(), v.as_str().into())).collect();
+    match explicit {
+        None => {}
+        Some(serde_yaml::Value::Mapping(map)) => {
+            for (k, v) in map {
+                let value = match v {
+                    serde_yaml::Value::Number(n) => n.to_string().into(),
+                    serde_yaml::Value::Bool(b) => b.to_string().into(),
+                    _ => v.clone(),
+                };
+                labels.insert(k.clone(), value);
+            }
+        }
+        Some(value) if defaults.is_empty() => return Ok(value.clone()),
+        Some(serde_yaml::Value::Tagged(tagged)) if tagged.tag == "!expr" || tagged.tag == "!ref" => {
+            let expression = tagged.value.as_str().ok_or("a label expression must be a string")?;
+            let object: hcl::Object<hcl::ObjectKey, hcl::Expression> = defaults.iter()
+                .map(|(k, v)| (hcl::ObjectKey::from(k.as_str()), hcl::Expression::from(v.as_str())))
+                .collect();
+            let defaults = hcl::format::to_string(&hcl::Expression::Object(object)).map_err(|e| e.to_string())?;
+            return Ok(serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+                tag: serde_yaml::value::Tag::new("!expr"),
+                value: format!("merge({}, {})", defaults, expression).into(),
+            })));
+        }
+        Some(_) => return Err(t("labels-not-mergeable", &[])),
+    }
+    Ok(labels.into())
+}
+
+/// Keys of merged labels, `None` for a labels expression whose keys are only known at plan time.
+fn label_keys(labels: &serde_yaml::Value) -> Option<BTreeSet<String>> {
+    Some(labels.as_mapping()?.keys().filter_map(|k| k.as_str().map(str::to_string)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::AttributeSchema;
+
+    fn attribute(description: Option<&str>) -> AttributeSchema {
+        AttributeSchema {
+            required: false,
+            optional: true,
+            computed: false,
+            default: None,
+            description: description.map(str::to_string),
+            attr_type: Some(serde_json::json!("string")),
+        }
+    }
+
+    fn transpiler(config: &Config) -> Transpiler<'_> {
+        Transpiler::new(config, None, Vec::new(), "warn".to_string(), HashMap::new(), HashMap::new(), HashMap::new())
+    }
+
+    #[test]
+    fn parse_hcl_expr_reads_references_as_traversals() {
+        let config = Config::default();
+        let transpiler = transpiler(&config);
+        for reference in ["var.x[0]", "google_x.y[\"k\"].id", "google_folder.infra.id"] {
+            let expr = transpiler.parse_hcl_expr(reference);
+            assert!(matches!(expr, hcl::Expression::Traversal(_)), "{}: {:?}", reference, expr);
+            assert_eq!(expr.to_string(), reference);
+        }
+    }
+
+    #[test]
+    fn parse_hcl_expr_keeps_other_values_as_strings() {
+        let config = Config::default();
+        let transpiler = transpiler(&config);
+        for value in ["lookup(var.m, \"k\", null)", "var.a ? 1 : 2", "\"${var.p}-suffix\"", "folders/123", "alice@example.com"] {
+            assert_eq!(transpiler.parse_hcl_expr(value), hcl::Expression::String(value.to_string()), "{}", value);
+        }
+    }
+
+    #[test]
+    fn org_attribute_value_uses_resource_name_for_listed_parents() {
+        for tf_type in ["google_org_policy_policy", "google_access_context_manager_access_policy", "google_essential_contacts_contact"] {
+            assert_eq!(org_attribute_value(tf_type, "parent", &attribute(None), "123").as_deref(), Some("organizations/123"), "{}", tf_type);
+            assert_eq!(org_attribute_value(tf_type, "parent", &attribute(None), "organizations/123").as_deref(), Some("organizations/123"), "{}", tf_type);
+        }
+    }
+
+    #[test]
+    fn org_attribute_value_uses_bare_id_otherwise() {
+        assert_eq!(org_attribute_value("google_organization_iam_member", "org_id", &attribute(None), "organizations/123").as_deref(), Some("123"));
+        assert_eq!(org_attribute_value("google_folder", "parent", &attribute(None), "123"), None);
+    }
+
+    #[test]
+    fn org_attribute_value_prefers_the_schema_description() {
+        let bare = attribute(Some("The numeric ID of the organization."));
+        assert_eq!(org_attribute_value("google_access_context_manager_access_policy", "parent", &bare, "123"), None);
+        let resource_name = attribute(Some("The parent, in the form organizations/{organization_id}."));
+        assert_eq!(org_attribute_value("google_tags_tag_value", "parent", &resource_name, "123").as_deref(), Some("organizations/123"));
+    }
+
+    #[test]
+    fn merge_labels_keeps_expression_values_and_merges_expressions() {
+        let defaults = BTreeMap::from([("env".to_string(), "prod".to_string()), ("team".to_string(), "infra".to_string())]);
+        let explicit: serde_yaml::Value = serde_yaml::from_str("{team: !ref var.team, tier: 1}").unwrap();
+        let merged = merge_labels(&defaults, Some(&explicit)).unwrap();
+        let expected: serde_yaml::Value = serde_yaml::from_str("{env: prod, team: !ref var.team, tier: '1'}").unwrap();
+        assert_eq!(merged, expected);
+
+        let explicit: serde_yaml::Value = serde_yaml::from_str("!expr var.labels").unwrap();
+        let merged = merge_labels(&defaults, Some(&explicit)).unwrap();
+        let serde_yaml::Value::Tagged(tagged) = &merged else { panic!("{:?}", merged) };
+        let expr: hcl::Expression = tagged.value.as_str().unwrap().parse().unwrap();
+        assert!(expr.to_string().starts_with("merge("), "{}", expr);
+        assert!(expr.to_string().contains("var.labels"), "{}", expr);
+        assert_eq!(label_keys(&merged), None);
+
+        assert!(merge_labels(&defaults, Some(&serde_yaml::Value::from("env=prod"))).is_err());
+        assert!(merge_labels(&BTreeMap::new(), Some(&serde_yaml::Value::from("env=prod"))).is_ok());
+    }
+
+    #[test]
+    fn key_references_to_counted_resources_need_an_instance() {
+        let sources = BTreeMap::from([
+            ("google_storage_bucket.logs".to_string(), "project.app.google_storage_bucket.logs".to_string()),
+        ]);
+        let multi_instance = BTreeSet::from(["google_storage_bucket.logs".to_string()]);
+        for (reference, resolved) in [("logs.name", None), ("logs[0].name", Some("google_storage_bucket.logs[0].name")), ("google_storage_bucket.logs.id", None)] {
+            let mut expr: hcl::Expression = reference.parse().unwrap();
+            let mut errors = Vec::new();
+            resolve_key_reference(&mut expr, &sources, &BTreeSet::from([reference.to_string()]), &multi_instance, &mut errors);
+            match resolved {
+                Some(resolved) => {
+                    assert!(errors.is_empty(), "{}: {:?}", reference, errors);
+                    assert_eq!(expr.to_string(), resolved);
+                }
+                None => assert!(errors.len() == 1 && errors[0].contains("count or for_each"), "{}: {:?}", reference, errors),
+            }
+        }
+    }
+}
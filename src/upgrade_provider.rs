@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use crate::schema::{BlockSchema, ResourceSchema};
+
+/// Arguments of every resource that are not part of its schema.
+const META_ARGUMENTS: &[&str] = &["count", "for_each", "provider", "depends_on", "lifecycle"];
+
+/// The changes of one resource type in a migration rules file (`upgrade-provider --rules`):
+///
+/// ```yaml
+/// google_sql_database_instance:
+///   rename:
+///     settings.ip_configuration.require_ssl: ssl_mode
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypeRules {
+    /// Attribute path -> new name of its last segment
+    #[serde(default)]
+    pub rename: BTreeMap<String, String>,
+}
+
+pub type MigrationRules = BTreeMap<String, TypeRules>;
+
+/// A change between two provider schemas that breaks a generated resource.
+pub struct Finding {
+    pub resource_type: String,
+    /// Attribute path (`settings.tier`); empty for the resource type itself
+    pub path: String,
+    pub message: String,
+    /// New name from the migration rules
+    pub renamed_to: Option<String>,
+}
+
+/// `8.x` -> `~> 8.0`, `8.2.x` -> `~> 8.2.0`; versions and constraints are used as they are.
+pub fn version_constraint(to: &str) -> String {
+    match to.strip_suffix(".x") {
+        Some(prefix) => format!("~> {}.0", prefix),
+        None => to.to_string(),
+    }
+}
+
+pub fn load_rules(path: &Path) -> Result<MigrationRules, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read migration rules '{}': {}", path.display(), e))?;
+    serde_yaml::from_str(&content).map_err(|e| format!("Invalid migration rules '{}': {}", path.display(), e).into())
+}
+
+/// The resource schemas of a schema file (`schemas/google.json`) by type.
+pub fn load_resource_schemas(path: &Path) -> Result<HashMap<String, ResourceSchema>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read schema file '{}': {}", path.display(), e))?;
+    let schema: crate::schema::Schema = serde_json::from_str(&content)?;
+    Ok(schema.provider_schemas.into_values().flat_map(|p| p.resource_schemas).collect())
+}
+
+/// Attribute and block paths set per resource type in generated HCL files.
+pub fn used_attributes(files: &[String]) -> BTreeMap<String, BTreeSet<String>> {
+    let mut used: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for content in files {
+        let Ok(body) = hcl::from_str::<hcl::Body>(content) else { continue };
+        for block in body.blocks().filter(|b| b.identifier() == "resource") {
+            let Some(tf_type) = block.labels().first() else { continue };
+            collect_paths(&block.body, "", used.entry(tf_type.as_str().to_string()).or_default());
+        }
+    }
+    used
+}
+
+fn collect_paths(body: &hcl::Body, prefix: &str, paths: &mut BTreeSet<String>) {
+    for structure in body.iter() {
+        let (key, nested) = match structure {
+            hcl::Structure::Attribute(attr) => (attr.key(), None),
+            hcl::Structure::Block(block) => (block.identifier(), Some(&block.body)),
+        };
+        if prefix.is_empty() && META_ARGUMENTS.contains(&key) {
+            continue;
+        }
+        let path = if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+        if let Some(nested) = nested {
+            collect_paths(nested, &path, paths);
+        }
+        paths.insert(path);
+    }
+}
+
+/// Resource types, attributes and blocks the generated resources use that the new schema drops or
+/// types differently, and attributes the new schema requires that they do not set. Paths the old
+/// schema does not know either are left to the validation.
+pub fn breaking_changes(old: &HashMap<String, ResourceSchema>, new: &HashMap<String, ResourceSchema>, used: &BTreeMap<String, BTreeSet<String>>, rules: &MigrationRules) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (tf_type, paths) in used {
+        let Some(old_schema) = old.get(tf_type) else { continue };
+        let renames = rules.get(tf_type).map(|r| &r.rename);
+        let finding = |path: &str, message: String| Finding {
+            resource_type: tf_type.clone(),
+            path: path.to_string(),
+            message,
+            renamed_to: renames.and_then(|r| r.get(path)).cloned(),
+        };
+        let Some(new_schema) = new.get(tf_type) else {
+            findings.push(finding("", "resource type no longer exists".to_string()));
+            continue;
+        };
+        for path in paths {
+            if !has_path(&old_schema.block, path) {
+                continue;
+            }
+            if !has_path(&new_schema.block, path) {
+                findings.push(finding(path, "no longer exists".to_string()));
+                continue;
+            }
+            if let (Some(before), Some(after)) = (attribute_type(&old_schema.block, path), attribute_type(&new_schema.block, path)) {
+                if before != after {
+                    findings.push(finding(path, format!("type changed from {} to {}", before, after)));
+                }
+            }
+        }
+        let mut required: Vec<(&String, _)> = new_schema.block.attributes.iter().filter(|(_, a)| a.required).collect();
+        required.sort_by_key(|(name, _)| *name);
+        for (name, _) in required {
+            let was_required = old_schema.block.attributes.get(name).is_some_and(|a| a.required);
+            let set = paths.contains(name) || renames.is_some_and(|r| r.values().any(|n| n == name));
+            if !was_required && !set {
+                findings.push(finding(name, "is now required".to_string()));
+            }
+        }
+    }
+    findings
+}
+
+/// The block an attribute path ends in and its last segment.
+fn walk<'a>(block: &'a BlockSchema, path: &'a str) -> Option<(&'a BlockSchema, &'a str)> {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = segments.pop()?;
+    let mut current = block;
+    for segment in segments {
+        current = &current.block_types.get(segment)?.block;
+    }
+    Some((current, last))
+}
+
+fn has_path(block: &BlockSchema, path: &str) -> bool {
+    walk(block, path).is_some_and(|(b, last)| b.attributes.contains_key(last) || b.block_types.contains_key(last))
+}
+
+fn attribute_type<'a>(block: &'a BlockSchema, path: &'a str) -> Option<&'a serde_json::Value> {
+    let (block, last) = walk(block, path)?;
+    block.attributes.get(last)?.attr_type.as_ref()
+}
+
+/// Renames the attributes of the migration rules in YAML text, keeping everything else as it is.
+/// Returns the new text and the renamed keys: (1-based line, `type.key.path -> new`).
+pub fn apply_renames(content: &str, rules: &MigrationRules) -> (String, Vec<(usize, String)>) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut renamed: BTreeMap<usize, String> = BTreeMap::new();
+    let mut changes = Vec::new();
+    for (tf_type, type_rules) in rules {
+        // `google_storage_bucket` is also written as `storage_bucket`
+        let types: Vec<&str> = std::iter::once(tf_type.as_str()).chain(tf_type.strip_prefix("google_")).collect();
+        for entry in crate::yaml_edit::entries(&lines, &types) {
+            for (path, new_name) in &type_rules.rename {
+                let Some((line, _)) = crate::yaml_edit::child(&lines, &entry, path) else { continue };
+                let old_name = path.rsplit('.').next().unwrap_or(path);
+                let indent = crate::yaml_edit::indentation(lines[line]);
+                renamed.insert(line, format!("{}{}", &lines[line][..indent], lines[line][indent..].replacen(old_name, new_name, 1)));
+                changes.push((line + 1, format!("{}.{}.{} -> {}", tf_type, entry.key, path, new_name)));
+            }
+        }
+    }
+    let mut result = lines.iter().enumerate()
+        .map(|(idx, line)| renamed.get(&idx).cloned().unwrap_or_else(|| line.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    changes.sort();
+    (result, changes)
+}
@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use crate::config::{Config, Folder, IamMode, Project};
+use crate::i18n::t;
+
+/// A problem with an IAM principal found in the YAML configuration.
+#[derive(Debug, Clone)]
+pub struct MemberFinding {
+    /// YAML path of the block granting the member (e.g. `folder.infra.google_folder_iam_member`)
+    pub yaml_path: String,
+    pub member: String,
+    pub message: String,
+    /// Domain violations are only reported when domain enforcement is enabled and are always fatal
+    pub domain_violation: bool,
+}
+
+/// Validates the syntax of every IAM principal in the configuration and, if `allowed_domains`
+/// is non-empty, checks that `user:`/`group:`/`domain:` principals belong to one of them.
+pub fn check_members(config: &Config, allowed_domains: &[String]) -> Vec<MemberFinding> {
+    let mut members = Vec::new();
+    collect_config_members(config, &mut members);
+
+    let mut findings = Vec::new();
+    for (yaml_path, member, bare_email_allowed) in members {
+        match parse_member(&member, bare_email_allowed) {
+            Err(message) => findings.push(MemberFinding {
+                yaml_path,
+                member,
+                message,
+                domain_violation: false,
+            }),
+            Ok(Some(domain)) if !allowed_domains.is_empty() && !domain_allowed(&domain, allowed_domains) => {
+                findings.push(MemberFinding {
+                    message: t("member-outside-domains", &[("member", &member), ("domains", &allowed_domains.join(", "))]),
+                    yaml_path,
+                    member,
+                    domain_violation: true,
+                });
+            }
+            Ok(_) => {}
+        }
+    }
+    findings
+}
+
+/// Parses an IAM principal. Returns the domain that must be checked against the allowlist
+/// (only for `user:`, `group:` and `domain:` principals), or an error message for invalid syntax.
+/// Cloud Identity memberships accept bare email addresses, which are treated like `user:`.
+fn parse_member(member: &str, bare_email_allowed: bool) -> Result<Option<String>, String> {
+    // Values built from interpolation are resolved by Terraform, not by us
+    if member.contains("${") {
+        return Ok(None);
+    }
+
+    if member == "allUsers" || member == "allAuthenticatedUsers" {
+        return Ok(None);
+    }
+
+    if let Some(rest) = member.strip_prefix("deleted:") {
+        // e.g. deleted:user:alice@example.com?uid=123
+        let inner = rest.split('?').next().unwrap_or("");
+        return parse_member(inner, false).map(|_| None);
+    }
+
+    if let Some(rest) = member.strip_prefix("principal://").or_else(|| member.strip_prefix("principalSet://")) {
+        if rest.trim().is_empty() || rest.contains(char::is_whitespace) {
+            return Err(t("member-invalid-principal", &[("member", &member)]));
+        }
+        return Ok(None);
+    }
+
+    let (kind, value) = match member.split_once(':') {
+        Some((kind, value)) => (kind, value),
+        None if bare_email_allowed && member.ends_with(".gserviceaccount.com") => ("serviceAccount", member),
+        None if bare_email_allowed => ("user", member),
+        None => {
+            return Err(t("member-no-type", &[("member", &member)]));
+        }
+    };
+
+    match kind {
+        "user" | "group" => {
+            let domain = email_domain(value)
+                .ok_or_else(|| t("member-invalid-email", &[("member", &member)]))?;
+            Ok(Some(domain))
+        }
+        "serviceAccount" => {
+            email_domain(value)
+                .ok_or_else(|| t("member-invalid-service-account", &[("member", &member)]))?;
+            Ok(None)
+        }
+        "domain" => {
+            if !is_valid_domain(value) {
+                return Err(t("member-invalid-domain", &[("member", &member)]));
+            }
+            Ok(Some(value.to_lowercase()))
+        }
+        "projectOwner" | "projectEditor" | "projectViewer" => {
+            if value.is_empty() {
+                return Err(t("member-missing-project", &[("member", &member)]));
+            }
+            Ok(None)
+        }
+        _ => Err(t("member-unknown-type", &[("member", &member), ("kind", &kind)])),
+    }
+}
+
+fn email_domain(email: &str) -> Option<String> {
+    let (local, domain) = email.split_once('@')?;
+    if local.is_empty() || domain.contains('@') || email.contains(char::is_whitespace) || !is_valid_domain(domain) {
+        return None;
+    }
+    Some(domain.to_lowercase())
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+/// A domain is allowed if it equals an allowlisted domain or is a subdomain of one.
+fn domain_allowed(domain: &str, allowed_domains: &[String]) -> bool {
+    allowed_domains.iter().any(|allowed| {
+        let allowed = allowed.to_lowercase();
+        domain == allowed || domain.ends_with(&format!(".{}", allowed))
+    })
+}
+
+fn collect_config_members(config: &Config, out: &mut Vec<(String, String, bool)>) {
+    if let Some(members) = &config.organization_iam_member {
+        for member in members.keys() {
+            out.push(("organization_iam_member".to_string(), member.clone(), false));
+        }
+    }
+
+    if let Some(serde_yaml::Value::Mapping(map)) = &config.billing_account_iam_member {
+        for (k, v) in map {
+            if let (Some(member), serde_yaml::Value::Sequence(_)) = (k.as_str(), v) {
+                if member != "billing_account_id" {
+                    out.push(("billing_account_iam_member".to_string(), member.to_string(), false));
+                }
+            }
+        }
+    }
+
+    collect_extra_members("", &config.extra, out);
+
+    if let Some(folders) = &config.folder {
+        for (key, folder) in folders {
+            collect_folder_members(&format!("folder.{}", key), folder, out);
+        }
+    }
+    if let Some(projects) = &config.project {
+        for (key, project) in projects {
+            collect_project_members(&format!("project.{}", key), project, out);
+        }
+    }
+}
+
+fn collect_folder_members(path: &str, folder: &Folder, out: &mut Vec<(String, String, bool)>) {
+    collect_extra_members(path, &folder.extra, out);
+
+    if let Some(folders) = &folder.folder {
+        for (key, sub) in folders {
+            collect_folder_members(&format!("{}.folder.{}", path, key), sub, out);
+        }
+    }
+    if let Some(projects) = &folder.project {
+        for (key, project) in projects {
+            collect_project_members(&format!("{}.project.{}", path, key), project, out);
+        }
+    }
+}
+
+fn collect_project_members(path: &str, project: &Project, out: &mut Vec<(String, String, bool)>) {
+    collect_extra_members(path, &project.extra, out);
+}
+
+fn collect_extra_members(path: &str, extra: &HashMap<String, serde_yaml::Value>, out: &mut Vec<(String, String, bool)>) {
+    for (resource_type, value) in extra {
+        let serde_yaml::Value::Mapping(map) = value else { continue };
+        let type_path = if path.is_empty() { resource_type.clone() } else { format!("{}.{}", path, resource_type) };
+
+        if resource_type == "cloud_identity_group" {
+            for (group, attrs) in map {
+                let group_path = format!("{}.{}", type_path, group.as_str().unwrap_or(""));
+                for key in ["member", "manager", "owner"] {
+                    match attrs.get(key) {
+                        Some(serde_yaml::Value::String(s)) => out.push((group_path.clone(), s.clone(), true)),
+                        Some(serde_yaml::Value::Sequence(seq)) => {
+                            for item in seq.iter().filter_map(|v| v.as_str()) {
+                                out.push((group_path.clone(), item.to_string(), true));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        } else if resource_type.contains("iam_member") {
+            for (k, v) in map {
+                match v {
+                    // Compact form: member -> [roles]
+                    serde_yaml::Value::Sequence(_) => {
+                        if let Some(member) = k.as_str() {
+                            out.push((type_path.clone(), member.to_string(), false));
+                        }
+                    }
+                    // Plain resource instance with a `member` attribute
+                    serde_yaml::Value::Mapping(attrs) => {
+                        if let Some(member) = attrs.get("member").and_then(|m| m.as_str()) {
+                            out.push((format!("{}.{}", type_path, k.as_str().unwrap_or("")), member.to_string(), false));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        } else if resource_type.contains("iam_binding") {
+            for (k, v) in map {
+                if let Some(serde_yaml::Value::Sequence(seq)) = v.get("members") {
+                    let instance_path = format!("{}.{}", type_path, k.as_str().unwrap_or(""));
+                    for member in seq.iter().filter_map(|m| m.as_str()) {
+                        out.push((instance_path.clone(), member.to_string(), false));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A semantic problem in the parsed configuration, found before transpiling.
+#[derive(Debug, Clone)]
+pub struct ConfigFinding {
+    /// YAML path of the offending value (e.g. `variables.deployment-mode`)
+    pub yaml_path: String,
+    pub message: String,
+    /// Problems that would otherwise panic or produce broken HCL are always fatal
+    pub fatal: bool,
+}
+
+/// Cross-field checks on the parsed configuration: organization ID, deployment mode and
+/// backend, infra project and Cloud Identity group references.
+pub fn check_config(config: &Config) -> Vec<ConfigFinding> {
+    let mut findings = Vec::new();
+    let mut fatal = |yaml_path: &str, message: String| findings.push(ConfigFinding { yaml_path: yaml_path.to_string(), message, fatal: true });
+
+    match config.extra.get("customer-organization-id") {
+        None => fatal("variables.customer-organization-id", t("org-id-missing", &[])),
+        Some(serde_yaml::Value::String(id)) if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) => {}
+        Some(other) => fatal(
+            "variables.customer-organization-id",
+            t("org-id-not-numeric", &[("value", &yaml_scalar(other))]),
+        ),
+    }
+
+    let mode = match config.extra.get("deployment-mode") {
+        None => "local".to_string(),
+        Some(v) => yaml_scalar(v),
+    };
+    if mode != "local" && mode != "cloud" {
+        fatal("variables.deployment-mode", t("deployment-mode-invalid", &[("value", &mode)]));
+    } else if mode == "cloud" {
+        let gcs = config.terraform.as_ref()
+            .and_then(|t| t.get("backend"))
+            .and_then(|b| b.get("gcs"));
+        match gcs {
+            None => fatal("terraform.backend", t("cloud-without-backend", &[])),
+            Some(gcs) if gcs.get("bucket").and_then(|b| b.as_str()).is_none_or(|b| b.is_empty()) => {
+                fatal("terraform.backend.gcs", t("backend-without-bucket", &[]))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut project_ids = Vec::new();
+    let mut declared_groups = Vec::new();
+    let domain = config.extra.get("customer-domain").and_then(|v| v.as_str()).unwrap_or("");
+    visit_scopes(config, &mut |extra, project_id| {
+        if let Some(id) = project_id {
+            project_ids.push(id.to_string());
+        }
+        if let Some(serde_yaml::Value::Mapping(groups)) = extra.get("cloud_identity_group") {
+            for (name, attrs) in groups {
+                let explicit = attrs.get("id").or_else(|| attrs.get("email")).and_then(|v| v.as_str());
+                match (explicit, name.as_str()) {
+                    (Some(email), _) => declared_groups.push(email.to_lowercase()),
+                    (None, Some(name)) => declared_groups.push(format!("{}@{}", name, domain).to_lowercase()),
+                    _ => {}
+                }
+            }
+        }
+    });
+
+    if let Some(infra) = config.extra.get("infra-project-name").and_then(|v| v.as_str()) {
+        if !infra.is_empty() && !project_ids.iter().any(|p| p == infra) {
+            findings.push(ConfigFinding {
+                yaml_path: "variables.infra-project-name".to_string(),
+                message: t("infra-project-undeclared", &[("project", &infra)]),
+                fatal: false,
+            });
+        }
+    }
+
+    // Nested groups of the customer domain must be declared, otherwise the membership cannot be created
+    let mut members = Vec::new();
+    collect_config_members(config, &mut members);
+    for (yaml_path, member, in_group) in members {
+        let Some(email) = member.strip_prefix("group:") else { continue };
+        if !in_group || domain.is_empty() || email.contains("${") {
+            continue;
+        }
+        if email_domain(email).as_deref() == Some(&domain.to_lowercase()) && !declared_groups.contains(&email.to_lowercase()) {
+            findings.push(ConfigFinding {
+                yaml_path,
+                message: t("group-member-undeclared", &[("member", &member)]),
+                fatal: false,
+            });
+        }
+    }
+
+    check_iam_lockout(config, &mut findings);
+    findings
+}
+
+/// Roles that let the IaC service account change the IAM of a scope.
+const IAM_ADMIN_ROLES: &[&str] = &[
+    "roles/owner", "roles/resourcemanager.organizationAdmin", "roles/resourcemanager.folderAdmin",
+    "roles/resourcemanager.folderIamAdmin", "roles/resourcemanager.projectIamAdmin", "roles/iam.securityAdmin",
+];
+
+/// Receives the YAML path, kind (`folder`, ...), `iam-mode`, (member, role) grants and resources
+/// of a scope.
+type IamScopeVisitor<'a> = dyn FnMut(&str, &str, IamMode, Vec<(String, String)>, &HashMap<String, serde_yaml::Value>) + 'a;
+
+/// Authoritative IAM (`iam-mode: binding`/`policy`, `*_iam_binding` entries) that would take the
+/// IaC service account's own access away: a binding of an IAM admin role, or of a role the
+/// account is granted in the same scope, without the account, and a policy that grants it no
+/// IAM admin role.
+fn check_iam_lockout(config: &Config, findings: &mut Vec<ConfigFinding>) {
+    let (Some(account), Some(project)) = (
+        config.extra.get("svc-iac-account").and_then(|v| v.as_str()),
+        config.extra.get("infra-project-name").and_then(|v| v.as_str()),
+    ) else { return };
+    let iac_member = format!("serviceAccount:{}@{}.iam.gserviceaccount.com", account, project);
+
+    let mut check_scope = |yaml_path: &str, kind: &str, mode: IamMode, member_grants: Vec<(String, String)>, extra: &HashMap<String, serde_yaml::Value>| {
+        let member_type = format!("google_{}_iam_member", kind);
+        let binding_type = format!("google_{}_iam_binding", kind);
+        let iac_roles: Vec<&str> = member_grants.iter().filter(|(m, _)| *m == iac_member).map(|(_, r)| r.as_str()).collect();
+
+        // (YAML path, role, members) of every binding the scope ends up with
+        let mut bindings: Vec<(String, String, Vec<String>)> = Vec::new();
+        if let Some(serde_yaml::Value::Mapping(entries)) = extra.get(&binding_type) {
+            for (key, entry) in entries {
+                let Some(key) = key.as_str() else { continue };
+                let (role, members) = match entry {
+                    serde_yaml::Value::Sequence(members) => (Some(key), members),
+                    serde_yaml::Value::Mapping(_) => match entry.get("members") {
+                        Some(serde_yaml::Value::Sequence(members)) => (entry.get("role").and_then(|r| r.as_str()), members),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                let Some(role) = role else { continue };
+                let members = members.iter().filter_map(|m| m.as_str()).map(|m| m.to_string()).collect();
+                bindings.push((join_path(yaml_path, &format!("{}.{}", binding_type, key)), role.to_string(), members));
+            }
+        }
+        if mode != IamMode::Member {
+            let path = join_path(yaml_path, &member_type);
+            for (member, role) in &member_grants {
+                match bindings.iter_mut().find(|(p, r, _)| *p == path && r == role) {
+                    Some((_, _, members)) => members.push(member.clone()),
+                    None => bindings.push((path.clone(), role.clone(), vec![member.clone()])),
+                }
+            }
+        }
+
+        let scope = if yaml_path.is_empty() { "organization" } else { yaml_path };
+        if mode == IamMode::Policy {
+            let keeps_admin = bindings.iter().any(|(_, role, members)| IAM_ADMIN_ROLES.contains(&role.as_str()) && members.contains(&iac_member));
+            if !keeps_admin {
+                findings.push(ConfigFinding {
+                    yaml_path: join_path(yaml_path, &member_type),
+                    message: t("iam-lockout-policy", &[("scope", &scope), ("member", &iac_member)]),
+                    fatal: false,
+                });
+            }
+            return;
+        }
+        for (path, role, members) in bindings {
+            let needed = IAM_ADMIN_ROLES.contains(&role.as_str()) || iac_roles.contains(&role.as_str());
+            if needed && !members.contains(&iac_member) {
+                findings.push(ConfigFinding {
+                    yaml_path: path,
+                    message: t("iam-lockout-binding", &[("role", &role), ("scope", &scope), ("member", &iac_member)]),
+                    fatal: false,
+                });
+            }
+        }
+    };
+
+    let root_mode = config.extra.get("iam-mode").and_then(|m| serde_yaml::from_value(m.clone()).ok()).unwrap_or_default();
+    let root_grants = config.organization_iam_member.iter()
+        .flat_map(|m| m.iter())
+        .flat_map(|(member, roles)| member_roles(member, roles))
+        .collect();
+    check_scope("", "organization", root_mode, root_grants, &config.extra);
+
+    fn visit_folders(folders: &HashMap<String, Folder>, parent: &str, f: &mut IamScopeVisitor) {
+        for (key, folder) in folders {
+            let path = join_path(parent, &format!("folder.{}", key));
+            f(&path, "folder", folder.iam_mode.unwrap_or_default(), extra_member_roles(&folder.extra, "google_folder_iam_member"), &folder.extra);
+            if let Some(sub) = &folder.folder {
+                visit_folders(sub, &path, f);
+            }
+            if let Some(projects) = &folder.project {
+                visit_projects(projects, &path, f);
+            }
+        }
+    }
+    fn visit_projects(projects: &HashMap<String, Project>, parent: &str, f: &mut IamScopeVisitor) {
+        for (key, project) in projects {
+            let path = join_path(parent, &format!("project.{}", key));
+            f(&path, "project", project.iam_mode.unwrap_or_default(), extra_member_roles(&project.extra, "google_project_iam_member"), &project.extra);
+        }
+    }
+    if let Some(folders) = &config.folder {
+        visit_folders(folders, "", &mut check_scope);
+    }
+    if let Some(projects) = &config.project {
+        visit_projects(projects, "", &mut check_scope);
+    }
+}
+
+/// (member, role) of the member map of `tf_type` in a scope.
+fn extra_member_roles(extra: &HashMap<String, serde_yaml::Value>, tf_type: &str) -> Vec<(String, String)> {
+    let Some(serde_yaml::Value::Mapping(members)) = extra.get(tf_type) else { return Vec::new() };
+    members.iter()
+        .filter_map(|(member, roles)| Some((member.as_str()?, roles.as_sequence()?)))
+        .flat_map(|(member, roles)| member_roles(member, roles))
+        .collect()
+}
+
+/// (member, role) of each role of a member; conditional grants (`{<role>: ~, condition: ...}`)
+/// count as well.
+fn member_roles(member: &str, roles: &[serde_yaml::Value]) -> Vec<(String, String)> {
+    roles.iter()
+        .filter_map(|role| match role {
+            serde_yaml::Value::String(role) => Some(role.clone()),
+            serde_yaml::Value::Mapping(grant) => grant.keys()
+                .filter_map(|k| k.as_str())
+                .find(|k| *k != "condition" && *k != "import-id")
+                .map(|k| k.to_string()),
+            _ => None,
+        })
+        .map(|role| (member.to_string(), role))
+        .collect()
+}
+
+fn join_path(parent: &str, child: &str) -> String {
+    if parent.is_empty() { child.to_string() } else { format!("{}.{}", parent, child) }
+}
+
+fn yaml_scalar(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    }
+}
+
+/// Receives the resources (`extra`) of a scope and, for projects, the project ID.
+type ScopeVisitor<'a> = dyn FnMut(&HashMap<String, serde_yaml::Value>, Option<&str>) + 'a;
+
+/// Calls `f(extra, project_id)` for the root, every folder and every project.
+fn visit_scopes(config: &Config, f: &mut ScopeVisitor) {
+    f(&config.extra, None);
+    for folder in config.folder.iter().flat_map(|m| m.values()) {
+        visit_folder(folder, f);
+    }
+    for project in config.project.iter().flat_map(|m| m.values()) {
+        f(&project.extra, Some(&project.project_id));
+    }
+}
+
+fn visit_folder(folder: &Folder, f: &mut ScopeVisitor) {
+    f(&folder.extra, None);
+    for sub in folder.folder.iter().flat_map(|m| m.values()) {
+        visit_folder(sub, f);
+    }
+    for project in folder.project.iter().flat_map(|m| m.values()) {
+        f(&project.extra, Some(&project.project_id));
+    }
+}
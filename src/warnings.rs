@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+use crate::i18n::t;
+
+/// Groups with more resources than this are printed as one summary line (unless `--show-all`).
+const GROUP_THRESHOLD: usize = 3;
+
+struct Warning {
+    /// The message without the resource it is about, e.g. `Unknown field 'foo' (google_storage_bucket)`
+    group: String,
+    /// Resource or YAML path the warning is about
+    subject: String,
+    message: String,
+}
+
+thread_local! {
+    /// Warnings of the current command; `None` prints them right away (e.g. in `serve`).
+    static COLLECTED: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+}
+
+/// Prints the warnings still held back when dropped, e.g. when a command returns with an error.
+pub struct Collecting {
+    show_all: bool,
+}
+
+impl Drop for Collecting {
+    fn drop(&mut self) {
+        let _ = finish(self.show_all, false);
+    }
+}
+
+/// Holds back the warnings of this thread until [`finish`] or until the guard is dropped.
+pub fn collect(show_all: bool) -> Collecting {
+    COLLECTED.with(|c| *c.borrow_mut() = Some(Vec::new()));
+    Collecting { show_all }
+}
+
+/// Reports a validation warning. Warnings with the same `group` for different `subject`s are
+/// summarized by [`finish`].
+pub fn warn(group: impl Into<String>, subject: impl Into<String>, message: String) {
+    let warning = Warning { group: group.into(), subject: subject.into(), message };
+    COLLECTED.with(|c| match c.borrow_mut().as_mut() {
+        Some(collected) => collected.push(warning),
+        None if crate::term::quiet() => {}
+        None => eprintln!("{}", t("warning", &[("message", &warning.message)])),
+    });
+}
+
+/// Number of warnings collected so far.
+pub fn count() -> usize {
+    COLLECTED.with(|c| c.borrow().as_ref().map_or(0, |w| w.len()))
+}
+
+/// Drops the warnings collected so far, e.g. those of old revisions in `history`.
+pub fn clear() {
+    COLLECTED.with(|c| if let Some(collected) = c.borrow_mut().as_mut() { collected.clear() });
+}
+
+/// Prints the collected warnings, grouped, and a "N warnings in M resources" footer. With
+/// `warnings_as_errors`, any warning fails the command. `--quiet` prints them only then.
+pub fn finish(show_all: bool, warnings_as_errors: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(warnings) = COLLECTED.with(|c| c.borrow_mut().take()) else { return Ok(()) };
+    if warnings.is_empty() || (crate::term::quiet() && !warnings_as_errors) {
+        return Ok(());
+    }
+
+    // Groups in order of their first occurrence
+    let mut groups: Vec<(&str, Vec<&Warning>)> = Vec::new();
+    for w in &warnings {
+        match groups.iter_mut().find(|(g, _)| *g == w.group) {
+            Some((_, members)) => members.push(w),
+            None => groups.push((&w.group, vec![w])),
+        }
+    }
+
+    for (group, members) in &groups {
+        if show_all || members.len() <= GROUP_THRESHOLD {
+            for w in members {
+                eprintln!("{}", t("warning", &[("message", &w.message)]));
+            }
+        } else {
+            let examples: Vec<&str> = members.iter().take(GROUP_THRESHOLD).map(|w| w.subject.as_str()).collect();
+            let summary = t("warning-group", &[("group", group), ("count", &members.len()), ("examples", &examples.join(", "))]);
+            eprintln!("{}", t("warning", &[("message", &summary)]));
+        }
+    }
+
+    let mut subjects: Vec<&str> = warnings.iter().map(|w| w.subject.as_str()).collect();
+    subjects.sort();
+    subjects.dedup();
+    eprintln!("{}", t("warning-summary", &[("count", &warnings.len()), ("resources", &subjects.len())]));
+
+    if warnings_as_errors {
+        return Err(t("warnings-as-errors", &[("count", &warnings.len())]).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::include_processor::INCLUDE_VARS_PREFIX;
+
+/// Descriptions of the variables in the `variables:` blocks of include-expanded YAML, taken from
+/// the comment at the end of the definition line or, without one, the comment lines directly above it.
+///
+/// ```yaml
+/// variables:
+///   # Region of all regional resources
+///   default-region: &default-region europe-west3
+///   deployment-mode: &deployment-mode local # switch by command
+/// ```
+///
+/// Like for the values, a later definition of a variable wins.
+pub fn variable_descriptions(content: &str) -> HashMap<String, String> {
+    let mut descriptions = HashMap::new();
+    // Indentation of the current `variables:` key and of its entries
+    let mut block: Option<(usize, Option<usize>)> = None;
+    let mut above: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if trimmed.is_empty() {
+            above.clear();
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            if !trimmed.starts_with("# cfg2hcl:") {
+                above.push(comment.trim().to_string());
+            }
+            continue;
+        }
+
+        if let Some((block_indent, entry_indent)) = block {
+            if indent <= block_indent {
+                block = None;
+            } else if entry_indent.is_none_or(|e| e == indent) {
+                block = Some((block_indent, Some(indent)));
+                if let Some((key, rest)) = split_key(trimmed) {
+                    let description = trailing_comment(rest).map(str::to_string)
+                        .or_else(|| (!above.is_empty()).then(|| above.join(" ")));
+                    match description.filter(|d| !d.is_empty()) {
+                        Some(description) => descriptions.insert(key, description),
+                        None => descriptions.remove(&key),
+                    };
+                }
+            }
+        }
+        if block.is_none() {
+            if let Some((key, _)) = split_key(trimmed) {
+                if key == "variables" || key.starts_with(INCLUDE_VARS_PREFIX) {
+                    block = Some((indent, None));
+                }
+            }
+        }
+        above.clear();
+    }
+    descriptions
+}
+
+/// `main.tf` with the comments of the YAML entries above the resources generated from them: the
+/// comment lines directly above the entry's key and a comment at the end of the key line.
+///
+/// ```yaml
+/// google_storage_bucket:
+///   # Terraform state of all stacks
+///   state: # versioned
+/// ```
+///
+/// Resources generated from the same entry (IAM members of a list, memberships of a group) get
+/// them once, above the first one.
+pub fn annotate(main_tf: &str, sources: &BTreeMap<String, String>, content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut commented: HashSet<&str> = HashSet::new();
+    let mut comments: HashMap<usize, Vec<String>> = HashMap::new();
+    for (line, address) in crate::provenance::top_level_blocks(main_tf) {
+        let Some(yaml_path) = address.and_then(|a| sources.get(&a)) else { continue };
+        if !commented.insert(yaml_path) {
+            continue;
+        }
+        let Some(idx) = crate::provenance::locate(content, yaml_path) else { continue };
+        let text = entry_comments(&lines, idx);
+        if !text.is_empty() {
+            comments.insert(line, text);
+        }
+    }
+    if comments.is_empty() {
+        return main_tf.to_string();
+    }
+
+    let mut annotated = String::with_capacity(main_tf.len());
+    for (idx, line) in main_tf.lines().enumerate() {
+        for comment in comments.get(&(idx + 1)).into_iter().flatten() {
+            annotated.push('#');
+            if !comment.is_empty() {
+                annotated.push(' ');
+                annotated.push_str(comment);
+            }
+            annotated.push('\n');
+        }
+        annotated.push_str(line);
+        annotated.push('\n');
+    }
+    annotated
+}
+
+/// Comment lines directly above the key at `idx` (without `# cfg2hcl:` annotations) and the
+/// comment at the end of its line.
+fn entry_comments(lines: &[&str], idx: usize) -> Vec<String> {
+    let mut above: Vec<String> = lines[..idx].iter().rev()
+        .map(|line| line.trim_start())
+        .filter(|line| !line.starts_with("# cfg2hcl:"))
+        .map_while(|line| line.strip_prefix('#'))
+        .map(|comment| comment.strip_prefix(' ').unwrap_or(comment).trim_end().to_string())
+        .collect();
+    above.reverse();
+    if let Some(trailing) = split_key(lines[idx].trim_start()).and_then(|(_, rest)| trailing_comment(rest)) {
+        above.push(trailing.to_string());
+    }
+    above
+}
+
+/// Key and the rest of a `key: value` line (without quotes around the key).
+fn split_key(line: &str) -> Option<(String, &str)> {
+    if line.starts_with('-') {
+        return None;
+    }
+    let (key, rest) = line.split_once(':')?;
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((key.trim().trim_matches(|c| c == '"' || c == '\'').to_string(), rest))
+}
+
+/// Text of a ` # comment` after a value, ignoring `#` inside quotes.
+fn trailing_comment(rest: &str) -> Option<&str> {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (idx, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if previous.is_whitespace() => return Some(rest[idx + 1..].trim()),
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
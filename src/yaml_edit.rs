@@ -0,0 +1,244 @@
+use serde_yaml::{Mapping, Value};
+
+/// Lines of context around each change in [`diff`].
+const DIFF_CONTEXT: usize = 3;
+
+/// A resource entry in a YAML text: the line of its key and the end of its body. Commands that
+/// change the user's YAML (`promote-policy`, `upgrade-provider`) edit it line by line, so that
+/// comments, anchors and formatting stay as they are.
+pub struct Entry {
+    pub line: usize,
+    pub end: usize,
+    pub key: String,
+}
+
+pub fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Neither blank nor a comment.
+pub fn is_content(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+/// `key: value` -> (`key`, `value`), with quotes removed from the key; `None` for lines that are
+/// no mapping key.
+pub fn mapping_key(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start().strip_prefix("- ").unwrap_or(line.trim_start());
+    let (key, rest) = trimmed.split_once(':')?;
+    (rest.is_empty() || rest.starts_with(' ')).then(|| (key.trim().trim_matches(|c| c == '"' || c == '\''), rest.trim()))
+}
+
+/// The end of the block opened by the line at `start`: the first following content line that is
+/// not indented deeper. Trailing blank and comment lines are not part of it.
+pub fn block_end(lines: &[&str], start: usize) -> usize {
+    let indent = indentation(lines[start]);
+    let mut end = start + 1;
+    for (idx, line) in lines.iter().enumerate().skip(start + 1) {
+        if is_content(line) {
+            if indentation(line) <= indent {
+                break;
+            }
+            end = idx + 1;
+        }
+    }
+    end
+}
+
+/// All entries directly below a key of `types`, wherever it is nested.
+pub fn entries(lines: &[&str], types: &[&str]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let Some((key, "")) = mapping_key(line) else { continue };
+        if !types.contains(&key) {
+            continue;
+        }
+        let end = block_end(lines, idx);
+        for (child, key) in children(lines, idx, end) {
+            entries.push(Entry { line: child, end: block_end(lines, child), key: key.to_string() });
+        }
+    }
+    entries
+}
+
+/// The mapping keys directly in the body of the block `start..end`: (line, key).
+fn children<'a>(lines: &[&'a str], start: usize, end: usize) -> Vec<(usize, &'a str)> {
+    let Some(indent) = lines[start + 1..end].iter().find(|l| is_content(l)).map(|l| indentation(l)) else { return Vec::new() };
+    (start + 1..end)
+        .filter(|idx| is_content(lines[*idx]) && indentation(lines[*idx]) == indent)
+        .filter_map(|idx| mapping_key(lines[idx]).map(|(key, _)| (idx, key)))
+        .collect()
+}
+
+/// Lines (start, end) of an attribute of an entry; dotted paths (`settings.tier`) go through
+/// nested mappings.
+pub fn child(lines: &[&str], entry: &Entry, path: &str) -> Option<(usize, usize)> {
+    let (mut start, mut end) = (entry.line, entry.end);
+    for key in path.split('.') {
+        let (line, _) = children(lines, start, end).into_iter().find(|(_, k)| *k == key)?;
+        (start, end) = (line, block_end(lines, line));
+    }
+    Some((start, end))
+}
+
+/// Rewrites every section under a key of `types` (wherever it is nested): `convert` gets the
+/// type, the key of the mapping the section is in (`None` at the top level) and the parsed
+/// entries, and returns the type and entries to write instead. If that mapping has a section of
+/// the new type already, the entries are appended to it; an entry whose key is taken there is
+/// combined with the existing one by `merge`, which rewrites that section as a whole. Text outside
+/// the sections stays as it is; comments inside rewritten sections are lost. Returns `None` if
+/// there is no such section.
+pub fn rewrite_sections(
+    content: &str,
+    types: &[&str],
+    mut convert: impl FnMut(&str, Option<&str>, &Mapping) -> Result<(String, Mapping), String>,
+    merge: impl Fn(&str, &mut Value, Value) -> Result<(), String>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut text = content.to_string();
+    let mut rewritten = false;
+    loop {
+        let lines: Vec<&str> = text.lines().collect();
+        let Some((start, tf_type)) = lines.iter().enumerate().find_map(|(idx, line)| match mapping_key(line) {
+            Some((key, "")) => types.iter().find(|t| **t == key).map(|t| (idx, *t)),
+            _ => None,
+        }) else { break };
+        let end = block_end(&lines, start);
+        let indent = indentation(lines[start]);
+
+        let parent_start = (0..start).rev().find(|idx| is_content(lines[*idx]) && indentation(lines[*idx]) < indent);
+        let scope = parent_start.and_then(|idx| mapping_key(lines[idx])).map(|(key, _)| key);
+        let (new_type, entries) = convert(tf_type, scope, &section_entries(&lines, start, end)?)
+            .map_err(|e| format!("{} (line {}): {}", tf_type, start + 1, e))?;
+
+        // A section of the new type in the same mapping, also without the `google_` prefix
+        let parent_end = (start..lines.len()).find(|idx| is_content(lines[*idx]) && indentation(lines[*idx]) < indent).unwrap_or(lines.len());
+        let unprefixed = |t: &str| t.strip_prefix("google_").unwrap_or(t).to_string();
+        let sibling = (parent_start.map_or(0, |p| p + 1)..parent_end)
+            .find(|idx| indentation(lines[*idx]) == indent && matches!(mapping_key(lines[*idx]), Some((key, "")) if unprefixed(key) == unprefixed(&new_type)));
+        let entry_indent = |start: usize, end: usize| lines[start + 1..end].iter().find(|l| is_content(l)).map_or(indent + 2, |l| indentation(l));
+
+        let mut result: Vec<String> = Vec::new();
+        match sibling {
+            Some(sibling) => {
+                let sibling_end = block_end(&lines, sibling);
+                let mut existing = section_entries(&lines, sibling, sibling_end)?;
+                let sibling_indent = entry_indent(sibling, sibling_end);
+                let (replaced, added) = if entries.keys().any(|k| existing.contains_key(k)) {
+                    for (key, value) in entries {
+                        match existing.get_mut(&key) {
+                            Some(current) => merge(key.as_str().unwrap_or_default(), current, value)
+                                .map_err(|e| format!("{} (line {}): {}", tf_type, start + 1, e))?,
+                            None => { existing.insert(key, value); }
+                        }
+                    }
+                    (Some(indented(&existing, sibling_indent)?), Vec::new())
+                } else {
+                    (None, indented(&entries, sibling_indent)?)
+                };
+                for (idx, line) in lines.iter().enumerate() {
+                    if (start..end).contains(&idx) || (replaced.is_some() && (sibling + 1..sibling_end).contains(&idx)) {
+                        continue;
+                    }
+                    result.push(line.to_string());
+                    if idx == sibling {
+                        result.extend(replaced.iter().flatten().cloned());
+                    }
+                    if idx + 1 == sibling_end {
+                        result.extend(added.iter().cloned());
+                    }
+                }
+            }
+            None => {
+                result.extend(lines[..start].iter().map(|l| l.to_string()));
+                result.push(format!("{}{}:", " ".repeat(indent), new_type));
+                result.extend(indented(&entries, entry_indent(start, end))?);
+                result.extend(lines[end..].iter().map(|l| l.to_string()));
+            }
+        }
+        text = result.join("\n");
+        if content.ends_with('\n') {
+            text.push('\n');
+        }
+        rewritten = true;
+    }
+    Ok(rewritten.then_some(text))
+}
+
+/// The entries of the section whose key is on line `start`.
+fn section_entries(lines: &[&str], start: usize, end: usize) -> Result<Mapping, String> {
+    let indent = indentation(lines[start]);
+    let section: String = lines[start..end].iter()
+        .map(|l| if indentation(l) >= indent { &l[indent..] } else { l.trim_start() })
+        .collect::<Vec<_>>().join("\n");
+    let parsed: Mapping = serde_yaml::from_str(&section).map_err(|e| format!("Failed to parse line {}: {}", start + 1, e))?;
+    match parsed.into_iter().next().map(|(_, v)| v) {
+        Some(Value::Mapping(entries)) => Ok(entries),
+        Some(Value::Null) | None => Ok(Mapping::new()),
+        Some(_) => Err(format!("Line {} is no mapping of entries", start + 1)),
+    }
+}
+
+/// The lines of `mapping` as YAML, indented by `indent`.
+fn indented(mapping: &Mapping, indent: usize) -> Result<Vec<String>, serde_yaml::Error> {
+    Ok(serde_yaml::to_string(mapping)?.lines().map(|l| format!("{}{}", " ".repeat(indent), l)).collect())
+}
+
+/// A unified diff of two texts with [`DIFF_CONTEXT`] lines of context, `""` if they are equal.
+pub fn diff(path: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    if prefix == old.len() && prefix == new.len() {
+        return String::new();
+    }
+
+    // Longest common subsequence of the differing middle parts
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+        }
+    }
+    let mut changes: Vec<(char, &str)> = old[..prefix].iter().map(|l| (' ', *l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            changes.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(('-', a[i]));
+            i += 1;
+        } else {
+            changes.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    changes.extend(old[old.len() - suffix..].iter().map(|l| (' ', *l)));
+
+    // Hunks: changed lines with their context, merged where the contexts touch
+    let changed: Vec<usize> = changes.iter().enumerate().filter(|(_, (c, _))| *c != ' ').map(|(idx, _)| idx).collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changed {
+        let (start, end) = (idx.saturating_sub(DIFF_CONTEXT), (idx + DIFF_CONTEXT + 1).min(changes.len()));
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let mut output = format!("--- {}\n+++ {}\n", path, path);
+    for (start, end) in hunks {
+        let old_start = changes[..start].iter().filter(|(c, _)| *c != '+').count();
+        let new_start = changes[..start].iter().filter(|(c, _)| *c != '-').count();
+        let old_len = changes[start..end].iter().filter(|(c, _)| *c != '+').count();
+        let new_len = changes[start..end].iter().filter(|(c, _)| *c != '-').count();
+        output.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start + 1, old_len, new_start + 1, new_len));
+        for (c, line) in &changes[start..end] {
+            output.push_str(&format!("{}{}\n", c, line));
+        }
+    }
+    output
+}